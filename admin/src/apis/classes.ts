@@ -1,7 +1,10 @@
-import type { PagingResponse } from '@/types/api'
+import type { DeletePreview, PagingResponse } from '@/types/api'
 import type {
   ClassInfo,
   ClassCreateRequest,
+  ClassDeleteImpact,
+  ClassTransferRequest,
+  ClassTransferResult,
   ClassListRequest,
   ClassUpdateRequest,
   ClassBulkCreatePayload
@@ -28,10 +31,18 @@ export const updateClass = async (id: number, data: ClassUpdateRequest): Promise
   return (await request.put(`/api/admin/classes/${id}`, data)).data
 }
 
-export const deleteClass = async (id: number): Promise<void> => {
-  return (await request.delete(`/api/admin/classes/${id}`)).data
+export const getClassDeletePreview = async (id: number): Promise<DeletePreview<ClassDeleteImpact>> => {
+  return (await request.get(`/api/admin/classes/${id}/delete-preview`)).data
+}
+
+export const deleteClass = async (id: number, confirmToken: string): Promise<void> => {
+  return (await request.delete(`/api/admin/classes/${id}`, { headers: { 'X-Confirm-Token': confirmToken } })).data
 }
 
 export const createClassesBulk = async (data: ClassBulkCreatePayload): Promise<void> => {
   return (await request.post('/api/admin/classes/bulk', data)).data
 }
+
+export const transferClass = async (id: number, data: ClassTransferRequest): Promise<ClassTransferResult> => {
+  return (await request.post(`/api/admin/classes/${id}/transfer`, data)).data
+}
@@ -0,0 +1,65 @@
+import type { PagingResponse } from '@/types/api'
+import type {
+  Campus,
+  CampusCreateRequest,
+  CampusUpdateRequest,
+  District,
+  DistrictBoard,
+  DistrictCreateRequest,
+  DistrictListRequest,
+  DistrictUpdateRequest
+} from '@/types/districts'
+import type { Stats, StatsRequest } from '@/types/stats'
+import request from '@/utils/request'
+
+export const getDistricts = async (params: DistrictListRequest): Promise<PagingResponse<District>> => {
+  return (await request.get('/api/admin/districts', { params })).data
+}
+
+export const getDistrict = async (id: number): Promise<District> => {
+  return (await request.get(`/api/admin/districts/${id}`)).data
+}
+
+export const createDistrict = async (data: DistrictCreateRequest): Promise<District> => {
+  return (await request.post('/api/admin/districts', data)).data
+}
+
+export const updateDistrict = async (id: number, data: DistrictUpdateRequest): Promise<District> => {
+  return (await request.put(`/api/admin/districts/${id}`, data)).data
+}
+
+export const deleteDistrict = async (id: number): Promise<void> => {
+  return (await request.delete(`/api/admin/districts/${id}`)).data
+}
+
+export const addDistrictAdmin = async (id: number, userId: number): Promise<void> => {
+  return (await request.post(`/api/admin/districts/${id}/admins`, { user_id: userId })).data
+}
+
+export const removeDistrictAdmin = async (id: number, userId: number): Promise<void> => {
+  return (await request.delete(`/api/admin/districts/${id}/admins/${userId}`)).data
+}
+
+export const getDistrictStats = async (id: number, params: Omit<StatsRequest, 'school_id'> = {}): Promise<Stats> => {
+  return (await request.get(`/api/admin/districts/${id}/stats`, { params })).data
+}
+
+export const getDistrictBoard = async (id: number): Promise<DistrictBoard> => {
+  return (await request.get(`/api/admin/districts/${id}/board`)).data
+}
+
+export const getCampuses = async (schoolId: number): Promise<Campus[]> => {
+  return (await request.get(`/api/admin/schools/${schoolId}/campuses`)).data
+}
+
+export const createCampus = async (schoolId: number, data: CampusCreateRequest): Promise<Campus> => {
+  return (await request.post(`/api/admin/schools/${schoolId}/campuses`, data)).data
+}
+
+export const updateCampus = async (id: number, data: CampusUpdateRequest): Promise<Campus> => {
+  return (await request.put(`/api/admin/campuses/${id}`, data)).data
+}
+
+export const deleteCampus = async (id: number): Promise<void> => {
+  return (await request.delete(`/api/admin/campuses/${id}`)).data
+}
@@ -3,4 +3,6 @@ export * from './users'
 export * from './roles'
 export * from './permissions'
 export * from './schools'
-export * from './classes'
\ No newline at end of file
+export * from './classes'
+export * from './stats'
+export * from './districts'
\ No newline at end of file
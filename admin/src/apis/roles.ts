@@ -1,5 +1,5 @@
-import type { PagingResponse } from '@/types/api'
-import type { Role, RoleCreateRequest, RoleListRequest, RoleUpdateRequest } from '@/types/roles'
+import type { DeletePreview, PagingResponse } from '@/types/api'
+import type { Role, RoleCreateRequest, RoleDeleteImpact, RoleListRequest, RoleUpdateRequest } from '@/types/roles'
 import request from '@/utils/request'
 
 export const getRoles = async (params: RoleListRequest): Promise<PagingResponse<Role>> => {
@@ -18,6 +18,10 @@ export const updateRole = async (id: number, data: RoleUpdateRequest): Promise<R
   return (await request.put(`/api/admin/roles/${id}`, data)).data
 }
 
-export const deleteRole = async (id: number): Promise<void> => {
-  return (await request.delete(`/api/admin/roles/${id}`)).data
+export const getRoleDeletePreview = async (id: number): Promise<DeletePreview<RoleDeleteImpact>> => {
+  return (await request.get(`/api/admin/roles/${id}/delete-preview`)).data
+}
+
+export const deleteRole = async (id: number, confirmToken: string): Promise<void> => {
+  return (await request.delete(`/api/admin/roles/${id}`, { headers: { 'X-Confirm-Token': confirmToken } })).data
 }
@@ -1,7 +1,8 @@
-import type { PagingResponse } from '@/types/api'
+import type { DeletePreview, PagingResponse } from '@/types/api'
 import type {
   School,
   SchoolCreateRequest,
+  SchoolDeleteImpact,
   SchoolListRequest,
   SchoolUpdateRequest
 } from '@/types/schools'
@@ -23,8 +24,12 @@ export const updateSchool = async (id: number, data: SchoolUpdateRequest): Promi
   return (await request.put(`/api/admin/schools/${id}`, data)).data
 }
 
-export const deleteSchool = async (id: number): Promise<void> => {
-  return (await request.delete(`/api/admin/schools/${id}`)).data
+export const getSchoolDeletePreview = async (id: number): Promise<DeletePreview<SchoolDeleteImpact>> => {
+  return (await request.get(`/api/admin/schools/${id}/delete-preview`)).data
+}
+
+export const deleteSchool = async (id: number, confirmToken: string): Promise<void> => {
+  return (await request.delete(`/api/admin/schools/${id}`, { headers: { 'X-Confirm-Token': confirmToken } })).data
 }
 
 export const getSimpleSchool = async (id: number): Promise<School> => {
@@ -0,0 +1,6 @@
+import type { Stats, StatsRequest } from '@/types/stats'
+import request from '@/utils/request'
+
+export const getStats = async (params: StatsRequest = {}): Promise<Stats> => {
+  return (await request.get('/api/admin/stats', { params })).data
+}
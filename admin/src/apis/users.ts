@@ -2,7 +2,8 @@
 
 import request from '@/utils/request'
 import type {  PagingResponse } from '@/types'
-import type { UserListRequest, User, UserCreateRequest, UserUpdateRequest } from '@/types/user'
+import type { DeletePreview } from '@/types/api'
+import type { UserListRequest, User, UserCreateRequest, UserUpdateRequest, UserDeleteImpact } from '@/types/user'
 
 export const getUsers = async (params: UserListRequest): Promise<PagingResponse<User>> => {
   return (await request.get('/api/admin/users', { params })).data
@@ -16,6 +17,10 @@ export async function updateUser(id: number, payload: UserUpdateRequest): Promis
   return (await request.put(`/api/admin/users/${id}`, payload)).data
 }
 
-export async function deleteUser(id: number): Promise<void> {
-  return (await request.delete(`/api/admin/users/${id}`)).data
+export async function getUserDeletePreview(id: number): Promise<DeletePreview<UserDeleteImpact>> {
+  return (await request.get(`/api/admin/users/${id}/delete-preview`)).data
+}
+
+export async function deleteUser(id: number, confirmToken: string): Promise<void> {
+  return (await request.delete(`/api/admin/users/${id}`, { headers: { 'X-Confirm-Token': confirmToken } })).data
 }
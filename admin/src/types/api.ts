@@ -14,4 +14,10 @@ export interface PagingResponse<T> {
 export interface ListParamsReq {
   page?: number
   page_size?: number
+}
+
+export interface DeletePreview<T> {
+  impact: T
+  confirm_token: string
+  expires_in: number
 }
\ No newline at end of file
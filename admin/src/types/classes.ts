@@ -12,6 +12,7 @@ export interface ClassInfo {
   class: number
   school_id: number
   school_name: string
+  campus_id: number | null
   status: number
   password?: string
   teacher_infos: ClassUserInfo[]
@@ -20,6 +21,7 @@ export interface ClassInfo {
 export type ClassListRequest = {
   name?: string
   school_id?: number
+  campus_id?: number
   grade?: number
   class?: number
   status?: number
@@ -30,6 +32,8 @@ export interface ClassUpdateRequest {
   grade?: number
   class?: number
   school_id?: number
+  /** 0 移出校区 */
+  campus_id?: number
   status?: number
   password?: string
 }
@@ -39,10 +43,39 @@ export interface ClassCreateRequest {
   grade: number
   class: number
   school_id: number
+  campus_id?: number
   status?: number
   password?: string
+  upsert?: boolean
+}
+
+export interface ClassTransferRequest {
+  school_id: number
+  grade?: number
+  class?: number
+  carry_teachers?: boolean
+}
+
+export interface ClassTransferResult {
+  class: Omit<ClassInfo, 'school_name' | 'teacher_infos'>
+  from_school_id: number
+  to_school_id: number
+  carried_teachers: number[]
+  dropped_teachers: number[]
+  new_members: number[]
 }
 
 export interface ClassBulkCreatePayload {
   classes: ClassCreateRequest[]
+  upsert?: boolean
+}
+
+export interface ClassDeleteImpact {
+  class_id: number
+  name: string
+  school_id: number
+  status: number
+  teacher_bindings: number
+  active_assignments: number
+  connected_screens: number
 }
@@ -0,0 +1,58 @@
+import type { ListParamsReq } from "./api";
+
+export interface District {
+  id: number
+  name: string
+  school_count: number
+  admin_ids: number[]
+  created_at: string
+}
+
+export type DistrictListRequest = {
+  name?: string
+}&ListParamsReq;
+
+export interface DistrictCreateRequest {
+  name: string
+}
+
+export interface DistrictUpdateRequest {
+  name?: string
+}
+
+export interface SchoolBoard {
+  school_id: number
+  school_name: string
+  classes: number
+  ongoing: number
+  dismissing: number
+  dismissed: number
+  on_hold: boolean
+  connected_screens: number
+}
+
+export interface DistrictBoard {
+  district_id: number
+  name: string
+  classes: number
+  ongoing: number
+  dismissing: number
+  dismissed: number
+  schools: SchoolBoard[]
+}
+
+export interface Campus {
+  id: number
+  school_id: number
+  name: string
+  address: string | null
+  class_count: number
+  created_at: string
+}
+
+export interface CampusCreateRequest {
+  name: string
+  address?: string
+}
+
+export type CampusUpdateRequest = Partial<CampusCreateRequest>
@@ -5,4 +5,6 @@ export * from './user'
 export * from './roles'
 export * from './permissions'
 export * from './classes'
-export * from './schools'
\ No newline at end of file
+export * from './schools'
+export * from './stats'
+export * from './districts'
\ No newline at end of file
@@ -21,3 +21,11 @@ export interface RoleCreateRequest {
   name: string
   description?: string
 }
+
+export interface RoleDeleteImpact {
+  role_id: number
+  name: string
+  users: number
+  usernames: string[]
+  permissions: number
+}
@@ -4,23 +4,38 @@ export interface School {
   id: number
   name: string
   password: string
+  district_id: number | null
 }
 
 export interface PublicSchool {
   id: number
   name: string
+  district_id: number | null
 }
 
 export type SchoolListRequest = {
   name?: string
+  district_id?: number
 }&ListParamsReq;
 
 export interface SchoolUpdateRequest {
   name?: string
   password?: string
+  /** 0 移出学区 */
+  district_id?: number
 }
 
 export interface SchoolCreateRequest {
   name: string
   password: string
+  district_id?: number
+}
+
+export interface SchoolDeleteImpact {
+  school_id: number
+  name: string
+  classes: number
+  teacher_bindings: number
+  users: number
+  connected_screens: number
 }
@@ -0,0 +1,32 @@
+export interface DismissalDuration {
+  school_id: number
+  school_name: string
+  avg_seconds: number
+  samples: number
+}
+
+export interface HourlyCount {
+  hour: string
+  count: number
+}
+
+export interface Stats {
+  school_id: number | null
+  district_id: number | null
+  from: string
+  to: string
+  schools: number
+  classes: number
+  teachers: number
+  unbound_classes: number
+  live_connections: number
+  today_status_changes: number
+  dismissal_durations: DismissalDuration[]
+  hourly_status_changes: HourlyCount[]
+}
+
+export interface StatsRequest {
+  school_id?: number
+  from?: string
+  to?: string
+}
@@ -14,13 +14,29 @@ export interface UserClassInfo {
   class: number
 }
 
+export interface SchoolMembership {
+  school_id: number
+  school_name: string
+  role: 'teacher' | 'manager'
+  status: 'active' | 'suspended'
+  created_at: string
+}
+
 export interface User {
   id: number
   username: string
   role_infos: UserRoleInfo[]
   class_infos: UserClassInfo[]
+  memberships: SchoolMembership[]
   school_id: number
   school_name: string
+  real_name: string | null
+  display_name: string | null
+  employee_no: string | null
+  /** 界面上显示的名字: 显示名, 真实姓名, 最后才是用户名 */
+  name: string
+  /** 教师填写真实姓名后才能修改班级状态 */
+  profile_complete: boolean
   phone: string
   wechat_openid: string
   wechat_unionid: string
@@ -31,6 +47,9 @@ export interface User {
 
 export type UserListRequest = {
   username?: string
+  /** 同时匹配用户名, 真实姓名和显示名 */
+  name?: string
+  employee_no?: string
   id?: number
 }&ListParamsReq;
 
@@ -39,6 +58,9 @@ export interface UserUpdateRequest {
   password?: string
   role_ids?: number[]
   class_ids?: number[]
+  real_name?: string
+  display_name?: string
+  employee_no?: string
 }
 
 export interface UserCreateRequest {
@@ -46,6 +68,9 @@ export interface UserCreateRequest {
   password: string
   role_ids: number[]
   class_ids: number[]
+  real_name?: string
+  display_name?: string
+  employee_no?: string
 }
 
 export interface AuthPayload {
@@ -60,9 +85,20 @@ export interface AuthResponse {
 export interface RegisterPayload {
     username: string;
     password: string;
+    real_name?: string;
+    display_name?: string;
 }
 
 export interface ChangePasswordPayload {
     old_password: string;
     new_password: string;
-}
\ No newline at end of file
+}
+
+export interface UserDeleteImpact {
+  user_id: number
+  username: string
+  roles: string[]
+  class_bindings: number
+  head_of_classes: number[]
+  active_assignments: number
+}
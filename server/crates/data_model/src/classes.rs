@@ -16,10 +16,30 @@ pub struct Model {
     pub password: String,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub status_version: i64,
+    pub academic_year_id: Option<i32>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub campus_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::academic_years::Entity",
+        from = "Column::AcademicYearId",
+        to = "super::academic_years::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    AcademicYears,
+    #[sea_orm(
+        belongs_to = "super::campuses::Entity",
+        from = "Column::CampusId",
+        to = "super::campuses::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Campuses,
     #[sea_orm(
         belongs_to = "super::schools::Entity",
         from = "Column::SchoolId",
@@ -30,6 +50,22 @@ pub enum Relation {
     Schools,
     #[sea_orm(has_many = "super::teacher_classes::Entity")]
     TeacherClasses,
+    #[sea_orm(has_many = "super::teacher_assignments::Entity")]
+    TeacherAssignments,
+    #[sea_orm(has_many = "super::class_status_logs::Entity")]
+    ClassStatusLogs,
+}
+
+impl Related<super::academic_years::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AcademicYears.def()
+    }
+}
+
+impl Related<super::campuses::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Campuses.def()
+    }
 }
 
 impl Related<super::schools::Entity> for Entity {
@@ -53,4 +89,16 @@ impl Related<super::users::Entity> for Entity {
     }
 }
 
+impl Related<super::teacher_assignments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TeacherAssignments.def()
+    }
+}
+
+impl Related<super::class_status_logs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ClassStatusLogs.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -10,3 +10,17 @@ pub mod users;
 pub mod schools;
 pub mod classes;
 pub mod teacher_classes;
+pub mod school_holds;
+pub mod announcements;
+pub mod outbox_events;
+pub mod webhook_endpoints;
+pub mod webhook_deliveries;
+pub mod teacher_assignments;
+pub mod class_status_logs;
+pub mod academic_years;
+pub mod dismissal_schedules;
+pub mod audit_logs;
+pub mod school_memberships;
+pub mod districts;
+pub mod district_admins;
+pub mod campuses;
@@ -2,11 +2,25 @@
 
 pub mod prelude;
 
+pub mod academic_years;
+pub mod announcements;
+pub mod audit_logs;
+pub mod campuses;
+pub mod class_status_logs;
 pub mod classes;
+pub mod dismissal_schedules;
+pub mod district_admins;
+pub mod districts;
+pub mod outbox_events;
 pub mod permissions;
 pub mod role_permissions;
 pub mod roles;
+pub mod school_holds;
+pub mod school_memberships;
 pub mod schools;
+pub mod teacher_assignments;
 pub mod teacher_classes;
 pub mod user_roles;
 pub mod users;
+pub mod webhook_deliveries;
+pub mod webhook_endpoints;
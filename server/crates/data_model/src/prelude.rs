@@ -1,10 +1,24 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.17
 
+pub use super::academic_years::Entity as AcademicYears;
+pub use super::announcements::Entity as Announcements;
+pub use super::audit_logs::Entity as AuditLogs;
+pub use super::campuses::Entity as Campuses;
+pub use super::class_status_logs::Entity as ClassStatusLogs;
 pub use super::classes::Entity as Classes;
+pub use super::dismissal_schedules::Entity as DismissalSchedules;
+pub use super::district_admins::Entity as DistrictAdmins;
+pub use super::districts::Entity as Districts;
+pub use super::outbox_events::Entity as OutboxEvents;
 pub use super::permissions::Entity as Permissions;
 pub use super::role_permissions::Entity as RolePermissions;
 pub use super::roles::Entity as Roles;
+pub use super::school_holds::Entity as SchoolHolds;
+pub use super::school_memberships::Entity as SchoolMemberships;
 pub use super::schools::Entity as Schools;
+pub use super::teacher_assignments::Entity as TeacherAssignments;
 pub use super::teacher_classes::Entity as TeacherClasses;
 pub use super::user_roles::Entity as UserRoles;
 pub use super::users::Entity as Users;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
+pub use super::webhook_endpoints::Entity as WebhookEndpoints;
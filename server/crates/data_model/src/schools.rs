@@ -12,14 +12,46 @@ pub struct Model {
     pub password: String,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub status_version: i64,
+    pub classes_reset_version: i64,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub district_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::districts::Entity",
+        from = "Column::DistrictId",
+        to = "super::districts::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Districts,
     #[sea_orm(has_many = "super::classes::Entity")]
     Classes,
     #[sea_orm(has_many = "super::users::Entity")]
     Users,
+    #[sea_orm(has_many = "super::school_holds::Entity")]
+    SchoolHolds,
+    #[sea_orm(has_many = "super::announcements::Entity")]
+    Announcements,
+    #[sea_orm(has_many = "super::webhook_endpoints::Entity")]
+    WebhookEndpoints,
+    #[sea_orm(has_many = "super::academic_years::Entity")]
+    AcademicYears,
+    #[sea_orm(has_many = "super::dismissal_schedules::Entity")]
+    DismissalSchedules,
+    #[sea_orm(has_many = "super::school_memberships::Entity")]
+    SchoolMemberships,
+    #[sea_orm(has_many = "super::campuses::Entity")]
+    Campuses,
+}
+
+impl Related<super::districts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Districts.def()
+    }
 }
 
 impl Related<super::classes::Entity> for Entity {
@@ -34,4 +66,46 @@ impl Related<super::users::Entity> for Entity {
     }
 }
 
+impl Related<super::school_holds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SchoolHolds.def()
+    }
+}
+
+impl Related<super::announcements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Announcements.def()
+    }
+}
+
+impl Related<super::webhook_endpoints::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookEndpoints.def()
+    }
+}
+
+impl Related<super::academic_years::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AcademicYears.def()
+    }
+}
+
+impl Related<super::dismissal_schedules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DismissalSchedules.def()
+    }
+}
+
+impl Related<super::school_memberships::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SchoolMemberships.def()
+    }
+}
+
+impl Related<super::campuses::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Campuses.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -21,8 +21,12 @@ pub struct Model {
     #[sea_orm(column_type = "Text", nullable)]
     pub wechat_avatar_url: Option<String>,
     pub school_id: Option<i32>,
+    pub real_name: Option<String>,
+    pub display_name: Option<String>,
+    pub employee_no: Option<String>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -39,6 +43,18 @@ pub enum Relation {
     TeacherClasses,
     #[sea_orm(has_many = "super::user_roles::Entity")]
     UserRoles,
+    #[sea_orm(has_many = "super::school_holds::Entity")]
+    SchoolHolds,
+    #[sea_orm(has_many = "super::announcements::Entity")]
+    Announcements,
+    #[sea_orm(has_many = "super::webhook_endpoints::Entity")]
+    WebhookEndpoints,
+    #[sea_orm(has_many = "super::class_status_logs::Entity")]
+    ClassStatusLogs,
+    #[sea_orm(has_many = "super::school_memberships::Entity")]
+    SchoolMemberships,
+    #[sea_orm(has_many = "super::district_admins::Entity")]
+    DistrictAdmins,
 }
 
 impl Related<super::schools::Entity> for Entity {
@@ -77,4 +93,40 @@ impl Related<super::roles::Entity> for Entity {
     }
 }
 
+impl Related<super::school_holds::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SchoolHolds.def()
+    }
+}
+
+impl Related<super::announcements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Announcements.def()
+    }
+}
+
+impl Related<super::webhook_endpoints::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookEndpoints.def()
+    }
+}
+
+impl Related<super::class_status_logs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ClassStatusLogs.def()
+    }
+}
+
+impl Related<super::school_memberships::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SchoolMemberships.def()
+    }
+}
+
+impl Related<super::district_admins::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DistrictAdmins.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_school_holds_school_id;
+DROP TABLE IF EXISTS school_holds;
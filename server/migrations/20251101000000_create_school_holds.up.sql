@@ -0,0 +1,13 @@
+-- 学校紧急管控(暂停放学)记录, 每次开启/解除都会追加一条
+CREATE TABLE school_holds (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    -- hold: 开启管控, lift: 解除管控
+    action VARCHAR(20) NOT NULL,
+    reason TEXT NOT NULL DEFAULT '',
+    actor_id INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "school_hold_action_check" CHECK (action IN ('hold', 'lift'))
+);
+
+CREATE INDEX idx_school_holds_school_id ON school_holds (school_id, id DESC);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_announcements_pending;
+DROP INDEX IF EXISTS idx_announcements_school_id;
+DROP TABLE IF EXISTS announcements;
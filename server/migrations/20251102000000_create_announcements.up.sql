@@ -0,0 +1,23 @@
+-- 学校公告, 推送到大屏
+CREATE TABLE announcements (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    -- info: 普通, warning: 提醒, critical: 紧急
+    severity VARCHAR(20) NOT NULL DEFAULT 'info',
+    start_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    end_at TIMESTAMPTZ,
+    -- 目标年级, 例如 [1, 2], 为空表示全校
+    target_grades JSONB,
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    -- 已经推送到大屏的时间
+    published_at TIMESTAMPTZ,
+    -- 后台任务标记过期的时间
+    expired_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "announcement_severity_check" CHECK (severity IN ('info', 'warning', 'critical'))
+);
+
+CREATE INDEX idx_announcements_school_id ON announcements (school_id);
+CREATE INDEX idx_announcements_pending ON announcements (start_at) WHERE expired_at IS NULL;
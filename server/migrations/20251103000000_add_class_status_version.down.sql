@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS class_status_version_trigger ON classes;
+DROP FUNCTION IF EXISTS bump_class_status_version();
+DROP INDEX IF EXISTS idx_classes_school_id_status_version;
+ALTER TABLE schools DROP COLUMN IF EXISTS classes_reset_version;
+ALTER TABLE schools DROP COLUMN IF EXISTS status_version;
+ALTER TABLE classes DROP COLUMN IF EXISTS status_version;
+DROP SEQUENCE IF EXISTS class_status_version_seq;
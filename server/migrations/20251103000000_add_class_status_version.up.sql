@@ -0,0 +1,55 @@
+-- 班级变更版本号, 所有学校共用一个递增序列, 用于 ETag 和增量拉取
+CREATE SEQUENCE class_status_version_seq;
+
+ALTER TABLE classes ADD COLUMN status_version BIGINT NOT NULL DEFAULT 0;
+-- 学校下任意班级新增、修改、删除后的最新版本号
+ALTER TABLE schools ADD COLUMN status_version BIGINT NOT NULL DEFAULT 0;
+-- 学校最近一次有班级被删除或移出的版本号, 早于它的增量拉取需要返回完整列表
+ALTER TABLE schools ADD COLUMN classes_reset_version BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_classes_school_id_status_version ON classes (school_id, status_version);
+
+-- 已有数据从 1 开始编号
+UPDATE classes SET status_version = nextval('class_status_version_seq');
+UPDATE schools s
+SET status_version = COALESCE((SELECT MAX(c.status_version) FROM classes c WHERE c.school_id = s.id), 0);
+
+-- 先锁住学校行再取版本号, 保证同一学校内版本号的提交顺序与大小一致,
+-- 增量拉取时不会因为小版本号晚提交而漏掉变更
+CREATE OR REPLACE FUNCTION bump_class_status_version()
+RETURNS TRIGGER AS $$
+DECLARE
+  version BIGINT;
+BEGIN
+  IF TG_OP = 'UPDATE' AND NEW IS NOT DISTINCT FROM OLD THEN
+    RETURN NEW;
+  END IF;
+  IF TG_OP = 'DELETE' THEN
+    PERFORM 1 FROM schools WHERE id = OLD.school_id FOR UPDATE;
+    version := nextval('class_status_version_seq');
+    UPDATE schools
+    SET status_version = version, classes_reset_version = version
+    WHERE id = OLD.school_id;
+    RETURN OLD;
+  END IF;
+  PERFORM 1 FROM schools WHERE id = NEW.school_id FOR UPDATE;
+  IF TG_OP = 'UPDATE' AND OLD.school_id IS DISTINCT FROM NEW.school_id THEN
+    PERFORM 1 FROM schools WHERE id = OLD.school_id FOR UPDATE;
+  END IF;
+  version := nextval('class_status_version_seq');
+  NEW.status_version := version;
+  NEW.updated_at := NOW();
+  UPDATE schools SET status_version = version WHERE id = NEW.school_id;
+  IF TG_OP = 'UPDATE' AND OLD.school_id IS DISTINCT FROM NEW.school_id THEN
+    UPDATE schools
+    SET status_version = version, classes_reset_version = version
+    WHERE id = OLD.school_id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER class_status_version_trigger
+BEFORE INSERT OR UPDATE OR DELETE ON classes
+FOR EACH ROW
+EXECUTE FUNCTION bump_class_status_version();
@@ -0,0 +1,18 @@
+CREATE OR REPLACE FUNCTION notify_class_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF OLD.status IS DISTINCT FROM NEW.status THEN
+    PERFORM pg_notify(
+      'class_status_updates',
+      json_build_object(
+        'school_id', NEW.school_id,
+        'grade', NEW.grade,
+        'class', NEW.class,
+        'class_id', NEW.id,
+        'new_status', NEW.status
+      )::text
+    );
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,35 @@
+CREATE OR REPLACE FUNCTION notify_class_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF OLD.status IS DISTINCT FROM NEW.status THEN
+    PERFORM pg_notify(
+      'class_status_updates',
+      json_build_object(
+        'school_id', NEW.school_id,
+        'grade', NEW.grade,
+        'class', NEW.class,
+        'class_id', NEW.id,
+        'new_status', NEW.status,
+        'status_version', NEW.status_version
+      )::text
+    );
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER class_status_change_trigger
+AFTER UPDATE OF status ON classes
+FOR EACH ROW
+EXECUTE FUNCTION notify_class_status_change();
+
+DROP TRIGGER IF EXISTS outbox_events_notify_trigger ON outbox_events;
+DROP FUNCTION IF EXISTS notify_outbox_event();
+DROP TRIGGER IF EXISTS announcements_outbox_trigger ON announcements;
+DROP TRIGGER IF EXISTS schools_outbox_trigger ON schools;
+DROP TRIGGER IF EXISTS teacher_classes_outbox_trigger ON teacher_classes;
+DROP TRIGGER IF EXISTS classes_outbox_trigger ON classes;
+DROP FUNCTION IF EXISTS enqueue_outbox_event();
+DROP INDEX IF EXISTS idx_outbox_events_processed_at;
+DROP INDEX IF EXISTS idx_outbox_events_pending;
+DROP TABLE IF EXISTS outbox_events;
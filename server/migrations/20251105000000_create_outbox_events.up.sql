@@ -0,0 +1,115 @@
+-- 事务性 outbox: 业务表的变更在同一个事务里写入事件, 由后台任务分发给大屏和 webhook
+CREATE TABLE outbox_events (
+    id BIGSERIAL PRIMARY KEY,
+    -- 幂等键, 同一事件重复投递时保持不变, 消费方据此去重
+    idempotency_key VARCHAR(64) NOT NULL DEFAULT gen_random_uuid()::text,
+    -- class, teacher_class, school, announcement
+    aggregate_type VARCHAR(50) NOT NULL,
+    aggregate_id INT NOT NULL,
+    -- 学校被删除后事件仍然保留, 所以不加外键
+    school_id INT,
+    -- 例如 class.status_changed, school.deleted
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- 投递失败后下一次重试的时间
+    available_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "outbox_events_idempotency_key_key" UNIQUE (idempotency_key)
+);
+
+CREATE INDEX idx_outbox_events_pending ON outbox_events (available_at, id) WHERE processed_at IS NULL;
+CREATE INDEX idx_outbox_events_processed_at ON outbox_events (processed_at) WHERE processed_at IS NOT NULL;
+
+-- 业务表变更时写入 outbox, 与业务数据在同一个事务里提交
+CREATE OR REPLACE FUNCTION enqueue_outbox_event()
+RETURNS TRIGGER AS $$
+DECLARE
+  v_aggregate TEXT;
+  v_aggregate_id INT;
+  v_school_id INT;
+  v_event_type TEXT;
+  v_data JSONB;
+  v_old_data JSONB;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    v_data := to_jsonb(OLD) - 'password';
+  ELSE
+    v_data := to_jsonb(NEW) - 'password';
+  END IF;
+  IF TG_OP = 'UPDATE' THEN
+    v_old_data := to_jsonb(OLD) - 'password';
+    -- 只有版本号和更新时间变化时不算业务变更
+    IF v_data - 'status_version' - 'classes_reset_version' - 'updated_at'
+       = v_old_data - 'status_version' - 'classes_reset_version' - 'updated_at' THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+  v_aggregate := CASE TG_TABLE_NAME
+    WHEN 'classes' THEN 'class'
+    WHEN 'teacher_classes' THEN 'teacher_class'
+    WHEN 'schools' THEN 'school'
+    WHEN 'announcements' THEN 'announcement'
+    ELSE TG_TABLE_NAME
+  END;
+  v_event_type := v_aggregate || '.' || CASE TG_OP
+    WHEN 'INSERT' THEN 'created'
+    WHEN 'UPDATE' THEN 'updated'
+    ELSE 'deleted'
+  END;
+  IF TG_TABLE_NAME = 'classes' AND TG_OP = 'UPDATE' AND OLD.status IS DISTINCT FROM NEW.status THEN
+    v_event_type := 'class.status_changed';
+    v_data := v_data || jsonb_build_object('old_status', OLD.status);
+  END IF;
+  IF TG_TABLE_NAME = 'schools' THEN
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := v_aggregate_id;
+  ELSIF TG_TABLE_NAME = 'teacher_classes' THEN
+    -- 绑定关系没有自己的 id, 归到班级下
+    v_aggregate_id := (v_data ->> 'class_id')::INT;
+    SELECT c.school_id INTO v_school_id FROM classes c WHERE c.id = v_aggregate_id;
+    v_data := v_data || jsonb_build_object('school_id', v_school_id);
+  ELSE
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := (v_data ->> 'school_id')::INT;
+  END IF;
+  INSERT INTO outbox_events (aggregate_type, aggregate_id, school_id, event_type, payload)
+  VALUES (v_aggregate, v_aggregate_id, v_school_id, v_event_type, v_data);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER classes_outbox_trigger
+AFTER INSERT OR UPDATE OR DELETE ON classes
+FOR EACH ROW EXECUTE FUNCTION enqueue_outbox_event();
+
+CREATE TRIGGER teacher_classes_outbox_trigger
+AFTER INSERT OR UPDATE OR DELETE ON teacher_classes
+FOR EACH ROW EXECUTE FUNCTION enqueue_outbox_event();
+
+CREATE TRIGGER schools_outbox_trigger
+AFTER INSERT OR UPDATE OR DELETE ON schools
+FOR EACH ROW EXECUTE FUNCTION enqueue_outbox_event();
+
+CREATE TRIGGER announcements_outbox_trigger
+AFTER INSERT OR UPDATE OR DELETE ON announcements
+FOR EACH ROW EXECUTE FUNCTION enqueue_outbox_event();
+
+-- 新事件提交后唤醒分发任务, 通知里只带 id, 事件内容以表里为准
+CREATE OR REPLACE FUNCTION notify_outbox_event()
+RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('outbox_events', NEW.id::text);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER outbox_events_notify_trigger
+AFTER INSERT ON outbox_events
+FOR EACH ROW EXECUTE FUNCTION notify_outbox_event();
+
+-- 班级状态通知改由 outbox 分发
+DROP TRIGGER IF EXISTS class_status_change_trigger ON classes;
+DROP FUNCTION IF EXISTS notify_class_status_change();
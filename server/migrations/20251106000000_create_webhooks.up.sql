@@ -0,0 +1,44 @@
+-- 学校配置的 webhook 地址, outbox 事件按类型投递过去
+CREATE TABLE webhook_endpoints (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    url VARCHAR(1000) NOT NULL,
+    -- HMAC-SHA256 签名密钥
+    secret VARCHAR(255) NOT NULL,
+    -- 订阅的事件类型, 例如 ["class.status_changed"], 为空表示全部
+    event_types JSONB NOT NULL DEFAULT '[]'::jsonb,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_webhook_endpoints_school_id ON webhook_endpoints (school_id);
+
+-- 每次投递的记录
+CREATE TABLE webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    endpoint_id INT NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    -- outbox 事件会定期清理, 所以只保留 id 不加外键
+    outbox_event_id BIGINT,
+    -- 手动重新投递时指向原来的记录
+    redelivery_of BIGINT REFERENCES webhook_deliveries(id) ON DELETE SET NULL,
+    event_type VARCHAR(100) NOT NULL,
+    -- 与 outbox 事件相同, 重新投递时保持不变
+    idempotency_key VARCHAR(64) NOT NULL,
+    payload JSONB NOT NULL,
+    -- pending, succeeded, failed
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    response_status INT,
+    response_body TEXT,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "webhook_delivery_status_check" CHECK (status IN ('pending', 'succeeded', 'failed'))
+);
+
+CREATE INDEX idx_webhook_deliveries_endpoint_id ON webhook_deliveries (endpoint_id, id);
+CREATE INDEX idx_webhook_deliveries_pending ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS teacher_classes_assignment_trigger ON teacher_classes;
+DROP FUNCTION IF EXISTS sync_teacher_assignment();
+DROP INDEX IF EXISTS idx_teacher_assignments_user_id;
+DROP INDEX IF EXISTS idx_teacher_assignments_class_id;
+DROP TABLE IF EXISTS teacher_assignments;
@@ -0,0 +1,79 @@
+-- 教师值班安排: 带有效期和角色, 代课需要班主任或管理员审批
+CREATE TABLE teacher_assignments (
+    id SERIAL PRIMARY KEY,
+    class_id INT NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- head: 班主任, assistant: 副班主任, substitute: 代课
+    role VARCHAR(20) NOT NULL,
+    -- pending: 待审批, approved: 已生效, rejected: 已拒绝, cancelled: 已撤销
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    valid_from TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- 为空表示长期有效
+    valid_to TIMESTAMPTZ,
+    reason TEXT NOT NULL DEFAULT '',
+    requested_by INT REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_by INT REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "teacher_assignment_role_check" CHECK (role IN ('head', 'assistant', 'substitute')),
+    CONSTRAINT "teacher_assignment_status_check" CHECK (status IN ('pending', 'approved', 'rejected', 'cancelled')),
+    CONSTRAINT "teacher_assignment_period_check" CHECK (valid_to IS NULL OR valid_to >= valid_from)
+);
+
+CREATE INDEX idx_teacher_assignments_class_id ON teacher_assignments (class_id, status);
+CREATE INDEX idx_teacher_assignments_user_id ON teacher_assignments (user_id, status);
+
+-- 现有的绑定转为长期安排; 绑定表没有记录绑定时间, 每个班级 user_id 最小的教师作为班主任
+INSERT INTO teacher_assignments (class_id, user_id, role, status)
+SELECT
+    tc.class_id,
+    tc.user_id,
+    CASE WHEN tc.user_id = MIN(tc.user_id) OVER (PARTITION BY tc.class_id) THEN 'head' ELSE 'assistant' END,
+    'approved'
+FROM teacher_classes tc;
+
+-- 绑定/解绑班级时同步长期安排, 代课安排不受影响
+CREATE OR REPLACE FUNCTION sync_teacher_assignment()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    UPDATE teacher_assignments
+    SET valid_to = NOW(), updated_at = NOW()
+    WHERE user_id = OLD.user_id
+      AND class_id = OLD.class_id
+      AND role <> 'substitute'
+      AND valid_to IS NULL;
+    RETURN NULL;
+  END IF;
+  -- 同一个事务里先解绑再绑定(例如整体替换教师的班级)时恢复原来的安排
+  UPDATE teacher_assignments
+  SET valid_to = NULL, updated_at = NOW()
+  WHERE user_id = NEW.user_id
+    AND class_id = NEW.class_id
+    AND role <> 'substitute'
+    AND valid_to = NOW();
+  IF NOT FOUND THEN
+    INSERT INTO teacher_assignments (class_id, user_id, role, status)
+    VALUES (
+      NEW.class_id,
+      NEW.user_id,
+      CASE WHEN EXISTS (
+        SELECT 1 FROM teacher_assignments a
+        WHERE a.class_id = NEW.class_id
+          AND a.role = 'head'
+          AND a.status = 'approved'
+          AND a.valid_from <= NOW()
+          AND (a.valid_to IS NULL OR a.valid_to > NOW())
+      ) THEN 'assistant' ELSE 'head' END,
+      'approved'
+    );
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER teacher_classes_assignment_trigger
+AFTER INSERT OR DELETE ON teacher_classes
+FOR EACH ROW
+EXECUTE FUNCTION sync_teacher_assignment();
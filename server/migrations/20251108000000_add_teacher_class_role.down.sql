@@ -0,0 +1,49 @@
+CREATE OR REPLACE FUNCTION sync_teacher_assignment()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    UPDATE teacher_assignments
+    SET valid_to = NOW(), updated_at = NOW()
+    WHERE user_id = OLD.user_id
+      AND class_id = OLD.class_id
+      AND role <> 'substitute'
+      AND valid_to IS NULL;
+    RETURN NULL;
+  END IF;
+  UPDATE teacher_assignments
+  SET valid_to = NULL, updated_at = NOW()
+  WHERE user_id = NEW.user_id
+    AND class_id = NEW.class_id
+    AND role <> 'substitute'
+    AND valid_to = NOW();
+  IF NOT FOUND THEN
+    INSERT INTO teacher_assignments (class_id, user_id, role, status)
+    VALUES (
+      NEW.class_id,
+      NEW.user_id,
+      CASE WHEN EXISTS (
+        SELECT 1 FROM teacher_assignments a
+        WHERE a.class_id = NEW.class_id
+          AND a.role = 'head'
+          AND a.status = 'approved'
+          AND a.valid_from <= NOW()
+          AND (a.valid_to IS NULL OR a.valid_to > NOW())
+      ) THEN 'assistant' ELSE 'head' END,
+      'approved'
+    );
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS teacher_classes_assignment_trigger ON teacher_classes;
+CREATE TRIGGER teacher_classes_assignment_trigger
+AFTER INSERT OR DELETE ON teacher_classes
+FOR EACH ROW
+EXECUTE FUNCTION sync_teacher_assignment();
+
+DROP INDEX IF EXISTS idx_teacher_classes_head;
+ALTER TABLE teacher_classes
+    DROP CONSTRAINT IF EXISTS "teacher_class_role_check",
+    DROP COLUMN IF EXISTS created_at,
+    DROP COLUMN IF EXISTS role;
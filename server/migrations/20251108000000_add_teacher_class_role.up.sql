@@ -0,0 +1,60 @@
+-- 每个绑定的角色: head 班主任(每个班级最多一个), co_teacher 协同教师, observer 只读
+ALTER TABLE teacher_classes
+    ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'co_teacher',
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD CONSTRAINT "teacher_class_role_check" CHECK (role IN ('head', 'co_teacher', 'observer'));
+
+-- 现有班主任安排对应的绑定设为 head, 不产生 outbox 事件
+ALTER TABLE teacher_classes DISABLE TRIGGER teacher_classes_outbox_trigger;
+UPDATE teacher_classes tc
+SET role = 'head'
+FROM (
+    SELECT DISTINCT ON (a.class_id) a.class_id, a.user_id
+    FROM teacher_assignments a
+    JOIN teacher_classes b ON b.class_id = a.class_id AND b.user_id = a.user_id
+    WHERE a.role = 'head' AND a.status = 'approved' AND a.valid_to IS NULL
+    ORDER BY a.class_id, a.valid_from, a.id
+) h
+WHERE tc.class_id = h.class_id AND tc.user_id = h.user_id;
+ALTER TABLE teacher_classes ENABLE TRIGGER teacher_classes_outbox_trigger;
+
+CREATE UNIQUE INDEX idx_teacher_classes_head ON teacher_classes (class_id) WHERE role = 'head';
+
+-- 长期安排跟随绑定的角色: head -> head, co_teacher -> assistant, observer 没有值班安排
+CREATE OR REPLACE FUNCTION sync_teacher_assignment()
+RETURNS TRIGGER AS $$
+DECLARE
+  v_role TEXT;
+BEGIN
+  IF TG_OP IN ('DELETE', 'UPDATE') THEN
+    UPDATE teacher_assignments
+    SET valid_to = NOW(), updated_at = NOW()
+    WHERE user_id = OLD.user_id
+      AND class_id = OLD.class_id
+      AND role <> 'substitute'
+      AND valid_to IS NULL;
+  END IF;
+  IF TG_OP = 'DELETE' OR NEW.role = 'observer' THEN
+    RETURN NULL;
+  END IF;
+  v_role := CASE NEW.role WHEN 'head' THEN 'head' ELSE 'assistant' END;
+  -- 同一个事务里先结束再恢复同样的角色时沿用原来的安排
+  UPDATE teacher_assignments
+  SET valid_to = NULL, updated_at = NOW()
+  WHERE user_id = NEW.user_id
+    AND class_id = NEW.class_id
+    AND role = v_role
+    AND valid_to = NOW();
+  IF NOT FOUND THEN
+    INSERT INTO teacher_assignments (class_id, user_id, role, status)
+    VALUES (NEW.class_id, NEW.user_id, v_role, 'approved');
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS teacher_classes_assignment_trigger ON teacher_classes;
+CREATE TRIGGER teacher_classes_assignment_trigger
+AFTER INSERT OR DELETE OR UPDATE OF role ON teacher_classes
+FOR EACH ROW
+EXECUTE FUNCTION sync_teacher_assignment();
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS classes_status_log_trigger ON classes;
+DROP FUNCTION IF EXISTS log_class_status_change();
+DROP INDEX IF EXISTS idx_class_status_logs_school_id;
+DROP INDEX IF EXISTS idx_class_status_logs_class_id;
+DROP TABLE IF EXISTS class_status_logs;
@@ -0,0 +1,36 @@
+-- 班级状态变更记录, 由触发器写入, 不论通过哪个接口修改都会记录
+CREATE TABLE class_status_logs (
+    id BIGSERIAL PRIMARY KEY,
+    class_id INT NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    school_id INT NOT NULL,
+    old_status INT NOT NULL,
+    new_status INT NOT NULL,
+    -- 操作人, 由应用在事务里通过 set_config('app.actor_id', ...) 传入
+    actor_id INT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_class_status_logs_class_id ON class_status_logs (class_id, id DESC);
+CREATE INDEX idx_class_status_logs_school_id ON class_status_logs (school_id, created_at);
+
+CREATE OR REPLACE FUNCTION log_class_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+  IF OLD.status IS DISTINCT FROM NEW.status THEN
+    INSERT INTO class_status_logs (class_id, school_id, old_status, new_status, actor_id)
+    VALUES (
+      NEW.id,
+      NEW.school_id,
+      OLD.status,
+      NEW.status,
+      NULLIF(current_setting('app.actor_id', true), '')::INT
+    );
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER classes_status_log_trigger
+AFTER UPDATE OF status ON classes
+FOR EACH ROW
+EXECUTE FUNCTION log_class_status_change();
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_classes_academic_year_id;
+ALTER TABLE classes DROP COLUMN IF EXISTS academic_year_id;
+DROP INDEX IF EXISTS idx_academic_years_current;
+DROP TABLE IF EXISTS academic_years;
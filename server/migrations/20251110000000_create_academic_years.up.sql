@@ -0,0 +1,37 @@
+-- 学年, 每个学校同时只有一个当前学年; 班级属于某个学年, 非当前学年的班级不再显示在大屏上
+CREATE TABLE academic_years (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    -- 例如 2025-2026
+    name VARCHAR(50) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    is_current BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "academic_years_school_id_name_key" UNIQUE (school_id, name),
+    CONSTRAINT "academic_year_period_check" CHECK (end_date > start_date)
+);
+
+CREATE UNIQUE INDEX idx_academic_years_current ON academic_years (school_id) WHERE is_current;
+
+ALTER TABLE classes ADD COLUMN academic_year_id INT REFERENCES academic_years(id) ON DELETE SET NULL;
+CREATE INDEX idx_classes_academic_year_id ON classes (academic_year_id);
+
+-- 已有学校按今天所在的学年(9 月 1 日开始)建立当前学年, 现有班级都归到这个学年
+INSERT INTO academic_years (school_id, name, start_date, end_date, is_current)
+SELECT s.id, y.year || '-' || (y.year + 1), make_date(y.year, 9, 1), make_date(y.year + 1, 8, 31), TRUE
+FROM schools s
+CROSS JOIN (
+    SELECT CASE
+        WHEN EXTRACT(MONTH FROM CURRENT_DATE) >= 9 THEN EXTRACT(YEAR FROM CURRENT_DATE)::INT
+        ELSE EXTRACT(YEAR FROM CURRENT_DATE)::INT - 1
+    END AS year
+) y;
+
+ALTER TABLE classes DISABLE TRIGGER classes_outbox_trigger;
+UPDATE classes c
+SET academic_year_id = a.id
+FROM academic_years a
+WHERE a.school_id = c.school_id AND a.is_current;
+ALTER TABLE classes ENABLE TRIGGER classes_outbox_trigger;
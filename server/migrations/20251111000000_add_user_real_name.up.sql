@@ -0,0 +1,2 @@
+-- 教师的真实姓名, 批量导入时填写
+ALTER TABLE users ADD COLUMN real_name VARCHAR(100);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS idx_class_status_logs_created_at;
@@ -0,0 +1,2 @@
+-- 不限学校的统计按时间范围扫描状态记录
+CREATE INDEX idx_class_status_logs_created_at ON class_status_logs (created_at);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS dismissal_schedules;
@@ -0,0 +1,14 @@
+-- 计划放学时间, grade 为空时是全校默认, 有年级的记录优先
+CREATE TABLE dismissal_schedules (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    grade INT,
+    -- 1 = 周一, 7 = 周日
+    weekday INT NOT NULL,
+    dismiss_at TIME NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "dismissal_schedule_weekday_check" CHECK (weekday BETWEEN 1 AND 7)
+);
+
+CREATE UNIQUE INDEX idx_dismissal_schedules_key ON dismissal_schedules (school_id, COALESCE(grade, 0), weekday);
@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS teacher_classes_audit_trigger ON teacher_classes;
+DROP TRIGGER IF EXISTS role_permissions_audit_trigger ON role_permissions;
+DROP TRIGGER IF EXISTS user_roles_audit_trigger ON user_roles;
+DROP TRIGGER IF EXISTS classes_audit_trigger ON classes;
+DROP TRIGGER IF EXISTS schools_audit_trigger ON schools;
+DROP TRIGGER IF EXISTS permissions_audit_trigger ON permissions;
+DROP TRIGGER IF EXISTS roles_audit_trigger ON roles;
+DROP TRIGGER IF EXISTS users_audit_trigger ON users;
+DROP FUNCTION IF EXISTS audit_row_change();
+DROP TABLE IF EXISTS audit_logs;
@@ -0,0 +1,115 @@
+-- 管理数据的增删改记录, 由触发器在同一事务里写入
+CREATE TABLE audit_logs (
+    id BIGSERIAL PRIMARY KEY,
+    -- 不加外键, 操作人被删除后仍保留记录
+    actor_id INT,
+    ip VARCHAR(64),
+    -- 例如 PUT /api/admin/users/3
+    route VARCHAR(255),
+    -- create, update, delete
+    action VARCHAR(10) NOT NULL,
+    -- 表名
+    entity_type VARCHAR(50) NOT NULL,
+    -- 主键, 联合主键用冒号连接, 例如 user_roles 的 "3:2"
+    entity_id VARCHAR(100),
+    -- update 时只包含有变化的字段; 密码只记录是否修改
+    before_data JSONB,
+    after_data JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_logs_entity ON audit_logs (entity_type, entity_id, id DESC);
+CREATE INDEX idx_audit_logs_actor_id ON audit_logs (actor_id, id DESC);
+CREATE INDEX idx_audit_logs_created_at ON audit_logs (created_at);
+
+-- 参数: 主键列(逗号分隔), 不记录的列(逗号分隔, 可选)
+CREATE OR REPLACE FUNCTION audit_row_change()
+RETURNS TRIGGER AS $$
+DECLARE
+  old_data JSONB := CASE WHEN TG_OP <> 'INSERT' THEN to_jsonb(OLD) END;
+  new_data JSONB := CASE WHEN TG_OP <> 'DELETE' THEN to_jsonb(NEW) END;
+  ignored TEXT[] := ARRAY['updated_at'] || COALESCE(string_to_array(NULLIF(TG_ARGV[1], ''), ','), ARRAY[]::TEXT[]);
+  redacted TEXT[] := ARRAY['password', 'password_hash'];
+  before_data JSONB;
+  after_data JSONB;
+  row_id TEXT;
+  k TEXT;
+BEGIN
+  IF TG_OP = 'UPDATE' THEN
+    before_data := '{}'::JSONB;
+    after_data := '{}'::JSONB;
+    FOR k IN SELECT jsonb_object_keys(new_data) LOOP
+      IF k = ANY(ignored) OR old_data -> k IS NOT DISTINCT FROM new_data -> k THEN
+        CONTINUE;
+      END IF;
+      before_data := before_data || jsonb_build_object(k, old_data -> k);
+      after_data := after_data || jsonb_build_object(k, new_data -> k);
+    END LOOP;
+    -- 只改了不记录的列, 例如班级状态(见 class_status_logs)
+    IF after_data = '{}'::JSONB THEN
+      RETURN NULL;
+    END IF;
+  ELSE
+    before_data := old_data - ignored;
+    after_data := new_data - ignored;
+  END IF;
+
+  FOREACH k IN ARRAY redacted LOOP
+    IF before_data ? k THEN
+      before_data := jsonb_set(before_data, ARRAY[k], '"***"');
+    END IF;
+    IF after_data ? k THEN
+      after_data := jsonb_set(after_data, ARRAY[k], '"***"');
+    END IF;
+  END LOOP;
+
+  SELECT string_agg(COALESCE(new_data, old_data) ->> t.col, ':' ORDER BY t.i)
+  INTO row_id
+  FROM unnest(string_to_array(TG_ARGV[0], ',')) WITH ORDINALITY AS t(col, i);
+
+  INSERT INTO audit_logs (actor_id, ip, route, action, entity_type, entity_id, before_data, after_data)
+  VALUES (
+    NULLIF(current_setting('app.actor_id', true), '')::INT,
+    NULLIF(current_setting('app.request_ip', true), ''),
+    NULLIF(current_setting('app.request_route', true), ''),
+    CASE TG_OP WHEN 'INSERT' THEN 'create' WHEN 'UPDATE' THEN 'update' ELSE 'delete' END,
+    TG_TABLE_NAME,
+    row_id,
+    before_data,
+    after_data
+  );
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON users
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER roles_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON roles
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER permissions_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON permissions
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER schools_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON schools
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id', 'status_version,classes_reset_version');
+
+CREATE TRIGGER classes_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON classes
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id', 'status,status_version');
+
+CREATE TRIGGER user_roles_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON user_roles
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('user_id,role_id');
+
+CREATE TRIGGER role_permissions_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON role_permissions
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('role_id,permission_id');
+
+CREATE TRIGGER teacher_classes_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON teacher_classes
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('user_id,class_id');
@@ -0,0 +1,97 @@
+CREATE OR REPLACE FUNCTION bump_class_status_version()
+RETURNS TRIGGER AS $$
+DECLARE
+  version BIGINT;
+BEGIN
+  IF TG_OP = 'UPDATE' AND NEW IS NOT DISTINCT FROM OLD THEN
+    RETURN NEW;
+  END IF;
+  IF TG_OP = 'DELETE' THEN
+    PERFORM 1 FROM schools WHERE id = OLD.school_id FOR UPDATE;
+    version := nextval('class_status_version_seq');
+    UPDATE schools
+    SET status_version = version, classes_reset_version = version
+    WHERE id = OLD.school_id;
+    RETURN OLD;
+  END IF;
+  PERFORM 1 FROM schools WHERE id = NEW.school_id FOR UPDATE;
+  IF TG_OP = 'UPDATE' AND OLD.school_id IS DISTINCT FROM NEW.school_id THEN
+    PERFORM 1 FROM schools WHERE id = OLD.school_id FOR UPDATE;
+  END IF;
+  version := nextval('class_status_version_seq');
+  NEW.status_version := version;
+  NEW.updated_at := NOW();
+  UPDATE schools SET status_version = version WHERE id = NEW.school_id;
+  IF TG_OP = 'UPDATE' AND OLD.school_id IS DISTINCT FROM NEW.school_id THEN
+    UPDATE schools
+    SET status_version = version, classes_reset_version = version
+    WHERE id = OLD.school_id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION enqueue_outbox_event()
+RETURNS TRIGGER AS $$
+DECLARE
+  v_aggregate TEXT;
+  v_aggregate_id INT;
+  v_school_id INT;
+  v_event_type TEXT;
+  v_data JSONB;
+  v_old_data JSONB;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    v_data := to_jsonb(OLD) - 'password';
+  ELSE
+    v_data := to_jsonb(NEW) - 'password';
+  END IF;
+  IF TG_OP = 'UPDATE' THEN
+    v_old_data := to_jsonb(OLD) - 'password';
+    -- 只有版本号和更新时间变化时不算业务变更
+    IF v_data - 'status_version' - 'classes_reset_version' - 'updated_at'
+       = v_old_data - 'status_version' - 'classes_reset_version' - 'updated_at' THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+  v_aggregate := CASE TG_TABLE_NAME
+    WHEN 'classes' THEN 'class'
+    WHEN 'teacher_classes' THEN 'teacher_class'
+    WHEN 'schools' THEN 'school'
+    WHEN 'announcements' THEN 'announcement'
+    ELSE TG_TABLE_NAME
+  END;
+  v_event_type := v_aggregate || '.' || CASE TG_OP
+    WHEN 'INSERT' THEN 'created'
+    WHEN 'UPDATE' THEN 'updated'
+    ELSE 'deleted'
+  END;
+  IF TG_TABLE_NAME = 'classes' AND TG_OP = 'UPDATE' AND OLD.status IS DISTINCT FROM NEW.status THEN
+    v_event_type := 'class.status_changed';
+    v_data := v_data || jsonb_build_object('old_status', OLD.status);
+  END IF;
+  IF TG_TABLE_NAME = 'schools' THEN
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := v_aggregate_id;
+  ELSIF TG_TABLE_NAME = 'teacher_classes' THEN
+    -- 绑定关系没有自己的 id, 归到班级下
+    v_aggregate_id := (v_data ->> 'class_id')::INT;
+    SELECT c.school_id INTO v_school_id FROM classes c WHERE c.id = v_aggregate_id;
+    v_data := v_data || jsonb_build_object('school_id', v_school_id);
+  ELSE
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := (v_data ->> 'school_id')::INT;
+  END IF;
+  INSERT INTO outbox_events (aggregate_type, aggregate_id, school_id, event_type, payload)
+  VALUES (v_aggregate, v_aggregate_id, v_school_id, v_event_type, v_data);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP INDEX IF EXISTS idx_users_deleted_at;
+DROP INDEX IF EXISTS idx_classes_deleted_at;
+DROP INDEX IF EXISTS idx_schools_deleted_at;
+
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE classes DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE schools DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,2 @@
+-- 迁移时移到回收站的重复班级不会自动恢复
+DROP INDEX IF EXISTS idx_classes_school_grade_class;
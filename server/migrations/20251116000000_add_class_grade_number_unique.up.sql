@@ -0,0 +1,44 @@
+-- 同一学校同一学年里年级和班号不能重复, 已删除的班级不参与
+-- 现有的重复班级保留最早创建的一个, 其余的教师绑定并入保留的班级后移到回收站
+DO $$
+DECLARE
+  dup RECORD;
+BEGIN
+  FOR dup IN
+    SELECT c.id, c.name, c.school_id, c.grade, c.class, k.keep_id
+    FROM classes c
+    JOIN (
+      SELECT school_id, COALESCE(academic_year_id, 0) AS year_key, grade, class, MIN(id) AS keep_id
+      FROM classes
+      WHERE deleted_at IS NULL
+      GROUP BY school_id, COALESCE(academic_year_id, 0), grade, class
+      HAVING COUNT(*) > 1
+    ) k ON k.school_id = c.school_id
+       AND k.year_key = COALESCE(c.academic_year_id, 0)
+       AND k.grade = c.grade
+       AND k.class = c.class
+    WHERE c.deleted_at IS NULL AND c.id <> k.keep_id
+    ORDER BY c.id
+  LOOP
+    RAISE NOTICE 'duplicate class % (%) of school % grade % class % merged into class %',
+      dup.id, dup.name, dup.school_id, dup.grade, dup.class, dup.keep_id;
+    -- 保留的班级已有班主任时, 重复班级的班主任并入后为协同教师
+    INSERT INTO teacher_classes (user_id, class_id, role)
+    SELECT tc.user_id, dup.keep_id,
+           CASE
+             WHEN tc.role = 'head' AND EXISTS (
+               SELECT 1 FROM teacher_classes h WHERE h.class_id = dup.keep_id AND h.role = 'head'
+             ) THEN 'co_teacher'
+             ELSE tc.role
+           END
+    FROM teacher_classes tc
+    WHERE tc.class_id = dup.id
+    ON CONFLICT (user_id, class_id) DO NOTHING;
+    DELETE FROM teacher_classes WHERE class_id = dup.id;
+    UPDATE classes SET deleted_at = NOW() WHERE id = dup.id;
+  END LOOP;
+END $$;
+
+CREATE UNIQUE INDEX idx_classes_school_grade_class
+    ON classes (school_id, COALESCE(academic_year_id, 0), grade, class)
+    WHERE deleted_at IS NULL;
@@ -0,0 +1,30 @@
+-- 教师可以属于多个学校; users.school_id 保留为默认学校, 未指定当前学校时使用
+-- role: teacher 教师, manager 学校管理员; status: active 正常, suspended 停用(不能访问该学校)
+CREATE TABLE school_memberships (
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL DEFAULT 'teacher',
+    status VARCHAR(20) NOT NULL DEFAULT 'active',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, school_id),
+    CONSTRAINT "school_membership_role_check" CHECK (role IN ('teacher', 'manager')),
+    CONSTRAINT "school_membership_status_check" CHECK (status IN ('active', 'suspended'))
+);
+
+CREATE INDEX idx_school_memberships_school_id ON school_memberships (school_id);
+
+-- 现有的所属学校, 以及任教班级所在的学校
+INSERT INTO school_memberships (user_id, school_id)
+SELECT id, school_id FROM users WHERE school_id IS NOT NULL;
+
+INSERT INTO school_memberships (user_id, school_id)
+SELECT DISTINCT tc.user_id, c.school_id
+FROM teacher_classes tc
+JOIN classes c ON c.id = tc.class_id
+WHERE c.deleted_at IS NULL
+ON CONFLICT (user_id, school_id) DO NOTHING;
+
+CREATE TRIGGER school_memberships_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON school_memberships
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('user_id,school_id');
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS idx_classes_campus_id;
+ALTER TABLE classes DROP COLUMN IF EXISTS campus_id;
+DROP TABLE IF EXISTS campuses;
+DROP TABLE IF EXISTS district_admins;
+DROP INDEX IF EXISTS idx_schools_district_id;
+ALTER TABLE schools DROP COLUMN IF EXISTS district_id;
+DROP TABLE IF EXISTS districts;
@@ -0,0 +1,46 @@
+-- 学区(教育局下的组织)在学校之上, 校区在学校之下, 都是可选的
+CREATE TABLE districts (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "districts_name_key" UNIQUE (name)
+);
+
+ALTER TABLE schools ADD COLUMN district_id INT REFERENCES districts(id) ON DELETE SET NULL;
+CREATE INDEX idx_schools_district_id ON schools (district_id);
+
+-- 学区管理员可以管理学区内的所有学校
+CREATE TABLE district_admins (
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    district_id INT NOT NULL REFERENCES districts(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, district_id)
+);
+
+CREATE INDEX idx_district_admins_district_id ON district_admins (district_id);
+
+CREATE TABLE campuses (
+    id SERIAL PRIMARY KEY,
+    school_id INT NOT NULL REFERENCES schools(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    address VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT "campuses_school_id_name_key" UNIQUE (school_id, name)
+);
+
+ALTER TABLE classes ADD COLUMN campus_id INT REFERENCES campuses(id) ON DELETE SET NULL;
+CREATE INDEX idx_classes_campus_id ON classes (campus_id);
+
+CREATE TRIGGER districts_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON districts
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER district_admins_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON district_admins
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('user_id,district_id');
+
+CREATE TRIGGER campuses_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON campuses
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
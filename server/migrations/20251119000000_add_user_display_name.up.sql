@@ -0,0 +1,5 @@
+-- 显示名和工号; 微信创建的用户 username 是 openid, 界面上显示 display_name
+ALTER TABLE users ADD COLUMN display_name VARCHAR(100);
+ALTER TABLE users ADD COLUMN employee_no VARCHAR(50);
+
+UPDATE users SET display_name = wechat_nickname WHERE wechat_nickname IS NOT NULL;
@@ -0,0 +1,68 @@
+DROP TRIGGER IF EXISTS school_holds_outbox_trigger ON school_holds;
+
+CREATE OR REPLACE FUNCTION enqueue_outbox_event()
+RETURNS TRIGGER AS $$
+DECLARE
+  v_aggregate TEXT;
+  v_aggregate_id INT;
+  v_school_id INT;
+  v_event_type TEXT;
+  v_data JSONB;
+  v_old_data JSONB;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    v_data := to_jsonb(OLD) - 'password';
+    -- 回收站过期后彻底删除, 软删除时已经通知过
+    IF v_data ->> 'deleted_at' IS NOT NULL THEN
+      RETURN NULL;
+    END IF;
+  ELSE
+    v_data := to_jsonb(NEW) - 'password';
+  END IF;
+  IF TG_OP = 'UPDATE' THEN
+    v_old_data := to_jsonb(OLD) - 'password';
+    -- 只有版本号和更新时间变化时不算业务变更
+    IF v_data - 'status_version' - 'classes_reset_version' - 'updated_at'
+       = v_old_data - 'status_version' - 'classes_reset_version' - 'updated_at' THEN
+      RETURN NULL;
+    END IF;
+  END IF;
+  v_aggregate := CASE TG_TABLE_NAME
+    WHEN 'classes' THEN 'class'
+    WHEN 'teacher_classes' THEN 'teacher_class'
+    WHEN 'schools' THEN 'school'
+    WHEN 'announcements' THEN 'announcement'
+    ELSE TG_TABLE_NAME
+  END;
+  v_event_type := v_aggregate || '.' || CASE TG_OP
+    WHEN 'INSERT' THEN 'created'
+    WHEN 'UPDATE' THEN 'updated'
+    ELSE 'deleted'
+  END;
+  IF TG_TABLE_NAME = 'classes' AND TG_OP = 'UPDATE' AND OLD.status IS DISTINCT FROM NEW.status THEN
+    v_event_type := 'class.status_changed';
+    v_data := v_data || jsonb_build_object('old_status', OLD.status);
+  END IF;
+  -- 软删除和恢复按删除、恢复通知
+  IF TG_OP = 'UPDATE' AND v_old_data ->> 'deleted_at' IS NULL AND v_data ->> 'deleted_at' IS NOT NULL THEN
+    v_event_type := v_aggregate || '.deleted';
+  ELSIF TG_OP = 'UPDATE' AND v_old_data ->> 'deleted_at' IS NOT NULL AND v_data ->> 'deleted_at' IS NULL THEN
+    v_event_type := v_aggregate || '.restored';
+  END IF;
+  IF TG_TABLE_NAME = 'schools' THEN
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := v_aggregate_id;
+  ELSIF TG_TABLE_NAME = 'teacher_classes' THEN
+    -- 绑定关系没有自己的 id, 归到班级下
+    v_aggregate_id := (v_data ->> 'class_id')::INT;
+    SELECT c.school_id INTO v_school_id FROM classes c WHERE c.id = v_aggregate_id;
+    v_data := v_data || jsonb_build_object('school_id', v_school_id);
+  ELSE
+    v_aggregate_id := (v_data ->> 'id')::INT;
+    v_school_id := (v_data ->> 'school_id')::INT;
+  END IF;
+  INSERT INTO outbox_events (aggregate_type, aggregate_id, school_id, event_type, payload)
+  VALUES (v_aggregate, v_aggregate_id, v_school_id, v_event_type, v_data);
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,6 @@
+DROP TRIGGER IF EXISTS dismissal_schedules_audit_trigger ON dismissal_schedules;
+DROP TRIGGER IF EXISTS academic_years_audit_trigger ON academic_years;
+DROP TRIGGER IF EXISTS teacher_assignments_audit_trigger ON teacher_assignments;
+DROP TRIGGER IF EXISTS webhook_endpoints_audit_trigger ON webhook_endpoints;
+DROP TRIGGER IF EXISTS school_holds_audit_trigger ON school_holds;
+DROP TRIGGER IF EXISTS announcements_audit_trigger ON announcements;
@@ -0,0 +1,25 @@
+-- 学校层面的配置和排班也要记录审计日志
+CREATE TRIGGER announcements_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON announcements
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id', 'published_at,expired_at');
+
+CREATE TRIGGER school_holds_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON school_holds
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+-- 签名密钥不写进审计日志
+CREATE TRIGGER webhook_endpoints_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON webhook_endpoints
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id', 'secret');
+
+CREATE TRIGGER teacher_assignments_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON teacher_assignments
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER academic_years_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON academic_years
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
+
+CREATE TRIGGER dismissal_schedules_audit_trigger
+AFTER INSERT OR UPDATE OR DELETE ON dismissal_schedules
+FOR EACH ROW EXECUTE FUNCTION audit_row_change('id');
@@ -0,0 +1,431 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::CLASS_STATUS_DISMISSED;
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::{DateTime, NaiveDate, Utc};
+use data_model::{academic_years, classes, schools, teacher_classes};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::sea_query::Query;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+/// 班级名称模板里可用的占位符
+const TEMPLATE_GRADE: &str = "{grade}";
+const TEMPLATE_CLASS: &str = "{class}";
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct AcademicYearCreatePayload {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// 设为当前学年时原来的当前学年会被取消
+    pub is_current: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct RolloverPayload {
+    /// 新学年的名称, 例如 2026-2027
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// 最高年级, 这个年级的班级毕业, 不再进入新学年
+    #[validate(range(min = 1, max = 12))]
+    pub max_grade: i32,
+    /// 新班级名称, 支持 {grade} 和 {class}; 为空时沿用原名称
+    #[validate(length(min = 1, max = 255))]
+    pub name_template: Option<String>,
+    /// 是否把教师绑定带到新班级, 默认 false
+    pub carry_over_teachers: Option<bool>,
+    /// 只返回计划, 不做任何修改
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AcademicYearInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub is_current: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RolloverClassPlan {
+    pub class_id: i32,
+    pub name: String,
+    pub grade: i32,
+    pub class: i32,
+    /// 升级后的班级, 毕业的班级为空; dry_run 时 new_class_id 为空
+    pub new_class_id: Option<i32>,
+    pub new_name: Option<String>,
+    pub new_grade: Option<i32>,
+    /// 当前绑定的教师数量
+    pub teacher_count: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RolloverPlan {
+    pub dry_run: bool,
+    pub from_year: AcademicYearInfo,
+    /// dry_run 时为空
+    pub to_year: Option<AcademicYearInfo>,
+    pub promoted: Vec<RolloverClassPlan>,
+    pub graduated: Vec<RolloverClassPlan>,
+    /// 带到新班级的教师绑定数量
+    pub carried_bindings: usize,
+    /// 从旧班级解除的教师绑定数量
+    pub removed_bindings: usize,
+}
+
+impl From<academic_years::Model> for AcademicYearInfo {
+    fn from(y: academic_years::Model) -> Self {
+        Self {
+            id: y.id,
+            school_id: y.school_id,
+            name: y.name,
+            start_date: y.start_date,
+            end_date: y.end_date,
+            is_current: y.is_current,
+            created_at: y.created_at.into(),
+        }
+    }
+}
+
+/// 班级没有学年或属于所在学校的当前学年, 大屏只显示这些班级
+pub fn in_current_year() -> Condition {
+    Condition::any()
+        .add(classes::Column::AcademicYearId.is_null())
+        .add(
+            classes::Column::AcademicYearId.in_subquery(
+                Query::select()
+                    .column(academic_years::Column::Id)
+                    .from(academic_years::Entity)
+                    .and_where(academic_years::Column::IsCurrent.eq(true))
+                    .to_owned(),
+            ),
+        )
+}
+
+/// 学校的当前学年, 没有时返回 None
+pub async fn current_year_id<C: ConnectionTrait>(db: &C, school_id: i32) -> Result<Option<i32>, DbErr> {
+    academic_years::Entity::find()
+        .select_only()
+        .column(academic_years::Column::Id)
+        .filter(academic_years::Column::SchoolId.eq(school_id))
+        .filter(academic_years::Column::IsCurrent.eq(true))
+        .into_tuple::<i32>()
+        .one(db)
+        .await
+}
+
+fn check_period(start_date: NaiveDate, end_date: NaiveDate) -> Result<(), AppError> {
+    if end_date <= start_date {
+        return Err(AppError::validation("end_date must be after start_date"));
+    }
+    Ok(())
+}
+
+async fn ensure_name_available<C: ConnectionTrait>(db: &C, school_id: i32, name: &str) -> Result<(), AppError> {
+    let existing = academic_years::Entity::find()
+        .filter(academic_years::Column::SchoolId.eq(school_id))
+        .filter(academic_years::Column::Name.eq(name))
+        .one(db)
+        .await?;
+    if existing.is_some() {
+        return Err(AppError::business_logic(
+            "ACADEMIC_YEAR_EXISTS",
+            "Academic year with this name already exists",
+        ));
+    }
+    Ok(())
+}
+
+async fn lock_school<C: ConnectionTrait>(db: &C, school_id: i32) -> Result<schools::Model, AppError> {
+    schools::Entity::find_live_by_id(school_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))
+}
+
+/// 取消原来的当前学年, 部分唯一索引要求先取消再设置
+async fn clear_current<C: ConnectionTrait>(db: &C, school_id: i32) -> Result<(), DbErr> {
+    academic_years::Entity::update_many()
+        .col_expr(academic_years::Column::IsCurrent, false.into())
+        .col_expr(academic_years::Column::UpdatedAt, Utc::now().into())
+        .filter(academic_years::Column::SchoolId.eq(school_id))
+        .filter(academic_years::Column::IsCurrent.eq(true))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+// Get academic years of a school
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<Vec<AcademicYearInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let list = academic_years::Entity::find()
+        .filter(academic_years::Column::SchoolId.eq(school_id))
+        .order_by_desc(academic_years::Column::StartDate)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(ApiResponse::success(list))
+}
+
+// Create academic year for a school
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<AcademicYearCreatePayload>,
+) -> Result<ApiResponse<AcademicYearInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let year = add_impl(&state, &audit, school_id, req).await?;
+    Ok(ApiResponse::success(year))
+}
+
+pub async fn add_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    school_id: i32,
+    req: AcademicYearCreatePayload,
+) -> Result<AcademicYearInfo, AppError> {
+    check_period(req.start_date, req.end_date)?;
+    let txn = audit::begin(state, audit).await?;
+    lock_school(&txn, school_id).await?;
+    ensure_name_available(&txn, school_id, &req.name).await?;
+    let is_current = req.is_current.unwrap_or(false);
+    if is_current {
+        clear_current(&txn, school_id).await?;
+    }
+    let year = academic_years::ActiveModel {
+        school_id: Set(school_id),
+        name: Set(req.name),
+        start_date: Set(req.start_date),
+        end_date: Set(req.end_date),
+        is_current: Set(is_current),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    Ok(year.into())
+}
+
+// Roll a school's classes over into a new academic year
+#[handler]
+pub async fn rollover(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<RolloverPayload>,
+) -> Result<ApiResponse<RolloverPlan>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let plan = rollover_impl(&state, &audit, school_id, req).await?;
+    Ok(ApiResponse::success(plan))
+}
+
+pub async fn rollover_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    school_id: i32,
+    req: RolloverPayload,
+) -> Result<RolloverPlan, AppError> {
+    check_period(req.start_date, req.end_date)?;
+    let dry_run = req.dry_run.unwrap_or(false);
+    let carry_over = req.carry_over_teachers.unwrap_or(false);
+
+    let txn = audit::begin(state, audit).await?;
+    lock_school(&txn, school_id).await?;
+    let from_year = academic_years::Entity::find()
+        .filter(academic_years::Column::SchoolId.eq(school_id))
+        .filter(academic_years::Column::IsCurrent.eq(true))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| {
+            AppError::business_logic("NO_CURRENT_ACADEMIC_YEAR", "School has no current academic year")
+        })?;
+    ensure_name_available(&txn, school_id, &req.name).await?;
+    if req.start_date <= from_year.start_date {
+        return Err(AppError::validation("new academic year must start after the current one"));
+    }
+
+    let old_classes = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(classes::Column::AcademicYearId.eq(from_year.id))
+        .order_by_asc(classes::Column::Grade)
+        .order_by_asc(classes::Column::Class)
+        .all(&txn)
+        .await?;
+    let old_class_ids: Vec<i32> = old_classes.iter().map(|c| c.id).collect();
+    let bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.is_in(old_class_ids.clone()))
+        .all(&txn)
+        .await?;
+    let mut bindings_by_class: HashMap<i32, Vec<&teacher_classes::Model>> = HashMap::new();
+    for binding in &bindings {
+        bindings_by_class.entry(binding.class_id).or_default().push(binding);
+    }
+
+    let mut promoted = Vec::new();
+    let mut graduated = Vec::new();
+    for class in &old_classes {
+        let teacher_count = bindings_by_class.get(&class.id).map_or(0, Vec::len);
+        let mut plan = RolloverClassPlan {
+            class_id: class.id,
+            name: class.name.clone(),
+            grade: class.grade,
+            class: class.class,
+            new_class_id: None,
+            new_name: None,
+            new_grade: None,
+            teacher_count,
+        };
+        if class.grade >= req.max_grade {
+            graduated.push(plan);
+            continue;
+        }
+        let new_grade = class.grade + 1;
+        plan.new_name = Some(match req.name_template.as_deref() {
+            Some(template) => template
+                .replace(TEMPLATE_GRADE, &new_grade.to_string())
+                .replace(TEMPLATE_CLASS, &class.class.to_string()),
+            None => class.name.clone(),
+        });
+        plan.new_grade = Some(new_grade);
+        promoted.push(plan);
+    }
+    let carried_bindings = if carry_over {
+        promoted.iter().map(|p| p.teacher_count).sum()
+    } else {
+        0
+    };
+
+    if dry_run {
+        txn.rollback().await?;
+        return Ok(RolloverPlan {
+            dry_run,
+            from_year: from_year.into(),
+            to_year: None,
+            promoted,
+            graduated,
+            carried_bindings,
+            removed_bindings: bindings.len(),
+        });
+    }
+
+    clear_current(&txn, school_id).await?;
+    let to_year = academic_years::ActiveModel {
+        school_id: Set(school_id),
+        name: Set(req.name),
+        start_date: Set(req.start_date),
+        end_date: Set(req.end_date),
+        is_current: Set(true),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    // 旧学年的班级不再有任教教师, 同时会结束对应的任课安排
+    teacher_classes::Entity::delete_many()
+        .filter(teacher_classes::Column::ClassId.is_in(old_class_ids))
+        .exec(&txn)
+        .await?;
+
+    let old_by_id: HashMap<i32, &classes::Model> = old_classes.iter().map(|c| (c.id, c)).collect();
+    for plan in promoted.iter_mut() {
+        let old = old_by_id[&plan.class_id];
+        let new_class = classes::ActiveModel {
+            name: Set(plan.new_name.clone().unwrap_or_default()),
+            grade: Set(plan.new_grade.unwrap_or(old.grade)),
+            class: Set(old.class),
+            school_id: Set(school_id),
+            campus_id: Set(old.campus_id),
+            status: Set(CLASS_STATUS_DISMISSED),
+            password: Set(old.password.clone()),
+            academic_year_id: Set(Some(to_year.id)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        plan.new_class_id = Some(new_class.id);
+        if !carry_over {
+            continue;
+        }
+        let carried: Vec<teacher_classes::ActiveModel> = bindings_by_class
+            .get(&old.id)
+            .into_iter()
+            .flatten()
+            .map(|tc| teacher_classes::ActiveModel {
+                user_id: Set(tc.user_id),
+                class_id: Set(new_class.id),
+                role: Set(tc.role.clone()),
+                ..Default::default()
+            })
+            .collect();
+        if !carried.is_empty() {
+            teacher_classes::Entity::insert_many(carried).exec(&txn).await?;
+        }
+    }
+
+    // 旧学年的班级从大屏消失, 提升重建版本号让客户端整体替换班级列表
+    txn.execute(Statement::from_sql_and_values(
+        txn.get_database_backend(),
+        "UPDATE schools SET status_version = v, classes_reset_version = v \
+         FROM (SELECT nextval('class_status_version_seq') AS v) AS seq WHERE id = $1",
+        [school_id.into()],
+    ))
+    .await?;
+    txn.commit().await?;
+    tracing::info!(
+        "School {} rolled over from {} to {}: {} promoted, {} graduated",
+        school_id,
+        from_year.name,
+        to_year.name,
+        promoted.len(),
+        graduated.len()
+    );
+
+    Ok(RolloverPlan {
+        dry_run,
+        from_year: AcademicYearInfo {
+            is_current: false,
+            ..from_year.into()
+        },
+        to_year: Some(to_year.into()),
+        promoted,
+        graduated,
+        carried_bindings,
+        removed_bindings: bindings.len(),
+    })
+}
@@ -0,0 +1,418 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::school_api::ensure_school_access;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::error::AppError;
+use crate::utils::convert::{from_str_optional, nullable};
+use crate::core::response::ApiResponse;
+use chrono::{DateTime, Utc};
+use data_model::announcements;
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+const SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+/// 与放学时间表的年级范围一致
+const MAX_GRADE: i32 = 12;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct AnnouncementCreatePayload {
+    pub school_id: i32,
+    #[validate(length(min = 1, max = 2000))]
+    pub content: String,
+    pub severity: Option<String>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub target_grades: Option<Vec<i32>>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct AnnouncementUpdatePayload {
+    #[validate(length(min = 1, max = 2000))]
+    pub content: Option<String>,
+    pub severity: Option<String>,
+    pub start_at: Option<DateTime<Utc>>,
+    /// 不传表示不修改, null 表示取消结束时间
+    #[serde(default, deserialize_with = "nullable")]
+    pub end_at: Option<Option<DateTime<Utc>>>,
+    pub target_grades: Option<Vec<i32>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct AnnouncementInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub content: String,
+    pub severity: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: Option<DateTime<Utc>>,
+    /// 为空表示全校
+    pub target_grades: Vec<i32>,
+    pub created_by: Option<i32>,
+    pub expired: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchAnnouncementsParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    pub severity: Option<String>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub active: Option<bool>,
+}
+
+impl From<announcements::Model> for AnnouncementInfo {
+    fn from(a: announcements::Model) -> Self {
+        let target_grades = a
+            .target_grades
+            .and_then(|v| serde_json::from_value::<Vec<i32>>(v).ok())
+            .unwrap_or_default();
+        Self {
+            id: a.id,
+            school_id: a.school_id,
+            content: a.content,
+            severity: a.severity,
+            start_at: a.start_at.into(),
+            end_at: a.end_at.map(Into::into),
+            target_grades,
+            created_by: a.created_by,
+            expired: a.expired_at.is_some(),
+            created_at: a.created_at.into(),
+        }
+    }
+}
+
+fn check_severity(severity: &str) -> Result<(), AppError> {
+    if !SEVERITIES.contains(&severity) {
+        return Err(AppError::validation(format!("invalid severity: {}", severity)));
+    }
+    Ok(())
+}
+
+fn check_target_grades(grades: &[i32]) -> Result<(), AppError> {
+    if let Some(grade) = grades.iter().find(|grade| !(1..=MAX_GRADE).contains(*grade)) {
+        return Err(AppError::validation(format!("invalid grade: {}", grade)));
+    }
+    Ok(())
+}
+
+fn check_time_range(start_at: DateTime<Utc>, end_at: Option<DateTime<Utc>>) -> Result<(), AppError> {
+    if end_at.is_some_and(|end_at| end_at <= start_at) {
+        return Err(AppError::validation("end_at must be later than start_at"));
+    }
+    Ok(())
+}
+
+/// 当前正在生效的公告: 已开始, 未结束, 未被后台任务标记过期
+fn active_condition(now: DateTime<Utc>) -> Condition {
+    Condition::all()
+        .add(announcements::Column::StartAt.lte(now))
+        .add(
+            Condition::any()
+                .add(announcements::Column::EndAt.is_null())
+                .add(announcements::Column::EndAt.gt(now)),
+        )
+        .add(announcements::Column::ExpiredAt.is_null())
+}
+
+fn is_active(a: &announcements::Model, now: DateTime<Utc>) -> bool {
+    a.expired_at.is_none() && a.start_at <= now && a.end_at.is_none_or(|end_at| end_at > now)
+}
+
+// Create Announcement
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    req: JsonBody<AnnouncementCreatePayload>,
+) -> Result<ApiResponse<AnnouncementInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    ensure_school_access(&state, claims, req.school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let announcement = add_impl(&state, &audit, claims.user_id, req).await?;
+    Ok(ApiResponse::success(announcement))
+}
+
+pub async fn add_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    actor_id: i32,
+    req: AnnouncementCreatePayload,
+) -> Result<AnnouncementInfo, AppError> {
+    let now = Utc::now();
+    let severity = req.severity.unwrap_or_else(|| "info".to_string());
+    check_severity(&severity)?;
+    let start_at = req.start_at.unwrap_or(now);
+    check_time_range(start_at, req.end_at)?;
+    if let Some(grades) = &req.target_grades {
+        check_target_grades(grades)?;
+    }
+
+    let new_announcement = announcements::ActiveModel {
+        school_id: Set(req.school_id),
+        content: Set(req.content),
+        severity: Set(severity),
+        start_at: Set(start_at.into()),
+        end_at: Set(req.end_at.map(Into::into)),
+        target_grades: Set(req.target_grades.map(|grades| serde_json::json!(grades))),
+        created_by: Set(Some(actor_id)),
+        ..Default::default()
+    };
+    let txn = audit::begin(state, audit).await?;
+    let announcement = new_announcement.insert(&txn).await?;
+    let announcement = publish_if_active(&txn, announcement, now).await?;
+    txn.commit().await?;
+    Ok(announcement.into())
+}
+
+// Update Announcement
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<AnnouncementUpdatePayload>,
+) -> Result<ApiResponse<AnnouncementInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let announcement = find_accessible(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let announcement = update_impl(&state, &audit, announcement, req).await?;
+    Ok(ApiResponse::success(announcement))
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    announcement: announcements::Model,
+    req: AnnouncementUpdatePayload,
+) -> Result<AnnouncementInfo, AppError> {
+    let now = Utc::now();
+    let start_at = req.start_at.unwrap_or_else(|| announcement.start_at.into());
+    let end_at = match req.end_at {
+        Some(end_at) => end_at,
+        None => announcement.end_at.map(Into::into),
+    };
+    check_time_range(start_at, end_at)?;
+
+    let mut active_model: announcements::ActiveModel = announcement.into();
+    if let Some(content) = req.content {
+        active_model.content = Set(content);
+    }
+    if let Some(severity) = req.severity {
+        check_severity(&severity)?;
+        active_model.severity = Set(severity);
+    }
+    if let Some(grades) = req.target_grades {
+        check_target_grades(&grades)?;
+        active_model.target_grades = Set(Some(serde_json::json!(grades)));
+    }
+    active_model.start_at = Set(start_at.into());
+    active_model.end_at = Set(end_at.map(Into::into));
+    // 修改时间后重新走一遍推送/过期流程
+    active_model.published_at = Set(None);
+    active_model.expired_at = Set(None);
+    active_model.updated_at = Set(now.into());
+    let txn = audit::begin(state, audit).await?;
+    let announcement = active_model.update(&txn).await?;
+    let announcement = publish_if_active(&txn, announcement, now).await?;
+    txn.commit().await?;
+    Ok(announcement.into())
+}
+
+/// 已经生效的公告立即标记推送, 还没到开始时间的交给后台任务; 大屏通过 outbox 收到公告的变化
+async fn publish_if_active<C: ConnectionTrait>(
+    db: &C,
+    announcement: announcements::Model,
+    now: DateTime<Utc>,
+) -> Result<announcements::Model, AppError> {
+    if !is_active(&announcement, now) {
+        return Ok(announcement);
+    }
+    let mut active_model: announcements::ActiveModel = announcement.into();
+    active_model.published_at = Set(Some(now.into()));
+    let announcement = active_model.update(db).await?;
+    Ok(announcement)
+}
+
+// Delete Announcement
+#[handler]
+pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let announcement = find_accessible(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    delete_impl(&state, &audit, announcement).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn delete_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    announcement: announcements::Model,
+) -> Result<(), AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let _ = announcement.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// Get Announcement by ID
+#[handler]
+pub async fn get_by_id(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<AnnouncementInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let announcement = find_accessible(&state, claims, id.into_inner()).await?;
+    Ok(ApiResponse::success(announcement.into()))
+}
+
+async fn find_accessible(
+    state: &AppState,
+    claims: &Claims,
+    id: i32,
+) -> Result<announcements::Model, AppError> {
+    let announcement = announcements::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("announcements".to_string(), Some(id)))?;
+    ensure_school_access(state, claims, announcement.school_id).await?;
+    Ok(announcement)
+}
+
+// Get Announcements List
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<AnnouncementInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let params = req.parse_queries::<SearchAnnouncementsParams>()?;
+    match params.school_id {
+        Some(school_id) => ensure_school_access(&state, claims, school_id).await?,
+        None if !claims.is_admin() => {
+            return Err(AppError::validation("school_id is required"));
+        }
+        None => {}
+    }
+    let list = get_list_impl(&state, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    params: SearchAnnouncementsParams,
+) -> Result<PagingResponse<AnnouncementInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+
+    let mut query = announcements::Entity::find();
+    crate::filter_if_some!(query, announcements::Column::SchoolId, params.school_id, eq);
+    crate::filter_if_some!(query, announcements::Column::Severity, params.severity, eq);
+    if params.active == Some(true) {
+        query = query.filter(active_condition(Utc::now()));
+    }
+
+    let paginator = query
+        .order_by_desc(announcements::Column::Id)
+        .paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let list = paginator
+        .fetch_page(page - 1)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(PagingResponse { list, total, page })
+}
+
+// Get active announcements of a school (public, used by screens)
+#[handler]
+pub async fn get_active_by_school_id(
+    depot: &mut Depot,
+    school_id: PathParam<i32>,
+) -> Result<ApiResponse<Vec<AnnouncementInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let list = get_active_impl(&state, school_id.into_inner()).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_active_impl(state: &AppState, school_id: i32) -> Result<Vec<AnnouncementInfo>, AppError> {
+    let list = announcements::Entity::find()
+        .filter(announcements::Column::SchoolId.eq(school_id))
+        .filter(active_condition(Utc::now()))
+        .order_by_asc(announcements::Column::StartAt)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(list)
+}
+
+/// 多个学校当前生效的公告, 一次查询
+pub async fn get_active_by_school_ids(
+    state: &AppState,
+    school_ids: Vec<i32>,
+) -> Result<Vec<AnnouncementInfo>, AppError> {
+    if school_ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let list = announcements::Entity::find()
+        .filter(announcements::Column::SchoolId.is_in(school_ids))
+        .filter(active_condition(Utc::now()))
+        .order_by_asc(announcements::Column::StartAt)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(list)
+}
+
+/// 后台任务: 推送到达开始时间的公告, 标记并撤下已经结束的公告
+pub async fn sync_announcements(state: &AppState) -> Result<(), AppError> {
+    let now = Utc::now();
+
+    let expired = announcements::Entity::find()
+        .filter(announcements::Column::ExpiredAt.is_null())
+        .filter(announcements::Column::EndAt.lte(now))
+        .all(&state.db)
+        .await?;
+    if !expired.is_empty() {
+        let ids: Vec<i32> = expired.iter().map(|a| a.id).collect();
+        announcements::Entity::update_many()
+            .col_expr(announcements::Column::ExpiredAt, Expr::value(now))
+            .filter(announcements::Column::Id.is_in(ids))
+            .exec(&state.db)
+            .await?;
+        tracing::info!("Expired {} announcements", expired.len());
+    }
+
+    let due = announcements::Entity::find()
+        .filter(active_condition(now))
+        .filter(announcements::Column::PublishedAt.is_null())
+        .all(&state.db)
+        .await?;
+    if !due.is_empty() {
+        let ids: Vec<i32> = due.iter().map(|a| a.id).collect();
+        announcements::Entity::update_many()
+            .col_expr(announcements::Column::PublishedAt, Expr::value(now))
+            .filter(announcements::Column::Id.is_in(ids))
+            .exec(&state.db)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::utils::convert::{from_str_optional, local_day_start};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use data_model::{audit_logs, users};
+use salvo::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const AUDIT_ACTIONS: [&str; 3] = ["create", "update", "delete"];
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchAuditLogsParams {
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub actor_id: Option<i32>,
+    /// 表名, 例如 schools, users, teacher_classes
+    pub entity_type: Option<String>,
+    /// 联合主键用 ':' 连接, 例如 teacher_classes 为 "user_id:class_id"
+    pub entity_id: Option<String>,
+    /// create, update, delete
+    pub action: Option<String>,
+    /// 按本地日期, 包含这一天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub from: Option<NaiveDate>,
+    /// 按本地日期, 包含这一天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub to: Option<NaiveDate>,
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuditLogInfo {
+    pub id: i64,
+    pub actor_id: Option<i32>,
+    pub actor_name: Option<String>,
+    pub ip: Option<String>,
+    pub route: Option<String>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    /// 更新时只包含变化的字段
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Search audit records (admin only)
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<AuditLogInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    if !claims.is_admin() {
+        return Err(AppError::forbidden("view audit logs".to_string()));
+    }
+    let params = req.parse_queries::<SearchAuditLogsParams>()?;
+    let list = get_list_impl(&state, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    params: SearchAuditLogsParams,
+) -> Result<PagingResponse<AuditLogInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    if let Some(action) = &params.action
+        && !AUDIT_ACTIONS.contains(&action.as_str())
+    {
+        return Err(AppError::validation(format!("invalid action: {}", action)));
+    }
+
+    let mut query = audit_logs::Entity::find();
+    crate::filter_if_some!(query, audit_logs::Column::ActorId, params.actor_id, eq);
+    crate::filter_if_some!(query, audit_logs::Column::EntityType, params.entity_type, eq);
+    crate::filter_if_some!(query, audit_logs::Column::EntityId, params.entity_id, eq);
+    crate::filter_if_some!(query, audit_logs::Column::Action, params.action, eq);
+    if let Some(from) = params.from {
+        query = query.filter(audit_logs::Column::CreatedAt.gte(local_day_start(from)));
+    }
+    if let Some(to) = params.to {
+        query = query.filter(audit_logs::Column::CreatedAt.lt(local_day_start(to + Duration::days(1))));
+    }
+    let paginator = query
+        .order_by_desc(audit_logs::Column::Id)
+        .paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let records = paginator.fetch_page(page - 1).await?;
+
+    let actor_ids: Vec<i32> = records.iter().filter_map(|r| r.actor_id).collect();
+    let users_map: HashMap<i32, String> = if actor_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find()
+            .filter(users::Column::Id.is_in(actor_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, display_name(&u)))
+            .collect()
+    };
+
+    let list = records
+        .into_iter()
+        .map(|r| AuditLogInfo {
+            id: r.id,
+            actor_id: r.actor_id,
+            actor_name: r.actor_id.and_then(|id| users_map.get(&id).cloned()),
+            ip: r.ip,
+            route: r.route,
+            action: r.action,
+            entity_type: r.entity_type,
+            entity_id: r.entity_id,
+            before: r.before_data,
+            after: r.after_data,
+            created_at: r.created_at.into(),
+        })
+        .collect();
+    Ok(PagingResponse { list, total, page })
+}
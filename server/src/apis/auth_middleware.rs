@@ -1,91 +1,117 @@
-use crate::core::app::AppState;
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use crate::apis::permission_api;
-use serde::{Deserialize, Serialize};
-use salvo::prelude::*;
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Claims {
-    pub user_id: i32,
-    pub role_ids: Vec<i32>,
-    pub exp: usize,
-}
-
-
-#[handler]
-pub async fn permission_handler(
-    req: &mut Request,
-    depot: &mut Depot,
-    res: &mut Response,
-    ctrl: &mut FlowCtrl,
-)->Result<(), StatusCode> {
- let state=match depot.obtain::<AppState>() {
-        Ok(s) => s,
-        Err(_) => {
-            // res.status_code = Some(StatusCode::INTERNAL_SERVER_ERROR);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-    let claims=match depot.obtain::<Claims>() {
-        Ok(c) => c,
-        Err(_) => {
-            return Err(StatusCode::UNAUTHORIZED);
-        }
-    };
-    let method=req.method().clone();
-    let path=req.uri().path().to_string();
-    let result=permission_api::check_path_permission_and_cache(state, claims.user_id, claims.role_ids.clone(), method.as_str(), path.as_str()).await;
-    let _=match result {
-        Ok(result) => result,
-        Err(_) => return Err(StatusCode::FORBIDDEN),
-    };
-    ctrl.call_next(req, depot, res).await;
-    Ok(())
-}
-
-
-#[handler]
-pub async fn auth(
-    req: &mut Request,
-    depot:&mut Depot,
-) -> Result<(), StatusCode>{
-    let state = depot.obtain::<AppState>().unwrap();
-    let token = req
-        .headers()
-        .get("Authorization")
-        .and_then(|auth_header| auth_header.to_str().ok())
-        .and_then(|auth_value| {
-            if auth_value.starts_with("Bearer ") {
-                Some(auth_value[7..].to_owned())
-            } else {
-                None
-            }
-        });
-
-    let token = token.ok_or(StatusCode::UNAUTHORIZED)?;
-    let decoded = decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(state.config.jwt.secret.as_ref()),
-        &Validation::default(),
-    )
-    .map_err(|_| StatusCode::UNAUTHORIZED)?;
-    depot.inject(decoded.claims);
-    Ok(())
-}
-
-#[handler]
-pub async fn error_handler(
-    req: &mut Request,
-    depot: &mut Depot,
-    res: &mut Response,
-    ctrl: &mut FlowCtrl,
-){
-    // 先放行到下游处理
-    ctrl.call_next(req, depot, res).await;
-    if let Some(code) = res.status_code{
-        if code.as_u16() >= 400 {
-            tracing::error!("Response status: {}", code);
-        }
-    }
-    // ctrl.call_next(req, depot, res).await;
+use crate::core::app::AppState;
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use crate::apis::permission_api;
+use crate::core::constants::{ADMIN_ROLE_ID, CURRENT_SCHOOL_HEADER};
+use crate::core::soft_delete::SoftDelete;
+use data_model::users;
+use sea_orm::PaginatorTrait;
+use serde::{Deserialize, Serialize};
+use salvo::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Claims {
+    pub user_id: i32,
+    pub role_ids: Vec<i32>,
+    /// 当前学校, 由 POST /api/admin/me/school 写入令牌, 或请求时用 X-School-Id 头指定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub school_id: Option<i32>,
+    pub exp: usize,
+}
+
+impl Claims {
+    pub fn is_admin(&self) -> bool {
+        self.role_ids.contains(&ADMIN_ROLE_ID)
+    }
+}
+
+
+#[handler]
+pub async fn permission_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+)->Result<(), StatusCode> {
+ let state=match depot.obtain::<AppState>() {
+        Ok(s) => s,
+        Err(_) => {
+            // res.status_code = Some(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let claims=match depot.obtain::<Claims>() {
+        Ok(c) => c,
+        Err(_) => {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+    let method=req.method().clone();
+    let path=req.uri().path().to_string();
+    let result=permission_api::check_path_permission_and_cache(state, claims.user_id, claims.role_ids.clone(), method.as_str(), path.as_str()).await;
+    let _=match result {
+        Ok(result) => result,
+        Err(_) => return Err(StatusCode::FORBIDDEN),
+    };
+    ctrl.call_next(req, depot, res).await;
+    Ok(())
+}
+
+
+#[handler]
+pub async fn auth(
+    req: &mut Request,
+    depot:&mut Depot,
+) -> Result<(), StatusCode>{
+    let state = depot.obtain::<AppState>().unwrap();
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|auth_header| auth_header.to_str().ok())
+        .and_then(|auth_value| {
+            if auth_value.starts_with("Bearer ") {
+                Some(auth_value[7..].to_owned())
+            } else {
+                None
+            }
+        });
+
+    let token = token.ok_or(StatusCode::UNAUTHORIZED)?;
+    let decoded = decode::<Claims>(
+        &token,
+        &DecodingKey::from_secret(state.config.jwt.secret.as_ref()),
+        &Validation::default(),
+    )
+    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let mut claims = decoded.claims;
+    // 已删除的用户即使令牌还没过期也不能继续使用
+    let live = users::Entity::find_live_by_id(claims.user_id)
+        .count(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if live == 0 {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    // 是否属于该学校由使用当前学校的接口检查
+    if let Some(school_id) = req.header::<i32>(CURRENT_SCHOOL_HEADER) {
+        claims.school_id = Some(school_id);
+    }
+    depot.inject(claims);
+    Ok(())
+}
+
+#[handler]
+pub async fn error_handler(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+    ctrl: &mut FlowCtrl,
+){
+    // 先放行到下游处理
+    ctrl.call_next(req, depot, res).await;
+    if let Some(code) = res.status_code{
+        if code.as_u16() >= 400 {
+            tracing::error!("Response status: {}", code);
+        }
+    }
+    // ctrl.call_next(req, depot, res).await;
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::{DateTime, Utc};
+use data_model::{campuses, classes, schools};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct CampusCreatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(max = 255))]
+    pub address: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct CampusUpdatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(length(max = 255))]
+    pub address: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CampusInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub name: String,
+    pub address: Option<String>,
+    /// 校区里未删除的班级
+    pub class_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+fn map_name_violation(err: DbErr) -> AppError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::business_logic("CAMPUS_ALREADY_EXISTS", "A campus with this name already exists in this school")
+        }
+        _ => err.into(),
+    }
+}
+
+/// 班级只能放在所在学校的校区里
+pub async fn ensure_campus_in_school<C: ConnectionTrait>(db: &C, campus_id: i32, school_id: i32) -> Result<(), AppError> {
+    let campus = campuses::Entity::find_by_id(campus_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("campuses".to_string(), Some(campus_id)))?;
+    if campus.school_id != school_id {
+        return Err(AppError::validation("campus does not belong to the school of this class"));
+    }
+    Ok(())
+}
+
+async fn find_campus(state: &AppState, claims: &Claims, id: i32) -> Result<campuses::Model, AppError> {
+    let campus = campuses::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("campuses".to_string(), Some(id)))?;
+    ensure_school_access(state, claims, campus.school_id).await?;
+    Ok(campus)
+}
+
+// Get campuses of a school
+#[handler]
+pub async fn get_list(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<Vec<CampusInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let list = campuses::Entity::find()
+        .filter(campuses::Column::SchoolId.eq(school_id))
+        .order_by_asc(campuses::Column::Id)
+        .all(&state.db)
+        .await?;
+    let class_counts: HashMap<i32, i64> = classes::Entity::find_live()
+        .select_only()
+        .column(classes::Column::CampusId)
+        .column_as(Expr::col(classes::Column::Id).count(), "count")
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(classes::Column::CampusId.is_not_null())
+        .group_by(classes::Column::CampusId)
+        .into_tuple::<(i32, i64)>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+    let list = list
+        .into_iter()
+        .map(|c| CampusInfo {
+            class_count: class_counts.get(&c.id).copied().unwrap_or(0),
+            id: c.id,
+            school_id: c.school_id,
+            name: c.name,
+            address: c.address,
+            created_at: c.created_at.into(),
+        })
+        .collect();
+    Ok(ApiResponse::success(list))
+}
+
+// Create campus for a school
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<CampusCreatePayload>,
+) -> Result<ApiResponse<campuses::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    schools::Entity::find_live_by_id(school_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    let campus = campuses::ActiveModel {
+        school_id: Set(school_id),
+        name: Set(req.name),
+        address: Set(req.address),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(map_name_violation)?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(campus))
+}
+
+// Update campus
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<CampusUpdatePayload>,
+) -> Result<ApiResponse<campuses::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let campus = find_campus(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    let mut active: campuses::ActiveModel = campus.into();
+    if let Some(name) = req.name {
+        active.name = Set(name);
+    }
+    if let Some(address) = req.address {
+        active.address = Set(Some(address));
+    }
+    active.updated_at = Set(Utc::now().into());
+    let campus = active.update(&txn).await.map_err(map_name_violation)?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(campus))
+}
+
+// Delete campus, its classes stay in the school without a campus
+#[handler]
+pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let campus = find_campus(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    campus.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
@@ -1,387 +1,1358 @@
-use crate::apis::list_api::{ListParamsReq, PagingResponse};
-use crate::core::app::AppState;
-use crate::core::error::AppError;
-use crate::core::response::ApiResponse;
-use crate::utils::convert::from_str_optional;
-use data_model::{classes, schools, teacher_classes, users};
-use salvo::{oapi::extract::*, prelude::*};
-use sea_orm::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use validator::Validate;
-use crate::apis::auth_middleware::Claims;
-
-#[derive(Deserialize, Debug, Validate, ToSchema)]
-pub struct ClassCreatePayload {
-    #[validate(length(min = 1, max = 255))]
-    pub name: String,
-    pub grade: i32,
-    pub class: i32,
-    pub school_id: i32,
-    pub status: Option<i32>,
-    #[validate(length(max = 255))]
-    pub password: Option<String>,
-}
-
-#[derive(Deserialize, Debug, Validate, ToSchema)]
-pub struct ClassUpdatePayload {
-    #[validate(length(min = 1, max = 255))]
-    pub name: Option<String>,
-    pub grade: Option<i32>,
-    pub class: Option<i32>,
-    pub school_id: Option<i32>,
-    pub status: Option<i32>,
-    #[validate(length(max = 255))]
-    pub password: Option<String>,
-}
-
-
-#[derive(Deserialize, Debug, Validate, ToSchema)]
-pub struct ClassBulkCreatePayload {
-    pub classes: Vec<ClassCreatePayload>,
-}
-
-#[derive(Deserialize, Serialize, Debug, ToSchema)]
-pub struct UserClassInfo {
-    pub user_id: i32,
-    pub user_name: String,
-}
-
-#[derive(Deserialize, Serialize, Debug, ToSchema)]
-pub struct ClassInfo {
-    pub id: i32,
-    pub name: String,
-    pub grade: i32,
-    pub class: i32,
-    pub school_id: i32,
-    pub school_name: String,
-    pub status: i32,
-    pub password: String,
-    pub teacher_infos: Vec<UserClassInfo>,
-}
-
-
-#[derive(Deserialize, Debug,Serialize, Validate, ToSchema)]
-pub struct ClassSimpleInfo{
-    pub id: i32,
-    pub name: String,
-    pub grade: i32,
-    pub class: i32,
-    pub school_id: i32,
-    pub status: i32,
-}
-
-#[derive(Deserialize, Debug, Default)]
-pub struct SearchClassesParams {
-    #[serde(flatten)]
-    pub pagination: ListParamsReq,
-    pub name: Option<String>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub school_id: Option<i32>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub grade: Option<i32>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub class: Option<i32>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub id: Option<i32>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub status: Option<i32>,
-}
-
-#[derive(Deserialize, Debug, Validate, ToSchema)]
-pub struct ClassStatusUpdatePayload {
-    pub status: i32,
-}
-
-// Create Class
-#[handler]
-pub async fn add(
-    depot: &mut Depot,
-    req: JsonBody<ClassCreatePayload>,
-) -> Result<ApiResponse<classes::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let entity = add_impl(&state, req.into_inner()).await?;
-    Ok(ApiResponse::success(entity))
-}
-
-#[handler]
-pub async fn add_bulk(
-    depot: &mut Depot,
-    req: JsonBody<ClassBulkCreatePayload>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let new_classes: Vec<classes::ActiveModel> = req
-        .classes
-        .iter()
-        .map(|c| classes::ActiveModel {
-            name: Set(c.name.clone()),
-            grade: Set(c.grade),
-            class: Set(c.class),
-            school_id: Set(c.school_id),
-            status: Set(c.status.unwrap_or(0)),
-            password: Set(c.password.clone().unwrap_or("".to_string())),
-            ..Default::default()
-        })
-        .collect();
-
-    classes::Entity::insert_many(new_classes).exec(&state.db).await?;
-    Ok(ApiResponse::success(()))
-}
-
-pub async fn add_impl(state: &AppState, req: ClassCreatePayload) -> Result<classes::Model, AppError> {
-    let new_class = classes::ActiveModel {
-        name: Set(req.name),
-        grade: Set(req.grade),
-        class: Set(req.class),
-        school_id: Set(req.school_id),
-        status: Set(req.status.unwrap_or(0)),
-        password: Set(req.password.unwrap_or("".to_string())),
-        ..Default::default()
-    };
-    let class = new_class.insert(&state.db).await?;
-    Ok(class)
-}
-
-// Update Class
-#[handler]
-pub async fn update(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-    req: JsonBody<ClassUpdatePayload>,
-) -> Result<ApiResponse<classes::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let class = update_impl(&state, id.into_inner(), req.into_inner()).await?;
-    Ok(ApiResponse::success(class))
-}
-
-pub async fn update_impl(
-    state: &AppState,
-    id: i32,
-    req: ClassUpdatePayload,
-) -> Result<classes::Model, AppError> {
-    let class = classes::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
-
-    let mut class_active_model: classes::ActiveModel = class.into();
-
-    if let Some(name) = req.name {
-        class_active_model.name = Set(name);
-    }
-    if let Some(grade) = req.grade {
-        class_active_model.grade = Set(grade);
-    }
-    if let Some(class) = req.class {
-        class_active_model.class = Set(class);
-    }
-    if let Some(school_id) = req.school_id {
-        class_active_model.school_id = Set(school_id);
-    }
-    if let Some(status) = req.status {
-        class_active_model.status = Set(status);
-    }
-    if let Some(password) = req.password {
-        class_active_model.password = Set(password);
-    }
-
-    let class = class_active_model.update(&state.db).await?;
-    Ok(class)
-}
-
-// Delete Class
-#[handler]
-pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    delete_impl(&state, id.into_inner()).await?;
-    Ok(ApiResponse::success(()))
-}
-
-pub async fn delete_impl(state: &AppState, id: i32) -> Result<(), AppError> {
-    let class = classes::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
-    let _ = class.delete(&state.db).await?;
-    Ok(())
-}
-
-// Get Classes List
-#[handler]
-pub async fn get_list(
-    depot: &mut Depot,
-    req: &mut Request,
-) -> Result<ApiResponse<PagingResponse<ClassInfo>>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let params = req.parse_queries::<SearchClassesParams>()?;
-    let list = get_list_impl(&state, params).await?;
-    Ok(ApiResponse::success(list))
-}
-
-async fn enrich_classes_with_details(
-    state: &AppState,
-    class_models: Vec<classes::Model>,
-) -> Result<Vec<ClassInfo>, AppError> {
-    if class_models.is_empty() {
-        return Ok(vec![]);
-    }
-    let class_ids: Vec<i32> = class_models.iter().map(|c| c.id).collect();
-
-    let school_ids: Vec<i32> = class_models.iter().map(|c| c.school_id).collect();
-    let schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
-        HashMap::new()
-    } else {
-        schools::Entity::find()
-            .filter(schools::Column::Id.is_in(school_ids.clone()))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|s| (s.id, s))
-            .collect()
-    };
-
-    let teacher_classes_list = teacher_classes::Entity::find()
-        .filter(teacher_classes::Column::ClassId.is_in(class_ids.clone()))
-        .all(&state.db)
-        .await?;
-    
-    let user_ids: Vec<i32> = teacher_classes_list.iter().map(|tc| tc.user_id).collect();
-
-    let users_map: HashMap<i32, users::Model> = if user_ids.is_empty() {
-        HashMap::new()
-    } else {
-        users::Entity::find()
-            .filter(users::Column::Id.is_in(user_ids))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|u| (u.id, u))
-            .collect()
-    };
-
-    let list = class_models
-        .into_iter()
-        .map(|class| {
-            let teacher_infos: Vec<UserClassInfo> = teacher_classes_list
-                .iter()
-                .filter(|tc| tc.class_id == class.id)
-                .filter_map(|tc| {
-                    users_map.get(&tc.user_id).map(|u| UserClassInfo {
-                        user_id: u.id,
-                        user_name: u.username.clone(),
-                    })
-                })
-                .collect();
-            
-            let school_name = schools_map.get(&class.school_id).map(|s| s.name.clone()).unwrap_or_default();
-            ClassInfo {
-                id: class.id,
-                name: class.name,
-                grade: class.grade,
-                class: class.class,
-                school_id: class.school_id,
-                school_name,
-                status: class.status,
-                password: class.password,
-                teacher_infos,
-            }
-        })
-        .collect();
-
-    Ok(list)
-}
-
-pub async fn get_list_impl(
-    state: &AppState,
-    params: SearchClassesParams,
-) -> Result<PagingResponse<ClassInfo>, AppError> {
-    let page = params.pagination.page.unwrap_or(1);
-    let page_size = params.pagination.page_size.unwrap_or(20);
-
-    let mut query = classes::Entity::find();
-
-    crate::filter_if_some!(query, classes::Column::Id, params.id, eq);
-    crate::filter_if_some!(query, classes::Column::Name, params.name, like);
-    crate::filter_if_some!(query, classes::Column::SchoolId, params.school_id, eq);
-    crate::filter_if_some!(query, classes::Column::Grade, params.grade, eq);
-    crate::filter_if_some!(query, classes::Column::Class, params.class, eq);
-    crate::filter_if_some!(query, classes::Column::Status, params.status, eq);
-
-    let paginator = query.paginate(&state.db, page_size);
-    let total = paginator.num_items().await?;
-    let class_models = paginator.fetch_page(page - 1).await?;
-    
-    let list = enrich_classes_with_details(state, class_models).await?;
-    Ok(PagingResponse { list, total, page })
-}
-
-#[handler]
-pub async fn get_all_class_by_school_id(
-    depot: &mut Depot,
-    school_id: PathParam<i32>,
-) -> Result<ApiResponse<Vec<ClassSimpleInfo>>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let school_id = school_id.into_inner();
-    let classes = classes::Entity::find()
-        .filter(classes::Column::SchoolId.eq(school_id))
-        .all(&state.db)
-        .await?;
-    let list = classes.iter().map(|c| ClassSimpleInfo {
-        id: c.id,
-        name: c.name.clone(),
-        grade: c.grade,
-        class: c.class,
-        school_id: c.school_id,
-        status: c.status,
-    }).collect();
-    Ok(ApiResponse::success(list))
-}
-
-// Get Class by ID
-#[handler]
-pub async fn get_by_id(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-) -> Result<ApiResponse<ClassInfo>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let class = get_by_id_impl(&state, id.into_inner()).await?;
-    Ok(ApiResponse::success(class))
-}
-
-pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<ClassInfo, AppError> {
-    let class = classes::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
-    
-    let mut class_infos = enrich_classes_with_details(state, vec![class]).await?;
-    if class_infos.is_empty() {
-        return Err(AppError::not_found("classes".to_string(), Some(id)));
-    }
-    Ok(class_infos.remove(0))
-}
-
-#[handler]
-pub async fn update_status(
-    depot: &mut Depot,
-    class_id: PathParam<i32>,
-    req: JsonBody<ClassStatusUpdatePayload>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    //check if the user is a teacher
-    let claims = depot.obtain::<Claims>().unwrap();
-    let teacher_class = teacher_classes::Entity::find()
-        .filter(teacher_classes::Column::UserId.eq(claims.user_id))
-        .filter(teacher_classes::Column::ClassId.eq(class_id.into_inner()))
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("users".to_string(), Some(claims.user_id)))?;
-    let class = classes::Entity::find_by_id(teacher_class.class_id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(teacher_class.class_id)))?;
-    let mut class_active_model: classes::ActiveModel = class.into();
-    class_active_model.status = Set(req.status);
-    class_active_model.update(&state.db).await?;
-    Ok(ApiResponse::success(()))
-}
+use crate::apis::academic_year_api::{current_year_id, in_current_year};
+use crate::apis::announcement_api::{get_active_by_school_ids, AnnouncementInfo};
+use crate::apis::campus_api::ensure_campus_in_school;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::school_hold_api::is_school_on_hold;
+use crate::apis::school_membership_api::{find_membership, join_school};
+use crate::apis::teacher_assignment_api::{active_condition, is_on_duty};
+use crate::apis::user_api::{display_name, is_profile_complete};
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::delete_confirm::{self, DeletePreview};
+use crate::core::constants::{
+    ASSIGNMENT_ROLE_SUBSTITUTE, CLASS_CHANGES_MAX_WAIT_SECS, CLASS_STATUS_DISMISSED, CLASS_STATUS_DISMISSING,
+    CLASS_TEACHER_ROLE_CO_TEACHER, CLASS_TEACHER_ROLE_HEAD, MEMBERSHIP_STATUS_ACTIVE,
+};
+use crate::core::error::AppError;
+use crate::core::event_hub;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::{from_str_optional, local_datetime};
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc};
+use data_model::{class_status_logs, classes, dismissal_schedules, schools, teacher_assignments, teacher_classes, users};
+use salvo::http::header::{ETAG, IF_NONE_MATCH};
+use salvo::http::HeaderValue;
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use validator::Validate;
+use crate::apis::auth_middleware::Claims;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct ClassCreatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub school_id: i32,
+    /// 所在学校的校区, 可以不传
+    pub campus_id: Option<i32>,
+    pub status: Option<i32>,
+    #[validate(length(max = 255))]
+    pub password: Option<String>,
+    /// 已有相同年级和班号的班级时更新它(名称、校区、密码, 不改状态), 否则返回 CLASS_ALREADY_EXISTS
+    pub upsert: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct ClassUpdatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub grade: Option<i32>,
+    pub class: Option<i32>,
+    /// 只能是当前学校, 换学校用转校接口
+    pub school_id: Option<i32>,
+    /// 0 表示不属于任何校区
+    pub campus_id: Option<i32>,
+    pub status: Option<i32>,
+    #[validate(length(max = 255))]
+    pub password: Option<String>,
+}
+
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct ClassBulkCreatePayload {
+    #[validate(nested)]
+    pub classes: Vec<ClassCreatePayload>,
+    /// 对所有班级生效, 同 ClassCreatePayload::upsert
+    pub upsert: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ClassMergePayload {
+    /// 被合并的重复班级, 可以已在回收站
+    pub source_id: i32,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ClassMergeResult {
+    pub class_id: i32,
+    pub source_id: i32,
+    pub moved_bindings: usize,
+    /// 已经是保留班级教师的绑定
+    pub skipped_bindings: usize,
+    pub moved_assignments: u64,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ClassTransferPayload {
+    pub school_id: i32,
+    /// 不传时沿用原来的年级和班号
+    pub grade: Option<i32>,
+    pub class: Option<i32>,
+    /// 为 true 时教师绑定跟着班级走, 否则全部解除
+    pub carry_teachers: Option<bool>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClassTransferResult {
+    pub class: classes::Model,
+    pub from_school_id: i32,
+    pub to_school_id: i32,
+    pub carried_teachers: Vec<i32>,
+    /// 解除绑定的教师; 带教师转校时, 在新学校被停用的教师也会解除
+    pub dropped_teachers: Vec<i32>,
+    /// 因为转校新加入目标学校的教师
+    pub new_members: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+pub struct UserClassInfo {
+    pub user_id: i32,
+    pub user_name: String,
+    /// head, co_teacher, observer
+    pub role: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+pub struct ClassInfo {
+    pub id: i32,
+    pub name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub school_id: i32,
+    pub school_name: String,
+    pub campus_id: Option<i32>,
+    pub status: i32,
+    pub password: String,
+    pub academic_year_id: Option<i32>,
+    pub teacher_infos: Vec<UserClassInfo>,
+}
+
+
+#[derive(Deserialize, Debug,Serialize, Validate, ToSchema)]
+pub struct ClassSimpleInfo{
+    pub id: i32,
+    pub name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub school_id: i32,
+    pub status: i32,
+    pub status_version: i64,
+}
+
+/// 删除班级会解除的绑定和值班安排
+#[derive(Serialize, Debug)]
+pub struct ClassDeleteImpact {
+    pub class_id: i32,
+    pub name: String,
+    pub school_id: i32,
+    pub status: i32,
+    pub teacher_bindings: u64,
+    pub active_assignments: u64,
+    /// 该学校当前连接的大屏(本实例), 删除后班级从大屏上消失
+    pub connected_screens: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ClassChangesParams {
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub since: Option<i64>,
+    /// 没有变更时最多等待的秒数
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub wait: Option<u64>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ClassChangesInfo {
+    /// 学校当前的版本号, 下次请求作为 since 传入
+    pub version: i64,
+    /// since 之后有班级被删除或移出时为 true, 此时 classes 是完整列表, 客户端应整体替换
+    pub reset: bool,
+    pub classes: Vec<ClassSimpleInfo>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchClassesParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub name: Option<String>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub grade: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub class: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub id: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub status: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub academic_year_id: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub campus_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct ClassStatusUpdatePayload {
+    pub status: i32,
+}
+
+// Create Class
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    req: JsonBody<ClassCreatePayload>,
+) -> Result<ApiResponse<classes::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    ensure_can_set_status(&state, claims, req.status.is_some()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let entity = add_impl(&state, &audit, req).await?;
+    Ok(ApiResponse::success(entity))
+}
+
+#[handler]
+pub async fn add_bulk(
+    depot: &mut Depot,
+    req: JsonBody<ClassBulkCreatePayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    req.validate()?;
+    let req = req.into_inner();
+    ensure_can_set_status(&state, claims, req.classes.iter().any(|c| c.status.is_some())).await?;
+    let audit = AuditContext::from_depot(depot);
+    add_bulk_impl(&state, &audit, req).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn add_bulk_impl(state: &AppState, audit: &AuditContext, req: ClassBulkCreatePayload) -> Result<(), AppError> {
+    let mut seen = HashSet::new();
+    for c in &req.classes {
+        if !seen.insert((c.school_id, c.grade, c.class)) {
+            return Err(AppError::validation(format!(
+                "duplicate grade {} class {} for school {}",
+                c.grade, c.class, c.school_id
+            )));
+        }
+    }
+    let upsert_all = req.upsert.unwrap_or(false);
+
+    let txn = audit::begin(state, audit).await?;
+    let mut year_ids: HashMap<i32, Option<i32>> = HashMap::new();
+    for c in &req.classes {
+        if let Entry::Vacant(entry) = year_ids.entry(c.school_id) {
+            entry.insert(current_year_id(&txn, c.school_id).await?);
+        }
+    }
+    let mut new_classes = Vec::new();
+    for c in req.classes {
+        if let Some(campus_id) = c.campus_id {
+            ensure_campus_in_school(&txn, campus_id, c.school_id).await?;
+        }
+        let academic_year_id = year_ids[&c.school_id];
+        match find_conflict(&txn, c.school_id, academic_year_id, c.grade, c.class, None).await? {
+            Some(existing) if upsert_all || c.upsert.unwrap_or(false) => {
+                apply_upsert(existing, c).update(&txn).await?;
+            }
+            Some(existing) => return Err(class_conflict(&existing)),
+            None => {
+                ensure_dismissal_allowed(&txn, c.school_id, CLASS_STATUS_DISMISSED, c.status.unwrap_or(0)).await?;
+                new_classes.push(new_class_model(c, academic_year_id));
+            }
+        }
+    }
+    if !new_classes.is_empty() {
+        classes::Entity::insert_many(new_classes)
+            .exec(&txn)
+            .await
+            .map_err(map_unique_violation)?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+pub async fn add_impl(state: &AppState, audit: &AuditContext, req: ClassCreatePayload) -> Result<classes::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    if let Some(campus_id) = req.campus_id {
+        ensure_campus_in_school(&txn, campus_id, req.school_id).await?;
+    }
+    // 新班级归到学校的当前学年
+    let academic_year_id = current_year_id(&txn, req.school_id).await?;
+    let class = match find_conflict(&txn, req.school_id, academic_year_id, req.grade, req.class, None).await? {
+        Some(existing) if req.upsert.unwrap_or(false) => apply_upsert(existing, req).update(&txn).await?,
+        Some(existing) => return Err(class_conflict(&existing)),
+        None => {
+            ensure_dismissal_allowed(&txn, req.school_id, CLASS_STATUS_DISMISSED, req.status.unwrap_or(0)).await?;
+            new_class_model(req, academic_year_id)
+                .insert(&txn)
+                .await
+                .map_err(map_unique_violation)?
+        }
+    };
+    txn.commit().await?;
+    Ok(class)
+}
+
+fn new_class_model(req: ClassCreatePayload, academic_year_id: Option<i32>) -> classes::ActiveModel {
+    classes::ActiveModel {
+        name: Set(req.name),
+        grade: Set(req.grade),
+        class: Set(req.class),
+        school_id: Set(req.school_id),
+        campus_id: Set(req.campus_id),
+        status: Set(req.status.unwrap_or(0)),
+        password: Set(req.password.unwrap_or("".to_string())),
+        academic_year_id: Set(academic_year_id),
+        ..Default::default()
+    }
+}
+
+/// upsert 时只更新名称, 以及传了的校区和密码; 状态只能走状态接口, 不在这里改
+fn apply_upsert(existing: classes::Model, req: ClassCreatePayload) -> classes::ActiveModel {
+    let mut active: classes::ActiveModel = existing.into();
+    active.name = Set(req.name);
+    if let Some(campus_id) = req.campus_id {
+        active.campus_id = Set(Some(campus_id));
+    }
+    if let Some(password) = req.password {
+        active.password = Set(password);
+    }
+    active
+}
+
+/// 同一学校同一学年里年级和班号相同的未删除班级, 对应 idx_classes_school_grade_class;
+/// 没有学年的旧班级和当前学年的班级一起显示在大屏上, 也算冲突
+pub async fn find_conflict<C: ConnectionTrait>(
+    db: &C,
+    school_id: i32,
+    academic_year_id: Option<i32>,
+    grade: i32,
+    class: i32,
+    exclude_id: Option<i32>,
+) -> Result<Option<classes::Model>, DbErr> {
+    let mut query = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(classes::Column::Grade.eq(grade))
+        .filter(classes::Column::Class.eq(class))
+        .filter(match academic_year_id {
+            Some(year_id) => Condition::any()
+                .add(classes::Column::AcademicYearId.eq(year_id))
+                .add(classes::Column::AcademicYearId.is_null()),
+            None => Condition::all().add(classes::Column::AcademicYearId.is_null()),
+        });
+    if let Some(id) = exclude_id {
+        query = query.filter(classes::Column::Id.ne(id));
+    }
+    query.one(db).await
+}
+
+fn class_conflict(existing: &classes::Model) -> AppError {
+    AppError::business_logic(
+        "CLASS_ALREADY_EXISTS",
+        format!(
+            "Grade {} class {} already exists in this school as class {} ({})",
+            existing.grade, existing.class, existing.id, existing.name
+        ),
+    )
+}
+
+/// 并发创建时由唯一索引兜底
+pub fn map_unique_violation(err: DbErr) -> AppError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => AppError::business_logic(
+            "CLASS_ALREADY_EXISTS",
+            "A class with the same grade and class number already exists in this school",
+        ),
+        _ => err.into(),
+    }
+}
+
+// Update Class
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<ClassUpdatePayload>,
+) -> Result<ApiResponse<classes::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    let req = req.into_inner();
+    if req.status.is_some() {
+        ensure_can_change_status(&state, claims, id).await?;
+    }
+    let audit = AuditContext::from_depot(depot);
+    let class = update_impl(&state, &audit, id, req).await?;
+    Ok(ApiResponse::success(class))
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    id: i32,
+    req: ClassUpdatePayload,
+) -> Result<classes::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+
+    if let Some(status) = req.status {
+        ensure_dismissal_allowed(&txn, class.school_id, class.status, status).await?;
+    }
+    // 换学校要同时处理教师绑定和历史记录, 只能通过转校接口
+    if req.school_id.is_some_and(|school_id| school_id != class.school_id) {
+        return Err(AppError::business_logic(
+            "USE_CLASS_TRANSFER",
+            "Use POST /api/admin/classes/{id}/transfer to move a class to another school",
+        ));
+    }
+    if let Some(campus_id) = req.campus_id.filter(|id| *id != 0) {
+        ensure_campus_in_school(&txn, campus_id, class.school_id).await?;
+    }
+    let grade = req.grade.unwrap_or(class.grade);
+    let class_no = req.class.unwrap_or(class.class);
+    if let Some(existing) =
+        find_conflict(&txn, class.school_id, class.academic_year_id, grade, class_no, Some(class.id)).await?
+    {
+        return Err(class_conflict(&existing));
+    }
+    let mut class_active_model: classes::ActiveModel = class.into();
+
+    if let Some(name) = req.name {
+        class_active_model.name = Set(name);
+    }
+    if let Some(grade) = req.grade {
+        class_active_model.grade = Set(grade);
+    }
+    if let Some(class) = req.class {
+        class_active_model.class = Set(class);
+    }
+    if let Some(campus_id) = req.campus_id {
+        class_active_model.campus_id = Set(Some(campus_id).filter(|id| *id != 0));
+    }
+    if let Some(status) = req.status {
+        class_active_model.status = Set(status);
+    }
+    if let Some(password) = req.password {
+        class_active_model.password = Set(password);
+    }
+
+    let class = class_active_model.update(&txn).await.map_err(map_unique_violation)?;
+    txn.commit().await?;
+    Ok(class)
+}
+
+// Preview what deleting a class affects
+#[handler]
+pub async fn delete_preview(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<DeletePreview<ClassDeleteImpact>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    let impact = delete_impact(&state, id).await?;
+    let preview = delete_confirm::issue(&state, claims, "classes", id, impact).await?;
+    Ok(ApiResponse::success(preview))
+}
+
+pub async fn delete_impact(state: &AppState, id: i32) -> Result<ClassDeleteImpact, AppError> {
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    let teacher_bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(id))
+        .count(&state.db)
+        .await?;
+    let active_assignments = teacher_assignments::Entity::find()
+        .filter(teacher_assignments::Column::ClassId.eq(id))
+        .filter(active_condition(Utc::now()))
+        .count(&state.db)
+        .await?;
+    Ok(ClassDeleteImpact {
+        class_id: class.id,
+        name: class.name,
+        school_id: class.school_id,
+        status: class.status,
+        teacher_bindings,
+        active_assignments,
+        connected_screens: event_hub::subscriber_count(class.school_id),
+    })
+}
+
+// Delete Class
+#[handler]
+pub async fn delete(depot: &mut Depot, req: &mut Request, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    delete_confirm::consume(&state, claims, req, "classes", id).await?;
+    let audit = AuditContext::from_depot(depot);
+    delete_impl(&state, &audit, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn delete_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<(), AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    let mut class_active_model: classes::ActiveModel = class.into();
+    class_active_model.deleted_at = Set(Some(Utc::now().into()));
+    class_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// Restore a deleted class
+#[handler]
+pub async fn restore(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<classes::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let class = restore_impl(&state, &audit, id.into_inner()).await?;
+    Ok(ApiResponse::success(class))
+}
+
+pub async fn restore_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<classes::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let class = classes::Entity::find_deleted()
+        .filter(classes::Column::Id.eq(id))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("deleted classes".to_string(), Some(id)))?;
+    if schools::Entity::find_live_by_id(class.school_id).one(&txn).await?.is_none() {
+        return Err(AppError::business_logic(
+            "SCHOOL_DELETED",
+            "The school of this class is deleted, restore the school first",
+        ));
+    }
+    if let Some(existing) =
+        find_conflict(&txn, class.school_id, class.academic_year_id, class.grade, class.class, None).await?
+    {
+        return Err(AppError::business_logic(
+            "CLASS_ALREADY_EXISTS",
+            format!(
+                "Class {} ({}) now has the same grade and class number, merge into it instead",
+                existing.id, existing.name
+            ),
+        ));
+    }
+    let mut class_active_model: classes::ActiveModel = class.into();
+    class_active_model.deleted_at = Set(None);
+    let class = class_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(class)
+}
+
+// Merge a duplicate class into this one
+#[handler]
+pub async fn merge(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<ClassMergePayload>,
+) -> Result<ApiResponse<ClassMergeResult>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let result = merge_impl(&state, &audit, id.into_inner(), req.into_inner()).await?;
+    Ok(ApiResponse::success(result))
+}
+
+/// 重复班级的教师绑定和临时代课安排并入保留的班级, 重复班级移到回收站
+pub async fn merge_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    id: i32,
+    req: ClassMergePayload,
+) -> Result<ClassMergeResult, AppError> {
+    if req.source_id == id {
+        return Err(AppError::validation("cannot merge a class into itself"));
+    }
+    let txn = audit::begin(state, audit).await?;
+    let target = classes::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    let source = classes::Entity::find_by_id(req.source_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(req.source_id)))?;
+    if source.school_id != target.school_id {
+        return Err(AppError::validation("classes must belong to the same school"));
+    }
+    // 只合并重复的班级, 不同班级的教师不能借合并挪过来
+    if (source.academic_year_id, source.grade, source.class) != (target.academic_year_id, target.grade, target.class) {
+        return Err(AppError::validation(
+            "only a class with the same academic year, grade and class number can be merged",
+        ));
+    }
+
+    let target_bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(target.id))
+        .all(&txn)
+        .await?;
+    let bound: HashMap<i32, &teacher_classes::Model> = target_bindings.iter().map(|tc| (tc.user_id, tc)).collect();
+    let mut has_head = target_bindings.iter().any(|tc| tc.role == CLASS_TEACHER_ROLE_HEAD);
+    let source_bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(source.id))
+        .all(&txn)
+        .await?;
+    let mut moved_bindings = 0;
+    let mut skipped_bindings = 0;
+    for binding in source_bindings {
+        // 删除绑定时触发器会结束原班级对应的任课安排, 新绑定会生成新的安排
+        let user_id = binding.user_id;
+        let mut role = binding.role.clone();
+        binding.delete(&txn).await?;
+        if let Some(existing) = bound.get(&user_id) {
+            // 原班级的班主任在保留的班级里只是普通教师, 保留的班级还没有班主任时升为班主任
+            if role == CLASS_TEACHER_ROLE_HEAD && !has_head {
+                let mut existing: teacher_classes::ActiveModel = (*existing).clone().into();
+                existing.role = Set(role);
+                existing.update(&txn).await?;
+                has_head = true;
+            }
+            skipped_bindings += 1;
+            continue;
+        }
+        // 保留的班级已有班主任时改为协同教师
+        if role == CLASS_TEACHER_ROLE_HEAD {
+            if has_head {
+                role = CLASS_TEACHER_ROLE_CO_TEACHER.to_string();
+            }
+            has_head = true;
+        }
+        teacher_classes::ActiveModel {
+            user_id: Set(user_id),
+            class_id: Set(target.id),
+            role: Set(role),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        moved_bindings += 1;
+    }
+    let moved_assignments = teacher_assignments::Entity::update_many()
+        .col_expr(teacher_assignments::Column::ClassId, Expr::value(target.id))
+        .col_expr(teacher_assignments::Column::UpdatedAt, Expr::value(Utc::now()))
+        .filter(teacher_assignments::Column::ClassId.eq(source.id))
+        .filter(teacher_assignments::Column::Role.eq(ASSIGNMENT_ROLE_SUBSTITUTE))
+        .filter(active_condition(Utc::now()))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    if source.deleted_at.is_none() {
+        let mut source_active_model: classes::ActiveModel = source.into();
+        source_active_model.deleted_at = Set(Some(Utc::now().into()));
+        source_active_model.update(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(ClassMergeResult {
+        class_id: id,
+        source_id: req.source_id,
+        moved_bindings,
+        skipped_bindings,
+        moved_assignments,
+    })
+}
+
+// Transfer a class to another school
+#[handler]
+pub async fn transfer(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<ClassTransferPayload>,
+) -> Result<ApiResponse<ClassTransferResult>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    let req = req.into_inner();
+    // 转出和转入的学校都要有管理权限
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    ensure_school_access(&state, claims, class.school_id).await?;
+    ensure_school_access(&state, claims, req.school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    // 两个学校的大屏通过 outbox 重新拉取完整的班级列表
+    let result = transfer_impl(&state, &audit, id, req).await?;
+    Ok(ApiResponse::success(result))
+}
+
+pub async fn transfer_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    id: i32,
+    req: ClassTransferPayload,
+) -> Result<ClassTransferResult, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    let from_school_id = class.school_id;
+    if req.school_id == from_school_id {
+        return Err(AppError::validation("class already belongs to this school"));
+    }
+    if class.status == CLASS_STATUS_DISMISSING {
+        return Err(AppError::business_logic(
+            "CLASS_DISMISSING",
+            "Class is being dismissed, transfer it after dismissal ends",
+        ));
+    }
+    schools::Entity::find_live_by_id(req.school_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(req.school_id)))?;
+    let academic_year_id = current_year_id(&txn, req.school_id).await?;
+    let grade = req.grade.unwrap_or(class.grade);
+    let class_no = req.class.unwrap_or(class.class);
+    if let Some(existing) = find_conflict(&txn, req.school_id, academic_year_id, grade, class_no, None).await? {
+        return Err(class_conflict(&existing));
+    }
+
+    let carry = req.carry_teachers.unwrap_or(false);
+    let bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(id))
+        .all(&txn)
+        .await?;
+    let mut carried_teachers = Vec::new();
+    let mut dropped_teachers = Vec::new();
+    let mut new_members = Vec::new();
+    for binding in bindings {
+        let user_id = binding.user_id;
+        if carry {
+            // 跟着走的教师同时成为新学校的成员, 原学校的成员关系保留; 在新学校被停用的不能跟着走
+            match find_membership(&txn, user_id, req.school_id).await? {
+                Some(m) if m.status == MEMBERSHIP_STATUS_ACTIVE => {
+                    carried_teachers.push(user_id);
+                    continue;
+                }
+                Some(_) => {}
+                None => {
+                    join_school(&txn, user_id, req.school_id).await?;
+                    new_members.push(user_id);
+                    carried_teachers.push(user_id);
+                    continue;
+                }
+            }
+        }
+        binding.delete(&txn).await?;
+        dropped_teachers.push(user_id);
+    }
+    // 解除绑定的教师的临时代课安排一起结束
+    if !dropped_teachers.is_empty() {
+        teacher_assignments::Entity::update_many()
+            .col_expr(teacher_assignments::Column::ValidTo, Expr::value(Utc::now()))
+            .col_expr(teacher_assignments::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(teacher_assignments::Column::ClassId.eq(id))
+            .filter(teacher_assignments::Column::UserId.is_in(dropped_teachers.clone()))
+            .filter(teacher_assignments::Column::Role.eq(ASSIGNMENT_ROLE_SUBSTITUTE))
+            .filter(active_condition(Utc::now()))
+            .exec(&txn)
+            .await?;
+    }
+    // 状态记录保留发生时的学校, 转校前的放学记录仍算在原学校
+
+    let mut class_active_model: classes::ActiveModel = class.into();
+    class_active_model.school_id = Set(req.school_id);
+    // 校区属于原学校, 转校后不再有校区
+    class_active_model.campus_id = Set(None);
+    class_active_model.academic_year_id = Set(academic_year_id);
+    class_active_model.grade = Set(grade);
+    class_active_model.class = Set(class_no);
+    let class = class_active_model.update(&txn).await.map_err(map_unique_violation)?;
+    txn.commit().await?;
+    tracing::info!(
+        "Class {} transferred from school {} to {}: {} teachers carried, {} dropped",
+        class.id,
+        from_school_id,
+        class.school_id,
+        carried_teachers.len(),
+        dropped_teachers.len()
+    );
+    Ok(ClassTransferResult {
+        from_school_id,
+        to_school_id: class.school_id,
+        class,
+        carried_teachers,
+        dropped_teachers,
+        new_members,
+    })
+}
+
+// Get Classes List
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<ClassInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let params = req.parse_queries::<SearchClassesParams>()?;
+    let list = get_list_impl(&state, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn enrich_classes_with_details(
+    state: &AppState,
+    class_models: Vec<classes::Model>,
+) -> Result<Vec<ClassInfo>, AppError> {
+    if class_models.is_empty() {
+        return Ok(vec![]);
+    }
+    let class_ids: Vec<i32> = class_models.iter().map(|c| c.id).collect();
+
+    let school_ids: Vec<i32> = class_models.iter().map(|c| c.school_id).collect();
+    let schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
+        HashMap::new()
+    } else {
+        schools::Entity::find_live()
+            .filter(schools::Column::Id.is_in(school_ids.clone()))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect()
+    };
+
+    let teacher_classes_list = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.is_in(class_ids.clone()))
+        .all(&state.db)
+        .await?;
+    
+    let user_ids: Vec<i32> = teacher_classes_list.iter().map(|tc| tc.user_id).collect();
+
+    let users_map: HashMap<i32, users::Model> = if user_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find_live()
+            .filter(users::Column::Id.is_in(user_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, u))
+            .collect()
+    };
+
+    let list = class_models
+        .into_iter()
+        .map(|class| {
+            let teacher_infos: Vec<UserClassInfo> = teacher_classes_list
+                .iter()
+                .filter(|tc| tc.class_id == class.id)
+                .filter_map(|tc| {
+                    users_map.get(&tc.user_id).map(|u| UserClassInfo {
+                        user_id: u.id,
+                        user_name: display_name(u),
+                        role: tc.role.clone(),
+                    })
+                })
+                .collect();
+            
+            let school_name = schools_map.get(&class.school_id).map(|s| s.name.clone()).unwrap_or_default();
+            ClassInfo {
+                id: class.id,
+                name: class.name,
+                grade: class.grade,
+                class: class.class,
+                school_id: class.school_id,
+                school_name,
+                campus_id: class.campus_id,
+                status: class.status,
+                password: class.password,
+                academic_year_id: class.academic_year_id,
+                teacher_infos,
+            }
+        })
+        .collect();
+
+    Ok(list)
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    params: SearchClassesParams,
+) -> Result<PagingResponse<ClassInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    let query = search_query(params);
+    let paginator = query.paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let class_models = paginator.fetch_page(page - 1).await?;
+    
+    let list = enrich_classes_with_details(state, class_models).await?;
+    Ok(PagingResponse { list, total, page })
+}
+
+/// 列表和导出共用的筛选条件
+pub fn search_query(params: SearchClassesParams) -> Select<classes::Entity> {
+    let mut query = classes::Entity::find_live();
+
+    crate::filter_if_some!(query, classes::Column::Id, params.id, eq);
+    crate::filter_if_some!(query, classes::Column::Name, params.name, like);
+    crate::filter_if_some!(query, classes::Column::SchoolId, params.school_id, eq);
+    crate::filter_if_some!(query, classes::Column::Grade, params.grade, eq);
+    crate::filter_if_some!(query, classes::Column::Class, params.class, eq);
+    crate::filter_if_some!(query, classes::Column::Status, params.status, eq);
+    crate::filter_if_some!(query, classes::Column::AcademicYearId, params.academic_year_id, eq);
+    crate::filter_if_some!(query, classes::Column::CampusId, params.campus_id, eq);
+    query
+}
+
+/// 支持 ETag/If-None-Match, 班级没有变化时返回 304
+#[handler]
+pub async fn get_all_class_by_school_id(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let school_id = req
+        .param::<i32>("school_id")
+        .ok_or_else(|| AppError::validation("invalid school id"))?;
+    // 先取版本号再取列表, 列表只会比版本号新, 客户端最多多拉一次
+    let version = get_school_status_version(&state, school_id).await?;
+    let etag = format!("\"{}\"", version);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        res.headers_mut().insert(ETAG, value);
+    }
+    if etag_matches(req, &etag) {
+        res.status_code(StatusCode::NOT_MODIFIED);
+        return Ok(());
+    }
+    let list = get_simple_list_by_school_id(&state, school_id).await?;
+    res.render(Json(ApiResponse::success(list)));
+    Ok(())
+}
+
+fn etag_matches(req: &Request, etag: &str) -> bool {
+    req.headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        })
+}
+
+/// 学校不存在或已删除时返回 not found
+pub async fn get_school_status_version(state: &AppState, school_id: i32) -> Result<i64, AppError> {
+    schools::Entity::find_live_by_id(school_id)
+        .select_only()
+        .column(schools::Column::StatusVersion)
+        .into_tuple::<i64>()
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))
+}
+
+/// 返回 since 之后变化的班级; 没有变化时等待 wait 秒, 期间有状态推送就重新查询
+#[handler]
+pub async fn get_changes_by_school_id(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<ApiResponse<ClassChangesInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let school_id = req
+        .param::<i32>("school_id")
+        .ok_or_else(|| AppError::validation("invalid school id"))?;
+    let params = req.parse_queries::<ClassChangesParams>()?;
+    let changes = get_changes_impl(&state, school_id, params).await?;
+    Ok(ApiResponse::success(changes))
+}
+
+pub async fn get_changes_impl(
+    state: &AppState,
+    school_id: i32,
+    params: ClassChangesParams,
+) -> Result<ClassChangesInfo, AppError> {
+    let since = params.since.unwrap_or_default();
+    let wait = Duration::from_secs(params.wait.unwrap_or_default().min(CLASS_CHANGES_MAX_WAIT_SECS));
+    let deadline = Instant::now() + wait;
+    // 先订阅再查询, 避免查询之后、等待之前的变更被漏掉
+    let (mut events, _) = event_hub::subscribe(school_id);
+    loop {
+        let changes = load_class_changes(state, school_id, since).await?;
+        if changes.reset || !changes.classes.is_empty() {
+            return Ok(changes);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(changes);
+        }
+        // 新增、改名等不会推送事件, 等到超时后重新查询时返回
+        if let Ok(Err(RecvError::Closed)) = tokio::time::timeout(remaining, events.recv()).await {
+            return Ok(changes);
+        }
+    }
+}
+
+async fn load_class_changes(
+    state: &AppState,
+    school_id: i32,
+    since: i64,
+) -> Result<ClassChangesInfo, AppError> {
+    let school = schools::Entity::find_live_by_id(school_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    // since 比当前版本还大说明客户端的数据来自重建之前的库, 同样返回完整列表
+    let reset = since < school.classes_reset_version || since > school.status_version;
+    let mut query = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(in_current_year());
+    if !reset {
+        query = query.filter(classes::Column::StatusVersion.gt(since));
+    }
+    let classes = query
+        .order_by_asc(classes::Column::StatusVersion)
+        .all(&state.db)
+        .await?;
+    let version = classes
+        .iter()
+        .map(|c| c.status_version)
+        .max()
+        .unwrap_or_default()
+        .max(school.status_version);
+    Ok(ClassChangesInfo {
+        version,
+        reset,
+        classes: classes.iter().map(to_simple_info).collect(),
+    })
+}
+
+fn to_simple_info(c: &classes::Model) -> ClassSimpleInfo {
+    ClassSimpleInfo {
+        id: c.id,
+        name: c.name.clone(),
+        grade: c.grade,
+        class: c.class,
+        school_id: c.school_id,
+        status: c.status,
+        status_version: c.status_version,
+    }
+}
+
+pub async fn get_simple_list_by_school_id(
+    state: &AppState,
+    school_id: i32,
+) -> Result<Vec<ClassSimpleInfo>, AppError> {
+    let classes = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(in_current_year())
+        .all(&state.db)
+        .await?;
+    let list = classes.iter().map(to_simple_info).collect();
+    Ok(list)
+}
+
+// Get Class by ID
+#[handler]
+pub async fn get_by_id(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<ClassInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let class = get_by_id_impl(&state, id.into_inner()).await?;
+    Ok(ApiResponse::success(class))
+}
+
+pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<ClassInfo, AppError> {
+    let class = classes::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(id)))?;
+    
+    let mut class_infos = enrich_classes_with_details(state, vec![class]).await?;
+    if class_infos.is_empty() {
+        return Err(AppError::not_found("classes".to_string(), Some(id)));
+    }
+    Ok(class_infos.remove(0))
+}
+
+#[derive(Serialize, Debug)]
+pub struct StatusChangeInfo {
+    pub old_status: i32,
+    pub new_status: i32,
+    pub actor_id: Option<i32>,
+    pub actor_name: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledTransitionInfo {
+    /// 按时间表切换到的状态, 目前只有放学中
+    pub status: i32,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MyClassInfo {
+    pub class_id: i32,
+    pub class_name: String,
+    pub school_id: i32,
+    pub school_name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub status: i32,
+    pub status_version: i64,
+    /// 在该班级的角色: head, co_teacher, observer
+    pub role: String,
+    /// 当前是否在岗, 在岗才能修改班级状态
+    pub on_duty: bool,
+    pub last_status_change: Option<StatusChangeInfo>,
+    /// 今天按放学时间表的状态切换时间, 学校没有设置时为空
+    pub today_transitions: Vec<ScheduledTransitionInfo>,
+    pub co_teacher_count: i64,
+    /// 学校当前生效且面向该年级的公告
+    pub announcements: Vec<AnnouncementInfo>,
+}
+
+// Get classes of current user
+#[handler]
+pub async fn get_my_classes(depot: &mut Depot) -> Result<ApiResponse<Vec<MyClassInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let mut list = get_my_classes_impl(&state, claims.user_id).await?;
+    // 选择了当前学校时只返回该学校的班级
+    if let Some(school_id) = claims.school_id {
+        list.retain(|c| c.school_id == school_id);
+    }
+    Ok(ApiResponse::success(list))
+}
+
+/// 查询次数固定, 与绑定的班级数量无关
+pub async fn get_my_classes_impl(state: &AppState, user_id: i32) -> Result<Vec<MyClassInfo>, AppError> {
+    let bindings: Vec<(teacher_classes::Model, classes::Model)> = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::UserId.eq(user_id))
+        .find_also_related(classes::Entity)
+        .filter(classes::Column::DeletedAt.is_null())
+        .order_by_asc(classes::Column::Grade)
+        .order_by_asc(classes::Column::Class)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .filter_map(|(tc, class)| class.map(|class| (tc, class)))
+        .collect();
+    if bindings.is_empty() {
+        return Ok(vec![]);
+    }
+    let class_ids: Vec<i32> = bindings.iter().map(|(_, c)| c.id).collect();
+    let mut school_ids: Vec<i32> = bindings.iter().map(|(_, c)| c.school_id).collect();
+    school_ids.sort_unstable();
+    school_ids.dedup();
+
+    let schools_map: HashMap<i32, String> = schools::Entity::find_live()
+        .filter(schools::Column::Id.is_in(school_ids.clone()))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s.name))
+        .collect();
+
+    // 每个班级最近一次状态变更
+    let last_changes = class_status_logs::Entity::find()
+        .filter(class_status_logs::Column::ClassId.is_in(class_ids.clone()))
+        .distinct_on([class_status_logs::Column::ClassId])
+        .order_by_asc(class_status_logs::Column::ClassId)
+        .order_by_desc(class_status_logs::Column::Id)
+        .all(&state.db)
+        .await?;
+    let actor_ids: Vec<i32> = last_changes.iter().filter_map(|l| l.actor_id).collect();
+    let actors_map: HashMap<i32, String> = if actor_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find_live()
+            .filter(users::Column::Id.is_in(actor_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, display_name(&u)))
+            .collect()
+    };
+
+    let co_teacher_counts: HashMap<i32, i64> = teacher_classes::Entity::find()
+        .select_only()
+        .column(teacher_classes::Column::ClassId)
+        .column_as(Expr::col(teacher_classes::Column::UserId).count(), "count")
+        .filter(teacher_classes::Column::ClassId.is_in(class_ids.clone()))
+        .filter(teacher_classes::Column::Role.eq(CLASS_TEACHER_ROLE_CO_TEACHER))
+        .group_by(teacher_classes::Column::ClassId)
+        .into_tuple::<(i32, i64)>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let now = Utc::now();
+    let on_duty_class_ids: HashSet<i32> = teacher_assignments::Entity::find()
+        .select_only()
+        .column(teacher_assignments::Column::ClassId)
+        .filter(teacher_assignments::Column::UserId.eq(user_id))
+        .filter(teacher_assignments::Column::ClassId.is_in(class_ids))
+        .filter(active_condition(now))
+        .into_tuple::<i32>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+
+    // 今天的放学时间, 年级没有单独设置时用全校默认
+    let today = Local::now().date_naive();
+    let schedules: HashMap<(i32, Option<i32>), NaiveTime> = dismissal_schedules::Entity::find()
+        .filter(dismissal_schedules::Column::SchoolId.is_in(school_ids.clone()))
+        .filter(dismissal_schedules::Column::Weekday.eq(today.weekday().number_from_monday() as i32))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|s| ((s.school_id, s.grade), s.dismiss_at))
+        .collect();
+
+    let announcements = get_active_by_school_ids(state, school_ids).await?;
+
+    let list = bindings
+        .into_iter()
+        .map(|(tc, class)| {
+            let today_transitions = schedules
+                .get(&(class.school_id, Some(class.grade)))
+                .or_else(|| schedules.get(&(class.school_id, None)))
+                .and_then(|time| local_datetime(today, *time))
+                .map(|at| ScheduledTransitionInfo {
+                    status: CLASS_STATUS_DISMISSING,
+                    at,
+                })
+                .into_iter()
+                .collect();
+            let last_status_change = last_changes
+                .iter()
+                .find(|l| l.class_id == class.id)
+                .map(|l| StatusChangeInfo {
+                    old_status: l.old_status,
+                    new_status: l.new_status,
+                    actor_id: l.actor_id,
+                    actor_name: l.actor_id.and_then(|id| actors_map.get(&id).cloned()),
+                    changed_at: l.created_at.into(),
+                });
+            let announcements = announcements
+                .iter()
+                .filter(|a| a.school_id == class.school_id)
+                .filter(|a| a.target_grades.is_empty() || a.target_grades.contains(&class.grade))
+                .cloned()
+                .collect();
+            MyClassInfo {
+                class_id: class.id,
+                class_name: class.name,
+                school_id: class.school_id,
+                school_name: schools_map.get(&class.school_id).cloned().unwrap_or_default(),
+                grade: class.grade,
+                class: class.class,
+                status: class.status,
+                status_version: class.status_version,
+                role: tc.role,
+                on_duty: on_duty_class_ids.contains(&class.id),
+                last_status_change,
+                today_transitions,
+                co_teacher_count: co_teacher_counts.get(&class.id).copied().unwrap_or(0),
+                announcements,
+            }
+        })
+        .collect();
+    Ok(list)
+}
+
+#[handler]
+pub async fn update_status(
+    depot: &mut Depot,
+    class_id: PathParam<i32>,
+    req: JsonBody<ClassStatusUpdatePayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    //check if the user is on duty for the class
+    let claims = depot.obtain::<Claims>().unwrap();
+    let class_id = class_id.into_inner();
+    ensure_can_change_status(&state, claims, class_id).await?;
+    let txn = audit::begin(&state, &AuditContext::from_depot(depot)).await?;
+    let class = classes::Entity::find_live_by_id(class_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(class_id)))?;
+    ensure_dismissal_allowed(&txn, class.school_id, class.status, req.status).await?;
+    let mut class_active_model: classes::ActiveModel = class.into();
+    class_active_model.status = Set(req.status);
+    class_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// 管理员之外只有正在值班的教师能修改班级状态
+async fn ensure_can_change_status(state: &AppState, claims: &Claims, class_id: i32) -> Result<(), AppError> {
+    if claims.is_admin() {
+        return Ok(());
+    }
+    if !is_on_duty(&state.db, claims.user_id, class_id).await? {
+        return Err(AppError::forbidden(format!("update status of class {}", class_id)));
+    }
+    ensure_profile_complete(state, claims.user_id).await
+}
+
+/// 创建或导入班级时带了状态, 同样要求填写过真实姓名; 是否值班由绑定决定, 这里不检查
+pub async fn ensure_can_set_status(state: &AppState, claims: &Claims, has_status: bool) -> Result<(), AppError> {
+    if !has_status || claims.is_admin() {
+        return Ok(());
+    }
+    ensure_profile_complete(state, claims.user_id).await
+}
+
+/// 教师要先填写真实姓名, 放学记录和大屏上才能看出是谁操作的
+async fn ensure_profile_complete(state: &AppState, user_id: i32) -> Result<(), AppError> {
+    let user = users::Entity::find_live_by_id(user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(user_id)))?;
+    if !is_profile_complete(&user) {
+        return Err(AppError::business_logic(
+            "PROFILE_INCOMPLETE",
+            "Fill in your real name before changing class status",
+        ));
+    }
+    Ok(())
+}
+
+/// 学校处于紧急管控时禁止班级进入放学中.
+/// 要在写班级状态的事务里调用: 共享锁住学校行, 与开启管控时的排他锁互斥
+pub async fn ensure_dismissal_allowed<C: ConnectionTrait>(
+    db: &C,
+    school_id: i32,
+    old_status: i32,
+    new_status: i32,
+) -> Result<(), AppError> {
+    if new_status != CLASS_STATUS_DISMISSING || old_status == CLASS_STATUS_DISMISSING {
+        return Ok(());
+    }
+    schools::Entity::find_by_id(school_id).lock_shared().one(db).await?;
+    if is_school_on_hold(db, school_id).await? {
+        return Err(AppError::business_logic(
+            "SCHOOL_ON_HOLD",
+            "School is on hold, dismissal is not allowed",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use crate::apis::academic_year_api::{current_year_id, in_current_year};
+use crate::apis::auth_middleware::Claims;
+use crate::apis::class_api::{ensure_can_set_status, ensure_dismissal_allowed};
+use crate::apis::school_api::ensure_school_access;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{CLASS_STATUS_DISMISSED, CLASS_STATUS_DISMISSING, CLASS_STATUS_ONGOING};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use crate::utils::table_import::{self, ImportRow, ImportRowError};
+use data_model::{classes, schools};
+use salvo::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+const ACTION_CREATE: &str = "create";
+const ACTION_UPDATE: &str = "update";
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ClassImportParams {
+    /// 文件里没有 school_id 列时使用
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    /// 只校验并返回计划, 不写入
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClassImportRowResult {
+    pub line: usize,
+    /// create 或 update
+    pub action: String,
+    /// dry_run 时新建的班级为空
+    pub class_id: Option<i32>,
+    pub school_id: i32,
+    pub grade: i32,
+    pub class: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClassImportReport {
+    pub dry_run: bool,
+    /// 有错误或 dry_run 时为 false, 不会写入任何一行
+    pub committed: bool,
+    pub total_rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub rows: Vec<ClassImportRowResult>,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// 校验通过的一行
+#[derive(Debug)]
+struct ClassRow {
+    line: usize,
+    school_id: i32,
+    name: String,
+    grade: i32,
+    class: i32,
+    status: Option<i32>,
+    password: Option<String>,
+}
+
+fn parse_int(row: &ImportRow, column: &str, errors: &mut Vec<ImportRowError>) -> Option<i32> {
+    let Some(value) = row.get(column) else {
+        errors.push(ImportRowError::new(row.line, Some(column), format!("{} is required", column)));
+        return None;
+    };
+    match value.parse::<i32>() {
+        Ok(v) => Some(v),
+        Err(_) => {
+            errors.push(ImportRowError::new(row.line, Some(column), format!("invalid {}: {}", column, value)));
+            None
+        }
+    }
+}
+
+fn parse_row(row: &ImportRow, default_school_id: Option<i32>, errors: &mut Vec<ImportRowError>) -> Option<ClassRow> {
+    let error_count = errors.len();
+    let school_id = match row.get("school_id") {
+        Some(_) => parse_int(row, "school_id", errors),
+        None => {
+            if default_school_id.is_none() {
+                errors.push(ImportRowError::new(row.line, Some("school_id"), "school_id is required"));
+            }
+            default_school_id
+        }
+    };
+    let grade = parse_int(row, "grade", errors);
+    let class = parse_int(row, "class", errors);
+    let name = row.get("name").map(str::to_string);
+    match name.as_deref() {
+        None => errors.push(ImportRowError::new(row.line, Some("name"), "name is required")),
+        Some(n) if n.chars().count() > 255 => {
+            errors.push(ImportRowError::new(row.line, Some("name"), "name is longer than 255 characters"))
+        }
+        _ => {}
+    }
+    let status = match row.get("status") {
+        Some(_) => {
+            let status = parse_int(row, "status", errors);
+            if let Some(s) = status
+                && ![CLASS_STATUS_DISMISSED, CLASS_STATUS_ONGOING, CLASS_STATUS_DISMISSING].contains(&s)
+            {
+                errors.push(ImportRowError::new(row.line, Some("status"), format!("invalid status: {}", s)));
+            }
+            status
+        }
+        None => None,
+    };
+    let password = row.get("password").map(str::to_string);
+    if password.as_ref().is_some_and(|p| p.chars().count() > 255) {
+        errors.push(ImportRowError::new(row.line, Some("password"), "password is longer than 255 characters"));
+    }
+    if errors.len() > error_count {
+        return None;
+    }
+    Some(ClassRow {
+        line: row.line,
+        school_id: school_id?,
+        name: name?,
+        grade: grade?,
+        class: class?,
+        status,
+        password,
+    })
+}
+
+// Import classes from a CSV or XLSX file
+#[handler]
+pub async fn import(depot: &mut Depot, req: &mut Request) -> Result<ApiResponse<ClassImportReport>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let params = req.parse_queries::<ClassImportParams>()?;
+    let rows = table_import::read_upload(req).await?;
+    let audit = AuditContext::from_depot(depot);
+    let report = import_impl(&state, claims, &audit, params, rows).await?;
+    Ok(ApiResponse::success(report))
+}
+
+pub async fn import_impl(
+    state: &AppState,
+    claims: &Claims,
+    audit: &AuditContext,
+    params: ClassImportParams,
+    rows: Vec<ImportRow>,
+) -> Result<ClassImportReport, AppError> {
+    let dry_run = params.dry_run.unwrap_or(false);
+    let total_rows = rows.len();
+    let mut errors = Vec::new();
+    let parsed: Vec<ClassRow> = rows
+        .iter()
+        .filter_map(|row| parse_row(row, params.school_id, &mut errors))
+        .collect();
+
+    // 学校必须存在, 且当前用户有权限管理
+    let school_ids: HashSet<i32> = parsed.iter().map(|r| r.school_id).collect();
+    let existing_schools: HashSet<i32> = schools::Entity::find_live()
+        .select_only()
+        .column(schools::Column::Id)
+        .filter(schools::Column::Id.is_in(school_ids.iter().copied()))
+        .into_tuple::<i32>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+    for school_id in &existing_schools {
+        ensure_school_access(state, claims, *school_id).await?;
+    }
+    ensure_can_set_status(state, claims, parsed.iter().any(|r| r.status.is_some())).await?;
+
+    let txn = audit::begin(state, audit).await?;
+    let existing: Vec<classes::Model> = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.is_in(existing_schools.iter().copied()))
+        .filter(in_current_year())
+        .order_by_asc(classes::Column::Id)
+        .all(&txn)
+        .await?;
+    let mut existing_by_key: HashMap<(i32, i32, i32), classes::Model> = HashMap::new();
+    for class in existing {
+        existing_by_key.entry((class.school_id, class.grade, class.class)).or_insert(class);
+    }
+
+    let mut seen: HashMap<(i32, i32, i32), usize> = HashMap::new();
+    let mut accepted = Vec::new();
+    for row in parsed {
+        if !existing_schools.contains(&row.school_id) {
+            errors.push(ImportRowError::new(
+                row.line,
+                Some("school_id"),
+                format!("school {} not found", row.school_id),
+            ));
+            continue;
+        }
+        let key = (row.school_id, row.grade, row.class);
+        if let Some(first_line) = seen.get(&key) {
+            errors.push(ImportRowError::new(
+                row.line,
+                Some("class"),
+                format!("duplicate grade {} class {}, first seen on line {}", row.grade, row.class, first_line),
+            ));
+            continue;
+        }
+        seen.insert(key, row.line);
+        accepted.push(row);
+    }
+    errors.sort_by_key(|e| e.line);
+
+    let mut results = Vec::new();
+    let commit = errors.is_empty() && !dry_run;
+    let mut year_ids: HashMap<i32, Option<i32>> = HashMap::new();
+    for row in accepted {
+        let existing = existing_by_key.remove(&(row.school_id, row.grade, row.class));
+        let action = if existing.is_some() { ACTION_UPDATE } else { ACTION_CREATE };
+        let mut class_id = existing.as_ref().map(|c| c.id);
+        if commit {
+            let class = match existing {
+                Some(class) => {
+                    if let Some(status) = row.status {
+                        ensure_dismissal_allowed(&txn, class.school_id, class.status, status).await?;
+                    }
+                    let mut active: classes::ActiveModel = class.into();
+                    active.name = Set(row.name.clone());
+                    if let Some(status) = row.status {
+                        active.status = Set(status);
+                    }
+                    if let Some(password) = row.password {
+                        active.password = Set(password);
+                    }
+                    active.update(&txn).await?
+                }
+                None => {
+                    let academic_year_id = match year_ids.get(&row.school_id) {
+                        Some(id) => *id,
+                        None => {
+                            let id = current_year_id(&txn, row.school_id).await?;
+                            year_ids.insert(row.school_id, id);
+                            id
+                        }
+                    };
+                    let status = row.status.unwrap_or(CLASS_STATUS_DISMISSED);
+                    ensure_dismissal_allowed(&txn, row.school_id, CLASS_STATUS_DISMISSED, status).await?;
+                    classes::ActiveModel {
+                        name: Set(row.name.clone()),
+                        grade: Set(row.grade),
+                        class: Set(row.class),
+                        school_id: Set(row.school_id),
+                        status: Set(status),
+                        password: Set(row.password.unwrap_or_default()),
+                        academic_year_id: Set(academic_year_id),
+                        ..Default::default()
+                    }
+                    .insert(&txn)
+                    .await?
+                }
+            };
+            class_id = Some(class.id);
+        }
+        results.push(ClassImportRowResult {
+            line: row.line,
+            action: action.to_string(),
+            class_id,
+            school_id: row.school_id,
+            grade: row.grade,
+            class: row.class,
+            name: row.name,
+        });
+    }
+
+    if commit {
+        txn.commit().await?;
+    } else {
+        txn.rollback().await?;
+    }
+    Ok(ClassImportReport {
+        dry_run,
+        committed: commit,
+        total_rows,
+        created: results.iter().filter(|r| r.action == ACTION_CREATE).count(),
+        updated: results.iter().filter(|r| r.action == ACTION_UPDATE).count(),
+        rows: results,
+        errors,
+    })
+}
@@ -0,0 +1,222 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::school_membership_api::is_active_member;
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{CLASS_TEACHER_ROLE_CO_TEACHER, CLASS_TEACHER_ROLE_HEAD, CLASS_TEACHER_ROLE_OBSERVER};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::{DateTime, Utc};
+use data_model::{classes, teacher_classes, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct ClassTeacherInvitePayload {
+    pub user_id: i32,
+    /// co_teacher 或 observer, 默认 co_teacher
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ClassTeacherInfo {
+    pub user_id: i32,
+    pub user_name: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 班级还没有班主任时第一个绑定的教师成为班主任, 否则为协同教师
+pub async fn default_binding_role<C: ConnectionTrait>(db: &C, class_id: i32) -> Result<String, DbErr> {
+    let head = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(class_id))
+        .filter(teacher_classes::Column::Role.eq(CLASS_TEACHER_ROLE_HEAD))
+        .one(db)
+        .await?;
+    let role = if head.is_some() { CLASS_TEACHER_ROLE_CO_TEACHER } else { CLASS_TEACHER_ROLE_HEAD };
+    Ok(role.to_string())
+}
+
+/// 两个教师同时绑定还没有班主任的班级时, 后提交的一方违反每个班级一个班主任的唯一索引
+pub fn map_head_conflict(err: DbErr) -> AppError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => AppError::business_logic(
+            "CLASS_HEAD_CONFLICT",
+            "Another teacher became the head of this class at the same time, please try again",
+        ),
+        _ => err.into(),
+    }
+}
+
+/// 班主任以绑定的角色为准, 值班安排里的班主任由绑定同步生成
+pub async fn is_class_head<C: ConnectionTrait>(db: &C, user_id: i32, class_id: i32) -> Result<bool, DbErr> {
+    let binding = teacher_classes::Entity::find_by_id((user_id, class_id)).one(db).await?;
+    Ok(binding.is_some_and(|tc| tc.role == CLASS_TEACHER_ROLE_HEAD))
+}
+
+/// 管理员或班级的班主任可以管理协同教师
+async fn ensure_class_head(state: &AppState, claims: &Claims, class_id: i32) -> Result<(), AppError> {
+    if claims.is_admin() {
+        return Ok(());
+    }
+    if !is_class_head(&state.db, claims.user_id, class_id).await? {
+        return Err(AppError::forbidden(format!("manage teachers of class {}", class_id)));
+    }
+    Ok(())
+}
+
+async fn find_class(state: &AppState, claims: &Claims, class_id: i32) -> Result<classes::Model, AppError> {
+    let class = classes::Entity::find_live_by_id(class_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(class_id)))?;
+    ensure_school_access(state, claims, class.school_id).await?;
+    Ok(class)
+}
+
+// Get teachers of a class
+#[handler]
+pub async fn get_list(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<Vec<ClassTeacherInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let class = find_class(&state, claims, id.into_inner()).await?;
+    let list = get_list_impl(&state, class.id).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(state: &AppState, class_id: i32) -> Result<Vec<ClassTeacherInfo>, AppError> {
+    let bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::ClassId.eq(class_id))
+        .order_by_asc(teacher_classes::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    let user_ids: Vec<i32> = bindings.iter().map(|tc| tc.user_id).collect();
+    let users_map: HashMap<i32, String> = if user_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find_live()
+            .filter(users::Column::Id.is_in(user_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, display_name(&u)))
+            .collect()
+    };
+    // 班主任排在最前面, 已删除的用户不显示
+    let mut list: Vec<ClassTeacherInfo> = bindings
+        .into_iter()
+        .filter_map(|tc| {
+            users_map.get(&tc.user_id).map(|user_name| ClassTeacherInfo {
+                user_name: user_name.clone(),
+                user_id: tc.user_id,
+                role: tc.role,
+                created_at: tc.created_at.into(),
+            })
+        })
+        .collect();
+    list.sort_by_key(|t| t.role != CLASS_TEACHER_ROLE_HEAD);
+    Ok(list)
+}
+
+// Invite a co-teacher or observer to a class
+#[handler]
+pub async fn invite(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<ClassTeacherInvitePayload>,
+) -> Result<ApiResponse<ClassTeacherInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let class = find_class(&state, claims, id.into_inner()).await?;
+    ensure_class_head(&state, claims, class.id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let teacher = invite_impl(&state, &audit, class, req.into_inner()).await?;
+    Ok(ApiResponse::success(teacher))
+}
+
+pub async fn invite_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    class: classes::Model,
+    req: ClassTeacherInvitePayload,
+) -> Result<ClassTeacherInfo, AppError> {
+    let role = req.role.unwrap_or_else(|| CLASS_TEACHER_ROLE_CO_TEACHER.to_string());
+    if ![CLASS_TEACHER_ROLE_CO_TEACHER, CLASS_TEACHER_ROLE_OBSERVER].contains(&role.as_str()) {
+        return Err(AppError::validation(format!("invalid role: {}", role)));
+    }
+    let user = users::Entity::find_live_by_id(req.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(req.user_id)))?;
+    if !is_active_member(&state.db, user.id, class.school_id).await? {
+        return Err(AppError::validation("user does not belong to the school of this class"));
+    }
+    let existing = teacher_classes::Entity::find_by_id((user.id, class.id))
+        .one(&state.db)
+        .await?;
+    if existing.is_some() {
+        return Err(already_bound());
+    }
+    let txn = audit::begin(state, audit).await?;
+    let binding = teacher_classes::ActiveModel {
+        user_id: Set(user.id),
+        class_id: Set(class.id),
+        role: Set(role),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| match err.sql_err() {
+        // 并发邀请同一个教师时由主键兜底
+        Some(SqlErr::UniqueConstraintViolation(_)) => already_bound(),
+        _ => err.into(),
+    })?;
+    txn.commit().await?;
+    Ok(ClassTeacherInfo {
+        user_id: user.id,
+        user_name: display_name(&user),
+        role: binding.role,
+        created_at: binding.created_at.into(),
+    })
+}
+
+fn already_bound() -> AppError {
+    AppError::business_logic("ALREADY_BOUND", "User is already a teacher of this class")
+}
+
+// Remove a co-teacher or observer from a class
+#[handler]
+pub async fn remove(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    user_id: PathParam<i32>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let class = find_class(&state, claims, id.into_inner()).await?;
+    ensure_class_head(&state, claims, class.id).await?;
+    let audit = AuditContext::from_depot(depot);
+    remove_impl(&state, &audit, class.id, user_id.into_inner()).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn remove_impl(state: &AppState, audit: &AuditContext, class_id: i32, user_id: i32) -> Result<(), AppError> {
+    let binding = teacher_classes::Entity::find_by_id((user_id, class_id))
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("binding for this class".to_string(), None))?;
+    if binding.role == CLASS_TEACHER_ROLE_HEAD {
+        return Err(AppError::business_logic(
+            "CANNOT_REMOVE_HEAD",
+            "Head teacher cannot be removed",
+        ));
+    }
+    let txn = audit::begin(state, audit).await?;
+    binding.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,519 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::stats_api::{log_range_filter, resolve_scope};
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::constants::{
+    CLASS_STATUS_DISMISSED, CLASS_STATUS_DISMISSING, DISMISSAL_DEFAULT_TOLERANCE_MINUTES,
+    DISMISSAL_REPORT_DEFAULT_DAYS, DISMISSAL_REPORT_MAX_RANGE_DAYS,
+};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::{from_str_optional, local_datetime, local_day_start};
+use crate::utils::export::{self, ExportFormat, ExportRecord, VecSource};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Utc};
+use data_model::{classes, dismissal_schedules, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const GROUP_BY_CLASS: &str = "class";
+const GROUP_BY_GRADE: &str = "grade";
+const GROUP_BY_TEACHER: &str = "teacher";
+const GROUP_BY_EVENTS: &str = "events";
+
+const PUNCTUALITY_ON_TIME: &str = "on_time";
+const PUNCTUALITY_LATE: &str = "late";
+const PUNCTUALITY_EARLY: &str = "early";
+const PUNCTUALITY_UNSCHEDULED: &str = "unscheduled";
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DismissalReportParams {
+    /// 管理员不传时统计所有学校, 其他用户默认自己所在的学校
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    /// 按本地日期, 包含这一天, 默认最近 4 周
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub from: Option<NaiveDate>,
+    /// 按本地日期, 包含这一天, 默认今天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub to: Option<NaiveDate>,
+    /// 比计划晚超过多少分钟算晚放学, 默认 5
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub late_minutes: Option<i64>,
+    /// 比计划早超过多少分钟算早放学, 默认 5
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub early_minutes: Option<i64>,
+    /// 只返回晚放学和早放学的记录, 仅对 events 有效
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub outliers_only: Option<bool>,
+    /// 默认 json, 也可以是 csv, xlsx 或 jsonl
+    pub format: Option<String>,
+}
+
+/// 一个班级一天的放学: 当天第一次改为放学中, 到随后改为已放学
+#[derive(Serialize, Debug)]
+pub struct DismissalEvent {
+    pub date: NaiveDate,
+    pub school_id: i32,
+    pub class_id: i32,
+    pub class_name: String,
+    pub grade: i32,
+    pub class: i32,
+    /// 开始放学的操作人
+    pub teacher_id: Option<i32>,
+    pub teacher_name: Option<String>,
+    pub scheduled_at: Option<DateTime<Utc>>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// 实际开始减计划时间, 正数为晚
+    pub delay_seconds: Option<i64>,
+    /// 放学中持续的时间
+    pub duration_seconds: Option<i64>,
+    /// on_time, late, early, 没有计划时为 unscheduled
+    pub punctuality: String,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct DismissalSummary {
+    pub dismissals: usize,
+    /// 有计划时间的次数
+    pub scheduled: usize,
+    pub avg_delay_seconds: Option<f64>,
+    pub max_delay_seconds: Option<i64>,
+    pub avg_duration_seconds: Option<f64>,
+    pub late: usize,
+    pub early: usize,
+    /// 准时次数 / 有计划时间的次数
+    pub on_time_rate: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WeeklyTrend {
+    /// 周一
+    pub week_start: NaiveDate,
+    #[serde(flatten)]
+    pub summary: DismissalSummary,
+    /// 平均延迟与上一周相比的变化, 正数为更晚
+    pub delay_change_seconds: Option<f64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DismissalGroupReport {
+    pub school_id: i32,
+    /// 按班级统计时有值
+    pub class_id: Option<i32>,
+    pub class_name: Option<String>,
+    /// 按班级或年级统计时有值
+    pub grade: Option<i32>,
+    /// 按教师统计时有值, 没有记录操作人的放学归到空教师
+    pub teacher_id: Option<i32>,
+    pub teacher_name: Option<String>,
+    #[serde(flatten)]
+    pub summary: DismissalSummary,
+    pub weeks: Vec<WeeklyTrend>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DismissalReport {
+    pub group_by: String,
+    pub school_id: Option<i32>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+    pub summary: DismissalSummary,
+    /// 晚放学次数多的排在前面
+    pub groups: Vec<DismissalGroupReport>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct DismissalRow {
+    class_id: i32,
+    school_id: i32,
+    actor_id: Option<i32>,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+struct ReportRange {
+    from: NaiveDate,
+    to: NaiveDate,
+    late_minutes: i64,
+    early_minutes: i64,
+}
+
+impl ReportRange {
+    fn from_params(params: &DismissalReportParams) -> Result<Self, AppError> {
+        let to = params.to.unwrap_or_else(|| Local::now().date_naive());
+        let from = params
+            .from
+            .unwrap_or(to - Duration::days(DISMISSAL_REPORT_DEFAULT_DAYS - 1));
+        if to < from {
+            return Err(AppError::validation("to must not be earlier than from"));
+        }
+        if (to - from).num_days() >= DISMISSAL_REPORT_MAX_RANGE_DAYS {
+            return Err(AppError::validation(format!(
+                "date range must not exceed {} days",
+                DISMISSAL_REPORT_MAX_RANGE_DAYS
+            )));
+        }
+        let late_minutes = params.late_minutes.unwrap_or(DISMISSAL_DEFAULT_TOLERANCE_MINUTES);
+        let early_minutes = params.early_minutes.unwrap_or(DISMISSAL_DEFAULT_TOLERANCE_MINUTES);
+        if !(0..=240).contains(&late_minutes) || !(0..=240).contains(&early_minutes) {
+            return Err(AppError::validation("late_minutes and early_minutes must be between 0 and 240"));
+        }
+        Ok(Self {
+            from,
+            to,
+            late_minutes,
+            early_minutes,
+        })
+    }
+}
+
+impl ExportRecord for DismissalEvent {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "date",
+            "school_id",
+            "class_id",
+            "class_name",
+            "grade",
+            "class",
+            "teacher_id",
+            "teacher_name",
+            "scheduled_at",
+            "started_at",
+            "ended_at",
+            "delay_seconds",
+            "duration_seconds",
+            "punctuality",
+        ]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.date.to_string(),
+            self.school_id.to_string(),
+            self.class_id.to_string(),
+            self.class_name.clone(),
+            self.grade.to_string(),
+            self.class.to_string(),
+            optional(self.teacher_id),
+            self.teacher_name.clone().unwrap_or_default(),
+            optional(self.scheduled_at.map(|t| t.to_rfc3339())),
+            self.started_at.to_rfc3339(),
+            optional(self.ended_at.map(|t| t.to_rfc3339())),
+            optional(self.delay_seconds),
+            optional(self.duration_seconds),
+            self.punctuality.clone(),
+        ]
+    }
+}
+
+impl ExportRecord for DismissalGroupReport {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "school_id",
+            "class_id",
+            "class_name",
+            "grade",
+            "teacher_id",
+            "teacher_name",
+            "dismissals",
+            "scheduled",
+            "avg_delay_seconds",
+            "max_delay_seconds",
+            "avg_duration_seconds",
+            "late",
+            "early",
+            "on_time_rate",
+        ]
+    }
+
+    fn values(&self) -> Vec<String> {
+        let s = &self.summary;
+        vec![
+            self.school_id.to_string(),
+            optional(self.class_id),
+            self.class_name.clone().unwrap_or_default(),
+            optional(self.grade),
+            optional(self.teacher_id),
+            self.teacher_name.clone().unwrap_or_default(),
+            s.dismissals.to_string(),
+            s.scheduled.to_string(),
+            optional(s.avg_delay_seconds.map(|v| format!("{:.1}", v))),
+            optional(s.max_delay_seconds),
+            optional(s.avg_duration_seconds.map(|v| format!("{:.1}", v))),
+            s.late.to_string(),
+            s.early.to_string(),
+            optional(s.on_time_rate.map(|v| format!("{:.3}", v))),
+        ]
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+// Get dismissal punctuality report grouped by class, grade or teacher, or the raw events
+#[handler]
+pub async fn get_report(
+    depot: &mut Depot,
+    group_by: PathParam<String>,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let group_by = group_by.into_inner();
+    if ![GROUP_BY_CLASS, GROUP_BY_GRADE, GROUP_BY_TEACHER, GROUP_BY_EVENTS].contains(&group_by.as_str()) {
+        return Err(AppError::validation(format!("invalid group_by: {}", group_by)));
+    }
+    let params = req.parse_queries::<DismissalReportParams>()?;
+    let format = match params.format.as_deref() {
+        None | Some("json") => None,
+        other => Some(ExportFormat::parse(other)?),
+    };
+    let range = ReportRange::from_params(&params)?;
+    let school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let events = load_events(&state, school_id, &range).await?;
+
+    if group_by == GROUP_BY_EVENTS {
+        let events: Vec<DismissalEvent> = if params.outliers_only.unwrap_or(false) {
+            events
+                .into_iter()
+                .filter(|e| e.punctuality == PUNCTUALITY_LATE || e.punctuality == PUNCTUALITY_EARLY)
+                .collect()
+        } else {
+            events
+        };
+        return match format {
+            Some(format) => export::render(res, state, format, "dismissal_events", VecSource::new(events)).await,
+            None => {
+                res.render(Json(ApiResponse::success(events)));
+                Ok(())
+            }
+        };
+    }
+
+    let groups = group_events(&group_by, &events);
+    match format {
+        Some(format) => {
+            let file_stem = format!("dismissal_by_{}", group_by);
+            export::render(res, state, format, &file_stem, VecSource::new(groups)).await
+        }
+        None => {
+            let report = DismissalReport {
+                group_by,
+                school_id,
+                from: range.from,
+                to: range.to,
+                late_minutes: range.late_minutes,
+                early_minutes: range.early_minutes,
+                summary: summarize(&events.iter().collect::<Vec<_>>()),
+                groups,
+            };
+            res.render(Json(ApiResponse::success(report)));
+            Ok(())
+        }
+    }
+}
+
+/// 状态记录里每次改为放学中的记录和它的下一条记录, 其余的计算在内存里做
+async fn load_events(
+    state: &AppState,
+    school_id: Option<i32>,
+    range: &ReportRange,
+) -> Result<Vec<DismissalEvent>, AppError> {
+    let (filter, values) = log_range_filter(
+        school_id.into(),
+        local_day_start(range.from),
+        local_day_start(range.to) + Duration::days(1),
+    );
+    let sql = format!(
+        "SELECT class_id, school_id, actor_id, created_at AS started_at, \
+                CASE WHEN next_status = {dismissed} THEN next_at END AS ended_at \
+         FROM ( \
+             SELECT class_id, school_id, actor_id, new_status, created_at, \
+                    LEAD(created_at) OVER w AS next_at, \
+                    LEAD(new_status) OVER w AS next_status \
+             FROM class_status_logs WHERE {} \
+             WINDOW w AS (PARTITION BY class_id ORDER BY id) \
+         ) t \
+         WHERE new_status = {dismissing} \
+         ORDER BY class_id, started_at",
+        filter,
+        dismissing = CLASS_STATUS_DISMISSING,
+        dismissed = CLASS_STATUS_DISMISSED,
+    );
+    let rows = DismissalRow::find_by_statement(Statement::from_sql_and_values(
+        state.db.get_database_backend(),
+        sql,
+        values,
+    ))
+    .all(&state.db)
+    .await?;
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let class_ids: HashSet<i32> = rows.iter().map(|r| r.class_id).collect();
+    let classes_map: HashMap<i32, classes::Model> = classes::Entity::find_live()
+        .filter(classes::Column::Id.is_in(class_ids))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|c| (c.id, c))
+        .collect();
+    let school_ids: HashSet<i32> = rows.iter().map(|r| r.school_id).collect();
+    let schedules: HashMap<(i32, Option<i32>, i32), NaiveTime> = dismissal_schedules::Entity::find()
+        .filter(dismissal_schedules::Column::SchoolId.is_in(school_ids))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|s| ((s.school_id, s.grade, s.weekday), s.dismiss_at))
+        .collect();
+    let actor_ids: HashSet<i32> = rows.iter().filter_map(|r| r.actor_id).collect();
+    let actors_map: HashMap<i32, String> = if actor_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find()
+            .filter(users::Column::Id.is_in(actor_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, display_name(&u)))
+            .collect()
+    };
+
+    let late_seconds = range.late_minutes * 60;
+    let early_seconds = range.early_minutes * 60;
+    let mut seen = HashSet::new();
+    let mut events = Vec::new();
+    for row in rows {
+        let Some(class) = classes_map.get(&row.class_id) else {
+            continue;
+        };
+        let date = row.started_at.with_timezone(&Local).date_naive();
+        // 同一天多次放学只算第一次
+        if !seen.insert((row.class_id, date)) {
+            continue;
+        }
+        let weekday = date.weekday().number_from_monday() as i32;
+        let scheduled_at = schedules
+            .get(&(row.school_id, Some(class.grade), weekday))
+            .or_else(|| schedules.get(&(row.school_id, None, weekday)))
+            .and_then(|time| local_datetime(date, *time));
+        let delay_seconds = scheduled_at.map(|s| (row.started_at - s).num_seconds());
+        let punctuality = match delay_seconds {
+            None => PUNCTUALITY_UNSCHEDULED,
+            Some(d) if d > late_seconds => PUNCTUALITY_LATE,
+            Some(d) if d < -early_seconds => PUNCTUALITY_EARLY,
+            Some(_) => PUNCTUALITY_ON_TIME,
+        };
+        events.push(DismissalEvent {
+            date,
+            school_id: row.school_id,
+            class_id: row.class_id,
+            class_name: class.name.clone(),
+            grade: class.grade,
+            class: class.class,
+            teacher_id: row.actor_id,
+            teacher_name: row.actor_id.and_then(|id| actors_map.get(&id).cloned()),
+            scheduled_at,
+            started_at: row.started_at,
+            ended_at: row.ended_at,
+            delay_seconds,
+            duration_seconds: row.ended_at.map(|e| (e - row.started_at).num_seconds()),
+            punctuality: punctuality.to_string(),
+        });
+    }
+    events.sort_by_key(|e| (e.date, e.started_at));
+    Ok(events)
+}
+
+fn group_events(group_by: &str, events: &[DismissalEvent]) -> Vec<DismissalGroupReport> {
+    let mut groups: HashMap<(i32, Option<i32>), Vec<&DismissalEvent>> = HashMap::new();
+    for event in events {
+        let key = match group_by {
+            GROUP_BY_CLASS => (event.school_id, Some(event.class_id)),
+            GROUP_BY_GRADE => (event.school_id, Some(event.grade)),
+            _ => (event.school_id, event.teacher_id),
+        };
+        groups.entry(key).or_default().push(event);
+    }
+    let mut reports: Vec<DismissalGroupReport> = groups
+        .into_iter()
+        .map(|((school_id, _), events)| {
+            let first = events[0];
+            let mut weeks: BTreeMap<NaiveDate, Vec<&DismissalEvent>> = BTreeMap::new();
+            for event in &events {
+                let week_start = event.date - Duration::days(event.date.weekday().num_days_from_monday() as i64);
+                weeks.entry(week_start).or_default().push(event);
+            }
+            let mut previous_delay: Option<f64> = None;
+            let weeks = weeks
+                .into_iter()
+                .map(|(week_start, week_events)| {
+                    let summary = summarize(&week_events);
+                    let delay_change_seconds = match (summary.avg_delay_seconds, previous_delay) {
+                        (Some(current), Some(previous)) => Some(current - previous),
+                        _ => None,
+                    };
+                    if summary.avg_delay_seconds.is_some() {
+                        previous_delay = summary.avg_delay_seconds;
+                    }
+                    WeeklyTrend {
+                        week_start,
+                        summary,
+                        delay_change_seconds,
+                    }
+                })
+                .collect();
+            DismissalGroupReport {
+                school_id,
+                class_id: (group_by == GROUP_BY_CLASS).then_some(first.class_id),
+                class_name: (group_by == GROUP_BY_CLASS).then(|| first.class_name.clone()),
+                grade: (group_by != GROUP_BY_TEACHER).then_some(first.grade),
+                teacher_id: (group_by == GROUP_BY_TEACHER).then_some(first.teacher_id).flatten(),
+                teacher_name: (group_by == GROUP_BY_TEACHER).then(|| first.teacher_name.clone()).flatten(),
+                summary: summarize(&events),
+                weeks,
+            }
+        })
+        .collect();
+    reports.sort_by(|a, b| {
+        let delay = |r: &DismissalGroupReport| r.summary.avg_delay_seconds.unwrap_or(0.0);
+        let key = |r: &DismissalGroupReport| (r.school_id, r.grade, r.class_id, r.teacher_id);
+        b.summary
+            .late
+            .cmp(&a.summary.late)
+            .then_with(|| delay(b).total_cmp(&delay(a)))
+            .then_with(|| key(a).cmp(&key(b)))
+    });
+    reports
+}
+
+fn summarize(events: &[&DismissalEvent]) -> DismissalSummary {
+    let delays: Vec<i64> = events.iter().filter_map(|e| e.delay_seconds).collect();
+    let durations: Vec<i64> = events.iter().filter_map(|e| e.duration_seconds).collect();
+    let count = |punctuality: &str| events.iter().filter(|e| e.punctuality == punctuality).count();
+    let on_time = count(PUNCTUALITY_ON_TIME);
+    DismissalSummary {
+        dismissals: events.len(),
+        scheduled: delays.len(),
+        avg_delay_seconds: average(&delays),
+        max_delay_seconds: delays.iter().copied().max(),
+        avg_duration_seconds: average(&durations),
+        late: count(PUNCTUALITY_LATE),
+        early: count(PUNCTUALITY_EARLY),
+        on_time_rate: (!delays.is_empty()).then(|| on_time as f64 / delays.len() as f64),
+    }
+}
+
+fn average(values: &[i64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64)
+}
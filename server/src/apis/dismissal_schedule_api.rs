@@ -0,0 +1,140 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::NaiveTime;
+use data_model::{dismissal_schedules, schools};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct DismissalScheduleItem {
+    /// 为空时是全校默认
+    #[validate(range(min = 1, max = 12))]
+    pub grade: Option<i32>,
+    /// 1 = 周一, 7 = 周日
+    #[validate(range(min = 1, max = 7))]
+    pub weekday: i32,
+    /// 例如 16:30:00
+    pub dismiss_at: NaiveTime,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct DismissalSchedulesPayload {
+    #[validate(nested)]
+    pub items: Vec<DismissalScheduleItem>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DismissalScheduleInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub grade: Option<i32>,
+    pub weekday: i32,
+    pub dismiss_at: NaiveTime,
+}
+
+impl From<dismissal_schedules::Model> for DismissalScheduleInfo {
+    fn from(s: dismissal_schedules::Model) -> Self {
+        Self {
+            id: s.id,
+            school_id: s.school_id,
+            grade: s.grade,
+            weekday: s.weekday,
+            dismiss_at: s.dismiss_at,
+        }
+    }
+}
+
+// Get dismissal schedules of a school
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<Vec<DismissalScheduleInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let list = get_list_impl(&state, school_id).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(state: &AppState, school_id: i32) -> Result<Vec<DismissalScheduleInfo>, AppError> {
+    let list = dismissal_schedules::Entity::find()
+        .filter(dismissal_schedules::Column::SchoolId.eq(school_id))
+        .order_by_asc(dismissal_schedules::Column::Grade)
+        .order_by_asc(dismissal_schedules::Column::Weekday)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(list)
+}
+
+// Replace all dismissal schedules of a school
+#[handler]
+pub async fn replace(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<DismissalSchedulesPayload>,
+) -> Result<ApiResponse<Vec<DismissalScheduleInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let list = replace_impl(&state, &audit, school_id, req.items).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn replace_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    school_id: i32,
+    items: Vec<DismissalScheduleItem>,
+) -> Result<Vec<DismissalScheduleInfo>, AppError> {
+    let mut seen = HashSet::new();
+    for item in &items {
+        if !seen.insert((item.grade, item.weekday)) {
+            return Err(AppError::validation(format!(
+                "duplicate schedule for grade {:?} on weekday {}",
+                item.grade, item.weekday
+            )));
+        }
+    }
+    let txn = audit::begin(state, audit).await?;
+    schools::Entity::find_live_by_id(school_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    dismissal_schedules::Entity::delete_many()
+        .filter(dismissal_schedules::Column::SchoolId.eq(school_id))
+        .exec(&txn)
+        .await?;
+    if !items.is_empty() {
+        let models: Vec<dismissal_schedules::ActiveModel> = items
+            .into_iter()
+            .map(|item| dismissal_schedules::ActiveModel {
+                school_id: Set(school_id),
+                grade: Set(item.grade),
+                weekday: Set(item.weekday),
+                dismiss_at: Set(item.dismiss_at),
+                ..Default::default()
+            })
+            .collect();
+        dismissal_schedules::Entity::insert_many(models).exec(&txn).await?;
+    }
+    txn.commit().await?;
+    get_list_impl(state, school_id).await
+}
@@ -0,0 +1,394 @@
+use crate::apis::academic_year_api::in_current_year;
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::school_hold_api::is_school_on_hold;
+use crate::apis::stats_api::{get_stats_impl, StatsInfo, StatsParams, StatsScope};
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{CLASS_STATUS_DISMISSED, CLASS_STATUS_DISMISSING, CLASS_STATUS_ONGOING};
+use crate::core::error::AppError;
+use crate::core::event_hub;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::{DateTime, Utc};
+use data_model::{classes, district_admins, districts, schools, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct DistrictCreatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct DistrictUpdatePayload {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DistrictAdminPayload {
+    pub user_id: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DistrictInfo {
+    pub id: i32,
+    pub name: String,
+    /// 学区内未删除的学校
+    pub school_count: u64,
+    pub admin_ids: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchDistrictsParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub name: Option<String>,
+}
+
+/// 学区放学看板里的一个学校, 只统计当前学年的班级
+#[derive(Serialize, Debug)]
+pub struct SchoolBoardInfo {
+    pub school_id: i32,
+    pub school_name: String,
+    pub classes: i64,
+    pub ongoing: i64,
+    pub dismissing: i64,
+    pub dismissed: i64,
+    pub on_hold: bool,
+    /// 当前连接的大屏(本实例)
+    pub connected_screens: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DistrictBoardInfo {
+    pub district_id: i32,
+    pub name: String,
+    pub classes: i64,
+    pub ongoing: i64,
+    pub dismissing: i64,
+    pub dismissed: i64,
+    pub schools: Vec<SchoolBoardInfo>,
+}
+
+pub async fn is_district_admin<C: ConnectionTrait>(db: &C, user_id: i32, district_id: i32) -> Result<bool, DbErr> {
+    let count = district_admins::Entity::find()
+        .inner_join(users::Entity)
+        .filter(district_admins::Column::UserId.eq(user_id))
+        .filter(district_admins::Column::DistrictId.eq(district_id))
+        .filter(users::Column::DeletedAt.is_null())
+        .count(db)
+        .await?;
+    Ok(count > 0)
+}
+
+/// 管理员可以管理所有学区, 学区管理员只能管理自己的学区
+pub async fn ensure_district_access(state: &AppState, claims: &Claims, district_id: i32) -> Result<(), AppError> {
+    if claims.is_admin() || is_district_admin(&state.db, claims.user_id, district_id).await? {
+        return Ok(());
+    }
+    Err(AppError::forbidden(format!("manage district {}", district_id)))
+}
+
+fn ensure_admin(claims: &Claims, action: &str) -> Result<(), AppError> {
+    if !claims.is_admin() {
+        return Err(AppError::forbidden(action.to_string()));
+    }
+    Ok(())
+}
+
+fn map_name_violation(err: DbErr) -> AppError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::business_logic("DISTRICT_ALREADY_EXISTS", "A district with this name already exists")
+        }
+        _ => err.into(),
+    }
+}
+
+async fn find_district<C: ConnectionTrait>(db: &C, id: i32) -> Result<districts::Model, AppError> {
+    districts::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::not_found("districts".to_string(), Some(id)))
+}
+
+async fn enrich_districts(state: &AppState, list: Vec<districts::Model>) -> Result<Vec<DistrictInfo>, AppError> {
+    if list.is_empty() {
+        return Ok(vec![]);
+    }
+    let ids: Vec<i32> = list.iter().map(|d| d.id).collect();
+    let school_counts: HashMap<i32, i64> = schools::Entity::find_live()
+        .select_only()
+        .column(schools::Column::DistrictId)
+        .column_as(Expr::col(schools::Column::Id).count(), "count")
+        .filter(schools::Column::DistrictId.is_in(ids.clone()))
+        .group_by(schools::Column::DistrictId)
+        .into_tuple::<(i32, i64)>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .collect();
+    let admins = district_admins::Entity::find()
+        .filter(district_admins::Column::DistrictId.is_in(ids))
+        .order_by_asc(district_admins::Column::CreatedAt)
+        .all(&state.db)
+        .await?;
+    Ok(list
+        .into_iter()
+        .map(|d| DistrictInfo {
+            school_count: school_counts.get(&d.id).copied().unwrap_or(0) as u64,
+            admin_ids: admins.iter().filter(|a| a.district_id == d.id).map(|a| a.user_id).collect(),
+            id: d.id,
+            name: d.name,
+            created_at: d.created_at.into(),
+        })
+        .collect())
+}
+
+// Get Districts List, district admins only see their own districts
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<DistrictInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let params = req.parse_queries::<SearchDistrictsParams>()?;
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    let mut query = districts::Entity::find();
+    crate::filter_if_some!(query, districts::Column::Name, params.name, like);
+    if !claims.is_admin() {
+        query = query.filter(
+            districts::Column::Id.in_subquery(
+                Query::select()
+                    .column(district_admins::Column::DistrictId)
+                    .from(district_admins::Entity)
+                    .and_where(district_admins::Column::UserId.eq(claims.user_id))
+                    .to_owned(),
+            ),
+        );
+    }
+    let paginator = query.order_by_asc(districts::Column::Id).paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let list = paginator.fetch_page(page - 1).await?;
+    let list = enrich_districts(&state, list).await?;
+    Ok(ApiResponse::success(PagingResponse { list, total, page }))
+}
+
+// Get District by ID
+#[handler]
+pub async fn get_by_id(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<DistrictInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    ensure_district_access(&state, claims, id).await?;
+    let district = find_district(&state.db, id).await?;
+    let mut list = enrich_districts(&state, vec![district]).await?;
+    Ok(ApiResponse::success(list.remove(0)))
+}
+
+// Create District (admin only)
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    req: JsonBody<DistrictCreatePayload>,
+) -> Result<ApiResponse<districts::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    ensure_admin(claims, "create districts")?;
+    let req = req.into_inner();
+    req.validate()?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    let district = districts::ActiveModel {
+        name: Set(req.name),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(map_name_violation)?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(district))
+}
+
+// Update District
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<DistrictUpdatePayload>,
+) -> Result<ApiResponse<districts::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    ensure_district_access(&state, claims, id).await?;
+    let req = req.into_inner();
+    req.validate()?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    let mut active: districts::ActiveModel = find_district(&txn, id).await?.into();
+    if let Some(name) = req.name {
+        active.name = Set(name);
+    }
+    active.updated_at = Set(Utc::now().into());
+    let district = active.update(&txn).await.map_err(map_name_violation)?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(district))
+}
+
+// Delete District (admin only), its schools leave the district
+#[handler]
+pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    ensure_admin(claims, "delete districts")?;
+    let id = id.into_inner();
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    find_district(&txn, id).await?.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+// Make a user an admin of the district (admin only)
+#[handler]
+pub async fn add_admin(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<DistrictAdminPayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    ensure_admin(claims, "assign district admins")?;
+    let id = id.into_inner();
+    let user_id = req.user_id;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    find_district(&txn, id).await?;
+    users::Entity::find_live_by_id(user_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(user_id)))?;
+    let existing = district_admins::Entity::find_by_id((user_id, id)).one(&txn).await?;
+    if existing.is_none() {
+        district_admins::ActiveModel {
+            user_id: Set(user_id),
+            district_id: Set(id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+// Remove a district admin (admin only)
+#[handler]
+pub async fn remove_admin(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    user_id: PathParam<i32>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    ensure_admin(claims, "remove district admins")?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    let result = district_admins::Entity::delete_by_id((user_id.into_inner(), id.into_inner()))
+        .exec(&txn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found("district admin".to_string(), None));
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+// Dashboard statistics across all schools of the district
+#[handler]
+pub async fn get_stats(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: &mut Request,
+) -> Result<ApiResponse<StatsInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    ensure_district_access(&state, claims, id).await?;
+    find_district(&state.db, id).await?;
+    let params = req.parse_queries::<StatsParams>()?;
+    let stats = get_stats_impl(&state, StatsScope::District(id), params.from, params.to).await?;
+    Ok(ApiResponse::success(stats))
+}
+
+// Current dismissal status of every school in the district
+#[handler]
+pub async fn get_board(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<DistrictBoardInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    ensure_district_access(&state, claims, id).await?;
+    let board = get_board_impl(&state, id).await?;
+    Ok(ApiResponse::success(board))
+}
+
+pub async fn get_board_impl(state: &AppState, id: i32) -> Result<DistrictBoardInfo, AppError> {
+    let district = find_district(&state.db, id).await?;
+    let school_list = schools::Entity::find_live()
+        .filter(schools::Column::DistrictId.eq(id))
+        .order_by_asc(schools::Column::Id)
+        .all(&state.db)
+        .await?;
+    let counts: HashMap<(i32, i32), i64> = classes::Entity::find_live()
+        .select_only()
+        .column(classes::Column::SchoolId)
+        .column(classes::Column::Status)
+        .column_as(Expr::col(classes::Column::Id).count(), "count")
+        .filter(in_current_year())
+        .filter(StatsScope::District(id).condition(classes::Column::SchoolId))
+        .group_by(classes::Column::SchoolId)
+        .group_by(classes::Column::Status)
+        .into_tuple::<(i32, i32, i64)>()
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|(school_id, status, count)| ((school_id, status), count))
+        .collect();
+    let count = |school_id: i32, status: i32| counts.get(&(school_id, status)).copied().unwrap_or(0);
+
+    let mut schools = Vec::with_capacity(school_list.len());
+    for school in school_list {
+        let ongoing = count(school.id, CLASS_STATUS_ONGOING);
+        let dismissing = count(school.id, CLASS_STATUS_DISMISSING);
+        let dismissed = count(school.id, CLASS_STATUS_DISMISSED);
+        schools.push(SchoolBoardInfo {
+            school_id: school.id,
+            classes: ongoing + dismissing + dismissed,
+            ongoing,
+            dismissing,
+            dismissed,
+            on_hold: is_school_on_hold(&state.db, school.id).await?,
+            connected_screens: event_hub::subscriber_count(school.id),
+            school_name: school.name,
+        });
+    }
+    Ok(DistrictBoardInfo {
+        district_id: district.id,
+        name: district.name,
+        classes: schools.iter().map(|s| s.classes).sum(),
+        ongoing: schools.iter().map(|s| s.ongoing).sum(),
+        dismissing: schools.iter().map(|s| s.dismissing).sum(),
+        dismissed: schools.iter().map(|s| s.dismissed).sum(),
+        schools,
+    })
+}
@@ -0,0 +1,447 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::class_api::{self, SearchClassesParams};
+use crate::apis::school_api::{self, SearchSchoolsParams};
+use crate::apis::stats_api::resolve_scope;
+use crate::apis::user_api::{self, SearchUsersParams};
+use crate::core::app::AppState;
+use crate::core::error::AppError;
+use crate::utils::convert::{from_str_optional, local_day_start};
+use crate::utils::export::{self, ExportFormat, ExportRecord, ExportSource, EXPORT_BATCH_SIZE};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use data_model::{class_status_logs, classes, schools, users};
+use salvo::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ExportParams {
+    /// csv, xlsx 或 jsonl, 默认 csv
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchStatusHistoryParams {
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub class_id: Option<i32>,
+    /// 按本地日期, 包含这一天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub from: Option<NaiveDate>,
+    /// 按本地日期, 包含这一天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SchoolExportRecord {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ClassExportRecord {
+    pub id: i32,
+    pub name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub school_id: i32,
+    pub school_name: String,
+    pub status: i32,
+    pub academic_year_id: Option<i32>,
+    /// 用户名(角色), 多个用分号分隔
+    pub teachers: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UserExportRecord {
+    pub id: i32,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub display_name: Option<String>,
+    pub employee_no: Option<String>,
+    pub phone: Option<String>,
+    pub school_id: Option<i32>,
+    pub school_name: Option<String>,
+    /// 角色名, 多个用分号分隔
+    pub roles: String,
+    /// 班级名(角色), 多个用分号分隔
+    pub classes: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StatusHistoryExportRecord {
+    pub id: i64,
+    pub changed_at: DateTime<Utc>,
+    pub school_id: i32,
+    pub class_id: i32,
+    pub class_name: String,
+    pub grade: Option<i32>,
+    pub class: Option<i32>,
+    pub old_status: i32,
+    pub new_status: i32,
+    pub actor_id: Option<i32>,
+    pub actor_name: Option<String>,
+}
+
+fn opt<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+impl ExportRecord for SchoolExportRecord {
+    fn headers() -> &'static [&'static str] {
+        &["id", "name", "created_at"]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![self.id.to_string(), self.name.clone(), self.created_at.to_rfc3339()]
+    }
+}
+
+impl ExportRecord for ClassExportRecord {
+    fn headers() -> &'static [&'static str] {
+        &["id", "name", "grade", "class", "school_id", "school_name", "status", "academic_year_id", "teachers"]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.grade.to_string(),
+            self.class.to_string(),
+            self.school_id.to_string(),
+            self.school_name.clone(),
+            self.status.to_string(),
+            opt(&self.academic_year_id),
+            self.teachers.clone(),
+        ]
+    }
+}
+
+impl ExportRecord for UserExportRecord {
+    fn headers() -> &'static [&'static str] {
+        &["id", "username", "real_name", "display_name", "employee_no", "phone", "school_id", "school_name", "roles", "classes", "created_at"]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.username.clone(),
+            opt(&self.real_name),
+            opt(&self.display_name),
+            opt(&self.employee_no),
+            opt(&self.phone),
+            opt(&self.school_id),
+            opt(&self.school_name),
+            self.roles.clone(),
+            self.classes.clone(),
+            self.created_at.to_rfc3339(),
+        ]
+    }
+}
+
+impl ExportRecord for StatusHistoryExportRecord {
+    fn headers() -> &'static [&'static str] {
+        &[
+            "id", "changed_at", "school_id", "class_id", "class_name", "grade", "class", "old_status", "new_status",
+            "actor_id", "actor_name",
+        ]
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.changed_at.to_rfc3339(),
+            self.school_id.to_string(),
+            self.class_id.to_string(),
+            self.class_name.clone(),
+            opt(&self.grade),
+            opt(&self.class),
+            self.old_status.to_string(),
+            self.new_status.to_string(),
+            opt(&self.actor_id),
+            opt(&self.actor_name),
+        ]
+    }
+}
+
+/// 按 id 递增分批读取, 导出期间新增的行不会打乱顺序
+struct SchoolSource {
+    query: Select<schools::Entity>,
+    after: i32,
+}
+
+impl ExportSource for SchoolSource {
+    type Record = SchoolExportRecord;
+
+    async fn next_batch(&mut self, state: &AppState) -> Result<Vec<SchoolExportRecord>, AppError> {
+        let batch = self
+            .query
+            .clone()
+            .filter(schools::Column::Id.gt(self.after))
+            .order_by_asc(schools::Column::Id)
+            .limit(EXPORT_BATCH_SIZE)
+            .all(&state.db)
+            .await?;
+        if let Some(last) = batch.last() {
+            self.after = last.id;
+        }
+        Ok(batch
+            .into_iter()
+            .map(|s| SchoolExportRecord {
+                id: s.id,
+                name: s.name,
+                created_at: s.created_at.into(),
+            })
+            .collect())
+    }
+}
+
+struct ClassSource {
+    query: Select<classes::Entity>,
+    after: i32,
+}
+
+impl ExportSource for ClassSource {
+    type Record = ClassExportRecord;
+
+    async fn next_batch(&mut self, state: &AppState) -> Result<Vec<ClassExportRecord>, AppError> {
+        let batch = self
+            .query
+            .clone()
+            .filter(classes::Column::Id.gt(self.after))
+            .order_by_asc(classes::Column::Id)
+            .limit(EXPORT_BATCH_SIZE)
+            .all(&state.db)
+            .await?;
+        if let Some(last) = batch.last() {
+            self.after = last.id;
+        }
+        let list = class_api::enrich_classes_with_details(state, batch).await?;
+        Ok(list
+            .into_iter()
+            .map(|c| ClassExportRecord {
+                teachers: c
+                    .teacher_infos
+                    .iter()
+                    .map(|t| format!("{}({})", t.user_name, t.role))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                id: c.id,
+                name: c.name,
+                grade: c.grade,
+                class: c.class,
+                school_id: c.school_id,
+                school_name: c.school_name,
+                status: c.status,
+                academic_year_id: c.academic_year_id,
+            })
+            .collect())
+    }
+}
+
+struct UserSource {
+    query: Select<users::Entity>,
+    after: i32,
+}
+
+impl ExportSource for UserSource {
+    type Record = UserExportRecord;
+
+    async fn next_batch(&mut self, state: &AppState) -> Result<Vec<UserExportRecord>, AppError> {
+        let batch = self
+            .query
+            .clone()
+            .filter(users::Column::Id.gt(self.after))
+            .order_by_asc(users::Column::Id)
+            .limit(EXPORT_BATCH_SIZE)
+            .all(&state.db)
+            .await?;
+        if let Some(last) = batch.last() {
+            self.after = last.id;
+        }
+        let list = user_api::enrich_users_with_details(state, batch).await?;
+        Ok(list
+            .into_iter()
+            .map(|u| UserExportRecord {
+                roles: u
+                    .role_infos
+                    .iter()
+                    .map(|r| r.role_name.clone())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                classes: u
+                    .class_infos
+                    .iter()
+                    .map(|c| format!("{}({})", c.class_name, c.role))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                id: u.id,
+                username: u.username,
+                real_name: u.real_name,
+                display_name: u.display_name,
+                employee_no: u.employee_no,
+                phone: u.phone,
+                school_id: u.school_id,
+                school_name: u.school_name,
+                created_at: u.created_at,
+            })
+            .collect())
+    }
+}
+
+struct StatusHistorySource {
+    query: Select<class_status_logs::Entity>,
+    after: i64,
+}
+
+impl ExportSource for StatusHistorySource {
+    type Record = StatusHistoryExportRecord;
+
+    async fn next_batch(&mut self, state: &AppState) -> Result<Vec<StatusHistoryExportRecord>, AppError> {
+        let batch = self
+            .query
+            .clone()
+            .filter(class_status_logs::Column::Id.gt(self.after))
+            .order_by_asc(class_status_logs::Column::Id)
+            .limit(EXPORT_BATCH_SIZE)
+            .all(&state.db)
+            .await?;
+        let Some(last) = batch.last() else {
+            return Ok(vec![]);
+        };
+        self.after = last.id;
+
+        let class_ids: Vec<i32> = batch.iter().map(|l| l.class_id).collect();
+        // 历史记录里的班级和操作人可能已被删除, 仍然显示名称
+        let classes_map: HashMap<i32, classes::Model> = classes::Entity::find()
+            .filter(classes::Column::Id.is_in(class_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect();
+        let actor_ids: Vec<i32> = batch.iter().filter_map(|l| l.actor_id).collect();
+        let actors_map: HashMap<i32, String> = if actor_ids.is_empty() {
+            HashMap::new()
+        } else {
+            users::Entity::find()
+                .filter(users::Column::Id.is_in(actor_ids))
+                .all(&state.db)
+                .await?
+                .into_iter()
+                .map(|u| (u.id, user_api::display_name(&u)))
+                .collect()
+        };
+        Ok(batch
+            .into_iter()
+            .map(|l| {
+                let class = classes_map.get(&l.class_id);
+                StatusHistoryExportRecord {
+                    id: l.id,
+                    changed_at: l.created_at.into(),
+                    school_id: l.school_id,
+                    class_id: l.class_id,
+                    class_name: class.map(|c| c.name.clone()).unwrap_or_default(),
+                    grade: class.map(|c| c.grade),
+                    class: class.map(|c| c.class),
+                    old_status: l.old_status,
+                    new_status: l.new_status,
+                    actor_id: l.actor_id,
+                    actor_name: l.actor_id.and_then(|id| actors_map.get(&id).cloned()),
+                }
+            })
+            .collect())
+    }
+}
+
+fn export_format(req: &mut Request) -> Result<ExportFormat, AppError> {
+    let params = req.parse_queries::<ExportParams>()?;
+    ExportFormat::parse(params.format.as_deref())
+}
+
+// Export schools
+#[handler]
+pub async fn export_schools(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let format = export_format(req)?;
+    let mut params = req.parse_queries::<SearchSchoolsParams>()?;
+    // 和统计一样, 非管理员只能导出自己所属的学校
+    params.id = resolve_scope(&state, claims, params.id).await?;
+    let source = SchoolSource {
+        query: school_api::search_query(params),
+        after: 0,
+    };
+    export::render(res, state, format, "schools", source).await
+}
+
+// Export classes with their teachers
+#[handler]
+pub async fn export_classes(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let format = export_format(req)?;
+    let mut params = req.parse_queries::<SearchClassesParams>()?;
+    params.school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let source = ClassSource {
+        query: class_api::search_query(params),
+        after: 0,
+    };
+    export::render(res, state, format, "classes", source).await
+}
+
+// Export users with roles and class bindings
+#[handler]
+pub async fn export_users(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let format = export_format(req)?;
+    let mut params = req.parse_queries::<SearchUsersParams>()?;
+    params.school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let source = UserSource {
+        query: user_api::search_query(params),
+        after: 0,
+    };
+    export::render(res, state, format, "users", source).await
+}
+
+// Export class status history over a date range
+#[handler]
+pub async fn export_status_history(
+    depot: &mut Depot,
+    req: &mut Request,
+    res: &mut Response,
+) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let format = export_format(req)?;
+    let mut params = req.parse_queries::<SearchStatusHistoryParams>()?;
+    params.school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let source = StatusHistorySource {
+        query: status_history_query(params)?,
+        after: 0,
+    };
+    export::render(res, state, format, "status_history", source).await
+}
+
+pub fn status_history_query(params: SearchStatusHistoryParams) -> Result<Select<class_status_logs::Entity>, AppError> {
+    if let (Some(from), Some(to)) = (params.from, params.to)
+        && to < from
+    {
+        return Err(AppError::validation("to must not be earlier than from"));
+    }
+    let mut query = class_status_logs::Entity::find();
+    crate::filter_if_some!(query, class_status_logs::Column::SchoolId, params.school_id, eq);
+    crate::filter_if_some!(query, class_status_logs::Column::ClassId, params.class_id, eq);
+    if let Some(from) = params.from {
+        query = query.filter(class_status_logs::Column::CreatedAt.gte(local_day_start(from)));
+    }
+    if let Some(to) = params.to {
+        query = query.filter(class_status_logs::Column::CreatedAt.lt(local_day_start(to) + Duration::days(1)));
+    }
+    Ok(query)
+}
@@ -0,0 +1,26 @@
+use crate::core::app::AppState;
+use crate::core::db_listener::{self, ListenerHealth};
+use crate::core::error::AppError;
+use crate::core::outbox;
+use crate::core::response::ApiResponse;
+use salvo::prelude::*;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ListenerHealthInfo {
+    #[serde(flatten)]
+    pub listener: ListenerHealth,
+    /// 还没分发的 outbox 事件数
+    pub pending_events: u64,
+}
+
+// Get database notification listener health
+#[handler]
+pub async fn get_listener_health(depot: &mut Depot) -> Result<ApiResponse<ListenerHealthInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let pending_events = outbox::pending_count(&state).await?;
+    Ok(ApiResponse::success(ListenerHealthInfo {
+        listener: db_listener::health(),
+        pending_events,
+    }))
+}
@@ -1,9 +1,28 @@
+pub mod academic_year_api;
+pub mod announcement_api;
+pub mod audit_api;
 pub mod auth_middleware;
+pub mod campus_api;
 pub mod class_api;
+pub mod class_import_api;
+pub mod export_api;
+pub mod class_teacher_api;
+pub mod dismissal_report_api;
+pub mod dismissal_schedule_api;
+pub mod district_api;
+pub mod health_api;
 pub mod list_api;
 pub mod permission_api;
 pub mod role_api;
 pub mod school_api;
+pub mod school_hold_api;
+pub mod school_membership_api;
+pub mod sse_api;
+pub mod stats_api;
+pub mod teacher_assignment_api;
+pub mod teacher_import_api;
+pub mod trash_api;
 pub mod user_api;
+pub mod webhook_api;
 pub mod wechat_api;
 pub mod ws_api;
\ No newline at end of file
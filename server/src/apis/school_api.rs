@@ -1,187 +1,420 @@
-use crate::apis::list_api::{ListParamsReq, PagingResponse};
-use crate::core::app::AppState;
-use crate::core::error::AppError;
-use crate::core::response::ApiResponse;
-use crate::utils::convert::from_str_optional;
-use data_model::schools;
-use salvo::{oapi::extract::*, prelude::*};
-use sea_orm::*;
-use serde::{Deserialize, Serialize};
-
-#[derive(Deserialize, Debug, ToSchema)]
-pub struct SchoolCreatePayload {
-    pub name: String,
-    pub password: String,
-}
-
-#[derive(Deserialize, Debug, ToSchema)]
-pub struct SchoolUpdatePayload {
-    pub name: Option<String>,
-    pub password: Option<String>,
-}
-
-#[derive(Deserialize, Serialize, Debug, FromQueryResult, ToSchema)]
-pub struct SchoolInfo {
-    pub id: i32,
-    pub name: String,
-    pub password: Option<String>,
-}
-
-#[derive(Deserialize, Debug, Default)]
-pub struct SearchSchoolsParams {
-    #[serde(flatten)]
-    pub pagination: ListParamsReq,
-    pub name: Option<String>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub id: Option<i32>,
-}
-
-// Create School
-#[handler]
-pub async fn add(
-    depot: &mut Depot,
-    req: JsonBody<SchoolCreatePayload>,
-) -> Result<ApiResponse<schools::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let entity = add_impl(&state, req.into_inner()).await?;
-    Ok(ApiResponse::success(entity))
-}
-
-pub async fn add_impl(state: &AppState, req: SchoolCreatePayload) -> Result<schools::Model, AppError> {
-    let new_school = schools::ActiveModel {
-        name: Set(req.name),
-        password: Set(req.password),
-        ..Default::default()
-    };
-    let school = new_school.insert(&state.db).await?;
-    Ok(school)
-}
-
-// Update School
-#[handler]
-pub async fn update(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-    req: JsonBody<SchoolUpdatePayload>,
-) -> Result<ApiResponse<schools::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let school = update_impl(&state, id.into_inner(), req.into_inner()).await?;
-    Ok(ApiResponse::success(school))
-}
-
-pub async fn update_impl(
-    state: &AppState,
-    id: i32,
-    req: SchoolUpdatePayload,
-) -> Result<schools::Model, AppError> {
-    let school = schools::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
-
-    let mut school_active_model: schools::ActiveModel = school.into();
-
-    if let Some(name) = req.name {
-        school_active_model.name = Set(name);
-    }
-
-    if let Some(password) = req.password {
-        school_active_model.password = Set(password);
-    }
-
-    let school = school_active_model.update(&state.db).await?;
-    Ok(school)
-}
-
-// Delete School
-#[handler]
-pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    delete_impl(&state, id.into_inner()).await?;
-    Ok(ApiResponse::success(()))
-}
-
-pub async fn delete_impl(state: &AppState, id: i32) -> Result<(), AppError> {
-    let school = schools::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
-    let _ = school.delete(&state.db).await?;
-    Ok(())
-}
-
-// Get Schools List
-#[handler]
-pub async fn get_list(
-    depot: &mut Depot,
-    req: &mut Request,
-) -> Result<ApiResponse<PagingResponse<SchoolInfo>>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let params = req.parse_queries::<SearchSchoolsParams>()?;
-    let list = get_list_impl(&state, params).await?;
-    Ok(ApiResponse::success(list))
-}
-
-#[handler]
-pub async fn get_all_schools(
-    depot: &mut Depot,
-) -> Result<ApiResponse<Vec<SchoolInfo>>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let schools = schools::Entity::find().all(&state.db).await?;
-    let list= schools.into_iter().map(|s| SchoolInfo {
-        id: s.id,
-        name: s.name.clone(),
-        password:None
-    }).collect();
-    Ok(ApiResponse::success(list))
-}
-
-pub async fn get_list_impl(
-    state: &AppState,
-    params: SearchSchoolsParams,
-) -> Result<PagingResponse<SchoolInfo>, AppError> {
-    let page = params.pagination.page.unwrap_or(1);
-    let page_size = params.pagination.page_size.unwrap_or(20);
-
-    let mut query = schools::Entity::find();
-
-    crate::filter_if_some!(query, schools::Column::Id, params.id, eq);
-    crate::filter_if_some!(query, schools::Column::Name, params.name, like);
-
-    let paginator = query.into_model().paginate(&state.db, page_size);
-    let total = paginator.num_items().await?;
-    let list = paginator.fetch_page(page - 1).await?;
-
-    Ok(PagingResponse { list, total, page })
-}
-
-// Get School by ID
-#[handler]
-pub async fn get_by_id(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-) -> Result<ApiResponse<SchoolInfo>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let school = get_by_id_impl(&state, id.into_inner()).await?;
-    Ok(ApiResponse::success(school))
-}
-
-#[handler]
-pub async  fn get_simple_by_id(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-) -> Result<ApiResponse<SchoolInfo>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let mut school = get_by_id_impl(&state, id.into_inner()).await?;
-    school.password = None;
-    Ok(ApiResponse::success(school))
-}
-
-pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<SchoolInfo, AppError> {
-    let school = schools::Entity::find_by_id(id)
-        .into_model::<SchoolInfo>()
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
-    Ok(school)
-}
+use crate::apis::auth_middleware::Claims;
+use crate::apis::class_api::map_unique_violation;
+use crate::apis::district_api::{ensure_district_access, is_district_admin};
+use crate::apis::school_membership_api::is_active_member;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::delete_confirm::{self, DeletePreview};
+use crate::core::error::AppError;
+use crate::core::event_hub;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use chrono::Utc;
+use data_model::{classes, school_memberships, schools, teacher_classes, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SchoolCreatePayload {
+    pub name: String,
+    pub password: String,
+    pub district_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SchoolUpdatePayload {
+    pub name: Option<String>,
+    pub password: Option<String>,
+    /// 0 表示移出学区
+    pub district_id: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, FromQueryResult, ToSchema)]
+pub struct SchoolInfo {
+    pub id: i32,
+    pub name: String,
+    pub password: Option<String>,
+    pub district_id: Option<i32>,
+}
+
+/// 删除学校会一起删除的班级、绑定和受影响的用户
+#[derive(Serialize, Debug)]
+pub struct SchoolDeleteImpact {
+    pub school_id: i32,
+    pub name: String,
+    pub classes: u64,
+    pub teacher_bindings: u64,
+    /// 属于该学校的用户, 删除后无法再访问学校数据
+    pub users: u64,
+    /// 当前连接的大屏(本实例)
+    pub connected_screens: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchSchoolsParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub name: Option<String>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub id: Option<i32>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub district_id: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AllSchoolsParams {
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub district_id: Option<i32>,
+}
+
+// Create School
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    req: JsonBody<SchoolCreatePayload>,
+) -> Result<ApiResponse<schools::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    // 只能把学校放进自己管理的学区
+    if let Some(district_id) = req.district_id {
+        if district_id == 0 {
+            return Err(AppError::validation("district_id must be a district, omit it for none"));
+        }
+        ensure_district_access(&state, claims, district_id).await?;
+    }
+    let audit = AuditContext::from_depot(depot);
+    let entity = add_impl(&state, &audit, req).await?;
+    Ok(ApiResponse::success(entity))
+}
+
+pub async fn add_impl(state: &AppState, audit: &AuditContext, req: SchoolCreatePayload) -> Result<schools::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let new_school = schools::ActiveModel {
+        name: Set(req.name),
+        password: Set(req.password),
+        district_id: Set(req.district_id),
+        ..Default::default()
+    };
+    let school = new_school.insert(&txn).await?;
+    txn.commit().await?;
+    Ok(school)
+}
+
+// Update School
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<SchoolUpdatePayload>,
+) -> Result<ApiResponse<schools::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    let id = id.into_inner();
+    ensure_school_access(&state, claims, id).await?;
+    if let Some(district_id) = req.district_id {
+        ensure_district_change_allowed(&state, claims, id, district_id).await?;
+    }
+    let audit = AuditContext::from_depot(depot);
+    let school = update_impl(&state, &audit, id, req).await?;
+    Ok(ApiResponse::success(school))
+}
+
+/// 移出原来的学区要有原学区的权限, 放进新学区要有新学区的权限; 0 表示移出学区
+async fn ensure_district_change_allowed(
+    state: &AppState,
+    claims: &Claims,
+    school_id: i32,
+    district_id: i32,
+) -> Result<(), AppError> {
+    let current = schools::Entity::find_by_id(school_id)
+        .select_only()
+        .column(schools::Column::DistrictId)
+        .into_tuple::<Option<i32>>()
+        .one(&state.db)
+        .await?
+        .flatten();
+    let target = Some(district_id).filter(|id| *id != 0);
+    if current == target {
+        return Ok(());
+    }
+    for district_id in [current, target].into_iter().flatten() {
+        ensure_district_access(state, claims, district_id).await?;
+    }
+    Ok(())
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    id: i32,
+    req: SchoolUpdatePayload,
+) -> Result<schools::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let school = schools::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
+
+    let mut school_active_model: schools::ActiveModel = school.into();
+
+    if let Some(name) = req.name {
+        school_active_model.name = Set(name);
+    }
+
+    if let Some(password) = req.password {
+        school_active_model.password = Set(password);
+    }
+
+    if let Some(district_id) = req.district_id {
+        school_active_model.district_id = Set(Some(district_id).filter(|id| *id != 0));
+    }
+
+    let school = school_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(school)
+}
+
+// Preview what deleting a school affects
+#[handler]
+pub async fn delete_preview(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<DeletePreview<SchoolDeleteImpact>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    let impact = delete_impact(&state, id).await?;
+    let preview = delete_confirm::issue(&state, claims, "schools", id, impact).await?;
+    Ok(ApiResponse::success(preview))
+}
+
+pub async fn delete_impact(state: &AppState, id: i32) -> Result<SchoolDeleteImpact, AppError> {
+    let school = schools::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
+    let classes = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(id))
+        .count(&state.db)
+        .await?;
+    let teacher_bindings = teacher_classes::Entity::find()
+        .filter(
+            teacher_classes::Column::ClassId.in_subquery(
+                Query::select()
+                    .column(classes::Column::Id)
+                    .from(classes::Entity)
+                    .and_where(classes::Column::SchoolId.eq(id))
+                    .and_where(classes::Column::DeletedAt.is_null())
+                    .to_owned(),
+            ),
+        )
+        .count(&state.db)
+        .await?;
+    let users = users::Entity::find_live()
+        .filter(
+            users::Column::Id.in_subquery(
+                Query::select()
+                    .column(school_memberships::Column::UserId)
+                    .from(school_memberships::Entity)
+                    .and_where(school_memberships::Column::SchoolId.eq(id))
+                    .to_owned(),
+            ),
+        )
+        .count(&state.db)
+        .await?;
+    Ok(SchoolDeleteImpact {
+        school_id: school.id,
+        name: school.name,
+        classes,
+        teacher_bindings,
+        users,
+        connected_screens: event_hub::subscriber_count(id),
+    })
+}
+
+// Delete School
+#[handler]
+pub async fn delete(depot: &mut Depot, req: &mut Request, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    delete_confirm::consume(&state, claims, req, "schools", id).await?;
+    let audit = AuditContext::from_depot(depot);
+    delete_impl(&state, &audit, id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn delete_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<(), AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let school = schools::Entity::find_live_by_id(id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
+    // 学校下的班级一起放入回收站, 删除时间相同, 恢复学校时一起恢复
+    let deleted_at: DateTimeWithTimeZone = Utc::now().into();
+    classes::Entity::update_many()
+        .col_expr(classes::Column::DeletedAt, Expr::value(deleted_at))
+        .filter(classes::Column::SchoolId.eq(id))
+        .filter(classes::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await?;
+    let mut school_active_model: schools::ActiveModel = school.into();
+    school_active_model.deleted_at = Set(Some(deleted_at));
+    school_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// Restore a deleted school and the classes deleted with it
+#[handler]
+pub async fn restore(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<schools::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let school = restore_impl(&state, &audit, id.into_inner()).await?;
+    Ok(ApiResponse::success(school))
+}
+
+pub async fn restore_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<schools::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let school = schools::Entity::find_deleted()
+        .filter(schools::Column::Id.eq(id))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("deleted schools".to_string(), Some(id)))?;
+    // 单独删除的班级不跟着恢复
+    classes::Entity::update_many()
+        .col_expr(classes::Column::DeletedAt, Expr::value(Option::<DateTimeWithTimeZone>::None))
+        .filter(classes::Column::SchoolId.eq(id))
+        .filter(classes::Column::DeletedAt.eq(school.deleted_at))
+        .exec(&txn)
+        .await
+        .map_err(map_unique_violation)?;
+    let mut school_active_model: schools::ActiveModel = school.into();
+    school_active_model.deleted_at = Set(None);
+    let school = school_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(school)
+}
+
+// Get Schools List
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<SchoolInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let params = req.parse_queries::<SearchSchoolsParams>()?;
+    let list = get_list_impl(&state, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+#[handler]
+pub async fn get_all_schools(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<Vec<SchoolInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let params = req.parse_queries::<AllSchoolsParams>()?;
+    let mut query = schools::Entity::find_live();
+    crate::filter_if_some!(query, schools::Column::DistrictId, params.district_id, eq);
+    let schools = query.all(&state.db).await?;
+    let list= schools.into_iter().map(|s| SchoolInfo {
+        id: s.id,
+        name: s.name.clone(),
+        password:None,
+        district_id: s.district_id,
+    }).collect();
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    params: SearchSchoolsParams,
+) -> Result<PagingResponse<SchoolInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    let query = search_query(params);
+
+    let paginator = query.into_model().paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let list = paginator.fetch_page(page - 1).await?;
+
+    Ok(PagingResponse { list, total, page })
+}
+
+/// 列表和导出共用的筛选条件
+pub fn search_query(params: SearchSchoolsParams) -> Select<schools::Entity> {
+    let mut query = schools::Entity::find_live();
+
+    crate::filter_if_some!(query, schools::Column::Id, params.id, eq);
+    crate::filter_if_some!(query, schools::Column::Name, params.name, like);
+    crate::filter_if_some!(query, schools::Column::DistrictId, params.district_id, eq);
+    query
+}
+
+// Get School by ID
+#[handler]
+pub async fn get_by_id(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<SchoolInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let school = get_by_id_impl(&state, id.into_inner()).await?;
+    Ok(ApiResponse::success(school))
+}
+
+#[handler]
+pub async  fn get_simple_by_id(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<SchoolInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let mut school = get_by_id_impl(&state, id.into_inner()).await?;
+    school.password = None;
+    Ok(ApiResponse::success(school))
+}
+
+pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<SchoolInfo, AppError> {
+    let school = schools::Entity::find_live_by_id(id)
+        .into_model::<SchoolInfo>()
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(id)))?;
+    Ok(school)
+}
+
+/// 管理员可以管理所有学校, 学区管理员可以管理学区内的学校, 其他用户只能管理自己所属(成员关系正常)的学校
+pub async fn ensure_school_access(
+    state: &AppState,
+    claims: &Claims,
+    school_id: i32,
+) -> Result<(), AppError> {
+    if !has_school_access(state, claims, school_id).await? {
+        return Err(AppError::forbidden(format!("manage school {}", school_id)));
+    }
+    Ok(())
+}
+
+pub async fn has_school_access(state: &AppState, claims: &Claims, school_id: i32) -> Result<bool, AppError> {
+    if claims.is_admin() || is_active_member(&state.db, claims.user_id, school_id).await? {
+        return Ok(true);
+    }
+    let district_id = schools::Entity::find_by_id(school_id)
+        .select_only()
+        .column(schools::Column::DistrictId)
+        .into_tuple::<Option<i32>>()
+        .one(&state.db)
+        .await?
+        .flatten();
+    match district_id {
+        Some(district_id) => Ok(is_district_admin(&state.db, claims.user_id, district_id).await?),
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,283 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{SCHOOL_HOLD_ACTION_HOLD, SCHOOL_HOLD_ACTION_LIFT};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use chrono::{DateTime, Utc};
+use data_model::{school_holds, schools, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct SchoolHoldPayload {
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct SchoolLiftPayload {
+    #[validate(length(max = 500))]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct SchoolHoldState {
+    pub school_id: i32,
+    pub on_hold: bool,
+    pub reason: Option<String>,
+    pub actor_id: Option<i32>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SchoolHoldLogInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub action: String,
+    pub reason: String,
+    pub actor_id: Option<i32>,
+    pub actor_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchSchoolHoldsParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+}
+
+impl SchoolHoldState {
+    pub fn from_latest(school_id: i32, latest: Option<&school_holds::Model>) -> Self {
+        match latest {
+            Some(record) if record.action == SCHOOL_HOLD_ACTION_HOLD => Self {
+                school_id,
+                on_hold: true,
+                reason: Some(record.reason.clone()),
+                actor_id: record.actor_id,
+                since: Some(record.created_at.into()),
+            },
+            _ => Self {
+                school_id,
+                on_hold: false,
+                reason: None,
+                actor_id: None,
+                since: None,
+            },
+        }
+    }
+}
+
+// Start hold
+#[handler]
+pub async fn hold(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<SchoolHoldPayload>,
+) -> Result<ApiResponse<SchoolHoldState>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let hold_state = hold_impl(&state, &audit, claims.user_id, school_id, req.reason).await?;
+    Ok(ApiResponse::success(hold_state))
+}
+
+pub async fn hold_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    actor_id: i32,
+    school_id: i32,
+    reason: String,
+) -> Result<SchoolHoldState, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    // 锁住学校行, 避免并发开启/解除产生交错的记录
+    schools::Entity::find_live_by_id(school_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    let latest = latest_hold_record(&txn, school_id).await?;
+    if latest.is_some_and(|r| r.action == SCHOOL_HOLD_ACTION_HOLD) {
+        return Err(AppError::business_logic(
+            "SCHOOL_ALREADY_ON_HOLD",
+            "School is already on hold",
+        ));
+    }
+    let record = school_holds::ActiveModel {
+        school_id: Set(school_id),
+        action: Set(SCHOOL_HOLD_ACTION_HOLD.to_string()),
+        reason: Set(reason),
+        actor_id: Set(Some(actor_id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    tracing::warn!(
+        "School {} put on hold by user {}: {}",
+        school_id,
+        actor_id,
+        record.reason
+    );
+    Ok(SchoolHoldState::from_latest(school_id, Some(&record)))
+}
+
+// Lift hold
+#[handler]
+pub async fn lift(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<SchoolLiftPayload>,
+) -> Result<ApiResponse<SchoolHoldState>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_school_access(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let hold_state = lift_impl(&state, &audit, claims.user_id, school_id, req.reason).await?;
+    Ok(ApiResponse::success(hold_state))
+}
+
+pub async fn lift_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    actor_id: i32,
+    school_id: i32,
+    reason: Option<String>,
+) -> Result<SchoolHoldState, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    schools::Entity::find_live_by_id(school_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    let latest = latest_hold_record(&txn, school_id).await?;
+    if latest.is_none_or(|r| r.action != SCHOOL_HOLD_ACTION_HOLD) {
+        return Err(AppError::business_logic(
+            "SCHOOL_NOT_ON_HOLD",
+            "School is not on hold",
+        ));
+    }
+    let record = school_holds::ActiveModel {
+        school_id: Set(school_id),
+        action: Set(SCHOOL_HOLD_ACTION_LIFT.to_string()),
+        reason: Set(reason.unwrap_or_default()),
+        actor_id: Set(Some(actor_id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+    tracing::warn!("School {} hold lifted by user {}", school_id, actor_id);
+    Ok(SchoolHoldState::from_latest(school_id, Some(&record)))
+}
+
+// Get current hold state (public, used by screens on load)
+#[handler]
+pub async fn get_state(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<SchoolHoldState>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let hold_state = get_state_impl(&state, id.into_inner()).await?;
+    Ok(ApiResponse::success(hold_state))
+}
+
+pub async fn get_state_impl(state: &AppState, school_id: i32) -> Result<SchoolHoldState, AppError> {
+    let latest = latest_hold_record(&state.db, school_id).await?;
+    Ok(SchoolHoldState::from_latest(school_id, latest.as_ref()))
+}
+
+pub async fn is_school_on_hold<C: ConnectionTrait>(db: &C, school_id: i32) -> Result<bool, AppError> {
+    let latest = latest_hold_record(db, school_id).await?;
+    Ok(latest.is_some_and(|r| r.action == SCHOOL_HOLD_ACTION_HOLD))
+}
+
+async fn latest_hold_record<C: ConnectionTrait>(
+    db: &C,
+    school_id: i32,
+) -> Result<Option<school_holds::Model>, DbErr> {
+    school_holds::Entity::find()
+        .filter(school_holds::Column::SchoolId.eq(school_id))
+        .order_by_desc(school_holds::Column::Id)
+        .one(db)
+        .await
+}
+
+// Get hold audit records of a school
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<SchoolHoldLogInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = req
+        .param::<i32>("id")
+        .ok_or_else(|| AppError::validation("invalid school id"))?;
+    ensure_school_access(&state, claims, school_id).await?;
+    let params = req.parse_queries::<SearchSchoolHoldsParams>()?;
+    let list = get_list_impl(&state, school_id, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    school_id: i32,
+    params: SearchSchoolHoldsParams,
+) -> Result<PagingResponse<SchoolHoldLogInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+
+    let paginator = school_holds::Entity::find()
+        .filter(school_holds::Column::SchoolId.eq(school_id))
+        .order_by_desc(school_holds::Column::Id)
+        .paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let records = paginator.fetch_page(page - 1).await?;
+
+    let actor_ids: Vec<i32> = records.iter().filter_map(|r| r.actor_id).collect();
+    let users_map: HashMap<i32, users::Model> = if actor_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find()
+            .filter(users::Column::Id.is_in(actor_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, u))
+            .collect()
+    };
+
+    let list = records
+        .into_iter()
+        .map(|r| {
+            let actor_name = r
+                .actor_id
+                .and_then(|actor_id| users_map.get(&actor_id).map(display_name));
+            SchoolHoldLogInfo {
+                id: r.id,
+                school_id: r.school_id,
+                action: r.action,
+                reason: r.reason,
+                actor_id: r.actor_id,
+                actor_name,
+                created_at: r.created_at.into(),
+            }
+        })
+        .collect();
+    Ok(PagingResponse { list, total, page })
+}
@@ -0,0 +1,265 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::has_school_access;
+use crate::apis::user_api::AuthResponse;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{
+    MEMBERSHIP_ROLE_MANAGER, MEMBERSHIP_ROLE_TEACHER, MEMBERSHIP_STATUS_ACTIVE, MEMBERSHIP_STATUS_SUSPENDED,
+};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::jwt::create_jwt;
+use chrono::{DateTime, Utc};
+use data_model::{classes, school_memberships, schools, teacher_classes, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::sea_query::Query;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct SchoolMembershipInfo {
+    pub school_id: i32,
+    pub school_name: String,
+    pub role: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SwitchSchoolPayload {
+    pub school_id: i32,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MembershipUpdatePayload {
+    pub role: Option<String>,
+    pub status: Option<String>,
+}
+
+pub async fn find_membership<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    school_id: i32,
+) -> Result<Option<school_memberships::Model>, DbErr> {
+    school_memberships::Entity::find_by_id((user_id, school_id)).one(db).await
+}
+
+/// 未删除的用户在该学校有正常的成员关系
+pub async fn is_active_member<C: ConnectionTrait>(db: &C, user_id: i32, school_id: i32) -> Result<bool, DbErr> {
+    let count = school_memberships::Entity::find()
+        .inner_join(users::Entity)
+        .filter(school_memberships::Column::UserId.eq(user_id))
+        .filter(school_memberships::Column::SchoolId.eq(school_id))
+        .filter(school_memberships::Column::Status.eq(MEMBERSHIP_STATUS_ACTIVE))
+        .filter(users::Column::DeletedAt.is_null())
+        .count(db)
+        .await?;
+    Ok(count > 0)
+}
+
+/// 加入学校, 已经是成员时不变; 停用的成员关系只能由学校管理员恢复
+pub async fn join_school<C: ConnectionTrait>(db: &C, user_id: i32, school_id: i32) -> Result<(), AppError> {
+    match find_membership(db, user_id, school_id).await? {
+        Some(m) if m.status == MEMBERSHIP_STATUS_SUSPENDED => Err(AppError::business_logic(
+            "MEMBERSHIP_SUSPENDED",
+            format!("Membership of school {} is suspended", school_id),
+        )),
+        Some(_) => Ok(()),
+        None => {
+            school_memberships::ActiveModel {
+                user_id: Set(user_id),
+                school_id: Set(school_id),
+                role: Set(MEMBERSHIP_ROLE_TEACHER.to_string()),
+                status: Set(MEMBERSHIP_STATUS_ACTIVE.to_string()),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+/// 当前学校: 令牌或 X-School-Id 头指定的学校, 没有指定时使用默认学校(users.school_id)
+pub async fn current_school_id(state: &AppState, claims: &Claims) -> Result<Option<i32>, AppError> {
+    if let Some(school_id) = claims.school_id {
+        if !has_school_access(state, claims, school_id).await? {
+            return Err(AppError::forbidden(format!("access school {}", school_id)));
+        }
+        return Ok(Some(school_id));
+    }
+    let user = users::Entity::find_live_by_id(claims.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(claims.user_id)))?;
+    Ok(user.school_id)
+}
+
+/// 管理员, 或该学校正常的学校管理员
+async fn ensure_school_manager(state: &AppState, claims: &Claims, school_id: i32) -> Result<(), AppError> {
+    if claims.is_admin() {
+        return Ok(());
+    }
+    let membership = find_membership(&state.db, claims.user_id, school_id).await?;
+    match membership {
+        Some(m) if m.role == MEMBERSHIP_ROLE_MANAGER && m.status == MEMBERSHIP_STATUS_ACTIVE => Ok(()),
+        _ => Err(AppError::forbidden(format!("manage members of school {}", school_id))),
+    }
+}
+
+pub async fn get_memberships_by_user_ids<C: ConnectionTrait>(
+    db: &C,
+    user_ids: Vec<i32>,
+) -> Result<Vec<(i32, SchoolMembershipInfo)>, DbErr> {
+    let rows = school_memberships::Entity::find()
+        .filter(school_memberships::Column::UserId.is_in(user_ids))
+        .find_also_related(schools::Entity)
+        .filter(schools::Column::DeletedAt.is_null())
+        .order_by_asc(school_memberships::Column::CreatedAt)
+        .all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(m, school)| {
+            school.map(|s| {
+                (
+                    m.user_id,
+                    SchoolMembershipInfo {
+                        school_id: m.school_id,
+                        school_name: s.name,
+                        role: m.role,
+                        status: m.status,
+                        created_at: m.created_at.into(),
+                    },
+                )
+            })
+        })
+        .collect())
+}
+
+// Switch the current school, returns a token carrying it
+#[handler]
+pub async fn switch_school(
+    depot: &mut Depot,
+    req: JsonBody<SwitchSchoolPayload>,
+) -> Result<ApiResponse<AuthResponse>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = req.school_id;
+    schools::Entity::find_live_by_id(school_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    if !has_school_access(&state, claims, school_id).await? {
+        return Err(AppError::forbidden(format!("access school {}", school_id)));
+    }
+    let token = create_jwt(claims.user_id, claims.role_ids.clone(), Some(school_id), &state.config.jwt)?;
+    Ok(ApiResponse::success(AuthResponse { token }))
+}
+
+// Change the role or status of a member
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    school_id: PathParam<i32>,
+    req: JsonBody<MembershipUpdatePayload>,
+) -> Result<ApiResponse<school_memberships::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = school_id.into_inner();
+    ensure_school_manager(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    let membership = update_impl(&state, &audit, id.into_inner(), school_id, req.into_inner()).await?;
+    Ok(ApiResponse::success(membership))
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    user_id: i32,
+    school_id: i32,
+    req: MembershipUpdatePayload,
+) -> Result<school_memberships::Model, AppError> {
+    if let Some(role) = &req.role
+        && ![MEMBERSHIP_ROLE_TEACHER, MEMBERSHIP_ROLE_MANAGER].contains(&role.as_str())
+    {
+        return Err(AppError::validation(format!("invalid role: {}", role)));
+    }
+    if let Some(status) = &req.status
+        && ![MEMBERSHIP_STATUS_ACTIVE, MEMBERSHIP_STATUS_SUSPENDED].contains(&status.as_str())
+    {
+        return Err(AppError::validation(format!("invalid status: {}", status)));
+    }
+    let txn = audit::begin(state, audit).await?;
+    let membership = find_membership(&txn, user_id, school_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("school membership".to_string(), None))?;
+    let mut active: school_memberships::ActiveModel = membership.into();
+    if let Some(role) = req.role {
+        active.role = Set(role);
+    }
+    if let Some(status) = req.status {
+        active.status = Set(status);
+    }
+    active.updated_at = Set(Utc::now().into());
+    let membership = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(membership)
+}
+
+// Remove a user from a school
+#[handler]
+pub async fn remove(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    school_id: PathParam<i32>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = school_id.into_inner();
+    ensure_school_manager(&state, claims, school_id).await?;
+    let audit = AuditContext::from_depot(depot);
+    remove_impl(&state, &audit, id.into_inner(), school_id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+/// 离开学校时解除该学校班级的绑定; 默认学校改为剩下的第一个学校
+pub async fn remove_impl(state: &AppState, audit: &AuditContext, user_id: i32, school_id: i32) -> Result<(), AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let membership = find_membership(&txn, user_id, school_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("school membership".to_string(), None))?;
+    membership.delete(&txn).await?;
+    teacher_classes::Entity::delete_many()
+        .filter(teacher_classes::Column::UserId.eq(user_id))
+        .filter(
+            teacher_classes::Column::ClassId.in_subquery(
+                Query::select()
+                    .column(classes::Column::Id)
+                    .from(classes::Entity)
+                    .and_where(classes::Column::SchoolId.eq(school_id))
+                    .to_owned(),
+            ),
+        )
+        .exec(&txn)
+        .await?;
+    let user = users::Entity::find_by_id(user_id).one(&txn).await?;
+    if let Some(user) = user.filter(|u| u.school_id == Some(school_id)) {
+        let next_school_id = school_memberships::Entity::find()
+            .select_only()
+            .column(school_memberships::Column::SchoolId)
+            .filter(school_memberships::Column::UserId.eq(user_id))
+            .filter(school_memberships::Column::Status.eq(MEMBERSHIP_STATUS_ACTIVE))
+            .order_by_asc(school_memberships::Column::CreatedAt)
+            .into_tuple::<i32>()
+            .one(&txn)
+            .await?;
+        let mut user_active_model: users::ActiveModel = user.into();
+        user_active_model.school_id = Set(next_school_id);
+        user_active_model.update(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
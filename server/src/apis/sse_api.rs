@@ -0,0 +1,135 @@
+use futures_util::{future, stream, StreamExt};
+use salvo::http::header::{CACHE_CONTROL, HeaderName};
+use salvo::http::HeaderValue;
+use salvo::prelude::*;
+use salvo::sse::{SseEvent, SseKeepAlive};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::wrappers::BroadcastStream;
+use crate::apis::ws_api::{self, ALL_TOPICS, DEFAULT_PROTOCOL, PROTOCOL_V1, Topic};
+use crate::core::app::AppState;
+use crate::core::event_hub::{self, SchoolEvent};
+
+/// 浏览器 EventSource 断线后自动重连的间隔
+const SSE_RETRY: Duration = Duration::from_secs(3);
+/// 没有事件时发送注释行保活, 避免被代理或电视盒子的网络栈断开
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// 与 /ws/school/{id} 推送相同事件的 SSE 通道, 用于无法建立 WebSocket 的网络
+///
+/// - `?protocol=1|2` 指定消息格式, 默认与 WebSocket 相同为 v1
+/// - `?topics=status,hold` 只接收指定主题
+/// - 每条事件的 id 为事件序号, 重连时浏览器会带上 `Last-Event-ID`, 服务端补发之后的事件;
+///   补发不了时先推送一条 initial_state 完整状态
+#[handler]
+pub async fn school_sse_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Result<(), StatusError> {
+    let school_id: i32 = req.param("id").unwrap_or_default();
+    if school_id == 0 {
+        return Err(StatusError::bad_request());
+    }
+    let version = ws_api::check_protocol(req.query::<u8>("protocol").unwrap_or(DEFAULT_PROTOCOL))?;
+    let topics = match req.query::<String>("topics") {
+        Some(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::parse::<Topic>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StatusError::bad_request().brief(e))?,
+        None => ALL_TOPICS.to_vec(),
+    };
+    // EventSource 首次连接无法自定义请求头, 所以也接受 ?last_event_id=
+    let resume_from = req
+        .header::<String>("last-event-id")
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .or_else(|| req.query::<u64>("last_event_id"));
+    let state = depot.obtain::<AppState>().unwrap().clone();
+
+    //先订阅再补发或拉取完整状态, 与 WebSocket 一致
+    let (receiver, last_seq) = event_hub::subscribe(school_id);
+    let mut initial = Vec::new();
+    let mut after = last_seq;
+    match resume_from.and_then(|seq| event_hub::events_since(school_id, seq).map(|events| (seq, events))) {
+        Some((seq, events)) => {
+            after = events.last().map(|e| e.seq()).unwrap_or(seq).max(last_seq);
+            initial.extend(
+                events
+                    .iter()
+                    .filter(|e| accepts(e, &topics))
+                    .filter_map(|e| to_sse_event(e, version)),
+            );
+        }
+        // v1 旧协议没有 initial_state 消息, 与 WebSocket 一致只推送之后的状态变化
+        None if version == PROTOCOL_V1 => {}
+        None => {
+            match ws_api::load_initial_state(&state, school_id, last_seq).await {
+                Ok(message) => {
+                    if let Some(text) = ws_api::render_direct(message) {
+                        initial.push(
+                            SseEvent::default()
+                                .name("initial_state")
+                                .id(last_seq.to_string())
+                                .text(text),
+                        );
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(error = ?e, "Failed to load initial state for school {}", school_id);
+                    return Err(StatusError::internal_server_error());
+                }
+            }
+        }
+    }
+    if let Some(last) = initial.pop() {
+        initial.push(last.retry(SSE_RETRY));
+    }
+    tracing::info!(
+        "New SSE connection: school_id={}, protocol=v{}, resume_from={:?}",
+        school_id,
+        version,
+        resume_from
+    );
+
+    // 积压过多(Lagged)时直接结束响应, 客户端带着 Last-Event-ID 重连后补发或拉取完整状态
+    let live = BroadcastStream::new(receiver)
+        .take_while(|event| future::ready(event.is_ok()))
+        .filter_map(move |event| {
+            let sse_event = event
+                .ok()
+                .filter(|e| e.seq() > after && accepts(e, &topics))
+                .and_then(|e| to_sse_event(&e, version));
+            future::ready(sse_event.map(Ok::<_, Infallible>))
+        });
+    let events = stream::iter(initial.into_iter().map(Ok::<_, Infallible>)).chain(live);
+
+    res.headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    // 关闭 nginx 的响应缓冲, 否则事件会被攒在代理里
+    res.headers_mut().insert(
+        HeaderName::from_static("x-accel-buffering"),
+        HeaderValue::from_static("no"),
+    );
+    SseKeepAlive::new(events)
+        .max_interval(SSE_KEEP_ALIVE)
+        .stream(res);
+    Ok(())
+}
+
+fn accepts(event: &SchoolEvent, topics: &[Topic]) -> bool {
+    event
+        .envelope
+        .message
+        .topic()
+        .is_none_or(|topic| topics.contains(&topic))
+}
+
+fn to_sse_event(event: &Arc<SchoolEvent>, version: u8) -> Option<SseEvent> {
+    let text = event.render(version)?;
+    Some(
+        SseEvent::default()
+            .name(event.envelope.message.message_type())
+            .id(event.seq().to_string())
+            .text(text),
+    )
+}
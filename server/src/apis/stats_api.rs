@@ -0,0 +1,330 @@
+use crate::apis::academic_year_api::in_current_year;
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::school_membership_api::current_school_id;
+use crate::core::app::AppState;
+use crate::core::constants::{
+    CLASS_STATUS_DISMISSED, CLASS_STATUS_DISMISSING, MEMBERSHIP_STATUS_ACTIVE, STATS_MAX_RANGE_DAYS,
+};
+use crate::core::error::AppError;
+use crate::core::event_hub;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::{from_str_optional, local_day_start};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use data_model::{class_status_logs, classes, school_memberships, schools, teacher_classes, users};
+use salvo::prelude::*;
+use sea_orm::sea_query::{Query, SelectStatement};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct StatsParams {
+    /// 管理员不传时统计所有学校, 其他用户默认自己所在的学校
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    /// 按本地日期, 包含这一天, 默认今天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub from: Option<NaiveDate>,
+    /// 按本地日期, 包含这一天, 默认今天
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Debug, FromQueryResult)]
+pub struct DismissalDurationInfo {
+    pub school_id: i32,
+    pub school_name: String,
+    /// 从放学中到已放学的平均秒数
+    pub avg_seconds: f64,
+    pub samples: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HourlyCountInfo {
+    pub hour: DateTime<Utc>,
+    pub count: i64,
+}
+
+/// 统计范围: 全部学校、一个学校或一个学区内的学校
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsScope {
+    All,
+    School(i32),
+    District(i32),
+}
+
+impl From<Option<i32>> for StatsScope {
+    fn from(school_id: Option<i32>) -> Self {
+        school_id.map_or(Self::All, Self::School)
+    }
+}
+
+impl StatsScope {
+    /// 按 school_id 列筛选, 学区用子查询
+    pub fn condition(self, column: impl ColumnTrait) -> Condition {
+        match self {
+            Self::All => Condition::all(),
+            Self::School(school_id) => Condition::all().add(column.eq(school_id)),
+            Self::District(district_id) => Condition::all().add(column.in_subquery(district_school_ids(district_id))),
+        }
+    }
+}
+
+/// 学区内未删除的学校
+pub fn district_school_ids(district_id: i32) -> SelectStatement {
+    Query::select()
+        .column(schools::Column::Id)
+        .from(schools::Entity)
+        .and_where(schools::Column::DistrictId.eq(district_id))
+        .and_where(schools::Column::DeletedAt.is_null())
+        .to_owned()
+}
+
+#[derive(Serialize, Debug)]
+pub struct StatsInfo {
+    pub school_id: Option<i32>,
+    pub district_id: Option<i32>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub schools: u64,
+    /// 当前学年的班级
+    pub classes: u64,
+    pub teachers: u64,
+    /// 没有绑定任何老师的班级
+    pub unbound_classes: u64,
+    /// 当前连接的大屏(WebSocket 和 SSE), 只统计本实例
+    pub live_connections: usize,
+    pub today_status_changes: u64,
+    /// 统计范围内每个学校放学用时
+    pub dismissal_durations: Vec<DismissalDurationInfo>,
+    /// 统计范围内每小时的状态变更次数, 没有变更的小时为 0
+    pub hourly_status_changes: Vec<HourlyCountInfo>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct HourlyBucket {
+    bucket: i32,
+    count: i64,
+}
+
+// Get dashboard statistics
+#[handler]
+pub async fn get_stats(depot: &mut Depot, req: &mut Request) -> Result<ApiResponse<StatsInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let params = req.parse_queries::<StatsParams>()?;
+    let school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let stats = get_stats_impl(&state, school_id.into(), params.from, params.to).await?;
+    Ok(ApiResponse::success(stats))
+}
+
+/// 管理员可以看全部(None)或任意学校, 其他用户只能看自己所属的学校, 不传时是当前学校
+pub async fn resolve_scope(state: &AppState, claims: &Claims, school_id: Option<i32>) -> Result<Option<i32>, AppError> {
+    if let Some(school_id) = school_id {
+        ensure_school_access(state, claims, school_id).await?;
+        return Ok(Some(school_id));
+    }
+    if claims.is_admin() && claims.school_id.is_none() {
+        return Ok(None);
+    }
+    match current_school_id(state, claims).await? {
+        Some(school_id) => Ok(Some(school_id)),
+        None => Err(AppError::forbidden("view school data without a school")),
+    }
+}
+
+pub async fn get_stats_impl(
+    state: &AppState,
+    scope: StatsScope,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Result<StatsInfo, AppError> {
+    let today = Local::now().date_naive();
+    let from = from.unwrap_or(today);
+    let to = to.unwrap_or(today);
+    if to < from {
+        return Err(AppError::validation("to must not be earlier than from"));
+    }
+    if (to - from).num_days() >= STATS_MAX_RANGE_DAYS {
+        return Err(AppError::validation(format!(
+            "date range must not exceed {} days",
+            STATS_MAX_RANGE_DAYS
+        )));
+    }
+    let range_start = local_day_start(from);
+    let range_end = local_day_start(to) + Duration::days(1);
+
+    let schools = match scope {
+        StatsScope::School(_) => 1,
+        _ => {
+            schools::Entity::find_live()
+                .filter(scope.condition(schools::Column::Id))
+                .count(&state.db)
+                .await?
+        }
+    };
+
+    let class_query = classes::Entity::find_live()
+        .filter(in_current_year())
+        .filter(scope.condition(classes::Column::SchoolId));
+    let classes = class_query.clone().count(&state.db).await?;
+    let unbound_classes = class_query
+        .filter(
+            classes::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(teacher_classes::Column::ClassId)
+                    .from(teacher_classes::Entity)
+                    .to_owned(),
+            ),
+        )
+        .count(&state.db)
+        .await?;
+
+    // 属于多个学校的教师在每个学校都算一次, 全部学校时只算一次
+    let members = Query::select()
+        .column(school_memberships::Column::UserId)
+        .from(school_memberships::Entity)
+        .and_where(school_memberships::Column::Status.eq(MEMBERSHIP_STATUS_ACTIVE))
+        .cond_where(scope.condition(school_memberships::Column::SchoolId))
+        .to_owned();
+    let teachers = users::Entity::find_live()
+        .filter(users::Column::Id.in_subquery(members))
+        .count(&state.db)
+        .await?;
+
+    let live_connections = match scope {
+        StatsScope::All => event_hub::total_subscriber_count(),
+        StatsScope::School(school_id) => event_hub::subscriber_count(school_id),
+        StatsScope::District(district_id) => schools::Entity::find_live()
+            .select_only()
+            .column(schools::Column::Id)
+            .filter(schools::Column::DistrictId.eq(district_id))
+            .into_tuple::<i32>()
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(event_hub::subscriber_count)
+            .sum(),
+    };
+
+    let today_start = local_day_start(today);
+    let today_status_changes = class_status_logs::Entity::find()
+        .filter(class_status_logs::Column::CreatedAt.gte(today_start))
+        .filter(class_status_logs::Column::CreatedAt.lt(today_start + Duration::days(1)))
+        .filter(scope.condition(class_status_logs::Column::SchoolId))
+        .count(&state.db)
+        .await?;
+
+    let dismissal_durations = dismissal_durations(state, scope, range_start, range_end).await?;
+    let hourly_status_changes = hourly_status_changes(state, scope, range_start, range_end).await?;
+
+    Ok(StatsInfo {
+        school_id: match scope {
+            StatsScope::School(school_id) => Some(school_id),
+            _ => None,
+        },
+        district_id: match scope {
+            StatsScope::District(district_id) => Some(district_id),
+            _ => None,
+        },
+        from,
+        to,
+        schools,
+        classes,
+        teachers,
+        unbound_classes,
+        live_connections,
+        today_status_changes,
+        dismissal_durations,
+        hourly_status_changes,
+    })
+}
+
+/// 状态记录的筛选条件, 有学校时才加 school_id 条件以便使用 (school_id, created_at) 索引
+pub fn log_range_filter(scope: StatsScope, start: DateTime<Utc>, end: DateTime<Utc>) -> (String, Vec<Value>) {
+    let mut sql = "created_at >= $1 AND created_at < $2".to_string();
+    let mut values: Vec<Value> = vec![start.into(), end.into()];
+    match scope {
+        StatsScope::All => {}
+        StatsScope::School(school_id) => {
+            sql.push_str(" AND school_id = $3");
+            values.push(school_id.into());
+        }
+        StatsScope::District(district_id) => {
+            sql.push_str(" AND school_id IN (SELECT id FROM schools WHERE district_id = $3 AND deleted_at IS NULL)");
+            values.push(district_id.into());
+        }
+    }
+    (sql, values)
+}
+
+/// 同一个班级的上一条记录把状态改成放学中, 这一条从放学中改成已放学, 两条的时间差就是一次放学用时
+async fn dismissal_durations(
+    state: &AppState,
+    scope: StatsScope,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<DismissalDurationInfo>, AppError> {
+    let (filter, values) = log_range_filter(scope, start, end);
+    let sql = format!(
+        "SELECT t.school_id, s.name AS school_name, \
+                AVG(EXTRACT(EPOCH FROM t.created_at - t.prev_at))::FLOAT8 AS avg_seconds, \
+                COUNT(*) AS samples \
+         FROM ( \
+             SELECT school_id, old_status, new_status, created_at, \
+                    LAG(created_at) OVER (PARTITION BY class_id ORDER BY id) AS prev_at, \
+                    LAG(new_status) OVER (PARTITION BY class_id ORDER BY id) AS prev_status \
+             FROM class_status_logs WHERE {} \
+         ) t \
+         JOIN schools s ON s.id = t.school_id AND s.deleted_at IS NULL \
+         WHERE t.old_status = {dismissing} AND t.new_status = {dismissed} AND t.prev_status = {dismissing} \
+         GROUP BY t.school_id, s.name \
+         ORDER BY t.school_id",
+        filter,
+        dismissing = CLASS_STATUS_DISMISSING,
+        dismissed = CLASS_STATUS_DISMISSED,
+    );
+    let list = DismissalDurationInfo::find_by_statement(Statement::from_sql_and_values(
+        state.db.get_database_backend(),
+        sql,
+        values,
+    ))
+    .all(&state.db)
+    .await?;
+    Ok(list)
+}
+
+/// 按小时分组在数据库里完成, 没有记录的小时在这里补 0
+async fn hourly_status_changes(
+    state: &AppState,
+    scope: StatsScope,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<HourlyCountInfo>, AppError> {
+    let (filter, values) = log_range_filter(scope, start, end);
+    let sql = format!(
+        "SELECT FLOOR(EXTRACT(EPOCH FROM created_at - $1) / 3600)::INT AS bucket, COUNT(*) AS count \
+         FROM class_status_logs WHERE {} \
+         GROUP BY bucket",
+        filter
+    );
+    let counts: HashMap<i32, i64> = HourlyBucket::find_by_statement(Statement::from_sql_and_values(
+        state.db.get_database_backend(),
+        sql,
+        values,
+    ))
+    .all(&state.db)
+    .await?
+    .into_iter()
+    .map(|b| (b.bucket, b.count))
+    .collect();
+    let hours = (end - start).num_hours() as i32;
+    Ok((0..hours)
+        .map(|bucket| HourlyCountInfo {
+            hour: start + Duration::hours(bucket as i64),
+            count: counts.get(&bucket).copied().unwrap_or(0),
+        })
+        .collect())
+}
@@ -0,0 +1,389 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::class_teacher_api::is_class_head;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::school_membership_api::is_active_member;
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{
+    ASSIGNMENT_ROLE_ASSISTANT, ASSIGNMENT_ROLE_HEAD, ASSIGNMENT_ROLE_SUBSTITUTE, ASSIGNMENT_STATUS_APPROVED,
+    ASSIGNMENT_STATUS_CANCELLED, ASSIGNMENT_STATUS_PENDING, ASSIGNMENT_STATUS_REJECTED,
+};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use chrono::{DateTime, Utc};
+use data_model::{classes, teacher_assignments, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+const ROLES: [&str; 3] = [ASSIGNMENT_ROLE_HEAD, ASSIGNMENT_ROLE_ASSISTANT, ASSIGNMENT_ROLE_SUBSTITUTE];
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct TeacherAssignmentPayload {
+    pub user_id: i32,
+    pub role: String,
+    /// 默认立即生效
+    pub valid_from: Option<DateTime<Utc>>,
+    /// 代课必须指定结束时间
+    pub valid_to: Option<DateTime<Utc>>,
+    #[validate(length(max = 500))]
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TeacherAssignmentInfo {
+    pub id: i32,
+    pub class_id: i32,
+    pub user_id: i32,
+    pub user_name: String,
+    pub role: String,
+    pub status: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub reason: String,
+    pub requested_by: Option<i32>,
+    pub reviewed_by: Option<i32>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// 当前是否在岗
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchTeacherAssignmentsParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub role: Option<String>,
+    pub status: Option<String>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub user_id: Option<i32>,
+    /// 只返回当前在岗的安排
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub active: Option<bool>,
+}
+
+/// 当前在岗的安排: 已批准, 已开始, 未结束
+pub fn active_condition(now: DateTime<Utc>) -> Condition {
+    Condition::all()
+        .add(teacher_assignments::Column::Status.eq(ASSIGNMENT_STATUS_APPROVED))
+        .add(teacher_assignments::Column::ValidFrom.lte(now))
+        .add(
+            Condition::any()
+                .add(teacher_assignments::Column::ValidTo.is_null())
+                .add(teacher_assignments::Column::ValidTo.gt(now)),
+        )
+}
+
+fn is_active(a: &teacher_assignments::Model, now: DateTime<Utc>) -> bool {
+    a.status == ASSIGNMENT_STATUS_APPROVED && a.valid_from <= now && a.valid_to.is_none_or(|valid_to| valid_to > now)
+}
+
+/// 用户当前是否在该班级值班, 修改班级状态以此为准
+pub async fn is_on_duty<C: ConnectionTrait>(db: &C, user_id: i32, class_id: i32) -> Result<bool, DbErr> {
+    // 在班级所在学校的成员关系被停用后, 原来的安排不再算在岗
+    let Some(class) = classes::Entity::find_live_by_id(class_id).one(db).await? else {
+        return Ok(false);
+    };
+    if !is_active_member(db, user_id, class.school_id).await? {
+        return Ok(false);
+    }
+    // 已删除的用户即使 token 未过期也不算在岗
+    let count = teacher_assignments::Entity::find()
+        .join(JoinType::InnerJoin, teacher_assignments::Relation::Users1.def())
+        .filter(users::Column::DeletedAt.is_null())
+        .filter(teacher_assignments::Column::UserId.eq(user_id))
+        .filter(teacher_assignments::Column::ClassId.eq(class_id))
+        .filter(active_condition(Utc::now()))
+        .count(db)
+        .await?;
+    Ok(count > 0)
+}
+
+/// 管理员或班级当前的班主任可以安排教师和审批代课
+async fn can_review(state: &AppState, claims: &Claims, class_id: i32) -> Result<bool, AppError> {
+    if claims.is_admin() {
+        return Ok(true);
+    }
+    Ok(is_class_head(&state.db, claims.user_id, class_id).await?)
+}
+
+async fn enrich_assignments(
+    state: &AppState,
+    models: Vec<teacher_assignments::Model>,
+) -> Result<Vec<TeacherAssignmentInfo>, AppError> {
+    let user_ids: Vec<i32> = models.iter().map(|a| a.user_id).collect();
+    let users_map: HashMap<i32, String> = if user_ids.is_empty() {
+        HashMap::new()
+    } else {
+        users::Entity::find_live()
+            .filter(users::Column::Id.is_in(user_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, display_name(&u)))
+            .collect()
+    };
+    let now = Utc::now();
+    let list = models
+        .into_iter()
+        .map(|a| TeacherAssignmentInfo {
+            active: is_active(&a, now),
+            id: a.id,
+            class_id: a.class_id,
+            user_id: a.user_id,
+            user_name: users_map.get(&a.user_id).cloned().unwrap_or_default(),
+            role: a.role,
+            status: a.status,
+            valid_from: a.valid_from.into(),
+            valid_to: a.valid_to.map(Into::into),
+            reason: a.reason,
+            requested_by: a.requested_by,
+            reviewed_by: a.reviewed_by,
+            reviewed_at: a.reviewed_at.map(Into::into),
+            created_at: a.created_at.into(),
+        })
+        .collect();
+    Ok(list)
+}
+
+async fn to_info(state: &AppState, model: teacher_assignments::Model) -> Result<TeacherAssignmentInfo, AppError> {
+    let mut list = enrich_assignments(state, vec![model]).await?;
+    Ok(list.remove(0))
+}
+
+async fn find_class(state: &AppState, claims: &Claims, class_id: i32) -> Result<classes::Model, AppError> {
+    let class = classes::Entity::find_live_by_id(class_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("classes".to_string(), Some(class_id)))?;
+    ensure_school_access(state, claims, class.school_id).await?;
+    Ok(class)
+}
+
+async fn find_assignment(state: &AppState, id: i32) -> Result<teacher_assignments::Model, AppError> {
+    teacher_assignments::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("teacher_assignments".to_string(), Some(id)))
+}
+
+// Create assignment or substitute request for a class
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<TeacherAssignmentPayload>,
+) -> Result<ApiResponse<TeacherAssignmentInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let class = find_class(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let assignment = add_impl(&state, &audit, claims, class, req).await?;
+    Ok(ApiResponse::success(assignment))
+}
+
+/// 副班主任只能由管理员或班主任安排; 代课任何本校教师都可以申请, 由管理员或班主任审批
+pub async fn add_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    claims: &Claims,
+    class: classes::Model,
+    req: TeacherAssignmentPayload,
+) -> Result<TeacherAssignmentInfo, AppError> {
+    if !ROLES.contains(&req.role.as_str()) {
+        return Err(AppError::validation(format!("invalid role: {}", req.role)));
+    }
+    let now = Utc::now();
+    let valid_from = req.valid_from.unwrap_or(now);
+    if req.valid_to.is_some_and(|valid_to| valid_to <= valid_from) {
+        return Err(AppError::validation("valid_to must be later than valid_from"));
+    }
+    if req.role == ASSIGNMENT_ROLE_SUBSTITUTE && req.valid_to.is_none() {
+        return Err(AppError::validation("valid_to is required for substitute"));
+    }
+    let user = users::Entity::find_live_by_id(req.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(req.user_id)))?;
+    if !is_active_member(&state.db, user.id, class.school_id).await? {
+        return Err(AppError::validation("user does not belong to the school of this class"));
+    }
+    let reviewer = can_review(state, claims, class.id).await?;
+    if req.role != ASSIGNMENT_ROLE_SUBSTITUTE && !reviewer {
+        return Err(AppError::forbidden(format!("assign teachers of class {}", class.id)));
+    }
+    // 班主任由班级绑定决定(见 class_teacher_api), 这里不能再安排第二个
+    if req.role == ASSIGNMENT_ROLE_HEAD {
+        return Err(AppError::validation("head follows the class binding and cannot be assigned here"));
+    }
+
+    let status = if reviewer { ASSIGNMENT_STATUS_APPROVED } else { ASSIGNMENT_STATUS_PENDING };
+    let assignment = teacher_assignments::ActiveModel {
+        class_id: Set(class.id),
+        user_id: Set(user.id),
+        role: Set(req.role),
+        status: Set(status.to_string()),
+        valid_from: Set(valid_from.into()),
+        valid_to: Set(req.valid_to.map(Into::into)),
+        reason: Set(req.reason.unwrap_or_default()),
+        requested_by: Set(Some(claims.user_id)),
+        reviewed_by: Set(reviewer.then_some(claims.user_id)),
+        reviewed_at: Set(reviewer.then(|| now.into())),
+        ..Default::default()
+    };
+    let txn = audit::begin(state, audit).await?;
+    let assignment = assignment.insert(&txn).await?;
+    txn.commit().await?;
+    to_info(state, assignment).await
+}
+
+// Get assignments of a class
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<TeacherAssignmentInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let class_id = req
+        .param::<i32>("id")
+        .ok_or_else(|| AppError::validation("invalid class id"))?;
+    let class = find_class(&state, claims, class_id).await?;
+    let params = req.parse_queries::<SearchTeacherAssignmentsParams>()?;
+    let list = get_list_impl(&state, class.id, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    class_id: i32,
+    params: SearchTeacherAssignmentsParams,
+) -> Result<PagingResponse<TeacherAssignmentInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+
+    let mut query = teacher_assignments::Entity::find()
+        .filter(teacher_assignments::Column::ClassId.eq(class_id));
+    crate::filter_if_some!(query, teacher_assignments::Column::Role, params.role, eq);
+    crate::filter_if_some!(query, teacher_assignments::Column::Status, params.status, eq);
+    crate::filter_if_some!(query, teacher_assignments::Column::UserId, params.user_id, eq);
+    if params.active == Some(true) {
+        query = query.filter(active_condition(Utc::now()));
+    }
+
+    let paginator = query
+        .order_by_desc(teacher_assignments::Column::ValidFrom)
+        .order_by_desc(teacher_assignments::Column::Id)
+        .paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let models = paginator.fetch_page(page - 1).await?;
+    let list = enrich_assignments(state, models).await?;
+    Ok(PagingResponse { list, total, page })
+}
+
+// Approve substitute request
+#[handler]
+pub async fn approve(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<TeacherAssignmentInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let assignment = review_impl(&state, &audit, claims, id.into_inner(), ASSIGNMENT_STATUS_APPROVED).await?;
+    Ok(ApiResponse::success(assignment))
+}
+
+// Reject substitute request
+#[handler]
+pub async fn reject(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<TeacherAssignmentInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let assignment = review_impl(&state, &audit, claims, id.into_inner(), ASSIGNMENT_STATUS_REJECTED).await?;
+    Ok(ApiResponse::success(assignment))
+}
+
+pub async fn review_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    claims: &Claims,
+    id: i32,
+    status: &str,
+) -> Result<TeacherAssignmentInfo, AppError> {
+    let assignment = find_assignment(state, id).await?;
+    find_class(state, claims, assignment.class_id).await?;
+    if !can_review(state, claims, assignment.class_id).await? {
+        return Err(AppError::forbidden(format!("review assignments of class {}", assignment.class_id)));
+    }
+    if assignment.status != ASSIGNMENT_STATUS_PENDING {
+        return Err(AppError::business_logic(
+            "ASSIGNMENT_NOT_PENDING",
+            "Assignment has already been reviewed",
+        ));
+    }
+    let now = Utc::now();
+    if status == ASSIGNMENT_STATUS_APPROVED && assignment.valid_to.is_some_and(|valid_to| valid_to <= now) {
+        return Err(AppError::business_logic("ASSIGNMENT_EXPIRED", "Assignment has already ended"));
+    }
+    let mut active_model: teacher_assignments::ActiveModel = assignment.into();
+    active_model.status = Set(status.to_string());
+    active_model.reviewed_by = Set(Some(claims.user_id));
+    active_model.reviewed_at = Set(Some(now.into()));
+    active_model.updated_at = Set(now.into());
+    let txn = audit::begin(state, audit).await?;
+    let assignment = active_model.update(&txn).await?;
+    txn.commit().await?;
+    to_info(state, assignment).await
+}
+
+// Cancel a pending request or end an assignment early (handover)
+#[handler]
+pub async fn cancel(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<TeacherAssignmentInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let audit = AuditContext::from_depot(depot);
+    let assignment = cancel_impl(&state, &audit, claims, id.into_inner()).await?;
+    Ok(ApiResponse::success(assignment))
+}
+
+/// 申请人和被安排的教师可以撤销, 管理员和班主任可以提前结束任何安排
+pub async fn cancel_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    claims: &Claims,
+    id: i32,
+) -> Result<TeacherAssignmentInfo, AppError> {
+    let assignment = find_assignment(state, id).await?;
+    find_class(state, claims, assignment.class_id).await?;
+    let own = assignment.user_id == claims.user_id || assignment.requested_by == Some(claims.user_id);
+    if !own && !can_review(state, claims, assignment.class_id).await? {
+        return Err(AppError::forbidden(format!("cancel assignment {}", id)));
+    }
+    let now = Utc::now();
+    let ended = assignment.valid_to.is_some_and(|valid_to| valid_to <= now);
+    if ![ASSIGNMENT_STATUS_PENDING, ASSIGNMENT_STATUS_APPROVED].contains(&assignment.status.as_str()) || ended {
+        return Err(AppError::business_logic(
+            "ASSIGNMENT_CLOSED",
+            "Assignment has already been closed",
+        ));
+    }
+    let started = assignment.status == ASSIGNMENT_STATUS_APPROVED && assignment.valid_from <= now;
+    let mut active_model: teacher_assignments::ActiveModel = assignment.into();
+    if started {
+        // 已经开始的安排保留记录, 只把结束时间提前到现在
+        active_model.valid_to = Set(Some(now.into()));
+    } else {
+        active_model.status = Set(ASSIGNMENT_STATUS_CANCELLED.to_string());
+    }
+    active_model.updated_at = Set(now.into());
+    let txn = audit::begin(state, audit).await?;
+    let assignment = active_model.update(&txn).await?;
+    txn.commit().await?;
+    to_info(state, assignment).await
+}
@@ -0,0 +1,453 @@
+use crate::apis::academic_year_api::in_current_year;
+use crate::apis::auth_middleware::Claims;
+use crate::apis::school_api::ensure_school_access;
+use crate::apis::school_membership_api::join_school;
+use crate::apis::user_api::{insert_bindings, AuthResponse};
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{TEACHER_INVITE_KEY_PREFIX, TEACHER_INVITE_TTL_DAYS, TEACHER_ROLE_ID};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use crate::utils::jwt::create_jwt;
+use crate::utils::table_import::{self, ImportRow, ImportRowError};
+use data_model::{classes, schools, user_roles, users};
+use rand::distr::{Alphanumeric, SampleString};
+use salvo::http::header::CONTENT_DISPOSITION;
+use salvo::http::HeaderValue;
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use validator::Validate;
+
+const CREDENTIAL_PASSWORD: &str = "password";
+const CREDENTIAL_INVITE: &str = "invite";
+const FORMAT_CSV: &str = "csv";
+const PASSWORD_LENGTH: usize = 10;
+const INVITE_CODE_LENGTH: usize = 16;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TeacherImportParams {
+    /// 只校验并返回计划, 不写入
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub dry_run: Option<bool>,
+    /// password: 生成初始密码; invite: 生成邀请码, 教师用邀请码自己设置密码
+    pub credential: Option<String>,
+    /// csv 时返回带账号凭据的结果文件, 否则返回 JSON
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct AcceptInvitePayload {
+    pub code: String,
+    #[validate(length(min = 6, max = 255))]
+    pub password: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TeacherImportRowResult {
+    pub line: usize,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub phone: Option<String>,
+    pub class_ids: Vec<i32>,
+    /// dry_run 时为空
+    pub user_id: Option<i32>,
+    pub password: Option<String>,
+    pub invite_code: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TeacherImportReport {
+    pub dry_run: bool,
+    /// 有错误或 dry_run 时为 false, 不会创建任何账号
+    pub committed: bool,
+    pub credential: String,
+    pub total_rows: usize,
+    pub created: usize,
+    pub rows: Vec<TeacherImportRowResult>,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// 邀请码对应的账号, 存在 Redis 里直到过期或被使用
+#[derive(Serialize, Deserialize, Debug)]
+struct TeacherInvite {
+    user_id: i32,
+}
+
+/// 校验通过的一行
+#[derive(Debug)]
+struct TeacherRow {
+    line: usize,
+    username: String,
+    real_name: Option<String>,
+    phone: Option<String>,
+    class_ids: Vec<i32>,
+}
+
+fn invite_key(code: &str) -> String {
+    format!("{}{}", TEACHER_INVITE_KEY_PREFIX, code)
+}
+
+/// "3-2;4-1" 表示三年级二班和四年级一班
+fn parse_class_keys(value: &str) -> Result<Vec<(i32, i32)>, String> {
+    value
+        .split([';', '；'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|item| {
+            let (grade, class) = item
+                .split_once('-')
+                .ok_or_else(|| format!("invalid class: {}, expected grade-class", item))?;
+            let grade = grade.trim().parse::<i32>().map_err(|_| format!("invalid class: {}", item))?;
+            let class = class.trim().parse::<i32>().map_err(|_| format!("invalid class: {}", item))?;
+            Ok((grade, class))
+        })
+        .collect()
+}
+
+fn parse_row(
+    row: &ImportRow,
+    classes_by_key: &HashMap<(i32, i32), i32>,
+    errors: &mut Vec<ImportRowError>,
+) -> Option<TeacherRow> {
+    let error_count = errors.len();
+    let username = row.get("username").map(str::to_string);
+    match username.as_deref() {
+        None => errors.push(ImportRowError::new(row.line, Some("username"), "username is required")),
+        Some(u) if u.chars().count() > 255 => {
+            errors.push(ImportRowError::new(row.line, Some("username"), "username is longer than 255 characters"))
+        }
+        _ => {}
+    }
+    let real_name = row.get("name").map(str::to_string);
+    if real_name.as_ref().is_some_and(|n| n.chars().count() > 100) {
+        errors.push(ImportRowError::new(row.line, Some("name"), "name is longer than 100 characters"));
+    }
+    let phone = row.get("phone").map(str::to_string);
+    if let Some(p) = phone.as_deref()
+        && (p.len() > 20 || !p.chars().all(|c| c.is_ascii_digit() || c == '+' || c == '-'))
+    {
+        errors.push(ImportRowError::new(row.line, Some("phone"), format!("invalid phone: {}", p)));
+    }
+    let mut class_ids = Vec::new();
+    if let Some(value) = row.get("classes") {
+        match parse_class_keys(value) {
+            Ok(keys) => {
+                for (grade, class) in keys {
+                    match classes_by_key.get(&(grade, class)) {
+                        Some(id) if !class_ids.contains(id) => class_ids.push(*id),
+                        Some(_) => {}
+                        None => errors.push(ImportRowError::new(
+                            row.line,
+                            Some("classes"),
+                            format!("class {}-{} not found in this school", grade, class),
+                        )),
+                    }
+                }
+            }
+            Err(message) => errors.push(ImportRowError::new(row.line, Some("classes"), message)),
+        }
+    }
+    if errors.len() > error_count {
+        return None;
+    }
+    Some(TeacherRow {
+        line: row.line,
+        username: username?,
+        real_name,
+        phone,
+        class_ids,
+    })
+}
+
+/// 文件内重复或者数据库里已经存在的用户名、手机号
+async fn check_conflicts(
+    state: &AppState,
+    parsed: Vec<TeacherRow>,
+    errors: &mut Vec<ImportRowError>,
+) -> Result<Vec<TeacherRow>, AppError> {
+    let usernames: Vec<String> = parsed.iter().map(|r| r.username.clone()).collect();
+    let phones: Vec<String> = parsed.iter().filter_map(|r| r.phone.clone()).collect();
+    let mut condition = Condition::any().add(users::Column::Username.is_in(usernames));
+    if !phones.is_empty() {
+        condition = condition.add(users::Column::Phone.is_in(phones));
+    }
+    let existing = users::Entity::find().filter(condition).all(&state.db).await?;
+    let existing_usernames: HashSet<String> = existing.iter().map(|u| u.username.clone()).collect();
+    let existing_phones: HashSet<String> = existing.iter().filter_map(|u| u.phone.clone()).collect();
+
+    let mut seen_usernames: HashMap<String, usize> = HashMap::new();
+    let mut seen_phones: HashMap<String, usize> = HashMap::new();
+    let mut accepted = Vec::new();
+    for row in parsed {
+        let error_count = errors.len();
+        if existing_usernames.contains(&row.username) {
+            errors.push(ImportRowError::new(
+                row.line,
+                Some("username"),
+                format!("username {} already exists", row.username),
+            ));
+        } else if let Some(first_line) = seen_usernames.get(&row.username) {
+            errors.push(ImportRowError::new(
+                row.line,
+                Some("username"),
+                format!("duplicate username {}, first seen on line {}", row.username, first_line),
+            ));
+        }
+        if let Some(phone) = row.phone.as_ref() {
+            if existing_phones.contains(phone) {
+                errors.push(ImportRowError::new(row.line, Some("phone"), format!("phone {} already exists", phone)));
+            } else if let Some(first_line) = seen_phones.get(phone) {
+                errors.push(ImportRowError::new(
+                    row.line,
+                    Some("phone"),
+                    format!("duplicate phone {}, first seen on line {}", phone, first_line),
+                ));
+            }
+            seen_phones.entry(phone.clone()).or_insert(row.line);
+        }
+        seen_usernames.entry(row.username.clone()).or_insert(row.line);
+        if errors.len() == error_count {
+            accepted.push(row);
+        }
+    }
+    Ok(accepted)
+}
+
+// Import teacher accounts of a school from a CSV or XLSX file
+#[handler]
+pub async fn import(depot: &mut Depot, req: &mut Request, res: &mut Response) -> Result<(), AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = req
+        .param::<i32>("id")
+        .ok_or_else(|| AppError::validation("invalid school id"))?;
+    ensure_school_access(&state, claims, school_id).await?;
+    let params = req.parse_queries::<TeacherImportParams>()?;
+    let as_csv = params.format.as_deref() == Some(FORMAT_CSV);
+    let rows = table_import::read_upload(req).await?;
+    let audit = AuditContext::from_depot(depot);
+    let report = import_impl(&state, &audit, school_id, params, rows).await?;
+    if as_csv {
+        render_csv(res, &report)?;
+    } else {
+        res.render(Json(ApiResponse::success(report)));
+    }
+    Ok(())
+}
+
+pub async fn import_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    school_id: i32,
+    params: TeacherImportParams,
+    rows: Vec<ImportRow>,
+) -> Result<TeacherImportReport, AppError> {
+    let dry_run = params.dry_run.unwrap_or(false);
+    let credential = params.credential.unwrap_or_else(|| CREDENTIAL_PASSWORD.to_string());
+    if ![CREDENTIAL_PASSWORD, CREDENTIAL_INVITE].contains(&credential.as_str()) {
+        return Err(AppError::validation(format!("invalid credential: {}", credential)));
+    }
+    schools::Entity::find_live_by_id(school_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+
+    let classes_by_key: HashMap<(i32, i32), i32> = classes::Entity::find_live()
+        .filter(classes::Column::SchoolId.eq(school_id))
+        .filter(in_current_year())
+        .order_by_desc(classes::Column::Id)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|c| ((c.grade, c.class), c.id))
+        .collect();
+
+    let total_rows = rows.len();
+    let mut errors = Vec::new();
+    let parsed: Vec<TeacherRow> = rows
+        .iter()
+        .filter_map(|row| parse_row(row, &classes_by_key, &mut errors))
+        .collect();
+    let accepted = check_conflicts(state, parsed, &mut errors).await?;
+    errors.sort_by_key(|e| e.line);
+
+    let commit = errors.is_empty() && !dry_run;
+    // bcrypt 很慢, 开事务之前在阻塞线程池里生成好所有初始密码;
+    // 邀请方式下不设置密码, 教师必须用邀请码设置密码
+    let mut passwords = if commit && credential == CREDENTIAL_PASSWORD {
+        let count = accepted.len();
+        tokio::task::spawn_blocking(move || generate_passwords(count))
+            .await
+            .map_err(|e| AppError::InternalError { message: e.to_string() })??
+    } else {
+        Vec::new()
+    }
+    .into_iter();
+    let mut results = Vec::new();
+    let mut invites = Vec::new();
+    let txn = audit::begin(state, audit).await?;
+    for row in accepted {
+        let mut result = TeacherImportRowResult {
+            line: row.line,
+            username: row.username,
+            real_name: row.real_name,
+            phone: row.phone,
+            class_ids: row.class_ids,
+            user_id: None,
+            password: None,
+            invite_code: None,
+        };
+        if commit {
+            let (password, password_hash) = passwords.next().unzip();
+            let user = users::ActiveModel {
+                username: Set(result.username.clone()),
+                password_hash: Set(password_hash.unwrap_or_default()),
+                phone: Set(result.phone.clone()),
+                real_name: Set(result.real_name.clone()),
+                school_id: Set(Some(school_id)),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            user_roles::ActiveModel {
+                user_id: Set(user.id),
+                role_id: Set(TEACHER_ROLE_ID),
+            }
+            .insert(&txn)
+            .await?;
+            join_school(&txn, user.id, school_id).await?;
+            insert_bindings(&txn, user.id, result.class_ids.clone()).await?;
+            result.user_id = Some(user.id);
+            if credential == CREDENTIAL_INVITE {
+                let code = Alphanumeric.sample_string(&mut rand::rng(), INVITE_CODE_LENGTH);
+                invites.push((code.clone(), user.id));
+                result.invite_code = Some(code);
+            } else {
+                result.password = password;
+            }
+        }
+        results.push(result);
+    }
+
+    if commit {
+        // 先保存邀请码再提交, 保存失败时整批回滚, 不会留下无法登录的教师
+        let ttl = Duration::from_secs(TEACHER_INVITE_TTL_DAYS * 24 * 3600);
+        for (code, user_id) in invites {
+            state
+                .redis
+                .set(&invite_key(&code), &TeacherInvite { user_id }, Some(ttl))
+                .await?;
+        }
+        txn.commit().await?;
+        tracing::info!("Imported {} teachers into school {}", results.len(), school_id);
+    } else {
+        txn.rollback().await?;
+    }
+    Ok(TeacherImportReport {
+        dry_run,
+        committed: commit,
+        credential,
+        total_rows,
+        created: if commit { results.len() } else { 0 },
+        rows: results,
+        errors,
+    })
+}
+
+/// 生成初始密码和对应的哈希
+fn generate_passwords(count: usize) -> Result<Vec<(String, String)>, bcrypt::BcryptError> {
+    (0..count)
+        .map(|_| {
+            let password = Alphanumeric.sample_string(&mut rand::rng(), PASSWORD_LENGTH);
+            bcrypt::hash(&password, 10).map(|hash| (password, hash))
+        })
+        .collect()
+}
+
+/// 结果文件包含生成的密码或邀请码, 只在导入时返回这一次
+fn render_csv(res: &mut Response, report: &TeacherImportReport) -> Result<(), AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let write_error = |e: csv::Error| AppError::validation(format!("failed to write csv: {}", e));
+    writer
+        .write_record(["line", "username", "name", "phone", "user_id", "password", "invite_code", "error"])
+        .map_err(write_error)?;
+    for row in &report.rows {
+        writer
+            .write_record([
+                row.line.to_string(),
+                row.username.clone(),
+                row.real_name.clone().unwrap_or_default(),
+                row.phone.clone().unwrap_or_default(),
+                row.user_id.map(|id| id.to_string()).unwrap_or_default(),
+                row.password.clone().unwrap_or_default(),
+                row.invite_code.clone().unwrap_or_default(),
+                String::new(),
+            ])
+            .map_err(write_error)?;
+    }
+    for error in &report.errors {
+        let message = match error.column.as_deref() {
+            Some(column) => format!("{}: {}", column, error.message),
+            None => error.message.clone(),
+        };
+        let empty = String::new;
+        writer
+            .write_record([error.line.to_string(), empty(), empty(), empty(), empty(), empty(), empty(), message])
+            .map_err(write_error)?;
+    }
+    let body = writer
+        .into_inner()
+        .map_err(|e| AppError::validation(format!("failed to write csv: {}", e)))?;
+    res.headers_mut().insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"teacher_import_result.csv\""),
+    );
+    res.render(Text::Csv(String::from_utf8_lossy(&body).into_owned()));
+    Ok(())
+}
+
+// Accept a teacher invite: set the password and log in
+#[handler]
+pub async fn accept_invite(
+    depot: &mut Depot,
+    req: JsonBody<AcceptInvitePayload>,
+) -> Result<ApiResponse<AuthResponse>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let token = accept_invite_impl(&state, req).await?;
+    Ok(ApiResponse::success(AuthResponse { token }))
+}
+
+pub async fn accept_invite_impl(state: &AppState, req: AcceptInvitePayload) -> Result<String, AppError> {
+    // 读取和删除是一个原子操作, 同一个邀请码并发使用时只有一次成功
+    let invite = state
+        .redis
+        .take::<TeacherInvite>(&invite_key(req.code.trim()))
+        .await?
+        .ok_or_else(|| AppError::business_logic("INVALID_INVITE", "Invite code is invalid or expired"))?;
+    let user = users::Entity::find_live_by_id(invite.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(invite.user_id)))?;
+    let user_id = user.id;
+    let mut active: users::ActiveModel = user.into();
+    active.password_hash = Set(bcrypt::hash(&req.password, 10)?);
+    active.update(&state.db).await?;
+
+    let role_ids = user_roles::Entity::find()
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .all(&state.db)
+        .await?
+        .iter()
+        .map(|r| r.role_id)
+        .collect();
+    let token = create_jwt(user_id, role_ids, None, &state.config.jwt)
+        .map_err(|_| AppError::auth_failed("Token creation failed"))?;
+    Ok(token)
+}
@@ -0,0 +1,132 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::apis::stats_api::resolve_scope;
+use crate::apis::user_api::display_name;
+use crate::core::app::AppState;
+use crate::core::constants::{TRASH_KIND_CLASSES, TRASH_KIND_SCHOOLS, TRASH_KIND_USERS};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use chrono::{DateTime, Utc};
+use data_model::{classes, school_memberships, schools, users};
+use salvo::prelude::*;
+use sea_orm::sea_query::Query;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchTrashParams {
+    /// 管理员不传时查看所有学校, 其他用户默认自己所在的学校
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TrashItem {
+    pub id: i32,
+    pub name: String,
+    pub school_id: Option<i32>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+// List soft-deleted schools, classes or users
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<TrashItem>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let kind = req.param::<String>("kind").unwrap_or_default();
+    let params = req.parse_queries::<SearchTrashParams>()?;
+    let school_id = resolve_scope(&state, claims, params.school_id).await?;
+    let list = get_list_impl(&state, &kind, school_id, params.pagination).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    kind: &str,
+    school_id: Option<i32>,
+    pagination: ListParamsReq,
+) -> Result<PagingResponse<TrashItem>, AppError> {
+    let page = pagination.page.unwrap_or(1);
+    let page_size = pagination.page_size.unwrap_or(20);
+    let (list, total) = match kind {
+        TRASH_KIND_SCHOOLS => {
+            let mut query = schools::Entity::find_deleted();
+            crate::filter_if_some!(query, schools::Column::Id, school_id, eq);
+            let paginator = query
+                .order_by_desc(schools::Column::DeletedAt)
+                .paginate(&state.db, page_size);
+            let total = paginator.num_items().await?;
+            let list = paginator
+                .fetch_page(page - 1)
+                .await?
+                .into_iter()
+                .map(|s| TrashItem {
+                    id: s.id,
+                    name: s.name,
+                    school_id: Some(s.id),
+                    deleted_at: s.deleted_at.unwrap_or_default().into(),
+                })
+                .collect();
+            (list, total)
+        }
+        TRASH_KIND_CLASSES => {
+            let mut query = classes::Entity::find_deleted();
+            crate::filter_if_some!(query, classes::Column::SchoolId, school_id, eq);
+            let paginator = query
+                .order_by_desc(classes::Column::DeletedAt)
+                .paginate(&state.db, page_size);
+            let total = paginator.num_items().await?;
+            let list = paginator
+                .fetch_page(page - 1)
+                .await?
+                .into_iter()
+                .map(|c| TrashItem {
+                    id: c.id,
+                    name: c.name,
+                    school_id: Some(c.school_id),
+                    deleted_at: c.deleted_at.unwrap_or_default().into(),
+                })
+                .collect();
+            (list, total)
+        }
+        TRASH_KIND_USERS => {
+            let mut query = users::Entity::find_deleted();
+            if let Some(school_id) = school_id {
+                query = query.filter(
+                    users::Column::Id.in_subquery(
+                        Query::select()
+                            .column(school_memberships::Column::UserId)
+                            .from(school_memberships::Entity)
+                            .and_where(school_memberships::Column::SchoolId.eq(school_id))
+                            .to_owned(),
+                    ),
+                );
+            }
+            let paginator = query
+                .order_by_desc(users::Column::DeletedAt)
+                .paginate(&state.db, page_size);
+            let total = paginator.num_items().await?;
+            let list = paginator
+                .fetch_page(page - 1)
+                .await?
+                .into_iter()
+                .map(|u| TrashItem {
+                    id: u.id,
+                    name: display_name(&u),
+                    school_id: u.school_id,
+                    deleted_at: u.deleted_at.unwrap_or_default().into(),
+                })
+                .collect();
+            (list, total)
+        }
+        _ => return Err(AppError::validation(format!("invalid trash kind: {}", kind))),
+    };
+    Ok(PagingResponse { list, total, page })
+}
@@ -1,662 +1,903 @@
-use crate::apis::auth_middleware::Claims;
-use crate::apis::list_api::ListParamsReq;
-use crate::apis::list_api::PagingResponse;
-use crate::core::app::AppState;
-use crate::core::constants;
-use crate::core::error::AppError;
-use crate::core::response::ApiResponse;
-use crate::utils::convert::from_str_optional;
-use crate::utils::jwt::create_jwt;
-use bcrypt::verify;
-use chrono::{DateTime, Utc};
-use data_model::{classes, roles, schools, teacher_classes, user_roles, users};
-use salvo::{oapi::extract::*, prelude::*};
-use sea_orm::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use tracing::info;
-use validator::Validate;
-
-type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
-
-#[derive(Deserialize, ToSchema)]
-pub struct AuthPayload {
-    pub username: String,
-    pub password: String,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct AuthResponse {
-    pub token: String,
-}
-
-#[derive(Deserialize, Debug, Validate)]
-pub struct UserCreatePayload {
-    pub username: String,
-    pub role_ids: Option<Vec<i32>>,
-    pub class_ids: Option<Vec<i32>>,
-    pub password: String,
-}
-
-#[derive(Deserialize, Debug, Validate)]
-pub struct UserUpdatePayload {
-    pub username: Option<String>,
-    pub role_ids: Option<Vec<i32>>,
-    pub class_ids: Option<Vec<i32>>,
-    pub password: Option<String>,
-    pub school_id: Option<i32>,
-    pub phone: Option<String>,
-    pub wechat_openid: Option<String>,
-    pub wechat_unionid: Option<String>,
-    pub wechat_nickname: Option<String>,
-    pub wechat_avatar_url: Option<String>,
-}
-
-#[derive(Deserialize, Debug, Validate)]
-pub struct ChangePasswordPayload {
-    pub old_password: String,
-    pub new_password: String,
-}
-
-#[derive(Deserialize, Serialize, Debug, FromQueryResult)]
-pub struct UserClassInfo {
-    pub class_id: i32,
-    pub class_name: String,
-    pub school_id: i32,
-    pub school_name: String,
-    pub grade: i32,
-    pub class: i32,
-    pub status: i32,
-}
-
-#[derive(Deserialize, Serialize, Debug, FromQueryResult)]
-pub struct UserRoleInfo {
-    pub role_id: i32,
-    pub role_name: String,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct UserInfo {
-    pub id: i32,
-    pub username: String,
-    pub school_id: Option<i32>,
-    pub school_name: Option<String>,
-    pub phone: Option<String>,
-    pub wechat_openid: Option<String>,
-    pub wechat_unionid: Option<String>,
-    pub wechat_nickname: Option<String>,
-    pub wechat_avatar_url: Option<String>,
-    pub class_infos: Vec<UserClassInfo>,
-    pub role_infos: Vec<UserRoleInfo>,
-    pub created_at: DateTime<Utc>,
-}
-
-#[derive(Deserialize, Debug, Default)]
-pub struct SearchUsersParams {
-    #[serde(flatten)]
-    pub pagination: ListParamsReq,
-    pub username: Option<String>,
-    #[serde(deserialize_with = "from_str_optional", default)]
-    pub id: Option<i32>,
-}
-
-#[handler]
-pub async fn register(
-    json: JsonBody<AuthPayload>,
-    depot: &mut Depot,
-) -> Result<ApiResponse<AuthResponse>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let user_exists = users::Entity::find()
-        .filter(users::Column::Username.eq(&json.username))
-        .one(&state.db)
-        .await?;
-    if user_exists.is_some() {
-        return Err(AppError::user_already_exists());
-    }
-    let new_user = add_impl(
-        &state,
-        UserCreatePayload {
-            username: json.username.clone(),
-            password: json.password.clone(),
-            role_ids: Some(vec![constants::DEFAULT_ROLE_ID]),
-            class_ids: None,
-        },
-        None,
-    )
-    .await?;
-    let user_info = get_by_id_impl(&state, new_user.id).await?;
-    info!("User registered: {}", user_info.username);
-    let role_ids = user_info.role_infos.iter().map(|r| r.role_id).collect();
-    let token = create_jwt(user_info.id, role_ids, &state.config.jwt)
-        .map_err(|_| AppError::auth_failed("Token creation failed"))?;
-    Ok(ApiResponse::success(AuthResponse { token }))
-}
-
-#[handler]
-pub async fn login(
-    payload: JsonBody<AuthPayload>,
-    depot: &mut Depot,
-) -> Result<ApiResponse<AuthResponse>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let user_result = users::Entity::find()
-        .filter(users::Column::Username.eq(&payload.username.clone()))
-        .find_also_related(roles::Entity)
-        .one(&state.db)
-        .await?;
-    let user_result = user_result.ok_or(AppError::NotFound {
-        resource: "user".to_string(),
-        id: None,
-    })?;
-    let is_valid = verify(&payload.password, &user_result.0.password_hash)
-        .map_err(|_| AppError::auth_failed("User or password error"))?;
-    if !is_valid {
-        return Err(AppError::auth_failed("User or password error"));
-    }
-    tracing::info!("User logged in: {}", user_result.0.username);
-    let user_id = user_result.0.id;
-    let role_ids = user_roles::Entity::find()
-        .filter(user_roles::Column::UserId.eq(user_id))
-        .all(&state.db)
-        .await?;
-    let role_ids = role_ids.iter().map(|r| r.role_id).collect();
-    let token = create_jwt(user_id, role_ids, &state.config.jwt)
-        .map_err(|_| AppError::auth_failed("Token creation failed"))?;
-    Ok(ApiResponse::success(AuthResponse { token }))
-}
-
-#[handler]
-pub async fn get_current_user(depot: &mut Depot) -> Result<ApiResponse<UserInfo>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let user_info = get_by_id_impl(&state, depot.obtain::<Claims>().unwrap().user_id).await?;
-    Ok(ApiResponse::success(user_info))
-}
-
-#[handler]
-pub async fn change_password(
-    payload: JsonBody<ChangePasswordPayload>,
-    depot: &mut Depot,
-) -> Result<ApiResponse<bool>, AppError> {
-    use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
-    let state = depot.obtain::<AppState>().unwrap();
-    let user = users::Entity::find_by_id(depot.obtain::<Claims>().unwrap().user_id)
-        .one(&state.db)
-        .await?;
-    let user = user.ok_or(AppError::auth_failed("User not found"))?;
-    let is_valid = verify(&payload.old_password, &user.password_hash)
-        .map_err(|_| AppError::auth_failed("Old password incorrect"))?;
-    if !is_valid {
-        return Err(AppError::auth_failed("Old password incorrect"));
-    }
-    let mut active = user.into_active_model();
-    active.password_hash = Set(bcrypt::hash(payload.new_password.clone(), 10)?);
-    let _ = active.update(&state.db).await?;
-    Ok(ApiResponse::success(true))
-}
-
-// Create User
-#[handler]
-pub async fn add(
-    depot: &mut Depot,
-    req: JsonBody<UserCreatePayload>,
-) -> Result<ApiResponse<users::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let entity = add_impl(&state, req.into_inner(), None).await?;
-    Ok(ApiResponse::success(entity))
-}
-
-pub async fn add_impl(
-    state: &AppState,
-    req: UserCreatePayload,
-    insert_callback: Option<
-        Box<dyn for<'a> FnOnce(&'a mut users::ActiveModel) -> BoxFuture<'a, Result<(), AppError>> + Send>,
-    >,
-) -> Result<users::Model, AppError> {
-    let txn = state.db.begin().await?;
-    let password_hash = bcrypt::hash(req.password, 10)?;
-
-    // 1. Create user
-    let mut new_user = users::ActiveModel {
-        username: Set(req.username),
-        password_hash: Set(password_hash),
-        ..Default::default()
-    };
-    if let Some(callback) = insert_callback {
-        callback(&mut new_user).await?;
-    }
-    let user_model = new_user.insert(&txn).await?;
-
-    // 2. Assign role
-    if let Some(role_ids) = req.role_ids {
-        for role_id in role_ids {
-            let new_user_role = user_roles::ActiveModel {
-                user_id: Set(user_model.id),
-                role_id: Set(role_id),
-            };
-            new_user_role.insert(&txn).await?;
-        }
-    }
-
-    // 3. Assign classes if provided
-    if let Some(class_ids) = req.class_ids {
-        let teacher_class_models: Vec<teacher_classes::ActiveModel> = class_ids
-            .into_iter()
-            .map(|class_id| teacher_classes::ActiveModel {
-                user_id: Set(user_model.id),
-                class_id: Set(class_id),
-            })
-            .collect();
-        if !teacher_class_models.is_empty() {
-            teacher_classes::Entity::insert_many(teacher_class_models)
-                .exec(&txn)
-                .await?;
-        }
-    }
-
-    txn.commit().await?;
-    Ok(user_model)
-}
-
-// Update User
-#[handler]
-pub async fn update(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-    req: JsonBody<UserUpdatePayload>,
-) -> Result<ApiResponse<users::Model>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let user = update_impl(&state, id.into_inner(), req.into_inner()).await?;
-    Ok(ApiResponse::success(user))
-}
-
-#[handler]
-pub async fn update_my_info(
-    depot: &mut Depot,
-    req: JsonBody<UserUpdatePayload>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let claims = depot.obtain::<Claims>().unwrap();
-    let mut req = req.into_inner();
-    req.role_ids=None;
-    req.school_id=None;
-    update_impl(&state, claims.user_id, req).await?;
-    Ok(ApiResponse::success(()))
-}
-
-pub async fn update_impl(
-    state: &AppState,
-    id: i32,
-    req: UserUpdatePayload,
-) -> Result<users::Model, AppError> {
-    let txn = state.db.begin().await?;
-    let user = users::Entity::find_by_id(id).one(&txn).await?;
-    let user = user.ok_or_else(|| AppError::not_found("users".to_string(), Some(id)))?;
-    let mut user_active_model: users::ActiveModel = user.into();
-    if let Some(username) = req.username {
-        user_active_model.username = Set(username);
-    }
-
-    if let Some(password) = req.password {
-        let hashed_password = bcrypt::hash(password, 10)?;
-        user_active_model.password_hash = Set(hashed_password);
-    }
-
-    if let Some(wechat_avatar_url) = req.wechat_avatar_url {
-        user_active_model.wechat_avatar_url = Set(Some(wechat_avatar_url));
-    }
-    if let Some(wechat_nickname) = req.wechat_nickname {
-        user_active_model.wechat_nickname = Set(Some(wechat_nickname));
-    }
-    if let Some(phone) = req.phone {
-        user_active_model.phone = Set(Some(phone));
-    }
-
-    if let Some(role_ids) = req.role_ids {
-        user_roles::Entity::delete_many()
-            .filter(user_roles::Column::UserId.eq(id))
-            .exec(&txn)
-            .await?;
-        for role_id in role_ids {
-            user_roles::ActiveModel {
-                user_id: Set(id),
-                role_id: Set(role_id),
-            }
-            .insert(&txn)
-            .await?;
-        }
-    }
-    if let Some(class_ids) = req.class_ids {
-        teacher_classes::Entity::delete_many()
-            .filter(teacher_classes::Column::UserId.eq(id))
-            .exec(&txn)
-            .await?;
-
-        if !class_ids.is_empty() {
-            let models: Vec<teacher_classes::ActiveModel> = class_ids
-                .into_iter()
-                .map(|class_id| teacher_classes::ActiveModel {
-                    user_id: Set(id),
-                    class_id: Set(class_id),
-                })
-                .collect();
-            teacher_classes::Entity::insert_many(models)
-                .exec(&txn)
-                .await?;
-        }
-    }
-
-    let user = user_active_model.update(&txn).await?;
-    txn.commit().await?;
-    Ok(user)
-}
-
-// Delete User
-#[handler]
-pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let claim = depot.obtain::<Claims>().unwrap();
-    let id = id.into_inner();
-    //cant delete self
-    if id == claim.user_id {
-        return Err(AppError::Message("cannot delete self".to_string()));
-    }
-    delete_impl(&state, id).await?;
-    Ok(ApiResponse::success(()))
-}
-
-pub async fn delete_impl(state: &AppState, id: i32) -> Result<(), AppError> {
-    let user = users::Entity::find_by_id(id).one(&state.db).await?;
-    let user = user.ok_or_else(|| AppError::not_found("users".to_string(), Some(id)))?;
-    let _ = user.delete(&state.db).await?;
-    Ok(())
-}
-
-// Get Users List
-#[handler]
-pub async fn get_list(
-    depot: &mut Depot,
-    req: &mut Request,
-) -> Result<ApiResponse<PagingResponse<UserInfo>>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let params = req.parse_queries::<SearchUsersParams>()?;
-    let list = get_list_impl(&state, params).await?;
-    Ok(ApiResponse::success(list))
-}
-
-async fn enrich_users_with_details(
-    state: &AppState,
-    user_models: Vec<users::Model>,
-) -> Result<Vec<UserInfo>, AppError> {
-    if user_models.is_empty() {
-        return Ok(vec![]);
-    }
-    let user_ids: Vec<i32> = user_models.iter().map(|u| u.id).collect();
-
-    // Fetch all related data in batches
-    let user_roles_list = user_roles::Entity::find()
-        .filter(user_roles::Column::UserId.is_in(user_ids.clone()))
-        .all(&state.db)
-        .await?;
-    let teacher_classes_list = teacher_classes::Entity::find()
-        .filter(teacher_classes::Column::UserId.is_in(user_ids.clone()))
-        .all(&state.db)
-        .await?;
-    // schoolids
-    let school_ids: Vec<i32> = user_models.iter().filter_map(|u| u.school_id).collect();
-    let user_schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
-        HashMap::new()
-    } else {
-        schools::Entity::find()
-            .filter(schools::Column::Id.is_in(school_ids))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|s| (s.id, s))
-            .collect()
-    };
-
-    let role_ids: Vec<i32> = user_roles_list.iter().map(|ur| ur.role_id).collect();
-    let class_ids: Vec<i32> = teacher_classes_list.iter().map(|tc| tc.class_id).collect();
-
-    let roles_map: HashMap<i32, roles::Model> = if role_ids.is_empty() {
-        HashMap::new()
-    } else {
-        roles::Entity::find()
-            .filter(roles::Column::Id.is_in(role_ids))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|r| (r.id, r))
-            .collect()
-    };
-
-    let classes_map: HashMap<i32, classes::Model> = if class_ids.is_empty() {
-        HashMap::new()
-    } else {
-        classes::Entity::find()
-            .filter(classes::Column::Id.is_in(class_ids))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|c| (c.id, c))
-            .collect()
-    };
-
-    let school_ids: Vec<i32> = classes_map.values().map(|c| c.school_id).collect();
-    let schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
-        HashMap::new()
-    } else {
-        schools::Entity::find()
-            .filter(schools::Column::Id.is_in(school_ids))
-            .all(&state.db)
-            .await?
-            .into_iter()
-            .map(|s| (s.id, s))
-            .collect()
-    };
-
-    // Map data to UserInfo
-    let list = user_models
-        .into_iter()
-        .map(|user| {
-            let role_infos: Vec<UserRoleInfo> = user_roles_list
-                .iter()
-                .filter(|ur| ur.user_id == user.id)
-                .filter_map(|ur| {
-                    roles_map.get(&ur.role_id).map(|r| UserRoleInfo {
-                        role_id: r.id,
-                        role_name: r.name.clone(),
-                    })
-                })
-                .collect();
-
-            let class_infos: Vec<UserClassInfo> = teacher_classes_list
-                .iter()
-                .filter(|tc| tc.user_id == user.id)
-                .filter_map(|tc| {
-                    classes_map.get(&tc.class_id).and_then(|c| {
-                        schools_map.get(&c.school_id).map(|s| UserClassInfo {
-                            class_id: c.id,
-                            class_name: c.name.clone(),
-                            school_id: s.id,
-                            school_name: s.name.clone(),
-                            grade: c.grade,
-                            class: c.class,
-                            status: c.status,
-                        })
-                    })
-                })
-                .collect();
-
-            let school_name = user
-                .school_id
-                .and_then(|school_id| user_schools_map.get(&school_id).map(|s| s.name.clone()));
-
-            UserInfo {
-                id: user.id,
-                username: user.username,
-                school_id: user.school_id,
-                school_name,
-                phone: user.phone,
-                wechat_openid: user.wechat_openid,
-                wechat_unionid: user.wechat_unionid,
-                wechat_nickname: user.wechat_nickname,
-                wechat_avatar_url: user.wechat_avatar_url,
-                created_at: user.created_at.into(),
-                role_infos,
-                class_infos,
-            }
-        })
-        .collect();
-    Ok(list)
-}
-
-pub async fn get_list_impl(
-    state: &AppState,
-    params: SearchUsersParams,
-) -> Result<PagingResponse<UserInfo>, AppError> {
-    let page = params.pagination.page.unwrap_or(1);
-    let page_size = params.pagination.page_size.unwrap_or(20);
-
-    let mut query = users::Entity::find();
-    crate::filter_if_some!(query, users::Column::Id, params.id, eq);
-    crate::filter_if_some!(query, users::Column::Username, params.username, like);
-
-    let paginator = query.paginate(&state.db, page_size);
-    let total = paginator.num_items().await?;
-    let user_models = paginator.fetch_page(page - 1).await?;
-    let list = enrich_users_with_details(state, user_models).await?;
-
-    Ok(PagingResponse { list, total, page })
-}
-
-// Get User by ID
-#[handler]
-pub async fn get_by_id(
-    depot: &mut Depot,
-    id: PathParam<i32>,
-) -> Result<ApiResponse<UserInfo>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let user = get_by_id_impl(&state, id.into_inner()).await?;
-    Ok(ApiResponse::success(user))
-}
-
-pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<UserInfo, AppError> {
-    let user = users::Entity::find_by_id(id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::UserNotFound { message: "User not found".to_string() })?;
-
-    let mut user_infos = enrich_users_with_details(state, vec![user]).await?;
-    if user_infos.is_empty() {
-        return Err(AppError::not_found("users".to_string(), Some(id)));
-    }
-    Ok(user_infos.remove(0))
-}
-
-#[derive(Deserialize, Debug, ToSchema)]
-pub struct BindClassPayload {
-    pub class_id: i32,
-    pub password: String,
-}
-
-#[handler]
-pub async fn bind_class(
-    depot: &mut Depot,
-    req: JsonBody<BindClassPayload>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let claims = depot.obtain::<Claims>().unwrap();
-
-    let class = classes::Entity::find()
-        .filter(
-            Condition::all()
-                .add(classes::Column::Password.eq(&req.password))
-                .add(classes::Column::Id.eq(req.class_id)),
-        )
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("class with this password".to_string(), None))?;
-
-    let existing_binding = teacher_classes::Entity::find()
-        .filter(
-            Condition::all()
-                .add(teacher_classes::Column::UserId.eq(claims.user_id))
-                .add(teacher_classes::Column::ClassId.eq(class.id)),
-        )
-        .one(&state.db)
-        .await?;
-
-    if existing_binding.is_some() {
-        return Err(AppError::Message("Already bound to this class".to_string()));
-    }
-
-    let new_binding = teacher_classes::ActiveModel {
-        user_id: Set(claims.user_id),
-        class_id: Set(class.id),
-    };
-    new_binding.insert(&state.db).await?;
-
-    Ok(ApiResponse::success(()))
-}
-
-#[handler]
-pub async fn unbind_class(
-    depot: &mut Depot,
-    class_id: PathParam<i32>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let claims = depot.obtain::<Claims>().unwrap();
-
-    let result = teacher_classes::Entity::delete_many()
-        .filter(
-            Condition::all()
-                .add(teacher_classes::Column::UserId.eq(claims.user_id))
-                .add(teacher_classes::Column::ClassId.eq(class_id.into_inner())),
-        )
-        .exec(&state.db)
-        .await?;
-
-    if result.rows_affected == 0 {
-        return Err(AppError::not_found(
-            "binding for this class".to_string(),
-            None,
-        ));
-    }
-
-    Ok(ApiResponse::success(()))
-}
-
-#[derive(Deserialize, Debug, ToSchema)]
-pub struct BindSchoolPayload {
-    pub school_id: i32,
-    pub password: String,
-}
-
-#[handler]
-pub async fn bind_school(
-    depot: &mut Depot,
-    req: JsonBody<BindSchoolPayload>,
-) -> Result<ApiResponse<()>, AppError> {
-    let state = depot.obtain::<AppState>().unwrap();
-    let claims = depot.obtain::<Claims>().unwrap();
-    let school = schools::Entity::find_by_id(req.school_id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("school".to_string(), Some(req.school_id)))?;
-    if school.password!=req.password {
-        return Err(AppError::auth_failed("School password incorrect"));
-    }
-    let user = users::Entity::find_by_id(claims.user_id)
-        .one(&state.db)
-        .await?
-        .ok_or_else(|| AppError::not_found("user".to_string(), Some(claims.user_id)))?;
-    let mut user_active_model: users::ActiveModel = user.into();
-    user_active_model.school_id = Set(Some(school.id));
-    user_active_model.update(&state.db).await?;  
-    Ok(ApiResponse::success(()))
-}
-
-#[handler]
-pub async fn logout() -> Result<ApiResponse<()>, AppError> {
-    Ok(ApiResponse::success(()))
-}
+use crate::apis::auth_middleware::Claims;
+use crate::apis::class_teacher_api::{default_binding_role, map_head_conflict};
+use crate::apis::school_membership_api::{get_memberships_by_user_ids, join_school, SchoolMembershipInfo};
+use crate::apis::teacher_assignment_api::active_condition;
+use crate::apis::list_api::ListParamsReq;
+use crate::apis::list_api::PagingResponse;
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::delete_confirm::{self, DeletePreview};
+use crate::core::constants;
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::utils::convert::from_str_optional;
+use crate::utils::jwt::create_jwt;
+use bcrypt::verify;
+use chrono::{DateTime, Utc};
+use data_model::{classes, roles, school_memberships, schools, teacher_assignments, teacher_classes, user_roles, users};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::sea_query::Query;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+use validator::Validate;
+
+type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+#[derive(Deserialize, ToSchema)]
+pub struct AuthPayload {
+    pub username: String,
+    pub password: String,
+}
+
+/// 注册时可以直接填写姓名, 省得教师第一次改班级状态前再补资料
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct RegisterPayload {
+    pub username: String,
+    pub password: String,
+    #[validate(length(max = 100))]
+    pub real_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub display_name: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuthResponse {
+    pub token: String,
+}
+
+/// 删除用户后失去的角色、班级绑定和值班安排
+#[derive(Serialize, Debug)]
+pub struct UserDeleteImpact {
+    pub user_id: i32,
+    pub username: String,
+    pub roles: Vec<String>,
+    pub class_bindings: u64,
+    /// 该用户是班主任的班级, 删除后没有班主任
+    pub head_of_classes: Vec<i32>,
+    pub active_assignments: u64,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct UserCreatePayload {
+    pub username: String,
+    pub role_ids: Option<Vec<i32>>,
+    pub class_ids: Option<Vec<i32>>,
+    pub password: String,
+    #[validate(length(max = 100))]
+    pub real_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 50))]
+    pub employee_no: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct UserUpdatePayload {
+    pub username: Option<String>,
+    pub role_ids: Option<Vec<i32>>,
+    pub class_ids: Option<Vec<i32>>,
+    pub password: Option<String>,
+    pub school_id: Option<i32>,
+    #[validate(length(max = 100))]
+    pub real_name: Option<String>,
+    #[validate(length(max = 100))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 50))]
+    pub employee_no: Option<String>,
+    pub phone: Option<String>,
+    pub wechat_openid: Option<String>,
+    pub wechat_unionid: Option<String>,
+    pub wechat_nickname: Option<String>,
+    pub wechat_avatar_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct ChangePasswordPayload {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, FromQueryResult)]
+pub struct UserClassInfo {
+    pub class_id: i32,
+    pub class_name: String,
+    pub school_id: i32,
+    pub school_name: String,
+    pub grade: i32,
+    pub class: i32,
+    pub status: i32,
+    /// 在该班级的角色: head, co_teacher, observer
+    pub role: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, FromQueryResult)]
+pub struct UserRoleInfo {
+    pub role_id: i32,
+    pub role_name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserInfo {
+    pub id: i32,
+    pub username: String,
+    pub school_id: Option<i32>,
+    pub school_name: Option<String>,
+    pub real_name: Option<String>,
+    pub display_name: Option<String>,
+    pub employee_no: Option<String>,
+    /// 界面上显示的名字, 见 [`display_name`]
+    pub name: String,
+    /// 填写了真实姓名; 教师资料不完整时不能修改班级状态
+    pub profile_complete: bool,
+    pub phone: Option<String>,
+    pub wechat_openid: Option<String>,
+    pub wechat_unionid: Option<String>,
+    pub wechat_nickname: Option<String>,
+    pub wechat_avatar_url: Option<String>,
+    pub class_infos: Vec<UserClassInfo>,
+    pub role_infos: Vec<UserRoleInfo>,
+    /// 所属的全部学校, school_id 是其中的默认学校
+    pub memberships: Vec<SchoolMembershipInfo>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchUsersParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub username: Option<String>,
+    /// 同时匹配用户名, 真实姓名和显示名
+    pub name: Option<String>,
+    pub employee_no: Option<String>,
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub id: Option<i32>,
+    /// 属于这个学校的用户
+    #[serde(deserialize_with = "from_str_optional", default)]
+    pub school_id: Option<i32>,
+}
+
+/// 界面上显示的名字: 显示名, 真实姓名, 最后才是用户名.
+/// 微信创建的用户 username 是 openid, 不能直接给人看
+pub fn display_name(user: &users::Model) -> String {
+    [&user.display_name, &user.real_name]
+        .into_iter()
+        .flatten()
+        .find(|name| !name.trim().is_empty())
+        .cloned()
+        .unwrap_or_else(|| user.username.clone())
+}
+
+pub fn is_profile_complete(user: &users::Model) -> bool {
+    user.real_name.as_ref().is_some_and(|name| !name.trim().is_empty())
+}
+
+#[handler]
+pub async fn register(
+    json: JsonBody<RegisterPayload>,
+    depot: &mut Depot,
+) -> Result<ApiResponse<AuthResponse>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    json.validate()?;
+    // 已删除的用户仍占用用户名, 恢复前不能重新注册
+    let user_exists = users::Entity::find()
+        .filter(users::Column::Username.eq(&json.username))
+        .one(&state.db)
+        .await?;
+    if user_exists.is_some() {
+        return Err(AppError::user_already_exists());
+    }
+    let new_user = add_impl(
+        &state,
+        &AuditContext::default(),
+        UserCreatePayload {
+            username: json.username.clone(),
+            password: json.password.clone(),
+            role_ids: Some(vec![constants::DEFAULT_ROLE_ID]),
+            class_ids: None,
+            real_name: json.real_name.clone(),
+            display_name: json.display_name.clone(),
+            employee_no: None,
+        },
+        None,
+    )
+    .await?;
+    let user_info = get_by_id_impl(&state, new_user.id).await?;
+    info!("User registered: {}", user_info.username);
+    let role_ids = user_info.role_infos.iter().map(|r| r.role_id).collect();
+    let token = create_jwt(user_info.id, role_ids, None, &state.config.jwt)
+        .map_err(|_| AppError::auth_failed("Token creation failed"))?;
+    Ok(ApiResponse::success(AuthResponse { token }))
+}
+
+#[handler]
+pub async fn login(
+    payload: JsonBody<AuthPayload>,
+    depot: &mut Depot,
+) -> Result<ApiResponse<AuthResponse>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let user_result = users::Entity::find_live()
+        .filter(users::Column::Username.eq(&payload.username.clone()))
+        .find_also_related(roles::Entity)
+        .one(&state.db)
+        .await?;
+    let user_result = user_result.ok_or(AppError::NotFound {
+        resource: "user".to_string(),
+        id: None,
+    })?;
+    let is_valid = verify(&payload.password, &user_result.0.password_hash)
+        .map_err(|_| AppError::auth_failed("User or password error"))?;
+    if !is_valid {
+        return Err(AppError::auth_failed("User or password error"));
+    }
+    tracing::info!("User logged in: {}", user_result.0.username);
+    let user_id = user_result.0.id;
+    let role_ids = user_roles::Entity::find()
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .all(&state.db)
+        .await?;
+    let role_ids = role_ids.iter().map(|r| r.role_id).collect();
+    let token = create_jwt(user_id, role_ids, None, &state.config.jwt)
+        .map_err(|_| AppError::auth_failed("Token creation failed"))?;
+    Ok(ApiResponse::success(AuthResponse { token }))
+}
+
+#[handler]
+pub async fn get_current_user(depot: &mut Depot) -> Result<ApiResponse<UserInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let user_info = get_by_id_impl(&state, depot.obtain::<Claims>().unwrap().user_id).await?;
+    Ok(ApiResponse::success(user_info))
+}
+
+#[handler]
+pub async fn change_password(
+    payload: JsonBody<ChangePasswordPayload>,
+    depot: &mut Depot,
+) -> Result<ApiResponse<bool>, AppError> {
+    use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
+    let state = depot.obtain::<AppState>().unwrap();
+    let txn = audit::begin(&state, &AuditContext::from_depot(depot)).await?;
+    let user = users::Entity::find_live_by_id(depot.obtain::<Claims>().unwrap().user_id)
+        .one(&txn)
+        .await?;
+    let user = user.ok_or(AppError::auth_failed("User not found"))?;
+    let is_valid = verify(&payload.old_password, &user.password_hash)
+        .map_err(|_| AppError::auth_failed("Old password incorrect"))?;
+    if !is_valid {
+        return Err(AppError::auth_failed("Old password incorrect"));
+    }
+    let mut active = user.into_active_model();
+    active.password_hash = Set(bcrypt::hash(payload.new_password.clone(), 10)?);
+    let _ = active.update(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(true))
+}
+
+// Create User
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    req: JsonBody<UserCreatePayload>,
+) -> Result<ApiResponse<users::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    req.validate()?;
+    let audit = AuditContext::from_depot(depot);
+    let entity = add_impl(&state, &audit, req.into_inner(), None).await?;
+    Ok(ApiResponse::success(entity))
+}
+
+pub async fn add_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    req: UserCreatePayload,
+    insert_callback: Option<
+        Box<dyn for<'a> FnOnce(&'a mut users::ActiveModel) -> BoxFuture<'a, Result<(), AppError>> + Send>,
+    >,
+) -> Result<users::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let password_hash = bcrypt::hash(req.password, 10)?;
+
+    // 1. Create user
+    let mut new_user = users::ActiveModel {
+        username: Set(req.username),
+        password_hash: Set(password_hash),
+        real_name: Set(req.real_name),
+        display_name: Set(req.display_name),
+        employee_no: Set(req.employee_no),
+        ..Default::default()
+    };
+    if let Some(callback) = insert_callback {
+        callback(&mut new_user).await?;
+    }
+    let user_model = new_user.insert(&txn).await?;
+
+    // 2. Assign role
+    if let Some(role_ids) = req.role_ids {
+        for role_id in role_ids {
+            let new_user_role = user_roles::ActiveModel {
+                user_id: Set(user_model.id),
+                role_id: Set(role_id),
+            };
+            new_user_role.insert(&txn).await?;
+        }
+    }
+
+    // 3. Assign classes if provided
+    if let Some(class_ids) = req.class_ids {
+        insert_bindings(&txn, user_model.id, class_ids).await?;
+    }
+
+    txn.commit().await?;
+    Ok(user_model)
+}
+
+// Update User
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<UserUpdatePayload>,
+) -> Result<ApiResponse<users::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    req.validate()?;
+    let audit = AuditContext::from_depot(depot);
+    let user = update_impl(&state, &audit, id.into_inner(), req.into_inner()).await?;
+    Ok(ApiResponse::success(user))
+}
+
+#[handler]
+pub async fn update_my_info(
+    depot: &mut Depot,
+    req: JsonBody<UserUpdatePayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    req.validate()?;
+    let mut req = req.into_inner();
+    req.role_ids=None;
+    req.school_id=None;
+    update_impl(&state, &AuditContext::from_depot(depot), claims.user_id, req).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    id: i32,
+    req: UserUpdatePayload,
+) -> Result<users::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let user = users::Entity::find_live_by_id(id).one(&txn).await?;
+    let user = user.ok_or_else(|| AppError::not_found("users".to_string(), Some(id)))?;
+    let mut user_active_model: users::ActiveModel = user.into();
+    if let Some(username) = req.username {
+        user_active_model.username = Set(username);
+    }
+
+    if let Some(password) = req.password {
+        let hashed_password = bcrypt::hash(password, 10)?;
+        user_active_model.password_hash = Set(hashed_password);
+    }
+
+    if let Some(wechat_avatar_url) = req.wechat_avatar_url {
+        user_active_model.wechat_avatar_url = Set(Some(wechat_avatar_url));
+    }
+    if let Some(wechat_nickname) = req.wechat_nickname {
+        user_active_model.wechat_nickname = Set(Some(wechat_nickname));
+    }
+    if let Some(phone) = req.phone {
+        user_active_model.phone = Set(Some(phone));
+    }
+    if let Some(real_name) = req.real_name {
+        user_active_model.real_name = Set(Some(real_name));
+    }
+    if let Some(display_name) = req.display_name {
+        user_active_model.display_name = Set(Some(display_name));
+    }
+    if let Some(employee_no) = req.employee_no {
+        user_active_model.employee_no = Set(Some(employee_no));
+    }
+
+    if let Some(role_ids) = req.role_ids {
+        user_roles::Entity::delete_many()
+            .filter(user_roles::Column::UserId.eq(id))
+            .exec(&txn)
+            .await?;
+        for role_id in role_ids {
+            user_roles::ActiveModel {
+                user_id: Set(id),
+                role_id: Set(role_id),
+            }
+            .insert(&txn)
+            .await?;
+        }
+    }
+    if let Some(class_ids) = req.class_ids {
+        // 只增删有变化的绑定, 保留已有绑定的角色
+        let existing: Vec<i32> = teacher_classes::Entity::find()
+            .filter(teacher_classes::Column::UserId.eq(id))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|tc| tc.class_id)
+            .collect();
+        teacher_classes::Entity::delete_many()
+            .filter(teacher_classes::Column::UserId.eq(id))
+            .filter(teacher_classes::Column::ClassId.is_not_in(class_ids.clone()))
+            .exec(&txn)
+            .await?;
+        let added: Vec<i32> = class_ids.into_iter().filter(|c| !existing.contains(c)).collect();
+        insert_bindings(&txn, id, added).await?;
+    }
+
+    let user = user_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(user)
+}
+
+/// 绑定班级的同时加入班级所在的学校, 不存在或已删除的班级不能绑定
+pub async fn insert_bindings<C: ConnectionTrait>(db: &C, user_id: i32, class_ids: Vec<i32>) -> Result<(), AppError> {
+    for class_id in class_ids {
+        let class = classes::Entity::find_live_by_id(class_id)
+            .one(db)
+            .await?
+            .ok_or_else(|| AppError::not_found("classes".to_string(), Some(class_id)))?;
+        join_school(db, user_id, class.school_id).await?;
+        teacher_classes::ActiveModel {
+            user_id: Set(user_id),
+            class_id: Set(class_id),
+            role: Set(default_binding_role(db, class_id).await?),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map_err(map_head_conflict)?;
+    }
+    Ok(())
+}
+
+// Preview what deleting a user affects
+#[handler]
+pub async fn delete_preview(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<DeletePreview<UserDeleteImpact>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    let impact = delete_impact(&state, id).await?;
+    let preview = delete_confirm::issue(&state, claims, "users", id, impact).await?;
+    Ok(ApiResponse::success(preview))
+}
+
+pub async fn delete_impact(state: &AppState, id: i32) -> Result<UserDeleteImpact, AppError> {
+    let user = users::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("users".to_string(), Some(id)))?;
+    let roles = user_roles::Entity::find()
+        .filter(user_roles::Column::UserId.eq(id))
+        .find_also_related(roles::Entity)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .filter_map(|(_, role)| role.map(|r| r.name))
+        .collect();
+    let bindings = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::UserId.eq(id))
+        .all(&state.db)
+        .await?;
+    let head_of_classes = bindings
+        .iter()
+        .filter(|tc| tc.role == constants::CLASS_TEACHER_ROLE_HEAD)
+        .map(|tc| tc.class_id)
+        .collect();
+    let active_assignments = teacher_assignments::Entity::find()
+        .filter(teacher_assignments::Column::UserId.eq(id))
+        .filter(active_condition(Utc::now()))
+        .count(&state.db)
+        .await?;
+    Ok(UserDeleteImpact {
+        user_id: user.id,
+        username: user.username,
+        roles,
+        class_bindings: bindings.len() as u64,
+        head_of_classes,
+        active_assignments,
+    })
+}
+
+// Delete User
+#[handler]
+pub async fn delete(depot: &mut Depot, req: &mut Request, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claim = depot.obtain::<Claims>().unwrap();
+    let id = id.into_inner();
+    //cant delete self
+    if id == claim.user_id {
+        return Err(AppError::Message("cannot delete self".to_string()));
+    }
+    delete_confirm::consume(&state, claim, req, "users", id).await?;
+    delete_impl(&state, &AuditContext::from_depot(depot), id).await?;
+    Ok(ApiResponse::success(()))
+}
+
+pub async fn delete_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<(), AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let user = users::Entity::find_live_by_id(id).one(&txn).await?;
+    let user = user.ok_or_else(|| AppError::not_found("users".to_string(), Some(id)))?;
+    // 角色和班级绑定保留, 恢复后继续有效
+    let mut user_active_model: users::ActiveModel = user.into();
+    user_active_model.deleted_at = Set(Some(Utc::now().into()));
+    user_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// Restore a deleted user
+#[handler]
+pub async fn restore(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<users::Model>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let user = restore_impl(&state, &AuditContext::from_depot(depot), id.into_inner()).await?;
+    Ok(ApiResponse::success(user))
+}
+
+pub async fn restore_impl(state: &AppState, audit: &AuditContext, id: i32) -> Result<users::Model, AppError> {
+    let txn = audit::begin(state, audit).await?;
+    let user = users::Entity::find_deleted()
+        .filter(users::Column::Id.eq(id))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::not_found("deleted users".to_string(), Some(id)))?;
+    let mut user_active_model: users::ActiveModel = user.into();
+    user_active_model.deleted_at = Set(None);
+    let user = user_active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(user)
+}
+
+// Get Users List
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<UserInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let params = req.parse_queries::<SearchUsersParams>()?;
+    let list = get_list_impl(&state, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn enrich_users_with_details(
+    state: &AppState,
+    user_models: Vec<users::Model>,
+) -> Result<Vec<UserInfo>, AppError> {
+    if user_models.is_empty() {
+        return Ok(vec![]);
+    }
+    let user_ids: Vec<i32> = user_models.iter().map(|u| u.id).collect();
+
+    // Fetch all related data in batches
+    let user_roles_list = user_roles::Entity::find()
+        .filter(user_roles::Column::UserId.is_in(user_ids.clone()))
+        .all(&state.db)
+        .await?;
+    let teacher_classes_list = teacher_classes::Entity::find()
+        .filter(teacher_classes::Column::UserId.is_in(user_ids.clone()))
+        .all(&state.db)
+        .await?;
+    let memberships_list = get_memberships_by_user_ids(&state.db, user_ids.clone()).await?;
+    // schoolids
+    let school_ids: Vec<i32> = user_models.iter().filter_map(|u| u.school_id).collect();
+    let user_schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
+        HashMap::new()
+    } else {
+        schools::Entity::find_live()
+            .filter(schools::Column::Id.is_in(school_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect()
+    };
+
+    let role_ids: Vec<i32> = user_roles_list.iter().map(|ur| ur.role_id).collect();
+    let class_ids: Vec<i32> = teacher_classes_list.iter().map(|tc| tc.class_id).collect();
+
+    let roles_map: HashMap<i32, roles::Model> = if role_ids.is_empty() {
+        HashMap::new()
+    } else {
+        roles::Entity::find()
+            .filter(roles::Column::Id.is_in(role_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|r| (r.id, r))
+            .collect()
+    };
+
+    let classes_map: HashMap<i32, classes::Model> = if class_ids.is_empty() {
+        HashMap::new()
+    } else {
+        classes::Entity::find_live()
+            .filter(classes::Column::Id.is_in(class_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|c| (c.id, c))
+            .collect()
+    };
+
+    let school_ids: Vec<i32> = classes_map.values().map(|c| c.school_id).collect();
+    let schools_map: HashMap<i32, schools::Model> = if school_ids.is_empty() {
+        HashMap::new()
+    } else {
+        schools::Entity::find_live()
+            .filter(schools::Column::Id.is_in(school_ids))
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect()
+    };
+
+    // Map data to UserInfo
+    let list = user_models
+        .into_iter()
+        .map(|user| {
+            let role_infos: Vec<UserRoleInfo> = user_roles_list
+                .iter()
+                .filter(|ur| ur.user_id == user.id)
+                .filter_map(|ur| {
+                    roles_map.get(&ur.role_id).map(|r| UserRoleInfo {
+                        role_id: r.id,
+                        role_name: r.name.clone(),
+                    })
+                })
+                .collect();
+
+            let class_infos: Vec<UserClassInfo> = teacher_classes_list
+                .iter()
+                .filter(|tc| tc.user_id == user.id)
+                .filter_map(|tc| {
+                    classes_map.get(&tc.class_id).and_then(|c| {
+                        schools_map.get(&c.school_id).map(|s| UserClassInfo {
+                            class_id: c.id,
+                            class_name: c.name.clone(),
+                            school_id: s.id,
+                            school_name: s.name.clone(),
+                            grade: c.grade,
+                            class: c.class,
+                            status: c.status,
+                            role: tc.role.clone(),
+                        })
+                    })
+                })
+                .collect();
+
+            let memberships: Vec<SchoolMembershipInfo> = memberships_list
+                .iter()
+                .filter(|(user_id, _)| *user_id == user.id)
+                .map(|(_, m)| m.clone())
+                .collect();
+
+            let school_name = user
+                .school_id
+                .and_then(|school_id| user_schools_map.get(&school_id).map(|s| s.name.clone()));
+
+            UserInfo {
+                name: display_name(&user),
+                profile_complete: is_profile_complete(&user),
+                id: user.id,
+                username: user.username,
+                school_id: user.school_id,
+                school_name,
+                real_name: user.real_name,
+                display_name: user.display_name,
+                employee_no: user.employee_no,
+                phone: user.phone,
+                wechat_openid: user.wechat_openid,
+                wechat_unionid: user.wechat_unionid,
+                wechat_nickname: user.wechat_nickname,
+                wechat_avatar_url: user.wechat_avatar_url,
+                created_at: user.created_at.into(),
+                role_infos,
+                class_infos,
+                memberships,
+            }
+        })
+        .collect();
+    Ok(list)
+}
+
+pub async fn get_list_impl(
+    state: &AppState,
+    params: SearchUsersParams,
+) -> Result<PagingResponse<UserInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    let query = search_query(params);
+
+    let paginator = query.paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let user_models = paginator.fetch_page(page - 1).await?;
+    let list = enrich_users_with_details(state, user_models).await?;
+
+    Ok(PagingResponse { list, total, page })
+}
+
+/// 列表和导出共用的筛选条件
+pub fn search_query(params: SearchUsersParams) -> Select<users::Entity> {
+    let mut query = users::Entity::find_live();
+    crate::filter_if_some!(query, users::Column::Id, params.id, eq);
+    crate::filter_if_some!(query, users::Column::Username, params.username, like);
+    crate::filter_if_some!(query, users::Column::EmployeeNo, params.employee_no, eq);
+    if let Some(school_id) = params.school_id {
+        query = query.filter(
+            users::Column::Id.in_subquery(
+                Query::select()
+                    .column(school_memberships::Column::UserId)
+                    .from(school_memberships::Entity)
+                    .and_where(school_memberships::Column::SchoolId.eq(school_id))
+                    .to_owned(),
+            ),
+        );
+    }
+    if let Some(name) = params.name.filter(|s| !s.is_empty()) {
+        let pattern = format!("%{}%", name);
+        query = query.filter(
+            Condition::any()
+                .add(users::Column::Username.like(&pattern))
+                .add(users::Column::RealName.like(&pattern))
+                .add(users::Column::DisplayName.like(&pattern)),
+        );
+    }
+    query
+}
+
+// Get User by ID
+#[handler]
+pub async fn get_by_id(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<UserInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let user = get_by_id_impl(&state, id.into_inner()).await?;
+    Ok(ApiResponse::success(user))
+}
+
+pub async fn get_by_id_impl(state: &AppState, id: i32) -> Result<UserInfo, AppError> {
+    let user = users::Entity::find_live_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::UserNotFound { message: "User not found".to_string() })?;
+
+    let mut user_infos = enrich_users_with_details(state, vec![user]).await?;
+    if user_infos.is_empty() {
+        return Err(AppError::not_found("users".to_string(), Some(id)));
+    }
+    Ok(user_infos.remove(0))
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BindClassPayload {
+    pub class_id: i32,
+    pub password: String,
+}
+
+#[handler]
+pub async fn bind_class(
+    depot: &mut Depot,
+    req: JsonBody<BindClassPayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+
+    let class = classes::Entity::find_live()
+        .filter(
+            Condition::all()
+                .add(classes::Column::Password.eq(&req.password))
+                .add(classes::Column::Id.eq(req.class_id)),
+        )
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("class with this password".to_string(), None))?;
+
+    let existing_binding = teacher_classes::Entity::find()
+        .filter(
+            Condition::all()
+                .add(teacher_classes::Column::UserId.eq(claims.user_id))
+                .add(teacher_classes::Column::ClassId.eq(class.id)),
+        )
+        .one(&state.db)
+        .await?;
+
+    if existing_binding.is_some() {
+        return Err(AppError::Message("Already bound to this class".to_string()));
+    }
+
+    let txn = audit::begin(&state, &AuditContext::from_depot(depot)).await?;
+    join_school(&txn, claims.user_id, class.school_id).await?;
+    let new_binding = teacher_classes::ActiveModel {
+        user_id: Set(claims.user_id),
+        class_id: Set(class.id),
+        role: Set(default_binding_role(&txn, class.id).await?),
+        ..Default::default()
+    };
+    new_binding.insert(&txn).await.map_err(map_head_conflict)?;
+    txn.commit().await?;
+
+    Ok(ApiResponse::success(()))
+}
+
+#[handler]
+pub async fn unbind_class(
+    depot: &mut Depot,
+    class_id: PathParam<i32>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+
+    let txn = audit::begin(&state, &AuditContext::from_depot(depot)).await?;
+    let result = teacher_classes::Entity::delete_many()
+        .filter(
+            Condition::all()
+                .add(teacher_classes::Column::UserId.eq(claims.user_id))
+                .add(teacher_classes::Column::ClassId.eq(class_id.into_inner())),
+        )
+        .exec(&txn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::not_found(
+            "binding for this class".to_string(),
+            None,
+        ));
+    }
+    txn.commit().await?;
+
+    Ok(ApiResponse::success(()))
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BindSchoolPayload {
+    pub school_id: i32,
+    pub password: String,
+}
+
+#[handler]
+pub async fn bind_school(
+    depot: &mut Depot,
+    req: JsonBody<BindSchoolPayload>,
+) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school = schools::Entity::find_live_by_id(req.school_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("school".to_string(), Some(req.school_id)))?;
+    if school.password!=req.password {
+        return Err(AppError::auth_failed("School password incorrect"));
+    }
+    let user = users::Entity::find_live_by_id(claims.user_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("user".to_string(), Some(claims.user_id)))?;
+    let txn = audit::begin(&state, &AuditContext::from_depot(depot)).await?;
+    join_school(&txn, user.id, school.id).await?;
+    // 第一个加入的学校作为默认学校, 之后再加入不覆盖
+    if user.school_id.is_none() {
+        let mut user_active_model: users::ActiveModel = user.into();
+        user_active_model.school_id = Set(Some(school.id));
+        user_active_model.update(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+#[handler]
+pub async fn logout() -> Result<ApiResponse<()>, AppError> {
+    Ok(ApiResponse::success(()))
+}
@@ -0,0 +1,340 @@
+use crate::apis::auth_middleware::Claims;
+use crate::apis::list_api::{ListParamsReq, PagingResponse};
+use crate::core::app::AppState;
+use crate::core::audit::{self, AuditContext};
+use crate::core::constants::{WEBHOOK_DELIVERY_FAILED, WEBHOOK_DELIVERY_PENDING, WEBHOOK_DELIVERY_SUCCEEDED, WEBHOOK_EVENT_TYPES};
+use crate::core::error::AppError;
+use crate::core::response::ApiResponse;
+use crate::core::soft_delete::SoftDelete;
+use crate::core::webhook;
+use chrono::{DateTime, Utc};
+use data_model::{schools, webhook_deliveries, webhook_endpoints};
+use salvo::{oapi::extract::*, prelude::*};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct WebhookCreatePayload {
+    #[validate(url, length(max = 1000))]
+    pub url: String,
+    #[validate(length(min = 16, max = 255))]
+    pub secret: String,
+    /// 为空表示订阅全部事件
+    pub event_types: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Validate)]
+pub struct WebhookUpdatePayload {
+    #[validate(url, length(max = 1000))]
+    pub url: Option<String>,
+    #[validate(length(min = 16, max = 255))]
+    pub secret: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WebhookEndpointInfo {
+    pub id: i32,
+    pub school_id: i32,
+    pub url: String,
+    /// 只返回密钥的最后 4 位
+    pub secret_hint: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub created_by: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct WebhookDeliveryInfo {
+    pub id: i64,
+    pub endpoint_id: i32,
+    pub outbox_event_id: Option<i64>,
+    pub redelivery_of: Option<i64>,
+    pub event_type: String,
+    pub idempotency_key: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SearchWebhookDeliveriesParams {
+    #[serde(flatten)]
+    pub pagination: ListParamsReq,
+    pub status: Option<String>,
+    pub event_type: Option<String>,
+}
+
+impl From<webhook_endpoints::Model> for WebhookEndpointInfo {
+    fn from(e: webhook_endpoints::Model) -> Self {
+        let event_types = webhook::subscribed_event_types(&e);
+        let hint_start = e.secret.char_indices().rev().nth(3).map(|(i, _)| i).unwrap_or(0);
+        Self {
+            id: e.id,
+            school_id: e.school_id,
+            url: e.url,
+            secret_hint: format!("****{}", &e.secret[hint_start..]),
+            event_types,
+            enabled: e.enabled,
+            created_by: e.created_by,
+            created_at: e.created_at.into(),
+            updated_at: e.updated_at.into(),
+        }
+    }
+}
+
+impl From<webhook_deliveries::Model> for WebhookDeliveryInfo {
+    fn from(d: webhook_deliveries::Model) -> Self {
+        Self {
+            id: d.id,
+            endpoint_id: d.endpoint_id,
+            outbox_event_id: d.outbox_event_id,
+            redelivery_of: d.redelivery_of,
+            event_type: d.event_type,
+            idempotency_key: d.idempotency_key,
+            payload: d.payload,
+            status: d.status,
+            attempts: d.attempts,
+            response_status: d.response_status,
+            response_body: d.response_body,
+            last_error: d.last_error,
+            next_attempt_at: d.next_attempt_at.into(),
+            delivered_at: d.delivered_at.map(Into::into),
+            created_at: d.created_at.into(),
+        }
+    }
+}
+
+fn check_event_types(event_types: &[String]) -> Result<(), AppError> {
+    if let Some(unknown) = event_types.iter().find(|t| !WEBHOOK_EVENT_TYPES.contains(&t.as_str())) {
+        return Err(AppError::validation(format!("invalid event type: {}", unknown)));
+    }
+    Ok(())
+}
+
+/// webhook 会让服务端向任意地址发请求, 只允许管理员配置
+fn ensure_admin(claims: &Claims) -> Result<(), AppError> {
+    if !claims.is_admin() {
+        return Err(AppError::forbidden("manage webhooks".to_string()));
+    }
+    Ok(())
+}
+
+// Create webhook endpoint for a school
+#[handler]
+pub async fn add(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<WebhookCreatePayload>,
+) -> Result<ApiResponse<WebhookEndpointInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let school_id = id.into_inner();
+    ensure_admin(claims)?;
+    let audit = AuditContext::from_depot(depot);
+    let endpoint = add_impl(&state, &audit, claims.user_id, school_id, req).await?;
+    Ok(ApiResponse::success(endpoint))
+}
+
+pub async fn add_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    actor_id: i32,
+    school_id: i32,
+    req: WebhookCreatePayload,
+) -> Result<WebhookEndpointInfo, AppError> {
+    let event_types = req.event_types.unwrap_or_default();
+    check_event_types(&event_types)?;
+    schools::Entity::find_live_by_id(school_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("schools".to_string(), Some(school_id)))?;
+    let endpoint = webhook_endpoints::ActiveModel {
+        school_id: Set(school_id),
+        url: Set(req.url),
+        secret: Set(req.secret),
+        event_types: Set(serde_json::json!(event_types)),
+        enabled: Set(req.enabled.unwrap_or(true)),
+        created_by: Set(Some(actor_id)),
+        ..Default::default()
+    };
+    let txn = audit::begin(state, audit).await?;
+    let endpoint = endpoint.insert(&txn).await?;
+    txn.commit().await?;
+    Ok(endpoint.into())
+}
+
+// Get webhook endpoints of a school
+#[handler]
+pub async fn get_list(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+) -> Result<ApiResponse<Vec<WebhookEndpointInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let school_id = id.into_inner();
+    ensure_admin(claims)?;
+    let list = webhook_endpoints::Entity::find()
+        .filter(webhook_endpoints::Column::SchoolId.eq(school_id))
+        .order_by_asc(webhook_endpoints::Column::Id)
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(ApiResponse::success(list))
+}
+
+// Update webhook endpoint
+#[handler]
+pub async fn update(
+    depot: &mut Depot,
+    id: PathParam<i32>,
+    req: JsonBody<WebhookUpdatePayload>,
+) -> Result<ApiResponse<WebhookEndpointInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let req = req.into_inner();
+    req.validate()?;
+    let endpoint = find_accessible(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let endpoint = update_impl(&state, &audit, endpoint, req).await?;
+    Ok(ApiResponse::success(endpoint))
+}
+
+pub async fn update_impl(
+    state: &AppState,
+    audit: &AuditContext,
+    endpoint: webhook_endpoints::Model,
+    req: WebhookUpdatePayload,
+) -> Result<WebhookEndpointInfo, AppError> {
+    let mut active_model: webhook_endpoints::ActiveModel = endpoint.into();
+    if let Some(url) = req.url {
+        active_model.url = Set(url);
+    }
+    if let Some(secret) = req.secret {
+        active_model.secret = Set(secret);
+    }
+    if let Some(event_types) = req.event_types {
+        check_event_types(&event_types)?;
+        active_model.event_types = Set(serde_json::json!(event_types));
+    }
+    if let Some(enabled) = req.enabled {
+        active_model.enabled = Set(enabled);
+    }
+    active_model.updated_at = Set(Utc::now().into());
+    let txn = audit::begin(state, audit).await?;
+    let endpoint = active_model.update(&txn).await?;
+    txn.commit().await?;
+    Ok(endpoint.into())
+}
+
+// Delete webhook endpoint
+#[handler]
+pub async fn delete(depot: &mut Depot, id: PathParam<i32>) -> Result<ApiResponse<()>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let endpoint = find_accessible(&state, claims, id.into_inner()).await?;
+    let audit = AuditContext::from_depot(depot);
+    let txn = audit::begin(&state, &audit).await?;
+    let _ = endpoint.delete(&txn).await?;
+    txn.commit().await?;
+    Ok(ApiResponse::success(()))
+}
+
+// Get delivery log of a webhook endpoint
+#[handler]
+pub async fn get_deliveries(
+    depot: &mut Depot,
+    req: &mut Request,
+) -> Result<ApiResponse<PagingResponse<WebhookDeliveryInfo>>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let endpoint_id = req
+        .param::<i32>("id")
+        .ok_or_else(|| AppError::validation("invalid webhook id"))?;
+    let endpoint = find_accessible(&state, claims, endpoint_id).await?;
+    let params = req.parse_queries::<SearchWebhookDeliveriesParams>()?;
+    let list = get_deliveries_impl(&state, endpoint.id, params).await?;
+    Ok(ApiResponse::success(list))
+}
+
+pub async fn get_deliveries_impl(
+    state: &AppState,
+    endpoint_id: i32,
+    params: SearchWebhookDeliveriesParams,
+) -> Result<PagingResponse<WebhookDeliveryInfo>, AppError> {
+    let page = params.pagination.page.unwrap_or(1);
+    let page_size = params.pagination.page_size.unwrap_or(20);
+    if let Some(status) = params.status.as_deref()
+        && ![WEBHOOK_DELIVERY_PENDING, WEBHOOK_DELIVERY_SUCCEEDED, WEBHOOK_DELIVERY_FAILED].contains(&status)
+    {
+        return Err(AppError::validation(format!("invalid status: {}", status)));
+    }
+
+    let mut query = webhook_deliveries::Entity::find()
+        .filter(webhook_deliveries::Column::EndpointId.eq(endpoint_id));
+    crate::filter_if_some!(query, webhook_deliveries::Column::Status, params.status, eq);
+    crate::filter_if_some!(query, webhook_deliveries::Column::EventType, params.event_type, eq);
+    let paginator = query
+        .order_by_desc(webhook_deliveries::Column::Id)
+        .paginate(&state.db, page_size);
+    let total = paginator.num_items().await?;
+    let list = paginator
+        .fetch_page(page - 1)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(PagingResponse { list, total, page })
+}
+
+// Redeliver a webhook delivery
+#[handler]
+pub async fn redeliver(
+    depot: &mut Depot,
+    delivery_id: PathParam<i64>,
+) -> Result<ApiResponse<WebhookDeliveryInfo>, AppError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let claims = depot.obtain::<Claims>().unwrap();
+    let delivery_id = delivery_id.into_inner();
+    let delivery = webhook_deliveries::Entity::find_by_id(delivery_id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("webhook_deliveries".to_string(), None))?;
+    let endpoint = find_accessible(&state, claims, delivery.endpoint_id).await?;
+    if !endpoint.enabled {
+        return Err(AppError::business_logic(
+            "WEBHOOK_DISABLED",
+            "Webhook endpoint is disabled",
+        ));
+    }
+    let redelivery = webhook::redeliver(&state, delivery).await?;
+    Ok(ApiResponse::success(redelivery.into()))
+}
+
+async fn find_accessible(
+    state: &AppState,
+    claims: &Claims,
+    id: i32,
+) -> Result<webhook_endpoints::Model, AppError> {
+    ensure_admin(claims)?;
+    let endpoint = webhook_endpoints::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found("webhook_endpoints".to_string(), Some(id)))?;
+    Ok(endpoint)
+}
@@ -1,77 +1,414 @@
-use futures_util::{StreamExt, FutureExt};
-use salvo::prelude::*;
-use salvo::websocket::{WebSocket, Message, WebSocketUpgrade};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::LazyLock;
-use tokio::sync::{mpsc, RwLock};
-use tokio_stream::wrappers::UnboundedReceiverStream;
-use crate::core::db_listener::NotificationPayload;
-
-type Connections = RwLock<HashMap<i32, Vec<mpsc::UnboundedSender<Result<Message, salvo::Error>>>>>;
-//NEXT_CONN_ID 是一个全局的原子计数器 (AtomicUsize)，确保每个连接都有一个唯一的 ID。
-static NEXT_CONN_ID: AtomicUsize = AtomicUsize::new(1);
-//CONNECTIONS 是一个全局的读写锁 (RwLock)，用于管理所有连接的映射。
-static CONNECTIONS: LazyLock<Connections> = LazyLock::new(Connections::default);
-
-#[handler]
-pub async fn school_ws_handler(req: &mut Request, res: &mut Response) -> Result<(), StatusError> {
-    let school_id: i32 = req.param("id").unwrap_or_default();
-    if school_id == 0 {
-        return Err(StatusError::bad_request());
-    }
-    WebSocketUpgrade::new()
-        .upgrade(req, res, move |ws| handle_socket(ws, school_id))
-        .await
-}
-
-async fn handle_socket(ws: WebSocket, school_id: i32,) {
-    let conn_id = NEXT_CONN_ID.fetch_add(1, Ordering::Relaxed);
-    tracing::info!("New WebSocket connection: conn_id={}, school_id={}", conn_id, school_id);
-
-    //将WebSocket连接拆分为发送和接收两部分。
-    //user_ws_tx 用于发送消息给客户端。
-    //user_ws_rx 用于接收客户端的消息。
-    let (user_ws_tx, mut user_ws_rx) = ws.split();
-    //创建一个无界通道 (unbounded channel)，用于在不同的任务之间传递消息。
-    let (tx, rx) = mpsc::unbounded_channel();
-    //将接收到的消息转换为流，以便在不同的任务之间传递消息。
-    let rx = UnboundedReceiverStream::new(rx);
-
-    //创建了一个新的后台异步任务。这个任务独立于 handle_socket 函数运行。
-    //它创建了一个 future，这个 future 会不断地从 mpsc 通道的接收端 rx 读取消息
-    tokio::task::spawn(rx.forward(user_ws_tx).map(|result| {
-        if let Err(e) = result {
-            tracing::error!(error = ?e, "WebSocket send error");
-        }
-    }));
-
-    CONNECTIONS.write().await.entry(school_id).or_default().push(tx);
-
-    while let Some(result) = user_ws_rx.next().await {
-        match result {
-            Ok(_) => {
-                // We don't process incoming messages in this scenario
-            }
-            Err(e) => {
-                tracing::error!(error = ?e, "WebSocket receive error");
-                break;
-            }
-        }
-    }
-    tracing::info!("WebSocket connection closed: conn_id={}", conn_id);
-    // On disconnect, the logic to remove the sender from the CONNECTIONS map is complex
-    // because we only have the receiver here. A more robust implementation might involve
-    // a channel to signal disconnection back to a central management task.
-    // For this example, we'll rely on the broadcast function to clean up dead connections.
-}
-
-
-pub async fn broadcast_status_update(payload: NotificationPayload) {
-    let mut conns = CONNECTIONS.write().await;
-    if let Some(school_conns) = conns.get_mut(&payload.school_id) {
-        school_conns.retain(|tx| {
-            tx.send(Ok(Message::text(serde_json::to_string(&payload).unwrap()))).is_ok()
-        });
-    }
-}
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use salvo::http::header::SEC_WEBSOCKET_PROTOCOL;
+use salvo::http::HeaderValue;
+use salvo::prelude::*;
+use salvo::websocket::{WebSocket, Message, WebSocketUpgrade};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use crate::apis::announcement_api::{self, AnnouncementInfo};
+use crate::apis::class_api::{self, ClassSimpleInfo};
+use crate::apis::school_hold_api::{self, SchoolHoldState};
+use crate::core::app::AppState;
+use crate::core::db_listener::NotificationPayload;
+use crate::core::error::AppError;
+use crate::core::event_hub::{self, SchoolEvent};
+
+//NEXT_CONN_ID 是一个全局的原子计数器 (AtomicUsize)，确保每个连接都有一个唯一的 ID。
+static NEXT_CONN_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// v1: 旧协议, 只推送裸的 NotificationPayload, 不处理客户端消息
+pub const PROTOCOL_V1: u8 = 1;
+/// v2: 所有消息都包在 {type, version, ts, seq, payload} 信封里
+pub const PROTOCOL_V2: u8 = 2;
+pub const PROTOCOL_VERSION: u8 = PROTOCOL_V2;
+/// WebSocket 和 SSE 都没有指定版本时使用, 已部署的大屏不带版本参数
+pub const DEFAULT_PROTOCOL: u8 = PROTOCOL_V1;
+/// Sec-WebSocket-Protocol 子协议名前缀, 例如 school-status.v2
+const SUBPROTOCOL_PREFIX: &str = "school-status.v";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Status,
+    Announcement,
+    Hold,
+}
+
+pub const ALL_TOPICS: [Topic; 3] = [Topic::Status, Topic::Announcement, Topic::Hold];
+
+impl std::str::FromStr for Topic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(Self::Status),
+            "announcement" => Ok(Self::Announcement),
+            "hold" => Ok(Self::Hold),
+            _ => Err(format!("unknown topic: {}", s)),
+        }
+    }
+}
+
+/// 服务端推送的消息, 序列化为 {"type": "...", "payload": {...}}
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// 新连接建立、重连补发失败或积压过多时推送的完整状态
+    InitialState {
+        last_seq: u64,
+        classes: Vec<ClassSimpleInfo>,
+        announcements: Vec<AnnouncementInfo>,
+        hold: SchoolHoldState,
+    },
+    StatusUpdate(NotificationPayload),
+    Announcement(AnnouncementInfo),
+    AnnouncementRemoved { id: i32 },
+    SchoolHold(SchoolHoldState),
+    Pong,
+    Subscribed { topics: Vec<Topic> },
+    Error { code: String, message: String },
+}
+
+impl ServerMessage {
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            Self::InitialState { .. } => "initial_state",
+            Self::StatusUpdate(_) => "status_update",
+            Self::Announcement(_) => "announcement",
+            Self::AnnouncementRemoved { .. } => "announcement_removed",
+            Self::SchoolHold(_) => "school_hold",
+            Self::Pong => "pong",
+            Self::Subscribed { .. } => "subscribed",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    pub fn topic(&self) -> Option<Topic> {
+        match self {
+            Self::StatusUpdate(_) => Some(Topic::Status),
+            Self::Announcement(_) | Self::AnnouncementRemoved { .. } => Some(Topic::Announcement),
+            Self::SchoolHold(_) => Some(Topic::Hold),
+            _ => None,
+        }
+    }
+
+    /// 旧客户端只认识班级状态更新
+    pub fn legacy_text(&self) -> Option<String> {
+        match self {
+            Self::StatusUpdate(payload) => serde_json::to_string(payload).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ServerEnvelope {
+    pub version: u8,
+    pub ts: DateTime<Utc>,
+    /// 学校内递增的事件序号, 只对广播事件有值, 用于 resume
+    pub seq: Option<u64>,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+/// 客户端发送的消息, 格式同服务端 {"type": "...", "payload": {...}}
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Ping,
+    /// 只接收指定主题的事件
+    Subscribe { topics: Vec<Topic> },
+    /// 补发 last_seq 之后的事件
+    Resume { last_seq: u64 },
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ClientEnvelope {
+    pub version: Option<u8>,
+    pub ts: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+struct Session {
+    version: u8,
+    topics: Vec<Topic>,
+    last_seq: u64,
+}
+
+impl Session {
+    fn accepts(&self, event: &SchoolEvent) -> bool {
+        event.seq() > self.last_seq
+            && event
+                .envelope
+                .message
+                .topic()
+                .is_none_or(|topic| self.topics.contains(&topic))
+    }
+}
+
+#[handler]
+pub async fn school_ws_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) -> Result<(), StatusError> {
+    let school_id: i32 = req.param("id").unwrap_or_default();
+    if school_id == 0 {
+        return Err(StatusError::bad_request());
+    }
+    let (version, subprotocol) = negotiate_protocol(req)?;
+    if let Some(value) = subprotocol.and_then(|name| HeaderValue::from_str(&name).ok()) {
+        res.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, value);
+    }
+    let state = depot.obtain::<AppState>().unwrap().clone();
+    WebSocketUpgrade::new()
+        .upgrade(req, res, move |ws| handle_socket(ws, school_id, version, state))
+        .await
+}
+
+pub fn check_protocol(version: u8) -> Result<u8, StatusError> {
+    if !(PROTOCOL_V1..=PROTOCOL_VERSION).contains(&version) {
+        return Err(StatusError::bad_request().brief("unsupported protocol version"));
+    }
+    Ok(version)
+}
+
+/// 协议版本优先取 ?protocol=2, 其次取 Sec-WebSocket-Protocol 中支持的最高版本, 都没有时按 v1 处理
+fn negotiate_protocol(req: &Request) -> Result<(u8, Option<String>), StatusError> {
+    if let Some(version) = req.query::<u8>("protocol") {
+        return check_protocol(version).map(|version| (version, None));
+    }
+    let offered = req
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let mut best: Option<(u8, String)> = None;
+    for name in offered.split(',').map(str::trim) {
+        let Some(version) = name
+            .strip_prefix(SUBPROTOCOL_PREFIX)
+            .and_then(|v| v.parse::<u8>().ok())
+        else {
+            continue;
+        };
+        if check_protocol(version).is_ok() && best.as_ref().is_none_or(|(b, _)| version > *b) {
+            best = Some((version, name.to_string()));
+        }
+    }
+    Ok(match best {
+        Some((version, name)) => (version, Some(name)),
+        None => (DEFAULT_PROTOCOL, None),
+    })
+}
+
+async fn handle_socket(ws: WebSocket, school_id: i32, version: u8, state: AppState) {
+    let conn_id = NEXT_CONN_ID.fetch_add(1, Ordering::Relaxed);
+    tracing::info!(
+        "New WebSocket connection: conn_id={}, school_id={}, protocol=v{}",
+        conn_id,
+        school_id,
+        version
+    );
+
+    //将WebSocket连接拆分为发送和接收两部分。
+    //ws_tx 用于发送消息给客户端。
+    //ws_rx 用于接收客户端的消息。
+    let (mut ws_tx, mut ws_rx) = ws.split();
+    //先订阅再拉取初始状态, 期间产生的事件会在之后送达, 客户端按 seq 去重即可
+    let (mut events, last_seq) = event_hub::subscribe(school_id);
+    let mut session = Session {
+        version,
+        topics: ALL_TOPICS.to_vec(),
+        last_seq,
+    };
+    if version != PROTOCOL_V1 && !send_snapshot(&mut ws_tx, &state, school_id, &mut session).await {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            incoming = ws_rx.next() => {
+                let msg = match incoming {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(e)) => {
+                        tracing::error!(error = ?e, "WebSocket receive error");
+                        break;
+                    }
+                    None => break,
+                };
+                if msg.is_close() {
+                    break;
+                }
+                // 旧协议的客户端消息直接忽略
+                if session.version == PROTOCOL_V1 {
+                    continue;
+                }
+                let Ok(text) = msg.as_str() else {
+                    continue;
+                };
+                if !handle_client_message(&mut ws_tx, &state, school_id, &mut session, text).await {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                match event {
+                    Ok(event) => {
+                        if !session.accepts(&event) {
+                            continue;
+                        }
+                        session.last_seq = event.seq();
+                        if let Some(text) = event.render(session.version)
+                            && !send_text(&mut ws_tx, text).await
+                        {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("WebSocket conn_id={} lagged behind by {} events", conn_id, skipped);
+                        if session.version != PROTOCOL_V1
+                            && !send_snapshot(&mut ws_tx, &state, school_id, &mut session).await
+                        {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+    tracing::info!("WebSocket connection closed: conn_id={}", conn_id);
+}
+
+/// 处理客户端消息, 返回 false 表示连接已经不可用
+async fn handle_client_message(
+    ws_tx: &mut SplitSink<WebSocket, Message>,
+    state: &AppState,
+    school_id: i32,
+    session: &mut Session,
+    text: &str,
+) -> bool {
+    let envelope = match serde_json::from_str::<ClientEnvelope>(text) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            let message = ServerMessage::Error {
+                code: "INVALID_MESSAGE".to_string(),
+                message: e.to_string(),
+            };
+            return send_message(ws_tx, message).await;
+        }
+    };
+    match envelope.message {
+        ClientMessage::Ping => send_message(ws_tx, ServerMessage::Pong).await,
+        ClientMessage::Subscribe { topics } => {
+            session.topics = topics.clone();
+            send_message(ws_tx, ServerMessage::Subscribed { topics }).await
+        }
+        ClientMessage::Resume { last_seq } => match event_hub::events_since(school_id, last_seq) {
+            Some(events) => {
+                for event in events {
+                    session.last_seq = session.last_seq.max(event.seq());
+                    if event.envelope.message.topic().is_some_and(|t| !session.topics.contains(&t)) {
+                        continue;
+                    }
+                    if !send_text(ws_tx, &event.text).await {
+                        return false;
+                    }
+                }
+                true
+            }
+            None => send_snapshot(ws_tx, state, school_id, session).await,
+        },
+    }
+}
+
+/// 用完整状态替代缺失的事件; 先取当前序号再读数据库, 之后的事件不会被漏掉
+async fn send_snapshot(
+    ws_tx: &mut SplitSink<WebSocket, Message>,
+    state: &AppState,
+    school_id: i32,
+    session: &mut Session,
+) -> bool {
+    session.last_seq = event_hub::last_seq(school_id);
+    match load_initial_state(state, school_id, session.last_seq).await {
+        Ok(message) => send_message(ws_tx, message).await,
+        Err(e) => {
+            tracing::error!(error = ?e, "Failed to load initial state for school {}", school_id);
+            true
+        }
+    }
+}
+
+pub async fn load_initial_state(
+    state: &AppState,
+    school_id: i32,
+    last_seq: u64,
+) -> Result<ServerMessage, AppError> {
+    let classes = class_api::get_simple_list_by_school_id(state, school_id).await?;
+    let announcements = announcement_api::get_active_impl(state, school_id).await?;
+    let hold = school_hold_api::get_state_impl(state, school_id).await?;
+    Ok(ServerMessage::InitialState {
+        last_seq,
+        classes,
+        announcements,
+        hold,
+    })
+}
+
+/// 班级列表整体变化(如转校)时给学校的所有连接推送完整状态
+pub async fn broadcast_initial_state(state: &AppState, school_id: i32) {
+    if event_hub::subscriber_count(school_id) == 0 {
+        return;
+    }
+    match load_initial_state(state, school_id, event_hub::last_seq(school_id)).await {
+        Ok(message) => {
+            event_hub::publish(school_id, message);
+        }
+        Err(e) => tracing::error!(error = ?e, "Failed to broadcast initial state for school {}", school_id),
+    }
+}
+
+/// 序列化只针对当前连接的消息(不进入广播序列)
+pub fn render_direct(message: ServerMessage) -> Option<String> {
+    let envelope = ServerEnvelope {
+        version: PROTOCOL_VERSION,
+        ts: Utc::now(),
+        seq: None,
+        message,
+    };
+    serde_json::to_string(&envelope)
+        .map_err(|e| tracing::error!(error = ?e, "Failed to serialize server message"))
+        .ok()
+}
+
+async fn send_message(ws_tx: &mut SplitSink<WebSocket, Message>, message: ServerMessage) -> bool {
+    match render_direct(message) {
+        Some(text) => send_text(ws_tx, &text).await,
+        None => true,
+    }
+}
+
+async fn send_text(ws_tx: &mut SplitSink<WebSocket, Message>, text: &str) -> bool {
+    if let Err(e) = ws_tx.send(Message::text(text)).await {
+        tracing::error!(error = ?e, "WebSocket send error");
+        return false;
+    }
+    true
+}
+
+/// 导出 WebSocket 消息的 JSON Schema, 供大屏和小程序生成类型
+#[handler]
+pub async fn get_protocol_schema(res: &mut Response) {
+    let mut components = salvo_oapi::Components::new();
+    let server_message = <ServerEnvelope as ToSchema>::to_schema(&mut components);
+    let client_message = <ClientEnvelope as ToSchema>::to_schema(&mut components);
+    let subprotocols: Vec<String> = (PROTOCOL_V1..=PROTOCOL_VERSION)
+        .map(|v| format!("{}{}", SUBPROTOCOL_PREFIX, v))
+        .collect();
+    res.render(Json(serde_json::json!({
+        "version": PROTOCOL_VERSION,
+        "subprotocols": subprotocols,
+        "server_message": server_message,
+        "client_message": client_message,
+        "components": components,
+    })));
+}
@@ -0,0 +1,105 @@
+use crate::apis::auth_middleware::Claims;
+use crate::core::app::AppState;
+use crate::core::error::AppError;
+use chrono::{Duration, Utc};
+use data_model::audit_logs;
+use salvo::prelude::*;
+use sea_orm::*;
+use std::net::IpAddr;
+
+/// 当前请求的操作人、IP 和路由, 由审计触发器在同一事务里读取
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<i32>,
+    pub ip: Option<String>,
+    pub route: Option<String>,
+}
+
+impl AuditContext {
+    pub fn from_request(req: &Request, claims: Option<&Claims>, trusted_proxies: &[IpAddr]) -> Self {
+        Self {
+            actor_id: claims.map(|c| c.user_id),
+            ip: client_ip(req, trusted_proxies).map(|ip| ip.to_string()),
+            route: Some(format!("{} {}", req.method(), req.uri().path())),
+        }
+    }
+
+    /// 管理接口由 inject_context 写入, 其他地方调用时没有上下文
+    pub fn from_depot(depot: &Depot) -> Self {
+        depot.obtain::<AuditContext>().cloned().unwrap_or_default()
+    }
+
+    /// 只在事务内有效, 事务结束后自动清除
+    pub async fn apply<C: ConnectionTrait>(&self, db: &C) -> Result<(), DbErr> {
+        db.execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "SELECT set_config('app.actor_id', $1, true), \
+                    set_config('app.request_ip', $2, true), \
+                    set_config('app.request_route', $3, true)",
+            [
+                self.actor_id.map(|id| id.to_string()).unwrap_or_default().into(),
+                self.ip.clone().unwrap_or_default().into(),
+                self.route.clone().unwrap_or_default().into(),
+            ],
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+/// 开启事务并写入审计上下文, 事务里的增删改由触发器记录到 audit_logs
+pub async fn begin(state: &AppState, audit: &AuditContext) -> Result<DatabaseTransaction, DbErr> {
+    let txn = state.db.begin().await?;
+    audit.apply(&txn).await?;
+    Ok(txn)
+}
+
+/// 放在 auth 之后, 为管理接口记录审计上下文
+#[handler]
+pub async fn inject_context(req: &mut Request, depot: &mut Depot) {
+    let trusted_proxies = depot
+        .obtain::<AppState>()
+        .map(|state| state.config.audit.trusted_proxies.clone())
+        .unwrap_or_default();
+    let context = AuditContext::from_request(req, depot.obtain::<Claims>().ok(), &trusted_proxies);
+    depot.inject(context);
+}
+
+/// 请求直接来自受信任的反向代理时, 从 X-Forwarded-For 右边往左跳过代理, 第一个不受信任的地址是客户端;
+/// 其他请求的转发头可以随意伪造, 只用连接的地址
+fn client_ip(req: &Request, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let remote = req
+        .remote_addr()
+        .as_ipv4()
+        .map(|a| IpAddr::V4(*a.ip()))
+        .or_else(|| req.remote_addr().as_ipv6().map(|a| IpAddr::V6(*a.ip())))?;
+    if !trusted_proxies.contains(&remote) {
+        return Some(remote);
+    }
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let forwarded: Vec<IpAddr> = header("x-forwarded-for")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|v| v.trim().parse().ok())
+        .collect();
+    forwarded
+        .into_iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .or_else(|| header("x-real-ip").and_then(|v| v.trim().parse().ok()))
+        .or(Some(remote))
+}
+
+/// 删除超过保留期的审计记录, 保留天数为 0 时不删除
+pub async fn purge_expired(state: &AppState) -> Result<u64, AppError> {
+    let retention_days = state.config.audit.retention_days;
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = Utc::now() - Duration::days(retention_days);
+    let result = audit_logs::Entity::delete_many()
+        .filter(audit_logs::Column::CreatedAt.lt(cutoff))
+        .exec(&state.db)
+        .await?;
+    Ok(result.rows_affected)
+}
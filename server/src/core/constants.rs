@@ -1,19 +1,111 @@
-pub const ADMIN_ROLE: &str = "admin";
-pub const USER_ROLE: &str = "user";
-pub const DEFAULT_ROLE_ID: i32 = 2;
-pub const ADMIN_ROLE_ID: i32 = 1;
-pub const TEACHER_ROLE_ID: i32 = 3;
-
-//stauts
-pub const APP_OK: u16 = 0;
-pub const APP_OTHER: u16 = 5000;
-pub const APP_NOT_IMPLEMENTED: u16 = 5001;
-pub const APP_INTERNAL_ERROR: u16 = 5002;
-pub const APP_NOT_FOUND: u16 = 5003;
-pub const APP_VALIDATION_ERROR: u16 = 5004;
-pub const APP_AUTH_FAILED: u16 = 5005;
-pub const APP_FORBIDDEN: u16 = 5006;
-pub const APP_BUSINESS_LOGIC: u16 = 5007;
-pub const APP_EXTERNAL_SERVICE: u16 = 5008;
-pub const APP_USER_ALREADY_EXISTS: u16 = 5009;
-pub const APP_USER_NOT_FOUND: u16 = 5010;
+pub const ADMIN_ROLE: &str = "admin";
+pub const USER_ROLE: &str = "user";
+pub const DEFAULT_ROLE_ID: i32 = 2;
+pub const ADMIN_ROLE_ID: i32 = 1;
+pub const TEACHER_ROLE_ID: i32 = 3;
+
+//class status
+pub const CLASS_STATUS_DISMISSED: i32 = 0;
+pub const CLASS_STATUS_ONGOING: i32 = 1;
+pub const CLASS_STATUS_DISMISSING: i32 = 2;
+
+//school hold
+pub const SCHOOL_HOLD_ACTION_HOLD: &str = "hold";
+pub const SCHOOL_HOLD_ACTION_LIFT: &str = "lift";
+
+//announcement
+pub const ANNOUNCEMENT_SYNC_INTERVAL_SECS: u64 = 30;
+
+//class changes long-poll
+pub const CLASS_CHANGES_MAX_WAIT_SECS: u64 = 60;
+
+//outbox
+pub const OUTBOX_CHANNEL: &str = "outbox_events";
+/// 分发完一批事件后通知所有实例推送给各自的大屏, 通知内容是逗号分隔的事件 id
+pub const OUTBOX_PUBLISH_CHANNEL: &str = "outbox_published";
+pub const OUTBOX_BATCH_SIZE: u64 = 100;
+pub const OUTBOX_POLL_INTERVAL_SECS: u64 = 5;
+pub const OUTBOX_RETENTION_DAYS: i64 = 7;
+pub const OUTBOX_EVENT_CLASS_STATUS_CHANGED: &str = "class.status_changed";
+
+//webhook
+pub const WEBHOOK_EVENT_TYPES: [&str; 12] = [
+    "class.created",
+    "class.updated",
+    "class.deleted",
+    "class.status_changed",
+    "teacher_class.created",
+    "teacher_class.updated",
+    "teacher_class.deleted",
+    "school.updated",
+    "announcement.created",
+    "announcement.updated",
+    "announcement.deleted",
+    "school_hold.created",
+];
+pub const WEBHOOK_DELIVERY_PENDING: &str = "pending";
+pub const WEBHOOK_DELIVERY_SUCCEEDED: &str = "succeeded";
+pub const WEBHOOK_DELIVERY_FAILED: &str = "failed";
+pub const WEBHOOK_MAX_ATTEMPTS: i32 = 8;
+pub const WEBHOOK_POLL_INTERVAL_SECS: u64 = 5;
+
+//class teacher binding role
+pub const CLASS_TEACHER_ROLE_HEAD: &str = "head";
+pub const CLASS_TEACHER_ROLE_CO_TEACHER: &str = "co_teacher";
+pub const CLASS_TEACHER_ROLE_OBSERVER: &str = "observer";
+
+//teacher assignment
+pub const ASSIGNMENT_ROLE_HEAD: &str = "head";
+pub const ASSIGNMENT_ROLE_ASSISTANT: &str = "assistant";
+pub const ASSIGNMENT_ROLE_SUBSTITUTE: &str = "substitute";
+pub const ASSIGNMENT_STATUS_PENDING: &str = "pending";
+pub const ASSIGNMENT_STATUS_APPROVED: &str = "approved";
+pub const ASSIGNMENT_STATUS_REJECTED: &str = "rejected";
+pub const ASSIGNMENT_STATUS_CANCELLED: &str = "cancelled";
+
+//school membership
+pub const MEMBERSHIP_ROLE_TEACHER: &str = "teacher";
+pub const MEMBERSHIP_ROLE_MANAGER: &str = "manager";
+pub const MEMBERSHIP_STATUS_ACTIVE: &str = "active";
+pub const MEMBERSHIP_STATUS_SUSPENDED: &str = "suspended";
+pub const CURRENT_SCHOOL_HEADER: &str = "x-school-id";
+
+//teacher import
+pub const TEACHER_INVITE_KEY_PREFIX: &str = "teacher_invite:";
+pub const TEACHER_INVITE_TTL_DAYS: u64 = 7;
+
+//audit
+pub const AUDIT_PURGE_INTERVAL_SECS: u64 = 3600;
+
+//delete confirmation
+pub const DELETE_CONFIRM_KEY_PREFIX: &str = "delete_confirm:";
+pub const DELETE_CONFIRM_TTL_SECS: u64 = 300;
+pub const DELETE_CONFIRM_HEADER: &str = "x-confirm-token";
+
+//trash
+pub const TRASH_PURGE_INTERVAL_SECS: u64 = 3600;
+pub const TRASH_KIND_SCHOOLS: &str = "schools";
+pub const TRASH_KIND_CLASSES: &str = "classes";
+pub const TRASH_KIND_USERS: &str = "users";
+
+//stats
+pub const STATS_MAX_RANGE_DAYS: i64 = 31;
+
+//dismissal report
+pub const DISMISSAL_REPORT_DEFAULT_DAYS: i64 = 28;
+pub const DISMISSAL_REPORT_MAX_RANGE_DAYS: i64 = 366;
+pub const DISMISSAL_DEFAULT_TOLERANCE_MINUTES: i64 = 5;
+
+//stauts
+pub const APP_OK: u16 = 0;
+pub const APP_OTHER: u16 = 5000;
+pub const APP_NOT_IMPLEMENTED: u16 = 5001;
+pub const APP_INTERNAL_ERROR: u16 = 5002;
+pub const APP_NOT_FOUND: u16 = 5003;
+pub const APP_VALIDATION_ERROR: u16 = 5004;
+pub const APP_AUTH_FAILED: u16 = 5005;
+pub const APP_FORBIDDEN: u16 = 5006;
+pub const APP_BUSINESS_LOGIC: u16 = 5007;
+pub const APP_EXTERNAL_SERVICE: u16 = 5008;
+pub const APP_USER_ALREADY_EXISTS: u16 = 5009;
+pub const APP_USER_NOT_FOUND: u16 = 5010;
@@ -1,41 +1,137 @@
-use crate::apis::ws_api::broadcast_status_update;
-use crate::core::config::DatabaseConfig;
-use serde::{Deserialize, Serialize};
-use sqlx::postgres::{PgListener, PgPoolOptions};
-use tracing::{error, info};
-
-#[derive(Deserialize, Serialize)]
-pub struct NotificationPayload {
-    pub school_id: i32,
-    pub grade: i32,
-    pub class: i32,
-    pub class_id: i32,
-    pub new_status: i32,
-}
-
-pub async fn listen_for_notifications(db_config: &DatabaseConfig) -> anyhow::Result<()> {
-    let pool = PgPoolOptions::new().connect(&db_config.db_url).await?;
-    let mut listener: PgListener = PgListener::connect_with(&pool).await?;
-    listener.listen_all(vec!["class_status_updates"]).await?;
-
-    info!("Listening for class status updates from PostgreSQL...");
-
-    loop {
-        let notification = listener.recv().await?;
-        let payload_str = notification.payload();
-        match serde_json::from_str::<NotificationPayload>(payload_str) {
-            Ok(payload) => {
-                info!(
-                    "Received status update for class {}: new status {}",
-                    payload.class_id, payload.new_status
-                );
-                tokio::spawn(async move {
-                    broadcast_status_update(payload).await;
-                });
-            }
-            Err(e) => {
-                error!("Failed to deserialize notification payload: {}", e);
-            }
-        }
-    }
-}
+use crate::core::app::AppState;
+use crate::apis::ws_api;
+use crate::core::constants::{OUTBOX_BATCH_SIZE, OUTBOX_CHANNEL, OUTBOX_POLL_INTERVAL_SECS, OUTBOX_PUBLISH_CHANNEL};
+use crate::core::{event_hub, outbox};
+use chrono::{DateTime, Utc};
+use salvo::oapi::ToSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
+pub struct NotificationPayload {
+    pub school_id: i32,
+    pub grade: i32,
+    pub class: i32,
+    pub class_id: i32,
+    pub new_status: i32,
+    /// 班级的版本号, 见 classes.status_version
+    #[serde(default)]
+    pub status_version: i64,
+}
+
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct ListenerHealth {
+    pub connected: bool,
+    /// 监听连接在数据库里的进程号
+    pub backend_pid: Option<i32>,
+    pub connected_at: Option<DateTime<Utc>>,
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub reconnect_count: u64,
+    /// 已经分发的 outbox 事件数
+    pub dispatched_count: u64,
+}
+
+static HEALTH: LazyLock<Mutex<ListenerHealth>> = LazyLock::new(Default::default);
+
+pub fn health() -> ListenerHealth {
+    HEALTH.lock().unwrap().clone()
+}
+
+/// 持续监听 outbox 的新事件并分发, 断线后按退避时间重连
+///
+/// 事件都保存在 outbox_events 表里, 断线期间写入的事件会在重连后补发, 不会丢失;
+/// 断线期间其他实例分发的事件收不到推送通知, 重连后给本实例的大屏推送完整状态
+pub async fn run_listener(state: AppState) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let started = Instant::now();
+        let e = match listen_for_notifications(&state).await {
+            Ok(()) => anyhow::anyhow!("listener connection closed"),
+            Err(e) => e,
+        };
+        // 连接正常工作过一段时间, 重新从最小退避开始
+        if started.elapsed() > MAX_BACKOFF {
+            backoff = MIN_BACKOFF;
+        }
+        {
+            let mut health = HEALTH.lock().unwrap();
+            health.connected = false;
+            health.backend_pid = None;
+            health.last_error = Some(e.to_string());
+            health.reconnect_count += 1;
+        }
+        error!("DB listener failed: {}. Reconnecting after {:?}...", e, backoff);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+pub async fn listen_for_notifications(state: &AppState) -> anyhow::Result<()> {
+    let pool = state.db.get_postgres_connection_pool();
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen_all([OUTBOX_CHANNEL, OUTBOX_PUBLISH_CHANNEL]).await?;
+    let backend_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(&mut listener)
+        .await?;
+    {
+        let mut health = HEALTH.lock().unwrap();
+        health.connected = true;
+        health.backend_pid = Some(backend_pid);
+        health.connected_at = Some(Utc::now());
+    }
+    info!("Listening for outbox events from PostgreSQL...");
+    for school_id in event_hub::subscribed_school_ids() {
+        ws_api::broadcast_initial_state(state, school_id).await;
+    }
+
+    let poll_interval = Duration::from_secs(OUTBOX_POLL_INTERVAL_SECS);
+    let mut last_purge = Instant::now();
+    loop {
+        // 先开始监听再分发, 分发期间写入的事件也会收到通知; 断线期间积压的事件在这里补发
+        dispatch_all(state).await?;
+        if last_purge.elapsed() > PURGE_INTERVAL {
+            last_purge = Instant::now();
+            let purged = outbox::purge_processed(state)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            info!("Purged {} processed outbox events", purged);
+        }
+        // recv() 会在内部悄悄重连; try_recv() 断线时返回 None, 由外层记录重连
+        // 超时后也分发一次, 兜底漏掉的通知
+        match tokio::time::timeout(poll_interval, listener.try_recv()).await {
+            Ok(Ok(Some(notification))) if notification.channel() == OUTBOX_PUBLISH_CHANNEL => {
+                let ids = outbox::parse_published_ids(notification.payload());
+                if let Err(e) = outbox::publish_events(state, ids).await {
+                    error!("Failed to publish outbox events: {}", e);
+                }
+            }
+            Ok(Ok(Some(_))) | Err(_) => {}
+            Ok(Ok(None)) => return Err(anyhow::anyhow!("listener connection lost")),
+            Ok(Err(e)) => return Err(e.into()),
+        }
+    }
+}
+
+async fn dispatch_all(state: &AppState) -> anyhow::Result<()> {
+    loop {
+        let count = outbox::dispatch_pending(state)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if count > 0 {
+            let mut health = HEALTH.lock().unwrap();
+            health.last_event_at = Some(Utc::now());
+            health.dispatched_count += count as u64;
+        }
+        if (count as u64) < OUTBOX_BATCH_SIZE {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::apis::auth_middleware::Claims;
+use crate::core::app::AppState;
+use crate::core::constants::{DELETE_CONFIRM_HEADER, DELETE_CONFIRM_KEY_PREFIX, DELETE_CONFIRM_TTL_SECS};
+use crate::core::error::AppError;
+use rand::distr::{Alphanumeric, SampleString};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const TOKEN_LENGTH: usize = 32;
+
+/// 删除预览: 受影响的数据和确认令牌, DELETE 时带上令牌才会执行
+#[derive(Serialize, Debug)]
+pub struct DeletePreview<T> {
+    pub impact: T,
+    pub confirm_token: String,
+    pub expires_in: u64,
+}
+
+/// 令牌只对预览时的用户和记录有效, 使用一次后失效
+#[derive(Serialize, Deserialize, Debug)]
+struct DeleteConfirmation {
+    resource: String,
+    id: i32,
+    user_id: i32,
+}
+
+fn confirm_key(token: &str) -> String {
+    format!("{}{}", DELETE_CONFIRM_KEY_PREFIX, token)
+}
+
+/// 生成确认令牌并与预览结果一起返回
+pub async fn issue<T>(
+    state: &AppState,
+    claims: &Claims,
+    resource: &str,
+    id: i32,
+    impact: T,
+) -> Result<DeletePreview<T>, AppError> {
+    let token = Alphanumeric.sample_string(&mut rand::rng(), TOKEN_LENGTH);
+    let confirmation = DeleteConfirmation {
+        resource: resource.to_string(),
+        id,
+        user_id: claims.user_id,
+    };
+    state
+        .redis
+        .set(&confirm_key(&token), &confirmation, Some(Duration::from_secs(DELETE_CONFIRM_TTL_SECS)))
+        .await?;
+    Ok(DeletePreview {
+        impact,
+        confirm_token: token,
+        expires_in: DELETE_CONFIRM_TTL_SECS,
+    })
+}
+
+/// 从 X-Confirm-Token 头或 confirm_token 参数取令牌, 校验通过后令牌失效
+pub async fn consume(
+    state: &AppState,
+    claims: &Claims,
+    req: &Request,
+    resource: &str,
+    id: i32,
+) -> Result<(), AppError> {
+    let token = req
+        .header::<String>(DELETE_CONFIRM_HEADER)
+        .or_else(|| req.query::<String>("confirm_token"))
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            AppError::business_logic(
+                "CONFIRMATION_REQUIRED",
+                format!("Request GET /{}/{}/delete-preview first and send its confirm_token", resource, id),
+            )
+        })?;
+    let confirmation = state.redis.take::<DeleteConfirmation>(&confirm_key(&token)).await?;
+    match confirmation {
+        Some(c) if c.resource == resource && c.id == id && c.user_id == claims.user_id => Ok(()),
+        _ => Err(AppError::business_logic(
+            "INVALID_CONFIRMATION",
+            "Confirm token is invalid, expired or issued for another record",
+        )),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::apis::ws_api::{PROTOCOL_V1, PROTOCOL_VERSION, ServerEnvelope, ServerMessage};
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::broadcast;
+
+/// 每个学校保留的最近事件数, 用于断线重连后补发
+const HISTORY_SIZE: usize = 256;
+/// 每个订阅者最多积压的事件数, 超过后订阅者会收到 Lagged 并重新拉取完整状态
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 推送给某个学校的一条事件, 发布时就按各协议版本序列化好, 避免每个连接重复序列化
+#[derive(Debug)]
+pub struct SchoolEvent {
+    pub school_id: i32,
+    pub envelope: ServerEnvelope,
+    /// 最新协议的 JSON 文本
+    pub text: String,
+    /// v1 旧协议的 JSON 文本, 旧客户端不认识的消息类型为 None
+    pub legacy_text: Option<String>,
+}
+
+impl SchoolEvent {
+    pub fn seq(&self) -> u64 {
+        self.envelope.seq.unwrap_or_default()
+    }
+
+    pub fn render(&self, version: u8) -> Option<&str> {
+        if version == PROTOCOL_V1 {
+            self.legacy_text.as_deref()
+        } else {
+            Some(&self.text)
+        }
+    }
+}
+
+struct SchoolChannel {
+    sender: broadcast::Sender<Arc<SchoolEvent>>,
+    last_seq: u64,
+    history: VecDeque<Arc<SchoolEvent>>,
+}
+
+impl SchoolChannel {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            last_seq: 0,
+            history: VecDeque::with_capacity(HISTORY_SIZE),
+        }
+    }
+}
+
+//HUB 按学校保存广播通道、事件序号和最近的事件
+static HUB: LazyLock<Mutex<HashMap<i32, SchoolChannel>>> = LazyLock::new(Default::default);
+
+/// 订阅某个学校的事件, 同时返回订阅时刻的最新序号
+pub fn subscribe(school_id: i32) -> (broadcast::Receiver<Arc<SchoolEvent>>, u64) {
+    let mut hub = HUB.lock().unwrap();
+    let channel = hub.entry(school_id).or_insert_with(SchoolChannel::new);
+    (channel.sender.subscribe(), channel.last_seq)
+}
+
+/// 发布一条事件给某个学校的所有订阅者
+pub fn publish(school_id: i32, message: ServerMessage) -> Arc<SchoolEvent> {
+    let mut hub = HUB.lock().unwrap();
+    let channel = hub.entry(school_id).or_insert_with(SchoolChannel::new);
+    channel.last_seq += 1;
+    let envelope = ServerEnvelope {
+        version: PROTOCOL_VERSION,
+        ts: Utc::now(),
+        seq: Some(channel.last_seq),
+        message,
+    };
+    let text = serde_json::to_string(&envelope).unwrap_or_else(|e| {
+        tracing::error!(error = ?e, "Failed to serialize server message");
+        String::new()
+    });
+    let legacy_text = envelope.message.legacy_text();
+    let event = Arc::new(SchoolEvent {
+        school_id,
+        envelope,
+        text,
+        legacy_text,
+    });
+    if channel.history.len() == HISTORY_SIZE {
+        channel.history.pop_front();
+    }
+    channel.history.push_back(event.clone());
+    // 没有订阅者时发送会失败, 忽略即可
+    let _ = channel.sender.send(event.clone());
+    event
+}
+
+/// 返回序号大于 last_seq 的事件; 如果这些事件已经不在缓存里, 返回 None, 调用方应重新拉取完整状态
+pub fn events_since(school_id: i32, last_seq: u64) -> Option<Vec<Arc<SchoolEvent>>> {
+    let hub = HUB.lock().unwrap();
+    let Some(channel) = hub.get(&school_id) else {
+        return if last_seq == 0 { Some(vec![]) } else { None };
+    };
+    if last_seq > channel.last_seq {
+        // 服务重启后序号会从头开始
+        return None;
+    }
+    let oldest = channel.history.front().map(|e| e.seq()).unwrap_or(channel.last_seq + 1);
+    if last_seq + 1 < oldest && last_seq < channel.last_seq {
+        return None;
+    }
+    Some(
+        channel
+            .history
+            .iter()
+            .filter(|e| e.seq() > last_seq)
+            .cloned()
+            .collect(),
+    )
+}
+
+/// 某个学校最近发布的事件序号
+pub fn last_seq(school_id: i32) -> u64 {
+    let hub = HUB.lock().unwrap();
+    hub.get(&school_id).map(|c| c.last_seq).unwrap_or(0)
+}
+
+/// 某个学校当前的订阅者数量
+pub fn subscriber_count(school_id: i32) -> usize {
+    let hub = HUB.lock().unwrap();
+    hub.get(&school_id).map(|c| c.sender.receiver_count()).unwrap_or(0)
+}
+
+/// 有订阅者的学校
+pub fn subscribed_school_ids() -> Vec<i32> {
+    let hub = HUB.lock().unwrap();
+    hub.iter()
+        .filter(|(_, c)| c.sender.receiver_count() > 0)
+        .map(|(school_id, _)| *school_id)
+        .collect()
+}
+
+/// 所有学校的订阅者数量
+pub fn total_subscriber_count() -> usize {
+    let hub = HUB.lock().unwrap();
+    hub.values().map(|c| c.sender.receiver_count()).sum()
+}
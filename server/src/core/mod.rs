@@ -1,8 +1,14 @@
-pub mod app;
-pub mod config;
-pub mod constants;
-pub mod error;
-pub mod redis;
-pub mod response;
-pub mod router;
+pub mod app;
+pub mod audit;
+pub mod config;
+pub mod constants;
+pub mod delete_confirm;
+pub mod error;
+pub mod event_hub;
+pub mod outbox;
+pub mod redis;
+pub mod response;
+pub mod router;
+pub mod soft_delete;
+pub mod webhook;
 pub mod db_listener;
\ No newline at end of file
@@ -0,0 +1,202 @@
+use crate::apis::school_hold_api::SchoolHoldState;
+use crate::apis::ws_api::{self, ServerMessage};
+use crate::core::app::AppState;
+use crate::core::constants::{
+    OUTBOX_BATCH_SIZE, OUTBOX_EVENT_CLASS_STATUS_CHANGED, OUTBOX_PUBLISH_CHANNEL, OUTBOX_RETENTION_DAYS,
+};
+use crate::core::db_listener::NotificationPayload;
+use crate::core::error::AppError;
+use crate::core::event_hub;
+use crate::core::webhook;
+use chrono::{DateTime, Duration, Utc};
+use data_model::{announcements, outbox_events, school_holds};
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 投递给学校 webhook 的事件内容, 重复投递时 idempotency_key 不变
+#[derive(Serialize, Debug, Clone)]
+pub struct OutboxEventMessage {
+    pub idempotency_key: String,
+    pub event_type: String,
+    pub aggregate_type: String,
+    pub aggregate_id: i32,
+    pub school_id: Option<i32>,
+    pub payload: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&outbox_events::Model> for OutboxEventMessage {
+    fn from(event: &outbox_events::Model) -> Self {
+        Self {
+            idempotency_key: event.idempotency_key.clone(),
+            event_type: event.event_type.clone(),
+            aggregate_type: event.aggregate_type.clone(),
+            aggregate_id: event.aggregate_id,
+            school_id: event.school_id,
+            payload: event.payload.clone(),
+            created_at: event.created_at.into(),
+        }
+    }
+}
+
+/// classes 表的一行, 见 class.status_changed 事件的 payload
+#[derive(Deserialize, Debug)]
+struct ClassRow {
+    id: i32,
+    school_id: i32,
+    grade: i32,
+    class: i32,
+    status: i32,
+    #[serde(default)]
+    status_version: i64,
+}
+
+/// 分发一批到期的事件, 返回处理的数量; 多个实例同时分发时用 SKIP LOCKED 互不阻塞
+///
+/// 事务里只创建 webhook 投递记录并标记已处理, 实际的 HTTP 请求交给 webhook 投递任务,
+/// 慢的接收地址不会拖住大屏推送, 也不会长时间锁住事件行.
+/// 提交时通过 OUTBOX_PUBLISH_CHANNEL 通知所有实例, 由各实例推送给自己的大屏, 见 [`publish_events`]
+pub async fn dispatch_pending(state: &AppState) -> Result<usize, AppError> {
+    let txn = state.db.begin().await?;
+    let events = outbox_events::Entity::find()
+        .filter(outbox_events::Column::ProcessedAt.is_null())
+        .filter(outbox_events::Column::AvailableAt.lte(Utc::now()))
+        .order_by_asc(outbox_events::Column::Id)
+        .limit(OUTBOX_BATCH_SIZE)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+    let mut deliveries = 0;
+    for event in &events {
+        deliveries += webhook::enqueue_for_event(&txn, event).await?;
+    }
+    let ids: Vec<i64> = events.iter().map(|e| e.id).collect();
+    outbox_events::Entity::update_many()
+        .col_expr(outbox_events::Column::ProcessedAt, Expr::value(Utc::now()))
+        .col_expr(outbox_events::Column::Attempts, Expr::col(outbox_events::Column::Attempts).add(1))
+        .filter(outbox_events::Column::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    let ids_text = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_notify($1, $2)",
+        [OUTBOX_PUBLISH_CHANNEL.into(), ids_text.into()],
+    ))
+    .await?;
+    txn.commit().await?;
+    if deliveries > 0 {
+        webhook::wake();
+    }
+    Ok(events.len())
+}
+
+/// 解析 OUTBOX_PUBLISH_CHANNEL 通知里的事件 id
+pub fn parse_published_ids(payload: &str) -> Vec<i64> {
+    payload.split(',').filter_map(|id| id.trim().parse().ok()).collect()
+}
+
+/// 把已分发的事件推送给本实例连接的大屏
+///
+/// 班级状态、公告和管控直接推送增量消息; 班级、教师绑定和学校的其他变化让大屏重新拉取完整状态,
+/// 同一批里每个学校只推送一次
+pub async fn publish_events(state: &AppState, ids: Vec<i64>) -> Result<(), AppError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let events = outbox_events::Entity::find()
+        .filter(outbox_events::Column::Id.is_in(ids))
+        .order_by_asc(outbox_events::Column::Id)
+        .all(&state.db)
+        .await?;
+    let mut refresh = BTreeSet::new();
+    for event in &events {
+        match hub_update(event) {
+            Ok(Some(HubUpdate::Message(school_id, message))) => {
+                event_hub::publish(school_id, message);
+            }
+            Ok(Some(HubUpdate::Refresh(school_ids))) => refresh.extend(school_ids),
+            Ok(None) => {}
+            Err(e) => tracing::error!("Failed to deserialize outbox event {}: {}", event.id, e),
+        }
+    }
+    for school_id in refresh {
+        ws_api::broadcast_initial_state(state, school_id).await;
+    }
+    Ok(())
+}
+
+enum HubUpdate {
+    Message(i32, ServerMessage),
+    Refresh(Vec<i32>),
+}
+
+fn hub_update(event: &outbox_events::Model) -> Result<Option<HubUpdate>, serde_json::Error> {
+    let Some(school_id) = event.school_id else {
+        return Ok(None);
+    };
+    let payload = event.payload.clone();
+    if event.event_type == OUTBOX_EVENT_CLASS_STATUS_CHANGED {
+        let row: ClassRow = serde_json::from_value(payload)?;
+        let payload = NotificationPayload {
+            school_id: row.school_id,
+            grade: row.grade,
+            class: row.class,
+            class_id: row.id,
+            new_status: row.status,
+            status_version: row.status_version,
+        };
+        return Ok(Some(HubUpdate::Message(row.school_id, ServerMessage::StatusUpdate(payload))));
+    }
+    let update = match event.aggregate_type.as_str() {
+        "announcement" => {
+            let announcement: announcements::Model = serde_json::from_value(payload)?;
+            // 公告生效时 published_at 有值, 撤下或过期时清空或写入 expired_at
+            let shown = announcement.published_at.is_some() && announcement.expired_at.is_none();
+            let message = if shown && !event.event_type.ends_with(".deleted") {
+                ServerMessage::Announcement(announcement.into())
+            } else {
+                ServerMessage::AnnouncementRemoved { id: event.aggregate_id }
+            };
+            HubUpdate::Message(school_id, message)
+        }
+        "school_hold" => {
+            let record: school_holds::Model = serde_json::from_value(payload)?;
+            let hold = SchoolHoldState::from_latest(school_id, Some(&record));
+            HubUpdate::Message(school_id, ServerMessage::SchoolHold(hold))
+        }
+        _ => {
+            // 转校的班级两个学校都要刷新
+            let old_school_id = payload.get("old_school_id").and_then(|v| v.as_i64());
+            let school_ids = std::iter::once(school_id)
+                .chain(old_school_id.map(|id| id as i32))
+                .collect();
+            HubUpdate::Refresh(school_ids)
+        }
+    };
+    Ok(Some(update))
+}
+
+/// 删除已经处理完且超过保留期的事件
+pub async fn purge_processed(state: &AppState) -> Result<u64, AppError> {
+    let cutoff = Utc::now() - Duration::days(OUTBOX_RETENTION_DAYS);
+    let result = outbox_events::Entity::delete_many()
+        .filter(outbox_events::Column::ProcessedAt.lt(cutoff))
+        .exec(&state.db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// 还没处理的事件数
+pub async fn pending_count(state: &AppState) -> Result<u64, AppError> {
+    let count = outbox_events::Entity::find()
+        .filter(outbox_events::Column::ProcessedAt.is_null())
+        .count(&state.db)
+        .await?;
+    Ok(count)
+}
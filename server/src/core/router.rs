@@ -1,100 +1,180 @@
-use crate::apis::*;
-use crate::core::app::{ AppState};
-use salvo::cors::{AllowHeaders, AllowOrigin, Cors};
-use salvo::http::Method;
-use salvo::prelude::*;
-use salvo_oapi::security::{Http, HttpAuthScheme};
-use salvo_oapi::{OpenApi, SecurityScheme};
-
-#[handler]
-async fn hello(res: &mut Response) {
-    res.render(Text::Plain("Hello, world!"));
-}
-
-pub fn build_router(app_state: AppState) -> Router {
-    let admin_routes = Router::with_path("/api/admin")
-        .hoop(auth_middleware::auth)
-        .hoop(auth_middleware::error_handler)
-        //users
-        .push(Router::with_path("/users").get(user_api::get_list))
-        .push(Router::with_path("/users/{id}").get(user_api::get_by_id))
-        .push(Router::with_path("/users").post(user_api::add))
-        .push(Router::with_path("/users/{id}").put(user_api::update))
-        .push(Router::with_path("/users/{id}").delete(user_api::delete))
-        .push(Router::with_path("/me").get(user_api::get_current_user))
-        .push(Router::with_path("/me/password").post(user_api::change_password))
-        .push(Router::with_path("/logout").post(user_api::logout))
-        .push(Router::with_path("/bind/class").post(user_api::bind_class))
-        .push(Router::with_path("/bind/school").post(user_api::bind_school))
-        .push(Router::with_path("/unbind/class/{class_id}").delete(user_api::unbind_class))
-        .push(Router::with_path("/me").put(user_api::update_my_info))
-        //roles
-        .push(Router::with_path("/roles").get(role_api::get_list))
-        .push(Router::with_path("/roles/{id}").get(role_api::get_by_id))
-        .push(Router::with_path("/roles").post(role_api::add))
-        .push(Router::with_path("/roles/{id}").put(role_api::update))
-        .push(Router::with_path("/roles/{id}").delete(role_api::delete))
-        //permissions
-        .push(Router::with_path("/permissions").get(permission_api::get_list))
-        .push(Router::with_path("/permissions/{id}").get(permission_api::get_by_id))
-        .push(Router::with_path("/permissions").post(permission_api::add))
-        .push(Router::with_path("/permissions/{id}").put(permission_api::update))
-        .push(Router::with_path("/permissions/{id}").delete(permission_api::delete))
-        //schools
-        .push(Router::with_path("/schools/{id}").get(school_api::get_by_id))
-        .push(Router::with_path("/schools").post(school_api::add))
-        .push(Router::with_path("/schools/{id}").put(school_api::update))
-        .push(Router::with_path("/schools/{id}").delete(school_api::delete))
-        .push(Router::with_path("/schools").get(school_api::get_list))
-        //classes
-        .push(Router::with_path("/classes").get(class_api::get_list))
-        .push(Router::with_path("/classes/{id}").get(class_api::get_by_id))
-        .push(Router::with_path("/classes").post(class_api::add))
-        .push(Router::with_path("/classes/{id}").put(class_api::update))
-        .push(Router::with_path("/classes/{id}").delete(class_api::delete))
-        .push(Router::with_path("/classes/bulk").post(class_api::add_bulk))
-        .push(Router::with_path("/classes/{class_id}/status").put(class_api::update_status));
-
-    let reigster_router = if app_state.config.system.register_allowed {
-        Router::with_path("/api/register").post(user_api::register)
-    } else {
-        Router::new()
-    };
-    Router::new()
-        .hoop(affix_state::inject(app_state))
-        .push(Router::with_path("/api/login").post(user_api::login))
-        .push(Router::with_path("/api/login/wechat").post(wechat_api::wechat_login))
-        .get(hello)
-        .push(reigster_router)
-        .push(Router::with_path("/api/classes/school/{school_id}").get(class_api::get_all_class_by_school_id))
-        .push(Router::with_path("/api/schools/all").get(school_api::get_all_schools))
-        .push(Router::with_path("/api/schools/{id}/simple").get(school_api::get_simple_by_id))
-        .push(Router::with_path("/ws/school/{id}").goal(ws_api::school_ws_handler))
-        .push(admin_routes)
-}
-
-pub fn create_router(app_state: AppState) -> Service {
-    let cors = Cors::new()
-        .allow_origin(AllowOrigin::any())
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-        ])
-        .allow_headers(AllowHeaders::any())
-        .into_handler();
-    let router = build_router(app_state);
-    let doc = OpenApi::new("app_server_api", "1.0.0")
-        .add_security_scheme(
-            "bearer",
-            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer).bearer_format("JWT")),
-        )
-        .merge_router(&router);
-    let router = router
-        .unshift(doc.into_router("/api-doc/openapi.json"))
-        .unshift(SwaggerUi::new("/api-doc/openapi.json").into_router("/swagger-ui"));
-    let service = Service::new(router).hoop(cors).hoop(Logger::new());
-    service
-}
+use crate::apis::*;
+use crate::core::app::{ AppState};
+use crate::core::audit;
+use salvo::cors::{AllowHeaders, AllowOrigin, Cors};
+use salvo::http::Method;
+use salvo::prelude::*;
+use salvo_oapi::security::{Http, HttpAuthScheme};
+use salvo_oapi::{OpenApi, SecurityScheme};
+
+#[handler]
+async fn hello(res: &mut Response) {
+    res.render(Text::Plain("Hello, world!"));
+}
+
+pub fn build_router(app_state: AppState) -> Router {
+    let admin_routes = Router::with_path("/api/admin")
+        .hoop(auth_middleware::auth)
+        .hoop(audit::inject_context)
+        .hoop(auth_middleware::error_handler)
+        //users
+        .push(Router::with_path("/users").get(user_api::get_list))
+        .push(Router::with_path("/users/{id}").get(user_api::get_by_id))
+        .push(Router::with_path("/users").post(user_api::add))
+        .push(Router::with_path("/users/{id}").put(user_api::update))
+        .push(Router::with_path("/users/{id}/delete-preview").get(user_api::delete_preview))
+        .push(Router::with_path("/users/{id}").delete(user_api::delete))
+        .push(Router::with_path("/users/{id}/restore").post(user_api::restore))
+        .push(Router::with_path("/users/{id}/schools/{school_id}").put(school_membership_api::update))
+        .push(Router::with_path("/users/{id}/schools/{school_id}").delete(school_membership_api::remove))
+        .push(Router::with_path("/me").get(user_api::get_current_user))
+        .push(Router::with_path("/me/password").post(user_api::change_password))
+        .push(Router::with_path("/me/classes").get(class_api::get_my_classes))
+        .push(Router::with_path("/me/school").post(school_membership_api::switch_school))
+        .push(Router::with_path("/logout").post(user_api::logout))
+        .push(Router::with_path("/bind/class").post(user_api::bind_class))
+        .push(Router::with_path("/bind/school").post(user_api::bind_school))
+        .push(Router::with_path("/unbind/class/{class_id}").delete(user_api::unbind_class))
+        .push(Router::with_path("/me").put(user_api::update_my_info))
+        //roles
+        .push(Router::with_path("/roles").get(role_api::get_list))
+        .push(Router::with_path("/roles/{id}").get(role_api::get_by_id))
+        .push(Router::with_path("/roles").post(role_api::add))
+        .push(Router::with_path("/roles/{id}").put(role_api::update))
+        .push(Router::with_path("/roles/{id}/delete-preview").get(role_api::delete_preview))
+        .push(Router::with_path("/roles/{id}").delete(role_api::delete))
+        //permissions
+        .push(Router::with_path("/permissions").get(permission_api::get_list))
+        .push(Router::with_path("/permissions/{id}").get(permission_api::get_by_id))
+        .push(Router::with_path("/permissions").post(permission_api::add))
+        .push(Router::with_path("/permissions/{id}").put(permission_api::update))
+        .push(Router::with_path("/permissions/{id}").delete(permission_api::delete))
+        //schools
+        .push(Router::with_path("/schools/{id}").get(school_api::get_by_id))
+        .push(Router::with_path("/schools").post(school_api::add))
+        .push(Router::with_path("/schools/{id}").put(school_api::update))
+        .push(Router::with_path("/schools/{id}/delete-preview").get(school_api::delete_preview))
+        .push(Router::with_path("/schools/{id}").delete(school_api::delete))
+        .push(Router::with_path("/schools/{id}/restore").post(school_api::restore))
+        .push(Router::with_path("/schools").get(school_api::get_list))
+        .push(Router::with_path("/schools/{id}/hold").post(school_hold_api::hold))
+        .push(Router::with_path("/schools/{id}/hold/lift").post(school_hold_api::lift))
+        .push(Router::with_path("/schools/{id}/holds").get(school_hold_api::get_list))
+        .push(Router::with_path("/schools/{id}/academic-years").get(academic_year_api::get_list))
+        .push(Router::with_path("/schools/{id}/academic-years").post(academic_year_api::add))
+        .push(Router::with_path("/schools/{id}/academic-years/rollover").post(academic_year_api::rollover))
+        .push(Router::with_path("/schools/{id}/teachers/import").post(teacher_import_api::import))
+        .push(Router::with_path("/schools/{id}/dismissal-schedules").get(dismissal_schedule_api::get_list))
+        .push(Router::with_path("/schools/{id}/dismissal-schedules").put(dismissal_schedule_api::replace))
+        .push(Router::with_path("/schools/{id}/campuses").get(campus_api::get_list))
+        .push(Router::with_path("/schools/{id}/campuses").post(campus_api::add))
+        .push(Router::with_path("/campuses/{id}").put(campus_api::update))
+        .push(Router::with_path("/campuses/{id}").delete(campus_api::delete))
+        //districts
+        .push(Router::with_path("/districts").get(district_api::get_list))
+        .push(Router::with_path("/districts/{id}").get(district_api::get_by_id))
+        .push(Router::with_path("/districts").post(district_api::add))
+        .push(Router::with_path("/districts/{id}").put(district_api::update))
+        .push(Router::with_path("/districts/{id}").delete(district_api::delete))
+        .push(Router::with_path("/districts/{id}/admins").post(district_api::add_admin))
+        .push(Router::with_path("/districts/{id}/admins/{user_id}").delete(district_api::remove_admin))
+        .push(Router::with_path("/districts/{id}/stats").get(district_api::get_stats))
+        .push(Router::with_path("/districts/{id}/board").get(district_api::get_board))
+        //stats
+        .push(Router::with_path("/stats").get(stats_api::get_stats))
+        .push(Router::with_path("/reports/dismissals/{group_by}").get(dismissal_report_api::get_report))
+        //trash
+        .push(Router::with_path("/trash/{kind}").get(trash_api::get_list))
+        //audit
+        .push(Router::with_path("/audit").get(audit_api::get_list))
+        //exports
+        .push(Router::with_path("/export/schools").get(export_api::export_schools))
+        .push(Router::with_path("/export/classes").get(export_api::export_classes))
+        .push(Router::with_path("/export/users").get(export_api::export_users))
+        .push(Router::with_path("/export/status-history").get(export_api::export_status_history))
+        //announcements
+        .push(Router::with_path("/announcements").get(announcement_api::get_list))
+        .push(Router::with_path("/announcements/{id}").get(announcement_api::get_by_id))
+        .push(Router::with_path("/announcements").post(announcement_api::add))
+        .push(Router::with_path("/announcements/{id}").put(announcement_api::update))
+        .push(Router::with_path("/announcements/{id}").delete(announcement_api::delete))
+        //webhooks
+        .push(Router::with_path("/schools/{id}/webhooks").get(webhook_api::get_list))
+        .push(Router::with_path("/schools/{id}/webhooks").post(webhook_api::add))
+        .push(Router::with_path("/webhooks/{id}").put(webhook_api::update))
+        .push(Router::with_path("/webhooks/{id}").delete(webhook_api::delete))
+        .push(Router::with_path("/webhooks/{id}/deliveries").get(webhook_api::get_deliveries))
+        .push(Router::with_path("/webhooks/deliveries/{delivery_id}/redeliver").post(webhook_api::redeliver))
+        //classes
+        .push(Router::with_path("/classes").get(class_api::get_list))
+        .push(Router::with_path("/classes/{id}").get(class_api::get_by_id))
+        .push(Router::with_path("/classes").post(class_api::add))
+        .push(Router::with_path("/classes/{id}").put(class_api::update))
+        .push(Router::with_path("/classes/{id}/delete-preview").get(class_api::delete_preview))
+        .push(Router::with_path("/classes/{id}").delete(class_api::delete))
+        .push(Router::with_path("/classes/{id}/restore").post(class_api::restore))
+        .push(Router::with_path("/classes/{id}/merge").post(class_api::merge))
+        .push(Router::with_path("/classes/{id}/transfer").post(class_api::transfer))
+        .push(Router::with_path("/classes/bulk").post(class_api::add_bulk))
+        .push(Router::with_path("/classes/import").post(class_import_api::import))
+        .push(Router::with_path("/classes/{class_id}/status").put(class_api::update_status))
+        .push(Router::with_path("/classes/{id}/teachers").get(class_teacher_api::get_list))
+        .push(Router::with_path("/classes/{id}/teachers").post(class_teacher_api::invite))
+        .push(Router::with_path("/classes/{id}/teachers/{user_id}").delete(class_teacher_api::remove))
+        //teacher assignments
+        .push(Router::with_path("/classes/{id}/assignments").get(teacher_assignment_api::get_list))
+        .push(Router::with_path("/classes/{id}/assignments").post(teacher_assignment_api::add))
+        .push(Router::with_path("/assignments/{id}/approve").post(teacher_assignment_api::approve))
+        .push(Router::with_path("/assignments/{id}/reject").post(teacher_assignment_api::reject))
+        .push(Router::with_path("/assignments/{id}/cancel").post(teacher_assignment_api::cancel));
+
+    let reigster_router = if app_state.config.system.register_allowed {
+        Router::with_path("/api/register").post(user_api::register)
+    } else {
+        Router::new()
+    };
+    Router::new()
+        .hoop(affix_state::inject(app_state))
+        .push(Router::with_path("/api/login").post(user_api::login))
+        .push(Router::with_path("/api/login/wechat").post(wechat_api::wechat_login))
+        .push(Router::with_path("/api/invite/accept").post(teacher_import_api::accept_invite))
+        .get(hello)
+        .push(reigster_router)
+        .push(Router::with_path("/api/classes/school/{school_id}").get(class_api::get_all_class_by_school_id))
+        .push(Router::with_path("/api/classes/school/{school_id}/changes").get(class_api::get_changes_by_school_id))
+        .push(Router::with_path("/api/schools/all").get(school_api::get_all_schools))
+        .push(Router::with_path("/api/schools/{id}/simple").get(school_api::get_simple_by_id))
+        .push(Router::with_path("/api/schools/{id}/hold").get(school_hold_api::get_state))
+        .push(Router::with_path("/api/schools/{school_id}/announcements").get(announcement_api::get_active_by_school_id))
+        .push(Router::with_path("/ws/school/{id}").goal(ws_api::school_ws_handler))
+        .push(Router::with_path("/api/ws/protocol").get(ws_api::get_protocol_schema))
+        .push(Router::with_path("/api/sse/school/{id}").get(sse_api::school_sse_handler))
+        .push(Router::with_path("/api/health/listener").get(health_api::get_listener_health))
+        .push(admin_routes)
+}
+
+pub fn create_router(app_state: AppState) -> Service {
+    let cors = Cors::new()
+        .allow_origin(AllowOrigin::any())
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers(AllowHeaders::any())
+        .into_handler();
+    let router = build_router(app_state);
+    let doc = OpenApi::new("app_server_api", "1.0.0")
+        .add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer).bearer_format("JWT")),
+        )
+        .merge_router(&router);
+    let router = router
+        .unshift(doc.into_router("/api-doc/openapi.json"))
+        .unshift(SwaggerUi::new("/api-doc/openapi.json").into_router("/swagger-ui"));
+    let service = Service::new(router).hoop(cors).hoop(Logger::new());
+    service
+}
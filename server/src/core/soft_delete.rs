@@ -0,0 +1,68 @@
+use crate::core::app::AppState;
+use crate::core::error::AppError;
+use chrono::{Duration, Utc};
+use data_model::{classes, schools, users};
+use sea_orm::*;
+
+/// 带 deleted_at 的表, 查询时用 find_live 排除已删除的记录
+pub trait SoftDelete: EntityTrait {
+    fn deleted_at_column() -> Self::Column;
+
+    fn find_live() -> Select<Self> {
+        Self::find().filter(Self::deleted_at_column().is_null())
+    }
+
+    fn find_live_by_id<T>(id: T) -> Select<Self>
+    where
+        T: Into<<Self::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+    {
+        Self::find_by_id(id).filter(Self::deleted_at_column().is_null())
+    }
+
+    /// 回收站里的记录
+    fn find_deleted() -> Select<Self> {
+        Self::find().filter(Self::deleted_at_column().is_not_null())
+    }
+}
+
+impl SoftDelete for schools::Entity {
+    fn deleted_at_column() -> Self::Column {
+        schools::Column::DeletedAt
+    }
+}
+
+impl SoftDelete for classes::Entity {
+    fn deleted_at_column() -> Self::Column {
+        classes::Column::DeletedAt
+    }
+}
+
+impl SoftDelete for users::Entity {
+    fn deleted_at_column() -> Self::Column {
+        users::Column::DeletedAt
+    }
+}
+
+/// 彻底删除超过保留期的记录, 外键级联删除绑定关系; 保留天数为 0 时不删除
+pub async fn purge_expired(state: &AppState) -> Result<u64, AppError> {
+    let retention_days = state.config.trash.retention_days;
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = Utc::now() - Duration::days(retention_days);
+    let txn = state.db.begin().await?;
+    let users = users::Entity::delete_many()
+        .filter(users::Column::DeletedAt.lt(cutoff))
+        .exec(&txn)
+        .await?;
+    let classes = classes::Entity::delete_many()
+        .filter(classes::Column::DeletedAt.lt(cutoff))
+        .exec(&txn)
+        .await?;
+    let schools = schools::Entity::delete_many()
+        .filter(schools::Column::DeletedAt.lt(cutoff))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(users.rows_affected + classes.rows_affected + schools.rows_affected)
+}
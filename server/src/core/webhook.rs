@@ -0,0 +1,245 @@
+use crate::core::app::AppState;
+use crate::core::constants::{
+    WEBHOOK_DELIVERY_FAILED, WEBHOOK_DELIVERY_PENDING, WEBHOOK_DELIVERY_SUCCEEDED, WEBHOOK_MAX_ATTEMPTS,
+    WEBHOOK_POLL_INTERVAL_SECS,
+};
+use crate::core::error::AppError;
+use crate::core::outbox::OutboxEventMessage;
+use chrono::{Duration, Utc};
+use data_model::{outbox_events, webhook_deliveries, webhook_endpoints};
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use sea_orm::*;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tokio::sync::Notify;
+
+const BATCH_SIZE: u64 = 20;
+/// 失败后的重试间隔从 10 秒开始翻倍, 最长 1 小时
+const RETRY_BASE_SECS: i64 = 10;
+const RETRY_MAX_SECS: i64 = 3600;
+/// 认领的记录在租期内不会被其他实例重复投递, 租期按整批都超时再加一些余量
+const CLAIM_MARGIN_SECS: i64 = 30;
+/// 投递记录里保留的响应内容长度
+const RESPONSE_BODY_LIMIT: usize = 2000;
+
+pub static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+static WAKE: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 签名内容为 "{timestamp}.{body}", 结果放在 X-Webhook-Signature: sha256=<hex>
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 唤醒投递任务, 不用等到下一次轮询
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+pub fn subscribed_event_types(endpoint: &webhook_endpoints::Model) -> Vec<String> {
+    serde_json::from_value(endpoint.event_types.clone()).unwrap_or_default()
+}
+
+fn subscribes(endpoint: &webhook_endpoints::Model, event_type: &str) -> bool {
+    let event_types = subscribed_event_types(endpoint);
+    event_types.is_empty() || event_types.iter().any(|t| t == event_type)
+}
+
+/// 为 outbox 事件创建投递记录, 与 outbox 标记已处理在同一个事务里
+pub async fn enqueue_for_event<C: ConnectionTrait>(
+    db: &C,
+    event: &outbox_events::Model,
+) -> Result<usize, DbErr> {
+    let Some(school_id) = event.school_id else {
+        return Ok(0);
+    };
+    let endpoints = webhook_endpoints::Entity::find()
+        .filter(webhook_endpoints::Column::SchoolId.eq(school_id))
+        .filter(webhook_endpoints::Column::Enabled.eq(true))
+        .all(db)
+        .await?;
+    let payload = serde_json::json!(OutboxEventMessage::from(event));
+    let deliveries: Vec<webhook_deliveries::ActiveModel> = endpoints
+        .iter()
+        .filter(|endpoint| subscribes(endpoint, &event.event_type))
+        .map(|endpoint| webhook_deliveries::ActiveModel {
+            endpoint_id: Set(endpoint.id),
+            outbox_event_id: Set(Some(event.id)),
+            event_type: Set(event.event_type.clone()),
+            idempotency_key: Set(event.idempotency_key.clone()),
+            payload: Set(payload.clone()),
+            ..Default::default()
+        })
+        .collect();
+    let count = deliveries.len();
+    if count > 0 {
+        webhook_deliveries::Entity::insert_many(deliveries).exec(db).await?;
+    }
+    Ok(count)
+}
+
+/// 按原来的内容和幂等键重新投递一次, 原记录保持不变
+pub async fn redeliver(
+    state: &AppState,
+    delivery: webhook_deliveries::Model,
+) -> Result<webhook_deliveries::Model, AppError> {
+    let redelivery = webhook_deliveries::ActiveModel {
+        endpoint_id: Set(delivery.endpoint_id),
+        outbox_event_id: Set(delivery.outbox_event_id),
+        redelivery_of: Set(Some(delivery.id)),
+        event_type: Set(delivery.event_type),
+        idempotency_key: Set(delivery.idempotency_key),
+        payload: Set(delivery.payload),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await?;
+    wake();
+    Ok(redelivery)
+}
+
+/// 持续投递到期的记录
+pub async fn run_worker(state: AppState) {
+    let poll_interval = std::time::Duration::from_secs(WEBHOOK_POLL_INTERVAL_SECS);
+    loop {
+        loop {
+            match deliver_pending(&state).await {
+                Ok(count) if count as u64 == BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!("Webhook delivery failed: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = tokio::time::timeout(poll_interval, WAKE.notified()).await;
+    }
+}
+
+/// 投递一批到期的记录, 返回处理的数量
+///
+/// 认领记录的事务提交后才发请求, 不在持有行锁时等待对方响应;
+/// 投递中途进程退出的记录在租期过后重新投递
+pub async fn deliver_pending(state: &AppState) -> Result<usize, AppError> {
+    let deliveries = claim_pending(state).await?;
+    let count = deliveries.len();
+    if count == 0 {
+        return Ok(0);
+    }
+    let endpoint_ids: Vec<i32> = deliveries.iter().map(|d| d.endpoint_id).collect();
+    let endpoints: HashMap<i32, webhook_endpoints::Model> = webhook_endpoints::Entity::find()
+        .filter(webhook_endpoints::Column::Id.is_in(endpoint_ids))
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|e| (e.id, e))
+        .collect();
+
+    for delivery in deliveries {
+        let Some(endpoint) = endpoints.get(&delivery.endpoint_id) else {
+            continue;
+        };
+        let result = send(state, endpoint, &delivery).await;
+        let now = Utc::now();
+        let attempts = delivery.attempts + 1;
+        let mut active: webhook_deliveries::ActiveModel = delivery.into();
+        active.attempts = Set(attempts);
+        active.updated_at = Set(now.into());
+        match result {
+            Ok((status, body)) => {
+                active.status = Set(WEBHOOK_DELIVERY_SUCCEEDED.to_string());
+                active.response_status = Set(Some(status));
+                active.response_body = Set(Some(body));
+                active.last_error = Set(None);
+                active.delivered_at = Set(Some(now.into()));
+            }
+            Err((status, body, error)) => {
+                tracing::warn!("Webhook delivery to {} failed (attempt {}): {}", endpoint.url, attempts, error);
+                active.response_status = Set(status);
+                active.response_body = Set(body);
+                active.last_error = Set(Some(error));
+                if attempts >= WEBHOOK_MAX_ATTEMPTS || !endpoint.enabled {
+                    active.status = Set(WEBHOOK_DELIVERY_FAILED.to_string());
+                } else {
+                    active.next_attempt_at = Set((now + retry_delay(attempts)).into());
+                }
+            }
+        }
+        active.update(&state.db).await?;
+    }
+    Ok(count)
+}
+
+/// 锁定一批到期的记录, 把下次尝试时间推到租期之后再提交
+async fn claim_pending(state: &AppState) -> Result<Vec<webhook_deliveries::Model>, DbErr> {
+    let txn = state.db.begin().await?;
+    let deliveries = webhook_deliveries::Entity::find()
+        .filter(webhook_deliveries::Column::Status.eq(WEBHOOK_DELIVERY_PENDING))
+        .filter(webhook_deliveries::Column::NextAttemptAt.lte(Utc::now()))
+        .order_by_asc(webhook_deliveries::Column::Id)
+        .limit(BATCH_SIZE)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await?;
+    if deliveries.is_empty() {
+        return Ok(deliveries);
+    }
+    let batch_timeout = state.config.outbox.webhook_timeout_secs as i64 * BATCH_SIZE as i64;
+    let lease_until = Utc::now() + Duration::seconds(batch_timeout + CLAIM_MARGIN_SECS);
+    webhook_deliveries::Entity::update_many()
+        .col_expr(webhook_deliveries::Column::NextAttemptAt, Expr::value(lease_until))
+        .filter(webhook_deliveries::Column::Id.is_in(deliveries.iter().map(|d| d.id)))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(deliveries)
+}
+
+fn retry_delay(attempts: i32) -> Duration {
+    let secs = RETRY_BASE_SECS
+        .saturating_mul(1 << (attempts - 1).clamp(0, 20))
+        .min(RETRY_MAX_SECS);
+    Duration::seconds(secs)
+}
+
+type SendError = (Option<i32>, Option<String>, String);
+
+async fn send(
+    state: &AppState,
+    endpoint: &webhook_endpoints::Model,
+    delivery: &webhook_deliveries::Model,
+) -> Result<(i32, String), SendError> {
+    let body = delivery.payload.to_string();
+    let timestamp = Utc::now().timestamp();
+    let response = HTTP_CLIENT
+        .post(&endpoint.url)
+        .timeout(std::time::Duration::from_secs(state.config.outbox.webhook_timeout_secs))
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Id", delivery.id.to_string())
+        .header("X-Webhook-Event", &delivery.event_type)
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", sign(&endpoint.secret, timestamp, &body))
+        .header("Idempotency-Key", &delivery.idempotency_key)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| (None, None, e.to_string()))?;
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    let text: String = text.chars().take(RESPONSE_BODY_LIMIT).collect();
+    if !status.is_success() {
+        return Err((
+            Some(status.as_u16() as i32),
+            Some(text),
+            format!("endpoint responded with {}", status),
+        ));
+    }
+    Ok((status.as_u16() as i32, text))
+}
@@ -1,4 +1,4 @@
-pub mod apis;
-pub mod core;
-pub mod utils;
-
+pub mod apis;
+pub mod core;
+pub mod utils;
+
@@ -1,6 +1,10 @@
 use salvo::prelude::*;
 use anyhow::{Context };
+use school_manager_server::apis::announcement_api;
 use school_manager_server::core;
+use school_manager_server::core::constants::{
+    ANNOUNCEMENT_SYNC_INTERVAL_SECS, AUDIT_PURGE_INTERVAL_SECS, TRASH_PURGE_INTERVAL_SECS,
+};
 
 #[tokio::main]
 async fn main() {
@@ -10,13 +14,47 @@ async fn main() {
 
     let app_state = core::app::init_app().await.context("init app failed").unwrap();
     
-    // Spawn the database listener as a background task
-    let db_config_clone = app_state.config.database.clone();
+    // Spawn the database listener as a background task, it reconnects by itself
+    tokio::spawn(core::db_listener::run_listener(app_state.clone()));
+    // Deliver school webhooks created from outbox events
+    tokio::spawn(core::webhook::run_worker(app_state.clone()));
+
+    // Publish scheduled announcements and expire finished ones
+    let announcement_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(ANNOUNCEMENT_SYNC_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = announcement_api::sync_announcements(&announcement_state).await {
+                tracing::error!("Announcement sync failed: {}", e);
+            }
+        }
+    });
+
+    // Remove audit logs older than the configured retention
+    let audit_state = app_state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(AUDIT_PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match core::audit::purge_expired(&audit_state).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} audit logs", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Audit purge failed: {}", e),
+            }
+        }
+    });
+
+    // Permanently remove soft-deleted rows older than the configured retention
+    let trash_state = app_state.clone();
     tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(TRASH_PURGE_INTERVAL_SECS));
         loop {
-            if let Err(e) = core::db_listener::listen_for_notifications(&db_config_clone).await {
-                tracing::error!("DB listener task failed: {}. Restarting after 5 seconds...", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            interval.tick().await;
+            match core::soft_delete::purge_expired(&trash_state).await {
+                Ok(purged) if purged > 0 => tracing::info!("Purged {} soft-deleted rows", purged),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Trash purge failed: {}", e),
             }
         }
     });
@@ -1,26 +1,53 @@
-use std::str::FromStr;
-use std::fmt::Display;
-use serde::de;
-pub fn from_str<'de, D,T>(deserializer: D) -> Result<T, D::Error>
-where
-    T: FromStr,
-    T::Err: Display,
-    D: serde::Deserializer<'de>,
-{
-    use serde::Deserialize;
-    let s: String = String::deserialize(deserializer)?;
-    T::from_str(&s).map_err(de::Error::custom)
-}
-
-
-pub fn from_str_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
-where
-    T: FromStr,
-    T::Err: Display,
-    D: serde::Deserializer<'de>,
-{
-    use serde::Deserialize;
-    let s: Option<String> = Option::deserialize(deserializer)?;
-    s.map(|s| T::from_str(&s).map_err(de::Error::custom))
-        .transpose()
-}
\ No newline at end of file
+use std::str::FromStr;
+use std::fmt::Display;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use serde::de;
+pub fn from_str<'de, D,T>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    let s: String = String::deserialize(deserializer)?;
+    T::from_str(&s).map_err(de::Error::custom)
+}
+
+
+pub fn from_str_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    s.map(|s| T::from_str(&s).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// 区分字段缺省和显式 null: 缺省为 None(配合 #[serde(default)]), null 为 Some(None)
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// 本地时间某一天 0 点对应的 UTC 时间
+pub fn local_day_start(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(Local).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// 本地时间某一天的某个时刻对应的 UTC 时间, 夏令时跳过的时刻返回 None
+pub fn local_datetime(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    date.and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
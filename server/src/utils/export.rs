@@ -0,0 +1,194 @@
+use crate::core::app::AppState;
+use crate::core::error::AppError;
+use chrono::Local;
+use rust_xlsxwriter::Workbook;
+use salvo::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use salvo::http::HeaderValue;
+use salvo::hyper::body::Bytes;
+use salvo::prelude::*;
+use serde::Serialize;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// 每次从数据库读取的行数
+pub const EXPORT_BATCH_SIZE: u64 = 500;
+/// XLSX 需要在内存里生成完整文件, 超过这个行数请用 CSV 或 JSON Lines
+pub const EXPORT_XLSX_MAX_ROWS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// 默认 CSV
+    pub fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        match value.unwrap_or("csv") {
+            "csv" => Ok(Self::Csv),
+            "xlsx" => Ok(Self::Xlsx),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(AppError::validation(format!("invalid export format: {}", other))),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+            Self::Jsonl => "jsonl",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+/// 导出的一行; JSON Lines 直接序列化, CSV/XLSX 按 headers 的顺序取 values
+pub trait ExportRecord: Serialize + Send + 'static {
+    fn headers() -> &'static [&'static str];
+    fn values(&self) -> Vec<String>;
+}
+
+/// 按批读取导出数据, 返回空列表表示读完
+pub trait ExportSource: Send + 'static {
+    type Record: ExportRecord;
+
+    fn next_batch(&mut self, state: &AppState) -> impl Future<Output = Result<Vec<Self::Record>, AppError>> + Send;
+}
+
+/// 已经在内存里算好的数据, 一次返回
+pub struct VecSource<R>(Option<Vec<R>>);
+
+impl<R> VecSource<R> {
+    pub fn new(records: Vec<R>) -> Self {
+        Self(Some(records))
+    }
+}
+
+impl<R: ExportRecord> ExportSource for VecSource<R> {
+    type Record = R;
+
+    async fn next_batch(&mut self, _state: &AppState) -> Result<Vec<R>, AppError> {
+        Ok(self.0.take().unwrap_or_default())
+    }
+}
+
+/// CSV 和 JSON Lines 边读边写; XLSX 读完后一次返回
+pub async fn render<S: ExportSource>(
+    res: &mut Response,
+    state: AppState,
+    format: ExportFormat,
+    file_stem: &str,
+    mut source: S,
+) -> Result<(), AppError> {
+    let file_name = format!("{}_{}.{}", file_stem, Local::now().format("%Y%m%d%H%M%S"), format.extension());
+    if let Ok(value) = HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name)) {
+        res.headers_mut().insert(CONTENT_DISPOSITION, value);
+    }
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+
+    if format == ExportFormat::Xlsx {
+        let body = build_xlsx(&state, &mut source).await?;
+        res.body(body);
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+    tokio::spawn(async move {
+        if let Err(e) = produce(&state, format, &mut source, &tx).await {
+            tracing::error!("Export failed: {}", e);
+            // 已经发出的部分无法撤回, 中断响应让客户端知道文件不完整
+            let _ = tx.send(Err(std::io::Error::other(e.to_string()))).await;
+        }
+    });
+    res.stream(ReceiverStream::new(rx));
+    Ok(())
+}
+
+async fn produce<S: ExportSource>(
+    state: &AppState,
+    format: ExportFormat,
+    source: &mut S,
+    tx: &mpsc::Sender<Result<Bytes, std::io::Error>>,
+) -> Result<(), AppError> {
+    if format == ExportFormat::Csv {
+        // 带 BOM, Excel 打开时才能识别 UTF-8
+        let mut header = "\u{feff}".as_bytes().to_vec();
+        header.extend(encode_csv(&[S::Record::headers().iter().map(|h| h.to_string()).collect()])?);
+        if tx.send(Ok(Bytes::from(header))).await.is_err() {
+            return Ok(());
+        }
+    }
+    loop {
+        let batch = source.next_batch(state).await?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let chunk = match format {
+            ExportFormat::Jsonl => {
+                let mut chunk = Vec::new();
+                for record in &batch {
+                    serde_json::to_writer(&mut chunk, record)
+                        .map_err(|e| AppError::Message(format!("failed to encode record: {}", e)))?;
+                    chunk.push(b'\n');
+                }
+                chunk
+            }
+            _ => encode_csv(&batch.iter().map(ExportRecord::values).collect::<Vec<_>>())?,
+        };
+        // 客户端断开后停止读取
+        if tx.send(Ok(Bytes::from(chunk))).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+fn encode_csv(rows: &[Vec<String>]) -> Result<Vec<u8>, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer
+            .write_record(row)
+            .map_err(|e| AppError::Message(format!("failed to encode csv: {}", e)))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| AppError::Message(format!("failed to encode csv: {}", e)))
+}
+
+async fn build_xlsx<S: ExportSource>(state: &AppState, source: &mut S) -> Result<Vec<u8>, AppError> {
+    let xlsx_error = |e: rust_xlsxwriter::XlsxError| AppError::Message(format!("failed to build xlsx: {}", e));
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    for (col, header) in S::Record::headers().iter().enumerate() {
+        sheet.write_string(0, col as u16, *header).map_err(xlsx_error)?;
+    }
+    let mut row = 0usize;
+    loop {
+        let batch = source.next_batch(state).await?;
+        if batch.is_empty() {
+            break;
+        }
+        if row + batch.len() > EXPORT_XLSX_MAX_ROWS {
+            return Err(AppError::business_logic(
+                "EXPORT_TOO_LARGE",
+                format!("xlsx export is limited to {} rows, use csv or jsonl", EXPORT_XLSX_MAX_ROWS),
+            ));
+        }
+        for record in batch {
+            row += 1;
+            for (col, value) in record.values().iter().enumerate() {
+                sheet.write_string(row as u32, col as u16, value).map_err(xlsx_error)?;
+            }
+        }
+    }
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
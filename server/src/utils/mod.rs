@@ -1,3 +1,5 @@
-pub mod convert;
-pub mod jwt;
-pub mod crud_macro;
\ No newline at end of file
+pub mod convert;
+pub mod jwt;
+pub mod crud_macro;
+pub mod export;
+pub mod table_import;
\ No newline at end of file
@@ -0,0 +1,123 @@
+use crate::core::error::AppError;
+use calamine::{Data, Reader, Xlsx};
+use salvo::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// 上传文件的字段名
+const FILE_FIELD: &str = "file";
+
+/// 表格的一行, 列名统一为小写; line 为文件中的行号(表头是第 1 行)
+#[derive(Debug)]
+pub struct ImportRow {
+    pub line: usize,
+    values: HashMap<String, String>,
+}
+
+impl ImportRow {
+    /// 去掉首尾空白, 空值返回 None
+    pub fn get(&self, column: &str) -> Option<&str> {
+        self.values
+            .get(column)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// 导入时某一行的错误
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl ImportRowError {
+    pub fn new(line: usize, column: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column: column.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+/// 读取 multipart 里的 file 字段, 按扩展名解析 CSV 或 XLSX
+pub async fn read_upload(req: &mut Request) -> Result<Vec<ImportRow>, AppError> {
+    let file = req
+        .file(FILE_FIELD)
+        .await
+        .ok_or_else(|| AppError::validation("file is required"))?;
+    let file_name = file.name().unwrap_or_default().to_lowercase();
+    let bytes = tokio::fs::read(file.path())
+        .await
+        .map_err(|e| AppError::validation(format!("failed to read uploaded file: {}", e)))?;
+    parse(&file_name, &bytes)
+}
+
+pub fn parse(file_name: &str, bytes: &[u8]) -> Result<Vec<ImportRow>, AppError> {
+    let records = if file_name.ends_with(".xlsx") {
+        read_xlsx(bytes)?
+    } else if file_name.ends_with(".csv") {
+        read_csv(bytes)?
+    } else {
+        return Err(AppError::validation("only .csv and .xlsx files are supported"));
+    };
+    let mut records = records.into_iter();
+    let headers: Vec<String> = records
+        .next()
+        .ok_or_else(|| AppError::validation("file is empty"))?
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    let rows = records
+        .enumerate()
+        .filter(|(_, record)| record.iter().any(|v| !v.trim().is_empty()))
+        .map(|(i, record)| ImportRow {
+            line: i + 2,
+            values: headers.iter().cloned().zip(record).collect(),
+        })
+        .collect();
+    Ok(rows)
+}
+
+fn read_csv(bytes: &[u8]) -> Result<Vec<Vec<String>>, AppError> {
+    // Excel 导出的 CSV 带 BOM
+    let bytes = bytes.strip_prefix("\u{feff}".as_bytes()).unwrap_or(bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(bytes);
+    reader
+        .records()
+        .map(|record| {
+            record
+                .map(|r| r.iter().map(str::to_string).collect())
+                .map_err(|e| AppError::validation(format!("invalid csv: {}", e)))
+        })
+        .collect()
+}
+
+fn read_xlsx(bytes: &[u8]) -> Result<Vec<Vec<String>>, AppError> {
+    let mut workbook = Xlsx::new(Cursor::new(bytes))
+        .map_err(|e| AppError::validation(format!("invalid xlsx: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| AppError::validation("xlsx has no worksheet"))?
+        .map_err(|e| AppError::validation(format!("invalid xlsx: {}", e)))?;
+    let rows = range
+        .rows()
+        .map(|row| row.iter().map(cell_to_string).collect())
+        .collect();
+    Ok(rows)
+}
+
+/// 整数在 Excel 里存成浮点数, 转回不带小数点的形式
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Float(f) if f.fract() == 0.0 => format!("{}", *f as i64),
+        Data::Empty => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,186 @@
+use salvo::prelude::*;
+use salvo::test::TestClient;
+use school_manager_server::core::constants::APP_BUSINESS_LOGIC;
+use serde_json::{json, Value};
+
+mod helpers;
+
+async fn post(app: &Service, token: &str, path: &str, payload: Value, label: &str) -> Value {
+    let response = TestClient::post(helpers::get_url(path))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&payload)
+        .send(app)
+        .await;
+    helpers::print_response_body_get_json(response, label).await
+}
+
+async fn create_class(app: &Service, token: &str, school_id: i32, grade: i32, class: i32) -> i32 {
+    let payload = json!({
+        "name": format!("{}年级{}班", grade, class),
+        "grade": grade,
+        "class": class,
+        "school_id": school_id,
+        "password": "class123"
+    });
+    let created = post(app, token, "/api/admin/classes", payload, "create_class").await;
+    created["data"]["id"].as_i64().unwrap() as i32
+}
+
+#[tokio::test]
+async fn rollover_promotes_classes_and_graduates_top_grade() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("year"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let payload = json!({"name": helpers::unique_name("year_school"), "password": "school123"});
+    let school = post(&app, &token, "/api/admin/schools", payload, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let bound = post(
+        &app,
+        &token,
+        "/api/admin/bind/school",
+        json!({"school_id": school_id, "password": "school123"}),
+        "bind_school",
+    )
+    .await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let years_path = format!("/api/admin/schools/{}/academic-years", school_id);
+    let payload = json!({"name": "2025-2026", "start_date": "2025-09-01", "end_date": "2026-08-31", "is_current": true});
+    let year = post(&app, &token, &years_path, payload, "create_year").await;
+    assert!(year["data"]["is_current"].as_bool().unwrap());
+    let from_year_id = year["data"]["id"].as_i64().unwrap();
+
+    let first = create_class(&app, &token, school_id, 1, 1).await;
+    let _second = create_class(&app, &token, school_id, 1, 2).await;
+    let top = create_class(&app, &token, school_id, 6, 1).await;
+    let campus_path = format!("/api/admin/schools/{}/campuses", school_id);
+    let campus = post(&app, &token, &campus_path, json!({"name": "north"}), "create_campus").await;
+    let campus_id = campus["data"]["id"].as_i64().unwrap();
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", first)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"campus_id": campus_id}))
+        .send(&app)
+        .await;
+    let moved = helpers::print_response_body_get_json(response, "move_class_to_campus").await;
+    assert_eq!(moved["data"]["campus_id"], campus_id);
+    let bound = post(
+        &app,
+        &token,
+        "/api/admin/bind/class",
+        json!({"class_id": first, "password": "class123"}),
+        "bind_class",
+    )
+    .await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let rollover_path = format!("{}/rollover", years_path);
+    let rollover = json!({
+        "name": "2026-2027",
+        "start_date": "2026-09-01",
+        "end_date": "2027-08-31",
+        "max_grade": 6,
+        "name_template": "{grade}年级{class}班",
+        "carry_over_teachers": true,
+        "dry_run": true
+    });
+    let plan = post(&app, &token, &rollover_path, rollover.clone(), "rollover_dry_run").await;
+    assert!(plan["data"]["dry_run"].as_bool().unwrap());
+    assert!(plan["data"]["to_year"].is_null());
+    let promoted = plan["data"]["promoted"].as_array().unwrap();
+    assert_eq!(promoted.len(), 2);
+    assert_eq!(promoted[0]["class_id"].as_i64().unwrap() as i32, first);
+    assert_eq!(promoted[0]["new_name"], "2年级1班");
+    assert_eq!(promoted[0]["new_grade"], 2);
+    assert!(promoted[0]["new_class_id"].is_null());
+    let graduated = plan["data"]["graduated"].as_array().unwrap();
+    assert_eq!(graduated.len(), 1);
+    assert_eq!(graduated[0]["class_id"].as_i64().unwrap() as i32, top);
+    assert_eq!(plan["data"]["carried_bindings"], 1);
+
+    // dry_run 不做任何修改
+    let response = TestClient::get(helpers::get_url(&years_path))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let years = helpers::print_response_body_get_json(response, "years_after_dry_run").await;
+    assert_eq!(years["data"].as_array().unwrap().len(), 1);
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/classes/school/{}", school_id)))
+        .send(&app)
+        .await;
+    let before = helpers::print_response_body_get_json(response, "screen_before").await;
+    assert_eq!(before["data"].as_array().unwrap().len(), 3);
+    let response = TestClient::get(helpers::get_url(&format!("/api/classes/school/{}/changes", school_id)))
+        .send(&app)
+        .await;
+    let changes = helpers::print_response_body_get_json(response, "changes_before").await;
+    let since = changes["data"]["version"].as_i64().unwrap();
+
+    let mut execute = rollover.clone();
+    execute["dry_run"] = json!(false);
+    let result = post(&app, &token, &rollover_path, execute.clone(), "rollover").await;
+    assert!(!result["data"]["dry_run"].as_bool().unwrap());
+    assert!(!result["data"]["from_year"]["is_current"].as_bool().unwrap());
+    assert!(result["data"]["to_year"]["is_current"].as_bool().unwrap());
+    let new_first = result["data"]["promoted"][0]["new_class_id"].as_i64().unwrap() as i32;
+
+    // 大屏只显示新学年的班级, 增量接口要求整体替换
+    let response = TestClient::get(helpers::get_url(&format!("/api/classes/school/{}", school_id)))
+        .send(&app)
+        .await;
+    let after = helpers::print_response_body_get_json(response, "screen_after").await;
+    let after = after["data"].as_array().unwrap();
+    assert_eq!(after.len(), 2);
+    assert!(after.iter().all(|c| c["grade"] == 2 && c["status"] == 0));
+    // 升级后的班级留在原来的校区
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/admin/classes?academic_year_id={}",
+        result["data"]["to_year"]["id"]
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let new_classes = helpers::print_response_body_get_json(response, "new_year_classes").await;
+    let new_classes = new_classes["data"]["list"].as_array().unwrap();
+    let promoted_first = new_classes.iter().find(|c| c["id"] == new_first).unwrap();
+    assert_eq!(promoted_first["campus_id"], campus_id);
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/classes/school/{}/changes?since={}",
+        school_id, since
+    )))
+    .send(&app)
+    .await;
+    let changes = helpers::print_response_body_get_json(response, "changes_after").await;
+    assert!(changes["data"]["reset"].as_bool().unwrap());
+    assert_eq!(changes["data"]["classes"].as_array().unwrap().len(), 2);
+
+    // 教师绑定转到了新班级, 旧班级保留在旧学年里
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}/teachers", new_first)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let teachers = helpers::print_response_body_get_json(response, "new_class_teachers").await;
+    assert_eq!(teachers["data"].as_array().unwrap().len(), 1);
+    assert_eq!(teachers["data"][0]["role"], "head");
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/admin/classes?academic_year_id={}",
+        from_year_id
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let old_classes = helpers::print_response_body_get_json(response, "old_year_classes").await;
+    assert_eq!(old_classes["data"]["total"], 3);
+    assert!(old_classes["data"]["list"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|c| c["teacher_infos"].as_array().unwrap().is_empty()));
+
+    let repeated = post(&app, &token, &rollover_path, execute, "rollover_again").await;
+    assert_eq!(repeated["code"].as_i64().unwrap(), APP_BUSINESS_LOGIC as i64);
+}
@@ -0,0 +1,109 @@
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn announcement_crud_flow() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("announcement");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("announcement_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_announcement").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/announcements"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({
+            "school_id": school_id,
+            "content": "Grade 1 dismisses early today",
+            "severity": "warning",
+            "target_grades": [1]
+        }))
+        .send(&app)
+        .await;
+    let created = helpers::print_response_body_get_json(response, "create_announcement").await;
+    assert!(created["success"].as_bool().unwrap());
+    let announcement_id = created["data"]["id"].as_i64().unwrap();
+    assert_eq!(created["data"]["target_grades"], json!([1]));
+
+    let response = TestClient::post(helpers::get_url("/api/admin/announcements"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({
+            "school_id": school_id,
+            "content": "Scheduled",
+            "start_at": "2099-01-01T00:00:00Z"
+        }))
+        .send(&app)
+        .await;
+    let scheduled = helpers::print_response_body_get_json(response, "create_scheduled_announcement").await;
+    let scheduled_id = scheduled["data"]["id"].as_i64().unwrap();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/announcements"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "content": "bad", "severity": "unknown"}))
+        .send(&app)
+        .await;
+    let invalid = helpers::print_response_body_get_json(response, "create_invalid_announcement").await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    let body = json!({"school_id": school_id, "content": "bad grade", "target_grades": [1, 13]});
+    let invalid = helpers::send(&app, &token, "POST", "/api/admin/announcements", Some(body)).await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/schools/{}/announcements", school_id)))
+        .send(&app)
+        .await;
+    let active = helpers::print_response_body_get_json(response, "active_announcements").await;
+    let active = active["data"].as_array().unwrap();
+    assert!(active.iter().any(|item| item["id"].as_i64() == Some(announcement_id)));
+    assert!(!active.iter().any(|item| item["id"].as_i64() == Some(scheduled_id)));
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/announcements/{}", announcement_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"content": "updated"}))
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "update_announcement").await;
+    assert_eq!(updated["data"]["content"].as_str().unwrap(), "updated");
+
+    // 不传 end_at 保持不变, 显式 null 取消结束时间
+    let path = format!("/api/admin/announcements/{}", scheduled_id);
+    let body = json!({"end_at": "2099-01-02T00:00:00Z"});
+    let updated = helpers::send(&app, &token, "PUT", &path, Some(body)).await;
+    assert_eq!(updated["data"]["end_at"], "2099-01-02T00:00:00Z");
+    let updated = helpers::send(&app, &token, "PUT", &path, Some(json!({"severity": "critical"}))).await;
+    assert_eq!(updated["data"]["end_at"], "2099-01-02T00:00:00Z");
+    let updated = helpers::send(&app, &token, "PUT", &path, Some(json!({"end_at": null}))).await;
+    assert!(updated["data"]["end_at"].is_null());
+    let invalid = helpers::send(&app, &token, "PUT", &path, Some(json!({"target_grades": [0]}))).await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    let response = TestClient::delete(helpers::get_url(&format!("/api/admin/announcements/{}", announcement_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let deleted = helpers::print_response_body_get_json(response, "delete_announcement").await;
+    assert!(deleted["success"].as_bool().unwrap());
+}
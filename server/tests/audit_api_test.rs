@@ -0,0 +1,141 @@
+use data_model::{user_roles, users};
+use salvo::http::HeaderValue;
+use salvo::test::TestClient;
+use salvo::Request;
+use school_manager_server::core::audit::AuditContext;
+use school_manager_server::core::router;
+use sea_orm::*;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+
+mod helpers;
+
+#[tokio::test]
+async fn admin_mutations_are_audited_with_diffs() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+    let app = router::create_router(state.clone());
+
+    // 直接在数据库里授予管理员角色, 重新登录拿到带角色的 token
+    let username = helpers::unique_name("auditor");
+    helpers::register_user(&app, &username, "testpass123").await;
+    let user = users::Entity::find()
+        .filter(users::Column::Username.eq(username.as_str()))
+        .one(&state.db)
+        .await
+        .unwrap()
+        .unwrap();
+    user_roles::ActiveModel {
+        user_id: Set(user.id),
+        role_id: Set(1),
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let login = helpers::login_user(&app, &username, "testpass123", "login_admin").await;
+    let token = login["data"]["token"].as_str().unwrap().to_string();
+
+    let name = helpers::unique_name("audit_school");
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": name, "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap();
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/schools/{}", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .add_header("X-Forwarded-For", "203.0.113.7, 10.0.0.1", true)
+        .json(&json!({"name": format!("{}_renamed", name)}))
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "update_school").await;
+    assert!(updated["success"].as_bool().unwrap());
+
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/admin/audit?entity_type=schools&entity_id={}",
+        school_id
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let audit = helpers::print_response_body_get_json(response, "audit_list").await;
+    assert_eq!(audit["data"]["total"], 2);
+    let list = audit["data"]["list"].as_array().unwrap();
+
+    let update = &list[0];
+    assert_eq!(update["action"], "update");
+    assert_eq!(update["actor_id"].as_i64().unwrap(), user.id as i64);
+    assert_eq!(update["actor_name"], username.as_str());
+    // 测试客户端不经过受信任的代理, 伪造的转发头不会被采用
+    assert!(update["ip"].is_null());
+    assert_eq!(update["route"], format!("PUT /api/admin/schools/{}", school_id).as_str());
+    assert_eq!(update["before"], json!({"name": name}));
+    assert_eq!(update["after"], json!({"name": format!("{}_renamed", name)}));
+
+    let create = &list[1];
+    assert_eq!(create["action"], "create");
+    assert!(create["before"].is_null());
+    assert_eq!(create["after"]["password"], "***");
+
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/admin/audit?actor_id={}&action=delete",
+        user.id
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let deletes = helpers::print_response_body_get_json(response, "audit_deletes").await;
+    assert_eq!(deletes["data"]["total"], 0);
+
+    // 停课记录同样写进审计日志, 并带上操作人
+    let response = TestClient::post(helpers::get_url(&format!("/api/admin/schools/{}/hold", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"reason": "storm"}))
+        .send(&app)
+        .await;
+    let hold = helpers::print_response_body_get_json(response, "hold_school").await;
+    assert!(hold["success"].as_bool().unwrap());
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/admin/audit?entity_type=school_holds&actor_id={}",
+        user.id
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let holds = helpers::print_response_body_get_json(response, "audit_holds").await;
+    assert_eq!(holds["data"]["total"], 1);
+    let record = &holds["data"]["list"][0];
+    assert_eq!(record["action"], "create");
+    assert_eq!(record["route"], format!("POST /api/admin/schools/{}/hold", school_id).as_str());
+    assert_eq!(record["after"]["reason"], "storm");
+
+    // 非管理员不能查看
+    let register = helpers::register_user(&app, &helpers::unique_name("audit_other"), "testpass123").await;
+    let other_token = register["data"]["token"].as_str().unwrap().to_string();
+    let response = TestClient::get(helpers::get_url("/api/admin/audit"))
+        .add_header("Authorization", helpers::bearer(&other_token), true)
+        .send(&app)
+        .await;
+    let forbidden = helpers::print_response_body_get_json(response, "audit_forbidden").await;
+    assert!(!forbidden["success"].as_bool().unwrap());
+}
+
+#[test]
+fn forwarded_for_is_only_read_behind_trusted_proxies() {
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut req = Request::new();
+    *req.remote_addr_mut() = SocketAddr::new(proxy, 443).into();
+    req.headers_mut()
+        .insert("x-forwarded-for", HeaderValue::from_static("198.51.100.9, 203.0.113.7, 10.0.0.1"));
+
+    // 从右往左跳过受信任的代理, 更左边的地址可能是客户端伪造的
+    let context = AuditContext::from_request(&req, None, &[proxy]);
+    assert_eq!(context.ip.as_deref(), Some("203.0.113.7"));
+    let context = AuditContext::from_request(&req, None, &[]);
+    assert_eq!(context.ip.as_deref(), Some("10.0.0.1"));
+}
@@ -1,65 +1,482 @@
-use salvo::test::TestClient;
-use serde_json::json;
-
-mod helpers;
-
-#[tokio::test]
-async fn class_crud_flow() {
-    let _guard = helpers::db_lock().await;
-    let app = helpers::create_test_app().await;
-    let username = helpers::unique_name("class");
-    let register = helpers::register_user(&app, &username, "testpass123").await;
-    let token = register["data"]["token"].as_str().unwrap().to_string();
-
-    let school_resp = TestClient::post(helpers::get_url("/api/admin/schools"))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .add_header("content-type", "application/json", true)
-        .json(&json!({"name": helpers::unique_name("class_school")}))
-        .send(&app)
-        .await;
-    let school = helpers::print_response_body_get_json(school_resp, "create_school_for_class").await;
-    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
-
-    let class_payload = json!({
-        "name": helpers::unique_name("class_name"),
-        "grade": 3,
-        "class": 1,
-        "school_id": school_id,
-        "status": 0,
-        "password": "pass123"
-    });
-
-    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .add_header("content-type", "application/json", true)
-        .json(&class_payload)
-        .send(&app)
-        .await;
-    let created = helpers::print_response_body_get_json(response, "create_class").await;
-    assert!(created["success"].as_bool().unwrap());
-    let class_id = created["data"]["id"].as_i64().unwrap() as i32;
-
-    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .add_header("content-type", "application/json", true)
-        .json(&json!({"status": 2}))
-        .send(&app)
-        .await;
-    let updated = helpers::print_response_body_get_json(response, "update_class").await;
-    assert_eq!(updated["data"]["status"].as_i64().unwrap(), 2);
-
-    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes?school_id={}", school_id)))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .send(&app)
-        .await;
-    let list = helpers::print_response_body_get_json(response, "list_classes").await;
-    assert!(list["data"]["list"].as_array().unwrap().iter().any(|item| item["id"].as_i64() == Some(class_id as i64)));
-
-    let response = TestClient::delete(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .send(&app)
-        .await;
-    let deleted = helpers::print_response_body_get_json(response, "delete_class").await;
-    assert!(deleted["success"].as_bool().unwrap());
-}
-
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn class_crud_flow() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("class");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let school_resp = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("class_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(school_resp, "create_school_for_class").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let body = json!({"school_id": school_id, "password": "school123"});
+    helpers::send(&app, &token, "POST", "/api/admin/bind/school", Some(body)).await;
+
+    let class_payload = json!({
+        "name": helpers::unique_name("class_name"),
+        "grade": 3,
+        "class": 1,
+        "school_id": school_id,
+        "status": 0,
+        "password": "pass123"
+    });
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&class_payload)
+        .send(&app)
+        .await;
+    let created = helpers::print_response_body_get_json(response, "create_class").await;
+    assert!(created["success"].as_bool().unwrap());
+    let class_id = created["data"]["id"].as_i64().unwrap() as i32;
+    // 绑定后作为班主任值班, 才能修改班级状态
+    let body = json!({"class_id": class_id, "password": "pass123"});
+    helpers::send(&app, &token, "POST", "/api/admin/bind/class", Some(body)).await;
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 2}))
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "update_class").await;
+    assert_eq!(updated["data"]["status"].as_i64().unwrap(), 2);
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes?school_id={}", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let list = helpers::print_response_body_get_json(response, "list_classes").await;
+    assert!(list["data"]["list"].as_array().unwrap().iter().any(|item| item["id"].as_i64() == Some(class_id as i64)));
+
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/classes/{}", class_id), "delete_class").await;
+    assert!(deleted["success"].as_bool().unwrap());
+}
+
+
+#[tokio::test]
+async fn class_list_etag_and_changes_feed() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("class_feed");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let school_resp = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("class_feed_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(school_resp, "create_school_for_feed").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let body = json!({"school_id": school_id, "password": "school123"});
+    helpers::send(&app, &token, "POST", "/api/admin/bind/school", Some(body)).await;
+
+    let mut class_ids = Vec::new();
+    for class in 1..=2 {
+        let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({
+                "name": helpers::unique_name("class_feed"),
+                "grade": 1,
+                "class": class,
+                "school_id": school_id,
+                "status": 0,
+                "password": "class123"
+            }))
+            .send(&app)
+            .await;
+        let created = helpers::print_response_body_get_json(response, "create_class_for_feed").await;
+        class_ids.push(created["data"]["id"].as_i64().unwrap());
+    }
+    // 绑定后作为班主任值班, 才能修改班级状态
+    let body = json!({"class_id": class_ids[1], "password": "class123"});
+    helpers::send(&app, &token, "POST", "/api/admin/bind/class", Some(body)).await;
+
+    let list_url = helpers::get_url(&format!("/api/classes/school/{}", school_id));
+    let response = TestClient::get(&list_url).send(&app).await;
+    let etag = response.headers().get("etag").unwrap().to_str().unwrap().to_string();
+
+    let response = TestClient::get(&list_url)
+        .add_header("If-None-Match", &etag, true)
+        .send(&app)
+        .await;
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::NOT_MODIFIED));
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/classes/school/{}/changes", school_id)))
+        .send(&app)
+        .await;
+    let changes = helpers::print_response_body_get_json(response, "class_changes_all").await;
+    assert_eq!(changes["data"]["classes"].as_array().unwrap().len(), 2);
+    let version = changes["data"]["version"].as_i64().unwrap();
+    assert_eq!(etag, format!("\"{}\"", version));
+
+    let status_url = format!("/api/admin/classes/{}/status", class_ids[1]);
+    let updated = helpers::send(&app, &token, "PUT", &status_url, Some(json!({"status": 1}))).await;
+    assert!(updated["success"].as_bool().unwrap());
+
+    let response = TestClient::get(&list_url)
+        .add_header("If-None-Match", &etag, true)
+        .send(&app)
+        .await;
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::OK));
+
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/classes/school/{}/changes?since={}&wait=5",
+        school_id, version
+    )))
+    .send(&app)
+    .await;
+    let changes = helpers::print_response_body_get_json(response, "class_changes_since").await;
+    let classes = changes["data"]["classes"].as_array().unwrap();
+    assert_eq!(classes.len(), 1);
+    assert_eq!(classes[0]["id"].as_i64().unwrap(), class_ids[1]);
+    assert_eq!(classes[0]["status"].as_i64().unwrap(), 1);
+    assert!(!changes["data"]["reset"].as_bool().unwrap());
+    let version = changes["data"]["version"].as_i64().unwrap();
+
+    helpers::delete_confirmed(&app, &token, &format!("/api/admin/classes/{}", class_ids[0]), "delete_class_for_feed").await;
+
+    let response = TestClient::get(helpers::get_url(&format!(
+        "/api/classes/school/{}/changes?since={}",
+        school_id, version
+    )))
+    .send(&app)
+    .await;
+    let changes = helpers::print_response_body_get_json(response, "class_changes_reset").await;
+    assert!(changes["data"]["reset"].as_bool().unwrap());
+    assert_eq!(changes["data"]["classes"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn my_classes_dashboard() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("my_classes"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::get(helpers::get_url("/api/admin/me/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let empty = helpers::print_response_body_get_json(response, "my_classes_empty").await;
+    assert!(empty["data"].as_array().unwrap().is_empty());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("my_classes_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_my_classes").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "bind_school_for_my_classes").await;
+
+    let mut class_ids = Vec::new();
+    for (grade, class_no) in [(2, 1), (1, 1)] {
+        let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"name": format!("my_{}_{}", grade, class_no), "grade": grade, "class": class_no, "school_id": school_id, "status": 1, "password": "class123"}))
+            .send(&app)
+            .await;
+        let class = helpers::print_response_body_get_json(response, "create_class_for_my_classes").await;
+        let class_id = class["data"]["id"].as_i64().unwrap() as i32;
+        let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"class_id": class_id, "password": "class123"}))
+            .send(&app)
+            .await;
+        helpers::print_response_body_get_json(response, "bind_class_for_my_classes").await;
+        class_ids.push(class_id);
+    }
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_ids[0])))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 2}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "update_status_for_my_classes").await;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/announcements"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "content": "grade 2 leaves early", "target_grades": [2]}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "announcement_for_my_classes").await;
+
+    // 全校默认 16:30 放学, 二年级单独 15:00
+    let items: Vec<_> = (1..=7)
+        .flat_map(|weekday| {
+            [
+                json!({"weekday": weekday, "dismiss_at": "16:30:00"}),
+                json!({"grade": 2, "weekday": weekday, "dismiss_at": "15:00:00"}),
+            ]
+        })
+        .collect();
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/schools/{}/dismissal-schedules", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"items": items}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "schedules_for_my_classes").await;
+
+    let response = TestClient::get(helpers::get_url("/api/admin/me/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let mine = helpers::print_response_body_get_json(response, "my_classes").await;
+    let list = mine["data"].as_array().unwrap();
+    assert_eq!(list.len(), 2);
+    // 按年级排序
+    assert_eq!(list[0]["grade"], 1);
+    assert_eq!(list[1]["class_id"].as_i64().unwrap() as i32, class_ids[0]);
+
+    let changed = &list[1];
+    assert_eq!(changed["status"], 2);
+    assert_eq!(changed["role"], "head");
+    assert!(changed["on_duty"].as_bool().unwrap());
+    assert_eq!(changed["co_teacher_count"], 0);
+    assert_eq!(changed["last_status_change"]["old_status"], 1);
+    assert_eq!(changed["last_status_change"]["new_status"], 2);
+    assert!(changed["last_status_change"]["actor_id"].as_i64().is_some());
+    assert_eq!(changed["today_transitions"][0]["status"], 2);
+    assert_eq!(changed["announcements"].as_array().unwrap().len(), 1);
+
+    let untouched = &list[0];
+    assert!(untouched["last_status_change"].is_null());
+    assert!(untouched["announcements"].as_array().unwrap().is_empty());
+    assert!(untouched["today_transitions"][0]["at"].is_string());
+    assert_ne!(untouched["today_transitions"][0]["at"], changed["today_transitions"][0]["at"]);
+}
+
+#[tokio::test]
+async fn duplicate_classes_are_rejected_upserted_or_merged() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("class_dup"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("class_dup_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_dup").await;
+    let school_id = school["data"]["id"].as_i64().unwrap();
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "bind_school_for_dup").await;
+
+    let post = |path: &str, body: serde_json::Value| {
+        TestClient::post(helpers::get_url(path))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&body)
+            .send(&app)
+    };
+    let class = |name: &str, class_no: i32| {
+        json!({"name": name, "grade": 1, "class": class_no, "school_id": school_id, "password": "class123"})
+    };
+
+    let created = helpers::print_response_body_get_json(post("/api/admin/classes", class("1年级1班", 1)).await, "create_first").await;
+    let first_id = created["data"]["id"].as_i64().unwrap();
+    let duplicate = helpers::print_response_body_get_json(post("/api/admin/classes", class("重复", 1)).await, "create_duplicate").await;
+    assert!(!duplicate["success"].as_bool().unwrap());
+    assert!(duplicate["message"].as_str().unwrap().contains("CLASS_ALREADY_EXISTS"));
+
+    // upsert 不改状态, 状态只能走状态接口
+    let mut upsert = class("一年级一班", 1);
+    upsert["upsert"] = json!(true);
+    upsert["status"] = json!(2);
+    let upserted = helpers::print_response_body_get_json(post("/api/admin/classes", upsert).await, "create_upsert").await;
+    assert_eq!(upserted["data"]["id"].as_i64().unwrap(), first_id);
+    assert_eq!(upserted["data"]["name"], "一年级一班");
+    assert_eq!(upserted["data"]["status"], created["data"]["status"]);
+
+    // 批量创建时有冲突的整批不创建
+    let bulk = json!({"classes": [class("1年级1班", 1), class("1年级2班", 2)]});
+    let rejected = helpers::print_response_body_get_json(post("/api/admin/classes/bulk", bulk).await, "bulk_conflict").await;
+    assert!(!rejected["success"].as_bool().unwrap());
+    let bulk = json!({"classes": [class("1年级1班", 1), class("1年级2班", 2)], "upsert": true});
+    let bulk_ok = helpers::print_response_body_get_json(post("/api/admin/classes/bulk", bulk).await, "bulk_upsert").await;
+    assert!(bulk_ok["success"].as_bool().unwrap());
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes?school_id={}", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let list = helpers::print_response_body_get_json(response, "list_after_bulk").await;
+    assert_eq!(list["data"]["total"], 2);
+    let second_id = list["data"]["list"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["class"] == 2)
+        .unwrap()["id"]
+        .as_i64()
+        .unwrap();
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", second_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class": 1}))
+        .send(&app)
+        .await;
+    let renumbered = helpers::print_response_body_get_json(response, "update_to_duplicate").await;
+    assert!(renumbered["message"].as_str().unwrap().contains("CLASS_ALREADY_EXISTS"));
+
+    // 第二个班级的班主任并入第一个班级
+    let bound = helpers::print_response_body_get_json(
+        post("/api/admin/bind/class", json!({"class_id": second_id, "password": "class123"})).await,
+        "bind_second_class",
+    )
+    .await;
+    assert!(bound["success"].as_bool().unwrap());
+    let mismatched = helpers::print_response_body_get_json(
+        post(&format!("/api/admin/classes/{}/merge", first_id), json!({"source_id": second_id})).await,
+        "merge_different_class",
+    )
+    .await;
+    assert!(!mismatched["success"].as_bool().unwrap());
+
+    // 重复的班级在回收站里, 保留的班级改成同样的班号后合并
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/classes/{}", second_id), "delete_second").await;
+    assert!(deleted["success"].as_bool().unwrap());
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", first_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class": 2}))
+        .send(&app)
+        .await;
+    let renumbered = helpers::print_response_body_get_json(response, "renumber_first").await;
+    assert!(renumbered["success"].as_bool().unwrap());
+    let merged = helpers::print_response_body_get_json(
+        post(&format!("/api/admin/classes/{}/merge", first_id), json!({"source_id": second_id})).await,
+        "merge_classes",
+    )
+    .await;
+    assert_eq!(merged["data"]["moved_bindings"], 1);
+    assert_eq!(merged["data"]["skipped_bindings"], 0);
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}/teachers", first_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let teachers = helpers::print_response_body_get_json(response, "teachers_after_merge").await;
+    assert_eq!(teachers["data"][0]["role"], "head");
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}", second_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let source = helpers::print_response_body_get_json(response, "merged_source").await;
+    assert!(!source["success"].as_bool().unwrap());
+
+    let itself = helpers::print_response_body_get_json(
+        post(&format!("/api/admin/classes/{}/merge", first_id), json!({"source_id": first_id})).await,
+        "merge_into_itself",
+    )
+    .await;
+    assert!(!itself["success"].as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn class_transfer_moves_teachers_to_the_new_school() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("class_transfer"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let post = |path: String, body: serde_json::Value| {
+        TestClient::post(helpers::get_url(&path))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&body)
+            .send(&app)
+    };
+    let mut school_ids = vec![];
+    for _ in 0..2 {
+        let body = json!({"name": helpers::unique_name("transfer_school"), "password": "school123"});
+        let school = helpers::print_response_body_get_json(post("/api/admin/schools".to_string(), body).await, "create_school").await;
+        school_ids.push(school["data"]["id"].as_i64().unwrap());
+    }
+    let (from_id, to_id) = (school_ids[0], school_ids[1]);
+    let body = json!({"school_id": from_id, "password": "school123"});
+    helpers::print_response_body_get_json(post("/api/admin/bind/school".to_string(), body).await, "bind_school").await;
+
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": from_id, "password": "class123"});
+    let class = helpers::print_response_body_get_json(post("/api/admin/classes".to_string(), body).await, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": to_id, "password": "class123"});
+    helpers::print_response_body_get_json(post("/api/admin/classes".to_string(), body).await, "create_target_class").await;
+    let body = json!({"class_id": class_id, "password": "class123"});
+    let bound = helpers::print_response_body_get_json(post("/api/admin/bind/class".to_string(), body).await, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    // 修改接口不能换学校
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": to_id}))
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "update_school_id").await;
+    assert!(updated["message"].as_str().unwrap().contains("USE_CLASS_TRANSFER"));
+
+    // 不是目标学校的成员不能把班级转过去
+    let transfer_url = format!("/api/admin/classes/{}/transfer", class_id);
+    let body = json!({"school_id": to_id, "class": 2, "carry_teachers": true});
+    let denied = helpers::print_response_body_get_json(post(transfer_url.clone(), body).await, "transfer_denied").await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let body = json!({"school_id": to_id, "password": "school123"});
+    helpers::print_response_body_get_json(post("/api/admin/bind/school".to_string(), body).await, "bind_target_school").await;
+
+    let body = json!({"school_id": to_id, "carry_teachers": true});
+    let conflict = helpers::print_response_body_get_json(post(transfer_url.clone(), body).await, "transfer_conflict").await;
+    assert!(conflict["message"].as_str().unwrap().contains("CLASS_ALREADY_EXISTS"));
+
+    let body = json!({"school_id": to_id, "class": 2, "carry_teachers": true});
+    let moved = helpers::print_response_body_get_json(post(transfer_url, body).await, "transfer_class").await;
+    assert_eq!(moved["data"]["class"]["school_id"].as_i64().unwrap(), to_id);
+    assert_eq!(moved["data"]["class"]["class"], 2);
+    assert_eq!(moved["data"]["carried_teachers"].as_array().unwrap().len(), 1);
+    assert!(moved["data"]["new_members"].as_array().unwrap().is_empty());
+    assert!(moved["data"]["dropped_teachers"].as_array().unwrap().is_empty());
+
+    let response = TestClient::get(helpers::get_url("/api/admin/me"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let me = helpers::print_response_body_get_json(response, "me_after_transfer").await;
+    assert_eq!(me["data"]["school_id"].as_i64().unwrap(), from_id);
+    let memberships = me["data"]["memberships"].as_array().unwrap();
+    assert!(memberships.iter().any(|m| m["school_id"].as_i64() == Some(to_id)));
+}
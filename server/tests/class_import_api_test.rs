@@ -0,0 +1,110 @@
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn class_import_reports_row_errors_and_upserts() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("import"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("import_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "old name", "grade": 1, "class": 1, "school_id": school_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+
+    let import_path = format!("/api/admin/classes/import?school_id={}", school_id);
+    let csv = "\u{feff}Name,Grade,Class,Status\n\
+        1年级1班,1,1,1\n\
+        1年级2班,1,2,\n\
+        1年级2班,1,2,\n\
+        bad grade,x,3,\n\
+        bad status,2,1,9\n";
+    let report = helpers::upload_file(&app, &token, &import_path, "classes.csv", csv.as_bytes()).await;
+    assert!(!report["data"]["committed"].as_bool().unwrap());
+    assert_eq!(report["data"]["total_rows"], 5);
+    let errors = report["data"]["errors"].as_array().unwrap();
+    let lines: Vec<i64> = errors.iter().map(|e| e["line"].as_i64().unwrap()).collect();
+    assert_eq!(lines, vec![4, 5, 6]);
+    assert_eq!(errors[0]["column"], "class");
+    assert_eq!(errors[1]["column"], "grade");
+    assert_eq!(errors[2]["column"], "status");
+
+    // 有错误时什么都不写入
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes?school_id={}", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let list = helpers::print_response_body_get_json(response, "list_after_failed_import").await;
+    assert_eq!(list["data"]["total"], 1);
+
+    let csv = "name,grade,class,status\n1年级1班,1,1,1\n1年级2班,1,2,\n";
+    let dry_run_path = format!("{}&dry_run=true", import_path);
+    let report = helpers::upload_file(&app, &token, &dry_run_path, "classes.csv", csv.as_bytes()).await;
+    assert!(!report["data"]["committed"].as_bool().unwrap());
+    assert_eq!(report["data"]["created"], 1);
+    assert_eq!(report["data"]["updated"], 1);
+    assert_eq!(report["data"]["rows"][0]["class_id"].as_i64().unwrap(), class_id);
+    assert!(report["data"]["rows"][1]["class_id"].is_null());
+
+    let report = helpers::upload_file(&app, &token, &import_path, "classes.csv", csv.as_bytes()).await;
+    assert!(report["data"]["committed"].as_bool().unwrap());
+    assert!(report["data"]["rows"][1]["class_id"].as_i64().is_some());
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "updated_class").await;
+    assert_eq!(updated["data"]["name"], "1年级1班");
+    assert_eq!(updated["data"]["status"], 1);
+    assert_eq!(updated["data"]["password"], "class123");
+
+    // 再导入一次只会更新, 不会产生重复班级
+    let report = helpers::upload_file(&app, &token, &import_path, "classes.csv", csv.as_bytes()).await;
+    assert_eq!(report["data"]["created"], 0);
+    assert_eq!(report["data"]["updated"], 2);
+
+    // 紧急管控期间导入也不能让班级进入放学中
+    let hold_path = format!("/api/admin/schools/{}/hold", school_id);
+    let held = helpers::send(&app, &token, "POST", &hold_path, Some(json!({"reason": "drill"}))).await;
+    assert!(held["success"].as_bool().unwrap());
+    let csv_dismissing = "name,grade,class,status\n1年级1班,1,1,2\n";
+    let report = helpers::upload_file(&app, &token, &import_path, "classes.csv", csv_dismissing.as_bytes()).await;
+    assert!(!report["success"].as_bool().unwrap());
+    let class = helpers::send(&app, &token, "GET", &format!("/api/admin/classes/{}", class_id), None).await;
+    assert_eq!(class["data"]["status"], 1);
+    let lifted = helpers::send(&app, &token, "POST", &format!("{}/lift", hold_path), Some(json!({}))).await;
+    assert!(lifted["success"].as_bool().unwrap());
+
+    let csv = "school_id,name,grade,class\n999999,missing,1,1\n";
+    let report = helpers::upload_file(&app, &token, "/api/admin/classes/import", "classes.csv", csv.as_bytes()).await;
+    assert_eq!(report["data"]["errors"][0]["column"], "school_id");
+    assert!(!report["data"]["committed"].as_bool().unwrap());
+
+    let report = helpers::upload_file(&app, &token, &import_path, "classes.txt", csv.as_bytes()).await;
+    assert!(!report["success"].as_bool().unwrap());
+}
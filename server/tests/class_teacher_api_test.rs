@@ -0,0 +1,114 @@
+use salvo::prelude::*;
+use salvo::test::TestClient;
+use school_manager_server::core::constants::{APP_BUSINESS_LOGIC, APP_FORBIDDEN};
+use serde_json::{json, Value};
+
+mod helpers;
+
+async fn invite(app: &Service, token: &str, class_id: i32, payload: Value, label: &str) -> Value {
+    let response = TestClient::post(helpers::get_url(&format!("/api/admin/classes/{}/teachers", class_id)))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&payload)
+        .send(app)
+        .await;
+    helpers::print_response_body_get_json(response, label).await
+}
+
+#[tokio::test]
+async fn head_teacher_manages_co_teachers_and_observers() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("class_owner"), "testpass123").await;
+    let owner_token = register["data"]["token"].as_str().unwrap().to_string();
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&owner_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("class_teacher_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_class_teachers").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+
+    let (head_token, head_id) = helpers::register_and_bind_school(&app, "class_head", school_id).await;
+    let (co_token, co_id) = helpers::register_and_bind_school(&app, "class_co", school_id).await;
+    let (observer_token, observer_id) = helpers::register_and_bind_school(&app, "class_observer", school_id).await;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "co_teacher_class", "grade": 2, "class": 1, "school_id": school_id, "status": 1, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class_for_class_teachers").await;
+    let class_id = class["data"]["id"].as_i64().unwrap() as i32;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class_as_head").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    // 只有班主任可以邀请
+    let denied = invite(&app, &co_token, class_id, json!({"user_id": co_id}), "invite_without_head").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+    let invalid = invite(&app, &head_token, class_id, json!({"user_id": co_id, "role": "head"}), "invite_as_head").await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    let invited = invite(&app, &head_token, class_id, json!({"user_id": co_id}), "invite_co_teacher").await;
+    assert_eq!(invited["data"]["role"], "co_teacher");
+    let invited = invite(&app, &head_token, class_id, json!({"user_id": observer_id, "role": "observer"}), "invite_observer").await;
+    assert_eq!(invited["data"]["role"], "observer");
+    let duplicate = invite(&app, &head_token, class_id, json!({"user_id": co_id}), "invite_twice").await;
+    assert_eq!(duplicate["code"].as_u64().unwrap(), APP_BUSINESS_LOGIC as u64);
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}/teachers", class_id)))
+        .add_header("Authorization", helpers::bearer(&co_token), true)
+        .send(&app)
+        .await;
+    let teachers = helpers::print_response_body_get_json(response, "class_teachers").await;
+    let teachers = teachers["data"].as_array().unwrap();
+    assert_eq!(teachers.len(), 3);
+    assert_eq!(teachers[0]["user_id"].as_i64().unwrap() as i32, head_id);
+    assert_eq!(teachers[0]["role"], "head");
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "class_with_teacher_roles").await;
+    let observer_info = class["data"]["teacher_infos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["user_id"].as_i64().unwrap() as i32 == observer_id)
+        .unwrap()
+        .clone();
+    assert_eq!(observer_info["role"], "observer");
+
+    // 协同教师可以修改状态, 观察者只读
+    let updated = helpers::update_status(&app, &co_token, class_id, 2, "status_as_co_teacher").await;
+    assert!(updated["success"].as_bool().unwrap());
+    let denied = helpers::update_status(&app, &observer_token, class_id, 0, "status_as_observer").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    let teacher_url = |user_id: i32| helpers::get_url(&format!("/api/admin/classes/{}/teachers/{}", class_id, user_id));
+    let response = TestClient::delete(teacher_url(head_id))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let denied = helpers::print_response_body_get_json(response, "remove_head").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_BUSINESS_LOGIC as u64);
+
+    let response = TestClient::delete(teacher_url(co_id))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let removed = helpers::print_response_body_get_json(response, "remove_co_teacher").await;
+    assert!(removed["success"].as_bool().unwrap());
+    let denied = helpers::update_status(&app, &co_token, class_id, 0, "status_after_removed").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+}
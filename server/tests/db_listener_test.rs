@@ -0,0 +1,104 @@
+use data_model::{classes, schools};
+use school_manager_server::apis::ws_api::ServerMessage;
+use school_manager_server::core::event_hub::{self, SchoolEvent};
+use school_manager_server::core::db_listener;
+use sea_orm::*;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+
+mod helpers;
+
+async fn wait_until_connected(previous_pid: Option<i32>) -> i32 {
+    for _ in 0..100 {
+        let health = db_listener::health();
+        if let Some(pid) = health.backend_pid.filter(|pid| health.connected && Some(*pid) != previous_pid) {
+            return pid;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("listener did not connect");
+}
+
+async fn set_status(db: &DatabaseConnection, class: &classes::Model, status: i32) {
+    let mut active: classes::ActiveModel = class.clone().into();
+    active.status = Set(status);
+    active.update(db).await.unwrap();
+}
+
+/// 等到大屏收到班级的新状态: 单条状态推送, 或者包含该状态的完整状态
+async fn wait_for_status(events: &mut Receiver<Arc<SchoolEvent>>, class_id: i32, status: i32) {
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match &event.envelope.message {
+            ServerMessage::StatusUpdate(p) if p.class_id == class_id && p.new_status == status => return,
+            ServerMessage::InitialState { classes, .. }
+                if classes.iter().any(|c| c.id == class_id && c.status == status) =>
+            {
+                return
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn listener_reconciles_updates_missed_while_disconnected() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+    tokio::spawn(db_listener::run_listener(state.clone()));
+    let pid = wait_until_connected(None).await;
+
+    let school = schools::ActiveModel {
+        name: Set(helpers::unique_name("listener_school")),
+        password: Set("pass123".to_string()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let class = classes::ActiveModel {
+        name: Set(helpers::unique_name("listener_class")),
+        grade: Set(1),
+        class: Set(1),
+        school_id: Set(school.id),
+        status: Set(0),
+        password: Set(String::new()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let (mut events, _) = event_hub::subscribe(school.id);
+
+    // 正常连接时通过 NOTIFY 推送
+    set_status(&state.db, &class, 1).await;
+    wait_for_status(&mut events, class.id, 1).await;
+
+    // 断开监听连接, 在重连之前修改状态, 这条通知不会被收到
+    sqlx::query("SELECT pg_terminate_backend($1)")
+        .bind(pid)
+        .execute(state.db.get_postgres_connection_pool())
+        .await
+        .unwrap();
+    for _ in 0..100 {
+        if !db_listener::health().connected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(!db_listener::health().connected);
+    set_status(&state.db, &class, 2).await;
+
+    // 重连后先推送完整状态, 补发的事件随后到达, 两者都能带上断线期间的变化
+    wait_for_status(&mut events, class.id, 2).await;
+
+    wait_until_connected(Some(pid)).await;
+    let health = db_listener::health();
+    assert!(health.reconnect_count >= 1);
+    assert!(health.dispatched_count >= 2);
+    assert!(health.last_event_at.is_some());
+}
@@ -0,0 +1,127 @@
+use chrono::{Datelike, Local};
+use salvo::prelude::*;
+use salvo::test::{ResponseExt, TestClient};
+use serde_json::json;
+
+mod helpers;
+
+async fn get_json(app: &Service, token: &str, path: &str) -> serde_json::Value {
+    let response = TestClient::get(helpers::get_url(path))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .send(app)
+        .await;
+    helpers::print_response_body_get_json(response, path).await
+}
+
+#[tokio::test]
+async fn dismissal_report_compares_schedule_with_actual_start() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("punctual");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("punctual_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    // 同一年级同一天重复的计划
+    let schedules_path = format!("/api/admin/schools/{}/dismissal-schedules", school_id);
+    let weekday = Local::now().weekday().number_from_monday();
+    let response = TestClient::put(helpers::get_url(&schedules_path))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"items": [
+            {"grade": 4, "weekday": weekday, "dismiss_at": "00:00:00"},
+            {"grade": 4, "weekday": weekday, "dismiss_at": "16:00:00"}
+        ]}))
+        .send(&app)
+        .await;
+    let duplicate = helpers::print_response_body_get_json(response, "duplicate_schedule").await;
+    assert!(!duplicate["success"].as_bool().unwrap());
+
+    // 今天 0 点计划放学, 实际开始一定晚于计划
+    let response = TestClient::put(helpers::get_url(&schedules_path))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"items": [
+            {"grade": 4, "weekday": weekday, "dismiss_at": "00:00:00"},
+            {"grade": null, "weekday": weekday, "dismiss_at": "23:59:59"}
+        ]}))
+        .send(&app)
+        .await;
+    let schedules = helpers::print_response_body_get_json(response, "replace_schedules").await;
+    assert_eq!(schedules["data"].as_array().unwrap().len(), 2);
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "4年级1班", "grade": 4, "class": 1, "school_id": school_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+    for status in [1, 2, 0] {
+        let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_id)))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"status": status}))
+            .send(&app)
+            .await;
+        let updated = helpers::print_response_body_get_json(response, "update_status").await;
+        assert!(updated["success"].as_bool().unwrap());
+    }
+
+    let report = get_json(&app, &token, "/api/admin/reports/dismissals/class?late_minutes=0").await;
+    let groups = report["data"]["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0]["class_id"].as_i64().unwrap(), class_id);
+    assert_eq!(groups[0]["dismissals"], 1);
+    assert_eq!(groups[0]["late"], 1);
+    assert!(groups[0]["avg_duration_seconds"].as_f64().is_some());
+    assert_eq!(groups[0]["weeks"].as_array().unwrap().len(), 1);
+
+    let report = get_json(&app, &token, "/api/admin/reports/dismissals/teacher?late_minutes=0").await;
+    assert_eq!(report["data"]["groups"][0]["teacher_name"], username.as_str());
+
+    let events = get_json(&app, &token, "/api/admin/reports/dismissals/events?late_minutes=0&outliers_only=true").await;
+    let events = events["data"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["punctuality"], "late");
+    assert!(events[0]["delay_seconds"].as_i64().unwrap() > 0);
+
+    let mut response = TestClient::get(helpers::get_url("/api/admin/reports/dismissals/grade?format=csv"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let text = response.take_string().await.unwrap();
+    let mut reader = csv::Reader::from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    assert_eq!(&reader.headers().unwrap()[0], "school_id");
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(&rows[0][3], "4");
+
+    let invalid = get_json(&app, &token, "/api/admin/reports/dismissals/room").await;
+    assert!(!invalid["success"].as_bool().unwrap());
+}
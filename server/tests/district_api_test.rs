@@ -0,0 +1,106 @@
+use data_model::{user_roles, users};
+use school_manager_server::core::router;
+use sea_orm::*;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn district_admins_see_stats_and_boards_across_their_schools() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+    let app = router::create_router(state.clone());
+
+    // 直接在数据库里授予管理员角色, 重新登录拿到带角色的 token
+    let admin_name = helpers::unique_name("district_admin");
+    helpers::register_user(&app, &admin_name, "testpass123").await;
+    let admin = users::Entity::find()
+        .filter(users::Column::Username.eq(admin_name.as_str()))
+        .one(&state.db)
+        .await
+        .unwrap()
+        .unwrap();
+    user_roles::ActiveModel {
+        user_id: Set(admin.id),
+        role_id: Set(1),
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let login = helpers::login_user(&app, &admin_name, "testpass123", "login_admin").await;
+    let token = login["data"]["token"].as_str().unwrap().to_string();
+
+    let register = helpers::register_user(&app, &helpers::unique_name("district_user"), "testpass123").await;
+    let user_token = register["data"]["token"].as_str().unwrap().to_string();
+    let me = helpers::send(&app, &user_token, "GET", "/api/admin/me", None).await;
+    let user_id = me["data"]["id"].as_i64().unwrap();
+
+    let denied = helpers::send(&app, &user_token, "POST", "/api/admin/districts", Some(json!({"name": "denied"}))).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let district = helpers::send(&app, &token, "POST", "/api/admin/districts", Some(json!({"name": helpers::unique_name("district")}))).await;
+    let district_id = district["data"]["id"].as_i64().unwrap();
+
+    let mut school_ids = vec![];
+    for district in [Some(district_id), Some(district_id), None] {
+        let body = json!({"name": helpers::unique_name("district_school"), "password": "school123", "district_id": district});
+        let school = helpers::send(&app, &token, "POST", "/api/admin/schools", Some(body)).await;
+        school_ids.push(school["data"]["id"].as_i64().unwrap());
+    }
+    let outside_id = school_ids[2];
+
+    let all = helpers::send(&app, &token, "GET", &format!("/api/schools/all?district_id={}", district_id), None).await;
+    let all = all["data"].as_array().unwrap();
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().all(|s| s["district_id"].as_i64() == Some(district_id)));
+
+    // 校区只能用在同一学校的班级上
+    let campus = helpers::send(&app, &token, "POST", &format!("/api/admin/schools/{}/campuses", school_ids[0]), Some(json!({"name": "东校区"}))).await;
+    let campus_id = campus["data"]["id"].as_i64().unwrap();
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": school_ids[1], "campus_id": campus_id});
+    let rejected = helpers::send(&app, &token, "POST", "/api/admin/classes", Some(body)).await;
+    assert!(!rejected["success"].as_bool().unwrap());
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": school_ids[0], "campus_id": campus_id});
+    let class = helpers::send(&app, &token, "POST", "/api/admin/classes", Some(body)).await;
+    assert_eq!(class["data"]["campus_id"].as_i64(), Some(campus_id));
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": outside_id});
+    helpers::send(&app, &token, "POST", "/api/admin/classes", Some(body)).await;
+    let classes = helpers::send(&app, &token, "GET", &format!("/api/admin/classes?campus_id={}", campus_id), None).await;
+    assert_eq!(classes["data"]["total"], 1);
+
+    // 学区管理员不是学校成员也能管理学区内的学校
+    let denied = helpers::send(&app, &user_token, "GET", &format!("/api/admin/districts/{}/board", district_id), None).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let added = helpers::send(&app, &token, "POST", &format!("/api/admin/districts/{}/admins", district_id), Some(json!({"user_id": user_id}))).await;
+    assert!(added["success"].as_bool().unwrap());
+
+    let board = helpers::send(&app, &user_token, "GET", &format!("/api/admin/districts/{}/board", district_id), None).await;
+    let board = &board["data"];
+    assert_eq!(board["schools"].as_array().unwrap().len(), 2);
+    assert_eq!(board["classes"], 1);
+    assert_eq!(board["dismissed"], 1);
+    let stats = helpers::send(&app, &user_token, "GET", &format!("/api/admin/districts/{}/stats", district_id), None).await;
+    assert_eq!(stats["data"]["district_id"].as_i64(), Some(district_id));
+    assert_eq!(stats["data"]["schools"], 2);
+    assert_eq!(stats["data"]["classes"], 1);
+
+    let campuses = helpers::send(&app, &user_token, "GET", &format!("/api/admin/schools/{}/campuses", school_ids[0]), None).await;
+    assert_eq!(campuses["data"][0]["class_count"], 1);
+    let denied = helpers::send(&app, &user_token, "GET", &format!("/api/admin/schools/{}/campuses", outside_id), None).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let districts = helpers::send(&app, &user_token, "GET", "/api/admin/districts", None).await;
+    assert_eq!(districts["data"]["total"], 1);
+
+    // 学区管理员不能把学区外的学校拉进自己的学区
+    let body = json!({"district_id": district_id});
+    let denied = helpers::send(&app, &user_token, "PUT", &format!("/api/admin/schools/{}", outside_id), Some(body)).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let body = json!({"name": helpers::unique_name("district_school"), "password": "school123", "district_id": 0});
+    let rejected = helpers::send(&app, &token, "POST", "/api/admin/schools", Some(body)).await;
+    assert!(!rejected["success"].as_bool().unwrap());
+
+    // 删除学区后学校移出学区
+    let deleted = helpers::send(&app, &token, "DELETE", &format!("/api/admin/districts/{}", district_id), None).await;
+    assert!(deleted["success"].as_bool().unwrap());
+    let school = helpers::send(&app, &token, "GET", &format!("/api/admin/schools/{}", school_ids[0]), None).await;
+    assert!(school["data"]["district_id"].is_null());
+}
@@ -0,0 +1,156 @@
+use salvo::prelude::*;
+use salvo::test::{ResponseExt, TestClient};
+use serde_json::{json, Value};
+
+mod helpers;
+
+async fn export(app: &Service, token: &str, path: &str) -> (Option<String>, Vec<u8>) {
+    let mut response = TestClient::get(helpers::get_url(path))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .send(app)
+        .await;
+    let disposition = response
+        .headers()
+        .get("content-disposition")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = response.take_bytes(None).await.unwrap().to_vec();
+    (disposition, body)
+}
+
+#[tokio::test]
+async fn export_classes_users_and_status_history() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("export");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("export_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "导出,班级", "grade": 2, "class": 3, "school_id": school_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+    for status in [1, 2, 0] {
+        let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_id)))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"status": status}))
+            .send(&app)
+            .await;
+        let updated = helpers::print_response_body_get_json(response, "update_status").await;
+        assert!(updated["success"].as_bool().unwrap());
+    }
+
+    let (disposition, body) = export(&app, &token, &format!("/api/admin/export/classes?school_id={}", school_id)).await;
+    assert!(disposition.unwrap().contains("classes_"));
+    let text = String::from_utf8(body).unwrap();
+    let mut reader = csv::Reader::from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    assert_eq!(&headers[0], "id");
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(&rows[0][1], "导出,班级");
+    assert_eq!(&rows[0][8], format!("{}(head)", username));
+
+    let (_, body) = export(&app, &token, &format!("/api/admin/export/users?username={}&format=jsonl", username)).await;
+    let users: Vec<Value> = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["username"], username.as_str());
+    assert_eq!(users[0]["classes"], "导出,班级(head)");
+
+    let (_, body) = export(
+        &app,
+        &token,
+        &format!("/api/admin/export/status-history?school_id={}&format=jsonl", school_id),
+    )
+    .await;
+    let history: Vec<Value> = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let transitions: Vec<(i64, i64)> = history
+        .iter()
+        .map(|h| (h["old_status"].as_i64().unwrap(), h["new_status"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(transitions, vec![(0, 1), (1, 2), (2, 0)]);
+    assert_eq!(history[0]["actor_name"], username.as_str());
+
+    // 日期范围在今天之前时没有记录
+    let (_, body) = export(
+        &app,
+        &token,
+        &format!(
+            "/api/admin/export/status-history?school_id={}&format=jsonl&from=2000-01-01&to=2000-01-31",
+            school_id
+        ),
+    )
+    .await;
+    assert!(body.is_empty());
+
+    let (disposition, body) = export(&app, &token, &format!("/api/admin/export/classes?school_id={}&format=xlsx", school_id)).await;
+    assert!(disposition.unwrap().ends_with(".xlsx\""));
+    assert!(body.starts_with(b"PK"));
+
+    let response = TestClient::get(helpers::get_url("/api/admin/export/classes?format=pdf"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let invalid = helpers::print_response_body_get_json(response, "invalid_format").await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    // 不传学校时只导出当前学校的数据
+    let (_, body) = export(&app, &token, "/api/admin/export/users?format=jsonl").await;
+    let users: Vec<Value> = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["username"], username.as_str());
+
+    // 其他学校的教师不能导出这个学校
+    let other = helpers::register_user(&app, &helpers::unique_name("export_other"), "testpass123").await;
+    let other_token = other["data"]["token"].as_str().unwrap().to_string();
+    for path in ["classes", "users", "status-history"] {
+        let (_, body) = export(&app, &other_token, &format!("/api/admin/export/{}?school_id={}", path, school_id)).await;
+        let denied: Value = serde_json::from_slice(&body).unwrap();
+        assert!(!denied["success"].as_bool().unwrap());
+    }
+    let (_, body) = export(&app, &other_token, &format!("/api/admin/export/schools?id={}", school_id)).await;
+    let denied: Value = serde_json::from_slice(&body).unwrap();
+    assert!(!denied["success"].as_bool().unwrap());
+}
@@ -16,6 +16,11 @@ static LOG_ONCE: Once = Once::new();
 // static LOG_GUARD: OnceCell<WorkerGuard> = OnceCell::new();
 
 pub async fn create_test_app() -> Service {
+    router::create_router(create_test_state().await)
+}
+
+#[allow(dead_code)]
+pub async fn create_test_state() -> app::AppState {
     dotenvy::from_filename(".env.test").unwrap();
     LOG_ONCE.call_once(|| { let _ = app::init_log(); });
     let app_state = app::init_app()
@@ -27,8 +32,7 @@ pub async fn create_test_app() -> Service {
     println!("init database");
     sqlx::migrate!("./migrations").run(&pool).await.unwrap();
     println!("init database success");
-    let app = router::create_router(app_state);
-    app
+    app_state
 }
 
 pub async fn db_lock() -> MutexGuard<'static, ()> {
@@ -103,7 +107,8 @@ pub fn bearer(token: &str) -> String {
 
 #[allow(dead_code)]
 pub async fn register_user(app: &Service, username: &str, password: &str) -> Value {
-    let payload = json!({"username": username, "password": password});
+    // 填上真实姓名, 教师才能修改班级状态
+    let payload = json!({"username": username, "password": password, "real_name": username});
     let response = TestClient::post(get_url("/api/register"))
         .add_header("content-type", "application/json", true)
         .json(&payload)
@@ -122,3 +127,107 @@ pub async fn login_user(app: &Service, username: &str, password: &str, label: &s
         .await;
     print_response_body_get_json(response, label).await
 }
+
+/// 注册一个教师并用学校密码绑定学校, 返回 token 和用户 id
+#[allow(dead_code)]
+pub async fn register_and_bind_school(app: &Service, prefix: &str, school_id: i32) -> (String, i32) {
+    let register = register_user(app, &unique_name(prefix), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+    let body = json!({"school_id": school_id, "password": "school123"});
+    let bound = send(app, &token, "POST", "/api/admin/bind/school", Some(body)).await;
+    assert!(bound["success"].as_bool().unwrap());
+    let me = send(app, &token, "GET", "/api/admin/me", None).await;
+    (token, me["data"]["id"].as_i64().unwrap() as i32)
+}
+
+#[allow(dead_code)]
+pub async fn update_status(app: &Service, token: &str, class_id: i32, status: i32, label: &str) -> Value {
+    let response = TestClient::put(get_url(&format!("/api/admin/classes/{}/status", class_id)))
+        .add_header("Authorization", bearer(token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": status}))
+        .send(app)
+        .await;
+    print_response_body_get_json(response, label).await
+}
+
+/// 带 token 发一个请求, 有 body 时按 JSON 发送
+#[allow(dead_code)]
+pub async fn send(app: &Service, token: &str, method: &str, path: &str, body: Option<Value>) -> Value {
+    send_in_school(app, token, method, path, None, body).await
+}
+
+/// 同 `send`, 可以用 X-School-Id 指定当前学校
+#[allow(dead_code)]
+pub async fn send_in_school(
+    app: &Service,
+    token: &str,
+    method: &str,
+    path: &str,
+    school_id: Option<i64>,
+    body: Option<Value>,
+) -> Value {
+    let url = get_url(path);
+    let mut client = match method {
+        "POST" => TestClient::post(url),
+        "PUT" => TestClient::put(url),
+        "DELETE" => TestClient::delete(url),
+        _ => TestClient::get(url),
+    }
+    .add_header("Authorization", bearer(token), true);
+    if let Some(school_id) = school_id {
+        client = client.add_header("X-School-Id", school_id.to_string(), true);
+    }
+    if let Some(body) = body {
+        client = client.add_header("content-type", "application/json", true).json(&body);
+    }
+    let response = client.send(app).await;
+    print_response_body_get_json(response, &format!("{} {}", method, path)).await
+}
+
+/// 在随机端口上启动真实服务, WebSocket 和 SSE 这类长连接无法用 TestClient 测试
+#[allow(dead_code)]
+pub async fn serve_test_app() -> std::net::SocketAddr {
+    let acceptor = TcpListener::new("127.0.0.1:0").bind().await;
+    let addr = acceptor.local_addr().unwrap();
+    let app = create_test_app().await;
+    tokio::spawn(Server::new(acceptor).serve(app));
+    addr
+}
+
+/// 以 multipart/form-data 上传文件, 字段名为 file
+#[allow(dead_code)]
+pub async fn upload_file(app: &Service, token: &str, path: &str, file_name: &str, content: &[u8]) -> Value {
+    let boundary = unique_name("boundary");
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+        boundary, file_name
+    )
+    .into_bytes();
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    let response = TestClient::post(get_url(path))
+        .add_header("Authorization", bearer(token), true)
+        .add_header("content-type", format!("multipart/form-data; boundary={}", boundary), true)
+        .body(body)
+        .send(app)
+        .await;
+    print_response_body_get_json(response, file_name).await
+}
+
+/// 先请求删除预览拿到确认令牌, 再带上令牌执行 DELETE
+#[allow(dead_code)]
+pub async fn delete_confirmed(app: &Service, token: &str, path: &str, label: &str) -> Value {
+    let response = TestClient::get(get_url(&format!("{}/delete-preview", path)))
+        .add_header("Authorization", bearer(token), true)
+        .send(app)
+        .await;
+    let preview = print_response_body_get_json(response, &format!("{}_preview", label)).await;
+    let confirm_token = preview["data"]["confirm_token"].as_str().unwrap_or_default().to_string();
+    let response = TestClient::delete(get_url(path))
+        .add_header("Authorization", bearer(token), true)
+        .add_header("X-Confirm-Token", confirm_token, true)
+        .send(app)
+        .await;
+    print_response_body_get_json(response, label).await
+}
@@ -0,0 +1,75 @@
+use data_model::{classes, outbox_events, schools};
+use school_manager_server::apis::ws_api::ServerMessage;
+use school_manager_server::core::{event_hub, outbox};
+use sea_orm::*;
+
+mod helpers;
+
+async fn school_events(db: &DatabaseConnection, school_id: i32) -> Vec<outbox_events::Model> {
+    outbox_events::Entity::find()
+        .filter(outbox_events::Column::SchoolId.eq(school_id))
+        .order_by_asc(outbox_events::Column::Id)
+        .all(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn outbox_records_changes_and_dispatches_status_events() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+
+    let school = schools::ActiveModel {
+        name: Set(helpers::unique_name("outbox_school")),
+        password: Set("secret".to_string()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let class = classes::ActiveModel {
+        name: Set(helpers::unique_name("outbox_class")),
+        grade: Set(2),
+        class: Set(3),
+        school_id: Set(school.id),
+        status: Set(0),
+        password: Set("secret".to_string()),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+
+    // 没有实际变化的更新不产生事件
+    let active: classes::ActiveModel = class.clone().into();
+    active.reset_all().update(&state.db).await.unwrap();
+
+    let mut active: classes::ActiveModel = class.clone().into();
+    active.status = Set(2);
+    active.update(&state.db).await.unwrap();
+
+    let events = school_events(&state.db, school.id).await;
+    let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(types, vec!["school.created", "class.created", "class.status_changed"]);
+    let status_event = &events[2];
+    assert_eq!(status_event.aggregate_id, class.id);
+    assert_eq!(status_event.payload["status"], 2);
+    assert_eq!(status_event.payload["old_status"], 0);
+    assert!(status_event.payload.get("password").is_none());
+    assert!(!status_event.idempotency_key.is_empty());
+    assert!(events.iter().all(|e| e.processed_at.is_none()));
+
+    let (mut hub, _) = event_hub::subscribe(school.id);
+    let dispatched = outbox::dispatch_pending(&state).await.unwrap();
+    assert!(dispatched >= 3);
+    // 分发只标记处理并发出通知, 每个实例收到通知后再推给自己的大屏
+    assert!(hub.try_recv().is_err());
+    let ids = school_events(&state.db, school.id).await.iter().map(|e| e.id).collect();
+    outbox::publish_events(&state, ids).await.unwrap();
+    let event = hub.try_recv().unwrap();
+    assert!(matches!(&event.envelope.message, ServerMessage::StatusUpdate(p) if p.class_id == class.id && p.new_status == 2));
+
+    let events = school_events(&state.db, school.id).await;
+    assert!(events.iter().all(|e| e.processed_at.is_some() && e.attempts == 1));
+    assert_eq!(outbox::dispatch_pending(&state).await.unwrap(), 0);
+}
@@ -1,40 +1,116 @@
-use salvo::test::TestClient;
-use serde_json::json;
-
-mod helpers;
-
-#[tokio::test]
-async fn school_crud_flow() {
-    let _guard = helpers::db_lock().await;
-    let app = helpers::create_test_app().await;
-    let username = helpers::unique_name("school");
-    let register = helpers::register_user(&app, &username, "testpass123").await;
-    let token = register["data"]["token"].as_str().unwrap().to_string();
-
-    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .add_header("content-type", "application/json", true)
-        .json(&json!({"name": helpers::unique_name("school_name")}))
-        .send(&app)
-        .await;
-    let created = helpers::print_response_body_get_json(response, "create_school").await;
-    assert!(created["success"].as_bool().unwrap());
-    let school_id = created["data"]["id"].as_i64().unwrap() as i32;
-
-    let response = TestClient::put(helpers::get_url(&format!("/api/admin/schools/{}", school_id)))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .add_header("content-type", "application/json", true)
-        .json(&json!({"name": "updated"}))
-        .send(&app)
-        .await;
-    let updated = helpers::print_response_body_get_json(response, "update_school").await;
-    assert_eq!(updated["data"]["name"].as_str().unwrap(), "updated");
-
-    let response = TestClient::delete(helpers::get_url(&format!("/api/admin/schools/{}", school_id)))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .send(&app)
-        .await;
-    let deleted = helpers::print_response_body_get_json(response, "delete_school").await;
-    assert!(deleted["success"].as_bool().unwrap());
-}
-
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn school_crud_flow() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("school");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("school_name"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let created = helpers::print_response_body_get_json(response, "create_school").await;
+    assert!(created["success"].as_bool().unwrap());
+    let school_id = created["data"]["id"].as_i64().unwrap() as i32;
+
+    // 只有学校成员能修改学校
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/schools/{}", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "updated"}))
+        .send(&app)
+        .await;
+    let updated = helpers::print_response_body_get_json(response, "update_school").await;
+    assert_eq!(updated["data"]["name"].as_str().unwrap(), "updated");
+
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/schools/{}", school_id), "delete_school").await;
+    assert!(deleted["success"].as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn school_delete_requires_preview_token() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("school_del"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("school_del"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap();
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "bind_school").await;
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": school_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+
+    let school_url = helpers::get_url(&format!("/api/admin/schools/{}", school_id));
+    let delete = |confirm_token: Option<&str>| {
+        let mut client = TestClient::delete(school_url.clone()).add_header("Authorization", helpers::bearer(&token), true);
+        if let Some(confirm_token) = confirm_token {
+            client = client.add_header("X-Confirm-Token", confirm_token.to_string(), true);
+        }
+        client.send(&app)
+    };
+
+    let denied = helpers::print_response_body_get_json(delete(None).await, "delete_without_token").await;
+    assert!(!denied["success"].as_bool().unwrap());
+    assert!(denied["message"].as_str().unwrap().contains("CONFIRMATION_REQUIRED"));
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/schools/{}/delete-preview", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let preview = helpers::print_response_body_get_json(response, "school_delete_preview").await;
+    let impact = &preview["data"]["impact"];
+    assert_eq!(impact["classes"], 1);
+    assert_eq!(impact["users"], 1);
+    let confirm_token = preview["data"]["confirm_token"].as_str().unwrap().to_string();
+
+    // 班级的令牌不能用来删除学校
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/classes/{}/delete-preview", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let class_preview = helpers::print_response_body_get_json(response, "class_delete_preview").await;
+    let class_token = class_preview["data"]["confirm_token"].as_str().unwrap().to_string();
+    let denied = helpers::print_response_body_get_json(delete(Some(&class_token)).await, "delete_with_class_token").await;
+    assert!(denied["message"].as_str().unwrap().contains("INVALID_CONFIRMATION"));
+
+    let deleted = helpers::print_response_body_get_json(delete(Some(&confirm_token)).await, "delete_with_token").await;
+    assert!(deleted["success"].as_bool().unwrap());
+    let reused = helpers::print_response_body_get_json(delete(Some(&confirm_token)).await, "delete_reused_token").await;
+    assert!(!reused["success"].as_bool().unwrap());
+}
+
@@ -0,0 +1,113 @@
+use salvo::test::TestClient;
+use school_manager_server::core::constants::APP_BUSINESS_LOGIC;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn school_hold_blocks_dismissal() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("hold");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("hold_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_hold").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "hold_class", "grade": 1, "class": 1, "school_id": school_id, "status": 1, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class_for_hold").await;
+    let class_id = class["data"]["id"].as_i64().unwrap() as i32;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url(&format!("/api/admin/schools/{}/hold", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"reason": "storm warning"}))
+        .send(&app)
+        .await;
+    let held = helpers::print_response_body_get_json(response, "hold_school").await;
+    assert!(held["data"]["on_hold"].as_bool().unwrap());
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/schools/{}/hold", school_id)))
+        .send(&app)
+        .await;
+    let hold_state = helpers::print_response_body_get_json(response, "hold_state").await;
+    assert!(hold_state["data"]["on_hold"].as_bool().unwrap());
+    assert_eq!(hold_state["data"]["reason"].as_str().unwrap(), "storm warning");
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 2}))
+        .send(&app)
+        .await;
+    let blocked = helpers::print_response_body_get_json(response, "dismiss_while_on_hold").await;
+    assert_eq!(blocked["code"].as_u64().unwrap(), APP_BUSINESS_LOGIC as u64);
+
+    // 新建班级也不能直接处于放学中
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "hold_class_2", "grade": 1, "class": 2, "school_id": school_id, "status": 2}))
+        .send(&app)
+        .await;
+    let blocked = helpers::print_response_body_get_json(response, "create_dismissing_while_on_hold").await;
+    assert_eq!(blocked["code"].as_u64().unwrap(), APP_BUSINESS_LOGIC as u64);
+
+    let response = TestClient::post(helpers::get_url(&format!("/api/admin/schools/{}/hold/lift", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"reason": "all clear"}))
+        .send(&app)
+        .await;
+    let lifted = helpers::print_response_body_get_json(response, "lift_hold").await;
+    assert!(!lifted["data"]["on_hold"].as_bool().unwrap());
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 2}))
+        .send(&app)
+        .await;
+    let dismissed = helpers::print_response_body_get_json(response, "dismiss_after_lift").await;
+    assert!(dismissed["success"].as_bool().unwrap());
+
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/schools/{}/holds", school_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let logs = helpers::print_response_body_get_json(response, "hold_logs").await;
+    let logs = logs["data"]["list"].as_array().unwrap();
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["action"].as_str().unwrap(), "lift");
+    assert_eq!(logs[1]["action"].as_str().unwrap(), "hold");
+}
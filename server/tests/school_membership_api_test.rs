@@ -0,0 +1,117 @@
+use data_model::{school_memberships, users};
+use salvo::prelude::*;
+use school_manager_server::core::router;
+use sea_orm::*;
+use serde_json::json;
+
+mod helpers;
+
+async fn register(app: &Service, prefix: &str) -> (String, i64) {
+    let register = helpers::register_user(app, &helpers::unique_name(prefix), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+    let me = helpers::send_in_school(app, &token, "GET", "/api/admin/me", None, None).await;
+    (token, me["data"]["id"].as_i64().unwrap())
+}
+
+#[tokio::test]
+async fn teachers_can_belong_to_several_schools() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+    let app = router::create_router(state.clone());
+    let (token, user_id) = register(&app, "member").await;
+
+    let mut school_ids = vec![];
+    for _ in 0..2 {
+        let body = json!({"name": helpers::unique_name("member_school"), "password": "school123"});
+        let school = helpers::send_in_school(&app, &token, "POST", "/api/admin/schools", None, Some(body)).await;
+        let school_id = school["data"]["id"].as_i64().unwrap();
+        let body = json!({"school_id": school_id, "password": "school123"});
+        let bound = helpers::send_in_school(&app, &token, "POST", "/api/admin/bind/school", None, Some(body)).await;
+        assert!(bound["success"].as_bool().unwrap());
+        school_ids.push(school_id);
+    }
+    let (first_id, second_id) = (school_ids[0], school_ids[1]);
+
+    // 加入第二个学校不覆盖默认学校
+    let me = helpers::send_in_school(&app, &token, "GET", "/api/admin/me", None, None).await;
+    assert_eq!(me["data"]["school_id"].as_i64().unwrap(), first_id);
+    let memberships = me["data"]["memberships"].as_array().unwrap();
+    assert_eq!(memberships.len(), 2);
+    assert!(memberships.iter().all(|m| m["role"] == "teacher" && m["status"] == "active"));
+
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": second_id, "password": "class123"});
+    let class = helpers::send_in_school(&app, &token, "POST", "/api/admin/classes", None, Some(body)).await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+    let body = json!({"class_id": class_id, "password": "class123"});
+    let bound = helpers::send_in_school(&app, &token, "POST", "/api/admin/bind/class", None, Some(body)).await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    // 成员关系停用期间原来的值班安排不能修改班级状态
+    let set_membership_status = |status: &'static str| {
+        school_memberships::Entity::update_many()
+            .col_expr(school_memberships::Column::Status, sea_orm::sea_query::Expr::value(status))
+            .filter(school_memberships::Column::UserId.eq(user_id as i32))
+            .filter(school_memberships::Column::SchoolId.eq(second_id as i32))
+            .exec(&state.db)
+    };
+    let status_url = format!("/api/admin/classes/{}/status", class_id);
+    set_membership_status("suspended").await.unwrap();
+    let denied = helpers::send_in_school(&app, &token, "PUT", &status_url, None, Some(json!({"status": 1}))).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    set_membership_status("active").await.unwrap();
+    let updated = helpers::send_in_school(&app, &token, "PUT", &status_url, None, Some(json!({"status": 1}))).await;
+    assert!(updated["success"].as_bool().unwrap());
+
+    // X-School-Id 头切换统计的学校
+    let stats = helpers::send_in_school(&app, &token, "GET", "/api/admin/stats", None, None).await;
+    assert_eq!(stats["data"]["school_id"].as_i64().unwrap(), first_id);
+    let stats = helpers::send_in_school(&app, &token, "GET", "/api/admin/stats", Some(second_id), None).await;
+    assert_eq!(stats["data"]["school_id"].as_i64().unwrap(), second_id);
+    assert_eq!(stats["data"]["teachers"], 1);
+
+    // 令牌里的当前学校
+    let switched = helpers::send_in_school(&app, &token, "POST", "/api/admin/me/school", None, Some(json!({"school_id": second_id}))).await;
+    let school_token = switched["data"]["token"].as_str().unwrap().to_string();
+    let stats = helpers::send_in_school(&app, &school_token, "GET", "/api/admin/stats", None, None).await;
+    assert_eq!(stats["data"]["school_id"].as_i64().unwrap(), second_id);
+    let classes = helpers::send_in_school(&app, &school_token, "GET", "/api/admin/me/classes", None, None).await;
+    assert_eq!(classes["data"].as_array().unwrap().len(), 1);
+    let classes = helpers::send_in_school(&app, &token, "GET", "/api/admin/me/classes", Some(first_id), None).await;
+    assert!(classes["data"].as_array().unwrap().is_empty());
+
+    // 不是成员不能切换到该学校
+    let (other_token, other_id) = register(&app, "member_other").await;
+    let body = json!({"school_id": first_id, "password": "school123"});
+    helpers::send_in_school(&app, &other_token, "POST", "/api/admin/bind/school", None, Some(body)).await;
+    let denied = helpers::send_in_school(&app, &other_token, "POST", "/api/admin/me/school", None, Some(json!({"school_id": second_id}))).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let denied = helpers::send_in_school(&app, &other_token, "GET", "/api/admin/stats", Some(second_id), None).await;
+    assert!(!denied["success"].as_bool().unwrap());
+
+    // 普通教师不能管理成员, 学校管理员可以停用和移出
+    let member_url = format!("/api/admin/users/{}/schools/{}", other_id, first_id);
+    let suspend = json!({"status": "suspended"});
+    let denied = helpers::send_in_school(&app, &token, "PUT", &member_url, None, Some(suspend.clone())).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    school_memberships::Entity::update_many()
+        .col_expr(school_memberships::Column::Role, sea_orm::sea_query::Expr::value("manager"))
+        .filter(school_memberships::Column::UserId.eq(user_id as i32))
+        .filter(school_memberships::Column::SchoolId.eq(first_id as i32))
+        .exec(&state.db)
+        .await
+        .unwrap();
+    let suspended = helpers::send_in_school(&app, &token, "PUT", &member_url, None, Some(suspend)).await;
+    assert_eq!(suspended["data"]["status"], "suspended");
+    let denied = helpers::send_in_school(&app, &other_token, "GET", "/api/admin/stats", Some(first_id), None).await;
+    assert!(!denied["success"].as_bool().unwrap());
+    let body = json!({"school_id": first_id, "password": "school123"});
+    let rejoined = helpers::send_in_school(&app, &other_token, "POST", "/api/admin/bind/school", None, Some(body)).await;
+    assert!(rejoined["message"].as_str().unwrap().contains("MEMBERSHIP_SUSPENDED"));
+
+    let removed = helpers::send_in_school(&app, &token, "DELETE", &member_url, None, None).await;
+    assert!(removed["success"].as_bool().unwrap());
+    let other = users::Entity::find_by_id(other_id as i32).one(&state.db).await.unwrap().unwrap();
+    assert_eq!(other.school_id, None);
+    let me = helpers::send_in_school(&app, &other_token, "GET", "/api/admin/me", None, None).await;
+    assert!(me["data"]["memberships"].as_array().unwrap().is_empty());
+}
@@ -0,0 +1,170 @@
+use salvo::test::TestClient;
+use school_manager_server::apis::ws_api::ServerMessage;
+use school_manager_server::core::db_listener::NotificationPayload;
+use school_manager_server::core::event_hub;
+use serde_json::Value;
+use std::time::Duration;
+
+mod helpers;
+
+#[tokio::test]
+async fn sse_handler_rejects_zero_id() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+
+    let response = TestClient::get(helpers::get_url("/api/sse/school/0"))
+        .send(&app)
+        .await;
+
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn sse_handler_rejects_unknown_protocol() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+
+    let response = TestClient::get(helpers::get_url("/api/sse/school/1?protocol=99"))
+        .send(&app)
+        .await;
+
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn sse_handler_rejects_unknown_topic() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+
+    let response = TestClient::get(helpers::get_url("/api/sse/school/1?topics=status,weather"))
+        .send(&app)
+        .await;
+
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
+}
+
+fn status_update(school_id: i32, class_id: i32) -> ServerMessage {
+    ServerMessage::StatusUpdate(NotificationPayload {
+        school_id,
+        grade: 1,
+        class: 1,
+        class_id,
+        new_status: 2,
+        status_version: 1,
+    })
+}
+
+/// 一条 SSE 事件: (事件名, id, data)
+type Event = (String, Option<String>, String);
+
+struct EventReader {
+    response: reqwest::Response,
+    buffer: String,
+}
+
+impl EventReader {
+    async fn open(addr: std::net::SocketAddr, school_id: i32, query: &str, last_event_id: Option<u64>) -> Self {
+        let mut request = reqwest::Client::new().get(format!("http://{}/api/sse/school/{}{}", addr, school_id, query));
+        if let Some(id) = last_event_id {
+            request = request.header("Last-Event-ID", id.to_string());
+        }
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/event-stream"));
+        Self {
+            response,
+            buffer: String::new(),
+        }
+    }
+
+    async fn next(&mut self) -> Event {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                let mut event = (String::from("message"), None, String::new());
+                for line in block.lines() {
+                    if let Some(name) = line.strip_prefix("event:") {
+                        event.0 = name.trim().to_string();
+                    } else if let Some(id) = line.strip_prefix("id:") {
+                        event.1 = Some(id.trim().to_string());
+                    } else if let Some(data) = line.strip_prefix("data:") {
+                        event.2.push_str(data.trim());
+                    }
+                }
+                // 跳过保活注释
+                if event.1.is_some() || !event.2.is_empty() {
+                    return event;
+                }
+                continue;
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.response.chunk())
+                .await
+                .expect("no sse event")
+                .unwrap()
+                .expect("sse stream ended");
+            self.buffer.push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn sse_streams_events_and_resumes_from_last_event_id() {
+    let _guard = helpers::db_lock().await;
+    let addr = helpers::serve_test_app().await;
+    let school_id = 920_001;
+
+    let mut events = EventReader::open(addr, school_id, "?protocol=2&topics=status", None).await;
+    let (name, id, data) = events.next().await;
+    assert_eq!(name, "initial_state");
+    assert_eq!(id.as_deref(), Some("0"));
+    assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["type"], "initial_state");
+
+    // 没有订阅的主题不推送
+    event_hub::publish(school_id, ServerMessage::AnnouncementRemoved { id: 1 });
+    let first = event_hub::publish(school_id, status_update(school_id, 1));
+    let (name, id, data) = events.next().await;
+    assert_eq!(name, "status_update");
+    assert_eq!(id, Some(first.seq().to_string()));
+    let data: Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(data["version"], 2);
+    assert_eq!(data["payload"]["class_id"], 1);
+    drop(events);
+
+    // 断线期间发布的事件在重连时按 Last-Event-ID 补发, 不再推送 initial_state
+    let second = event_hub::publish(school_id, status_update(school_id, 2));
+    let third = event_hub::publish(school_id, status_update(school_id, 3));
+    let mut events = EventReader::open(addr, school_id, "?protocol=2", Some(first.seq())).await;
+    let (name, id, _) = events.next().await;
+    assert_eq!(name, "status_update");
+    assert_eq!(id, Some(second.seq().to_string()));
+    let (_, id, _) = events.next().await;
+    assert_eq!(id, Some(third.seq().to_string()));
+
+    let fourth = event_hub::publish(school_id, status_update(school_id, 4));
+    let (_, id, _) = events.next().await;
+    assert_eq!(id, Some(fourth.seq().to_string()));
+    drop(events);
+
+    // 补发不了时先推送完整状态
+    let mut events = EventReader::open(addr, school_id, "?protocol=2&last_event_id=9999", None).await;
+    let (name, id, _) = events.next().await;
+    assert_eq!(name, "initial_state");
+    assert_eq!(id, Some(fourth.seq().to_string()));
+}
+
+#[tokio::test]
+async fn sse_defaults_to_legacy_protocol() {
+    let _guard = helpers::db_lock().await;
+    let addr = helpers::serve_test_app().await;
+    let school_id = 920_002;
+
+    let mut events = EventReader::open(addr, school_id, "", None).await;
+    event_hub::publish(school_id, ServerMessage::AnnouncementRemoved { id: 1 });
+    let update = event_hub::publish(school_id, status_update(school_id, 5));
+    let (name, id, data) = events.next().await;
+    assert_eq!(name, "status_update");
+    assert_eq!(id, Some(update.seq().to_string()));
+    let data: Value = serde_json::from_str(&data).unwrap();
+    assert!(data.get("type").is_none());
+    assert_eq!(data["class_id"], 5);
+}
@@ -0,0 +1,109 @@
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn stats_are_scoped_to_the_callers_school() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("stats"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("stats_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let mut class_ids = vec![];
+    for class in [1, 2] {
+        let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"name": format!("1年级{}班", class), "grade": 1, "class": class, "school_id": school_id, "password": "class123"}))
+            .send(&app)
+            .await;
+        let created = helpers::print_response_body_get_json(response, "create_class").await;
+        class_ids.push(created["data"]["id"].as_i64().unwrap());
+    }
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_ids[0], "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+    for status in [1, 2, 0] {
+        let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}/status", class_ids[0])))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"status": status}))
+            .send(&app)
+            .await;
+        let updated = helpers::print_response_body_get_json(response, "update_status").await;
+        assert!(updated["success"].as_bool().unwrap());
+    }
+
+    let response = TestClient::get(helpers::get_url("/api/admin/stats"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let stats = helpers::print_response_body_get_json(response, "stats").await;
+    let data = &stats["data"];
+    assert_eq!(data["school_id"].as_i64().unwrap() as i32, school_id);
+    assert_eq!(data["schools"], 1);
+    assert_eq!(data["classes"], 2);
+    assert_eq!(data["teachers"], 1);
+    assert_eq!(data["unbound_classes"], 1);
+    assert_eq!(data["today_status_changes"], 3);
+    let durations = data["dismissal_durations"].as_array().unwrap();
+    assert_eq!(durations.len(), 1);
+    assert_eq!(durations[0]["samples"], 1);
+    assert!(durations[0]["avg_seconds"].as_f64().unwrap() >= 0.0);
+    let hourly = data["hourly_status_changes"].as_array().unwrap();
+    assert!(hourly.len() >= 23);
+    assert_eq!(hourly.iter().map(|h| h["count"].as_i64().unwrap()).sum::<i64>(), 3);
+
+    // 没有记录的日期范围
+    let response = TestClient::get(helpers::get_url("/api/admin/stats?from=2000-01-01&to=2000-01-02"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let past = helpers::print_response_body_get_json(response, "stats_past").await;
+    assert!(past["data"]["dismissal_durations"].as_array().unwrap().is_empty());
+    assert!(past["data"]["hourly_status_changes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|h| h["count"] == 0));
+
+    let response = TestClient::get(helpers::get_url("/api/admin/stats?from=2000-01-01&to=2000-03-01"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let too_long = helpers::print_response_body_get_json(response, "stats_too_long").await;
+    assert!(!too_long["success"].as_bool().unwrap());
+
+    // 其他学校的用户不能查看
+    let register = helpers::register_user(&app, &helpers::unique_name("stats_other"), "testpass123").await;
+    let other_token = register["data"]["token"].as_str().unwrap().to_string();
+    let response = TestClient::get(helpers::get_url(&format!("/api/admin/stats?school_id={}", school_id)))
+        .add_header("Authorization", helpers::bearer(&other_token), true)
+        .send(&app)
+        .await;
+    let forbidden = helpers::print_response_body_get_json(response, "stats_forbidden").await;
+    assert!(!forbidden["success"].as_bool().unwrap());
+}
@@ -0,0 +1,147 @@
+use chrono::{Duration, Utc};
+use salvo::prelude::*;
+use salvo::test::TestClient;
+use school_manager_server::core::constants::APP_FORBIDDEN;
+use serde_json::{json, Value};
+
+mod helpers;
+
+async fn post_assignment_action(app: &Service, token: &str, assignment_id: i64, action: &str) -> Value {
+    let response = TestClient::post(helpers::get_url(&format!("/api/admin/assignments/{}/{}", assignment_id, action)))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .send(app)
+        .await;
+    helpers::print_response_body_get_json(response, action).await
+}
+
+#[tokio::test]
+async fn substitute_request_flow_controls_status_updates() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("roster_admin"), "testpass123").await;
+    let owner_token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&owner_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("roster_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_roster").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+
+    let (head_token, head_id) = helpers::register_and_bind_school(&app, "roster_head", school_id).await;
+    let (sub_token, sub_id) = helpers::register_and_bind_school(&app, "roster_sub", school_id).await;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "roster_class", "grade": 1, "class": 1, "school_id": school_id, "status": 1, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class_for_roster").await;
+    let class_id = class["data"]["id"].as_i64().unwrap() as i32;
+
+    // 第一个绑定班级的教师成为班主任
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/class"))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_id": class_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_class").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let assignments_url = helpers::get_url(&format!("/api/admin/classes/{}/assignments", class_id));
+    let response = TestClient::get(format!("{}?active=true", assignments_url))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let on_duty = helpers::print_response_body_get_json(response, "on_duty").await;
+    assert_eq!(on_duty["data"]["total"].as_u64().unwrap(), 1);
+    assert_eq!(on_duty["data"]["list"][0]["user_id"].as_i64().unwrap() as i32, head_id);
+    assert_eq!(on_duty["data"]["list"][0]["role"], "head");
+
+    let denied = helpers::update_status(&app, &sub_token, class_id, 2, "status_without_assignment").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+    // 编辑班级时带上状态也要检查值班
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&sub_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 2}))
+        .send(&app)
+        .await;
+    let denied = helpers::print_response_body_get_json(response, "edit_status_without_assignment").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    // 代课教师不能给自己安排班主任
+    let response = TestClient::post(&assignments_url)
+        .add_header("Authorization", helpers::bearer(&sub_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"user_id": sub_id, "role": "head"}))
+        .send(&app)
+        .await;
+    let denied = helpers::print_response_body_get_json(response, "self_assign_head").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    // 班主任跟随班级绑定, 不能通过值班安排再加一个
+    let response = TestClient::post(&assignments_url)
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"user_id": sub_id, "role": "head"}))
+        .send(&app)
+        .await;
+    let rejected = helpers::print_response_body_get_json(response, "assign_second_head").await;
+    assert!(!rejected["success"].as_bool().unwrap());
+
+    let valid_to = Utc::now() + Duration::hours(2);
+    let response = TestClient::post(&assignments_url)
+        .add_header("Authorization", helpers::bearer(&sub_token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"user_id": sub_id, "role": "substitute", "valid_to": valid_to, "reason": "sick leave"}))
+        .send(&app)
+        .await;
+    let request = helpers::print_response_body_get_json(response, "request_substitute").await;
+    assert_eq!(request["data"]["status"], "pending");
+    assert!(!request["data"]["active"].as_bool().unwrap());
+    let assignment_id = request["data"]["id"].as_i64().unwrap();
+
+    let denied = helpers::update_status(&app, &sub_token, class_id, 2, "status_while_pending").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+    let denied = post_assignment_action(&app, &sub_token, assignment_id, "approve").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    let approved = post_assignment_action(&app, &head_token, assignment_id, "approve").await;
+    assert_eq!(approved["data"]["status"], "approved");
+    assert_eq!(approved["data"]["reviewed_by"].as_i64().unwrap() as i32, head_id);
+    assert!(approved["data"]["active"].as_bool().unwrap());
+
+    let updated = helpers::update_status(&app, &sub_token, class_id, 2, "status_as_substitute").await;
+    assert!(updated["success"].as_bool().unwrap());
+
+    // 班主任提前结束代课, 记录保留但不再有权限
+    let ended = post_assignment_action(&app, &head_token, assignment_id, "cancel").await;
+    assert_eq!(ended["data"]["status"], "approved");
+    assert!(!ended["data"]["active"].as_bool().unwrap());
+    let denied = helpers::update_status(&app, &sub_token, class_id, 0, "status_after_handover").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    // 解绑后长期安排也随之结束
+    let updated = helpers::update_status(&app, &head_token, class_id, 0, "status_as_head").await;
+    assert!(updated["success"].as_bool().unwrap());
+    let response = TestClient::delete(helpers::get_url(&format!("/api/admin/unbind/class/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let unbound = helpers::print_response_body_get_json(response, "unbind_class").await;
+    assert!(unbound["success"].as_bool().unwrap());
+    let denied = helpers::update_status(&app, &head_token, class_id, 1, "status_after_unbind").await;
+    assert_eq!(denied["code"].as_u64().unwrap(), APP_FORBIDDEN as u64);
+
+    let response = TestClient::get(&assignments_url)
+        .add_header("Authorization", helpers::bearer(&head_token), true)
+        .send(&app)
+        .await;
+    let history = helpers::print_response_body_get_json(response, "assignment_history").await;
+    assert_eq!(history["data"]["total"].as_u64().unwrap(), 2);
+}
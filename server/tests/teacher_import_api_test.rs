@@ -0,0 +1,119 @@
+use salvo::test::{ResponseExt, TestClient};
+use school_manager_server::core::constants::{APP_BUSINESS_LOGIC, TEACHER_ROLE_ID};
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn teacher_import_creates_accounts_with_credentials() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("onboard"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("onboard_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "3年级2班", "grade": 3, "class": 2, "school_id": school_id, "password": "class123"}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+
+    let import_path = format!("/api/admin/schools/{}/teachers/import", school_id);
+    let first = helpers::unique_name("teacher_a");
+    let second = helpers::unique_name("teacher_b");
+    let csv = format!(
+        "username,name,phone,classes\n{first},张老师,,3-2\n{second},李老师,,9-9\n{first},王老师,,\n,无名,,\n"
+    );
+    let report = helpers::upload_file(&app, &token, &import_path, "teachers.csv", csv.as_bytes()).await;
+    assert!(!report["data"]["committed"].as_bool().unwrap());
+    let errors = report["data"]["errors"].as_array().unwrap();
+    let columns: Vec<(i64, &str)> = errors
+        .iter()
+        .map(|e| (e["line"].as_i64().unwrap(), e["column"].as_str().unwrap()))
+        .collect();
+    assert_eq!(columns, vec![(3, "classes"), (4, "username"), (5, "username")]);
+
+    let csv = format!("username,name,phone,classes\n{first},张老师,,3-2\n");
+    let report = helpers::upload_file(&app, &token, &import_path, "teachers.csv", csv.as_bytes()).await;
+    assert!(report["data"]["committed"].as_bool().unwrap());
+    let row = &report["data"]["rows"][0];
+    let password = row["password"].as_str().unwrap();
+    assert!(row["invite_code"].is_null());
+
+    let login = helpers::login_user(&app, &first, password, "login_imported").await;
+    let teacher_token = login["data"]["token"].as_str().unwrap().to_string();
+    let response = TestClient::get(helpers::get_url("/api/admin/me"))
+        .add_header("Authorization", helpers::bearer(&teacher_token), true)
+        .send(&app)
+        .await;
+    let me = helpers::print_response_body_get_json(response, "imported_me").await;
+    assert_eq!(me["data"]["real_name"], "张老师");
+    assert_eq!(me["data"]["school_id"].as_i64().unwrap() as i32, school_id);
+    assert_eq!(me["data"]["class_infos"][0]["class_id"].as_i64().unwrap(), class_id);
+    assert_eq!(me["data"]["class_infos"][0]["role"], "head");
+    assert_eq!(me["data"]["role_infos"][0]["role_id"].as_i64().unwrap() as i32, TEACHER_ROLE_ID);
+
+    // 邀请码方式, 结果以 CSV 文件返回
+    let csv = format!("username,name,phone,classes\n{second},李老师,13800000000,3-2\n");
+    let mut response = TestClient::post(helpers::get_url(&format!(
+        "{}?credential=invite&format=csv",
+        import_path
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .add_header("content-type", "multipart/form-data; boundary=b0undary", true)
+    .body(format!(
+        "--b0undary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"teachers.csv\"\r\nContent-Type: text/csv\r\n\r\n{}\r\n--b0undary--\r\n",
+        csv
+    ))
+    .send(&app)
+    .await;
+    assert!(response
+        .headers()
+        .get("content-disposition")
+        .is_some_and(|v| v.to_str().unwrap().contains("attachment")));
+    let result = response.take_string().await.unwrap();
+    let mut lines = result.lines();
+    assert!(lines.next().unwrap().starts_with("line,username"));
+    let fields: Vec<&str> = lines.next().unwrap().split(',').collect();
+    assert_eq!(fields[1], second);
+    assert!(fields[5].is_empty());
+    let invite_code = fields[6].to_string();
+    assert!(!invite_code.is_empty());
+
+    let response = TestClient::post(helpers::get_url("/api/invite/accept"))
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"code": invite_code, "password": "newpass123"}))
+        .send(&app)
+        .await;
+    let accepted = helpers::print_response_body_get_json(response, "accept_invite").await;
+    assert!(accepted["data"]["token"].as_str().is_some());
+    let login = helpers::login_user(&app, &second, "newpass123", "login_invited").await;
+    assert!(login["data"]["token"].as_str().is_some());
+
+    let response = TestClient::post(helpers::get_url("/api/invite/accept"))
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"code": invite_code, "password": "otherpass123"}))
+        .send(&app)
+        .await;
+    let reused = helpers::print_response_body_get_json(response, "accept_invite_again").await;
+    assert_eq!(reused["code"].as_i64().unwrap(), APP_BUSINESS_LOGIC as i64);
+}
@@ -0,0 +1,123 @@
+use salvo::prelude::*;
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn soft_deleted_schools_and_classes_can_be_restored() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("trash"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("trash_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school").await;
+    let school_id = school["data"]["id"].as_i64().unwrap();
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    let bound = helpers::print_response_body_get_json(response, "bind_school").await;
+    assert!(bound["success"].as_bool().unwrap());
+
+    let mut class_ids = vec![];
+    for class in [1, 2] {
+        let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+            .add_header("Authorization", helpers::bearer(&token), true)
+            .add_header("content-type", "application/json", true)
+            .json(&json!({"name": format!("1年级{}班", class), "grade": 1, "class": class, "school_id": school_id, "password": "class123"}))
+            .send(&app)
+            .await;
+        let created = helpers::print_response_body_get_json(response, "create_class").await;
+        class_ids.push(created["data"]["id"].as_i64().unwrap());
+    }
+
+    // 先单独删除一个班级, 再删除学校
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/classes/{}", class_ids[1]), "delete_class").await;
+    assert!(deleted["success"].as_bool().unwrap());
+    // 已删除的班级不能再绑定
+    let me = helpers::send(&app, &token, "GET", "/api/admin/me", None).await;
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/users/{}", me["data"]["id"])))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"class_ids": [class_ids[1]]}))
+        .send(&app)
+        .await;
+    let rebound = helpers::print_response_body_get_json(response, "bind_deleted_class").await;
+    assert!(rebound["message"].as_str().unwrap().contains("classes"));
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/schools/{}", school_id), "delete_school").await;
+    assert!(deleted["success"].as_bool().unwrap());
+
+    let school = helpers::send(&app, &token, "GET", &format!("/api/admin/schools/{}", school_id), None).await;
+    assert!(!school["success"].as_bool().unwrap());
+    let all = helpers::send(&app, &token, "GET", "/api/schools/all", None).await;
+    assert!(all["data"].as_array().unwrap().iter().all(|s| s["id"].as_i64() != Some(school_id)));
+    let board = helpers::send(&app, &token, "GET", &format!("/api/classes/school/{}", school_id), None).await;
+    assert!(!board["success"].as_bool().unwrap());
+
+    let trash = helpers::send(&app, &token, "GET", "/api/admin/trash/classes", None).await;
+    assert_eq!(trash["data"]["total"], 2);
+    let trash = helpers::send(&app, &token, "GET", "/api/admin/trash/schools", None).await;
+    assert_eq!(trash["data"]["list"][0]["id"].as_i64().unwrap(), school_id);
+    let invalid = helpers::send(&app, &token, "GET", "/api/admin/trash/rooms", None).await;
+    assert!(!invalid["success"].as_bool().unwrap());
+
+    // 学校恢复前不能恢复班级
+    let restored = helpers::send(&app, &token, "POST", &format!("/api/admin/classes/{}/restore", class_ids[0]), None).await;
+    assert!(!restored["success"].as_bool().unwrap());
+
+    // 恢复学校时只恢复一起删除的班级
+    let restored = helpers::send(&app, &token, "POST", &format!("/api/admin/schools/{}/restore", school_id), None).await;
+    assert!(restored["success"].as_bool().unwrap());
+    let class = helpers::send(&app, &token, "GET", &format!("/api/admin/classes/{}", class_ids[0]), None).await;
+    assert!(class["success"].as_bool().unwrap());
+    let class = helpers::send(&app, &token, "GET", &format!("/api/admin/classes/{}", class_ids[1]), None).await;
+    assert!(!class["success"].as_bool().unwrap());
+
+    let restored = helpers::send(&app, &token, "POST", &format!("/api/admin/classes/{}/restore", class_ids[1]), None).await;
+    assert!(restored["success"].as_bool().unwrap());
+    let trash = helpers::send(&app, &token, "GET", "/api/admin/trash/classes", None).await;
+    assert_eq!(trash["data"]["total"], 0);
+    let restored = helpers::send(&app, &token, "POST", &format!("/api/admin/classes/{}/restore", class_ids[1]), None).await;
+    assert!(!restored["success"].as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn soft_deleted_users_cannot_log_in_until_restored() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let register = helpers::register_user(&app, &helpers::unique_name("trash_admin"), "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+    let username = helpers::unique_name("trash_user");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let user_token = register["data"]["token"].as_str().unwrap().to_string();
+    let me = helpers::send(&app, &user_token, "GET", "/api/admin/me", None).await;
+    let user_id = me["data"]["id"].as_i64().unwrap();
+
+    let deleted = helpers::delete_confirmed(&app, &token, &format!("/api/admin/users/{}", user_id), "delete_user").await;
+    assert!(deleted["success"].as_bool().unwrap());
+    let login = helpers::login_user(&app, &username, "testpass123", "login_deleted").await;
+    assert!(!login["success"].as_bool().unwrap());
+    // 删除前签发的令牌也不能再用
+    let response = TestClient::get(helpers::get_url("/api/admin/me"))
+        .add_header("Authorization", helpers::bearer(&user_token), true)
+        .send(&app)
+        .await;
+    assert_eq!(response.status_code, Some(StatusCode::UNAUTHORIZED));
+    // 用户名仍被占用
+    let again = helpers::register_user(&app, &username, "testpass123").await;
+    assert!(!again["success"].as_bool().unwrap());
+
+    let restored = helpers::send(&app, &token, "POST", &format!("/api/admin/users/{}/restore", user_id), None).await;
+    assert!(restored["success"].as_bool().unwrap());
+    let login = helpers::login_user(&app, &username, "testpass123", "login_restored").await;
+    assert!(login["success"].as_bool().unwrap());
+}
@@ -1,22 +1,89 @@
-use salvo::test::TestClient;
-
-mod helpers;
-
-#[tokio::test]
-async fn current_user_returns_profile() {
-    let _guard = helpers::db_lock().await;
-    let app = helpers::create_test_app().await;
-    let username = helpers::unique_name("user_me");
-
-    let register = helpers::register_user(&app, &username, "testpass123").await;
-    let token = register["data"]["token"].as_str().unwrap();
-
-    let response = TestClient::get(helpers::get_url("/api/admin/me"))
-        .add_header("Authorization", helpers::bearer(token), true)
-        .send(&app)
-        .await;
-    let body = helpers::print_response_body_get_json(response, "me").await;
-    assert!(body["success"].as_bool().unwrap());
-    assert_eq!(body["data"]["username"].as_str().unwrap(), username);
-}
-
+use salvo::test::TestClient;
+use serde_json::json;
+
+mod helpers;
+
+#[tokio::test]
+async fn current_user_returns_profile() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("user_me");
+
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap();
+
+    let response = TestClient::get(helpers::get_url("/api/admin/me"))
+        .add_header("Authorization", helpers::bearer(token), true)
+        .send(&app)
+        .await;
+    let body = helpers::print_response_body_get_json(response, "me").await;
+    assert!(body["success"].as_bool().unwrap());
+    assert_eq!(body["data"]["username"].as_str().unwrap(), username);
+}
+
+
+#[tokio::test]
+async fn teachers_complete_profile_before_changing_status() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("user_profile");
+
+    // 不带真实姓名注册
+    let response = TestClient::post(helpers::get_url("/api/register"))
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"username": username, "password": "testpass123"}))
+        .send(&app)
+        .await;
+    let register = helpers::print_response_body_get_json(response, "register").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+    let me = helpers::send(&app, &token, "GET", "/api/admin/me", None).await;
+    assert!(!me["data"]["profile_complete"].as_bool().unwrap());
+    assert_eq!(me["data"]["name"].as_str().unwrap(), username);
+
+    let body = json!({"name": helpers::unique_name("profile_school"), "password": "school123"});
+    let school = helpers::send(&app, &token, "POST", "/api/admin/schools", Some(body)).await;
+    let body = json!({"name": "1年级1班", "grade": 1, "class": 1, "school_id": school["data"]["id"], "password": "class123"});
+    let class = helpers::send(&app, &token, "POST", "/api/admin/classes", Some(body)).await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+    let body = json!({"class_id": class_id, "password": "class123"});
+    helpers::send(&app, &token, "POST", "/api/admin/bind/class", Some(body)).await;
+
+    let status_url = format!("/api/admin/classes/{}/status", class_id);
+    let denied = helpers::send(&app, &token, "PUT", &status_url, Some(json!({"status": 1}))).await;
+    assert!(denied["message"].as_str().unwrap().contains("PROFILE_INCOMPLETE"));
+    // 创建时带状态也一样
+    let body = json!({"name": "1年级2班", "grade": 1, "class": 2, "school_id": school["data"]["id"], "status": 1});
+    let denied = helpers::send(&app, &token, "POST", "/api/admin/classes", Some(body.clone())).await;
+    assert!(denied["message"].as_str().unwrap().contains("PROFILE_INCOMPLETE"));
+    let denied = helpers::send(&app, &token, "POST", "/api/admin/classes/bulk", Some(json!({"classes": [body]}))).await;
+    assert!(denied["message"].as_str().unwrap().contains("PROFILE_INCOMPLETE"));
+
+    // 超过数据库列宽的资料直接拒绝
+    let body = json!({"real_name": "张三", "employee_no": "9".repeat(51)});
+    let too_long = helpers::send(&app, &token, "PUT", "/api/admin/me", Some(body)).await;
+    assert!(!too_long["success"].as_bool().unwrap());
+    let body = json!({"username": helpers::unique_name("long_name"), "password": "testpass123", "real_name": "张".repeat(101)});
+    let response = TestClient::post(helpers::get_url("/api/register"))
+        .add_header("content-type", "application/json", true)
+        .json(&body)
+        .send(&app)
+        .await;
+    let rejected = helpers::print_response_body_get_json(response, "register_long_name").await;
+    assert!(!rejected["success"].as_bool().unwrap());
+
+    let employee_no = helpers::unique_name("emp");
+    let body = json!({"real_name": "张三", "display_name": "张老师", "employee_no": employee_no});
+    let updated = helpers::send(&app, &token, "PUT", "/api/admin/me", Some(body)).await;
+    assert!(updated["success"].as_bool().unwrap());
+    let changed = helpers::send(&app, &token, "PUT", &status_url, Some(json!({"status": 1}))).await;
+    assert!(changed["success"].as_bool().unwrap());
+
+    // 班级教师和用户列表显示显示名而不是用户名
+    let class = helpers::send(&app, &token, "GET", &format!("/api/admin/classes/{}", class_id), None).await;
+    assert_eq!(class["data"]["teacher_infos"][0]["user_name"], "张老师");
+    let url = format!("/api/admin/users?name=%E5%BC%A0%E4%B8%89&employee_no={}", employee_no);
+    let users = helpers::send(&app, &token, "GET", &url, None).await;
+    assert_eq!(users["data"]["total"], 1);
+    assert_eq!(users["data"]["list"][0]["name"], "张老师");
+    assert!(users["data"]["list"][0]["profile_complete"].as_bool().unwrap());
+}
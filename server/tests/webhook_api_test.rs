@@ -0,0 +1,241 @@
+use data_model::{user_roles, users};
+use hmac::{Hmac, Mac};
+use salvo::test::TestClient;
+use school_manager_server::core::{outbox, router, webhook};
+use sea_orm::*;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+mod helpers;
+
+struct Captured {
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// 本地的 webhook 接收方, 记录收到的请求并按 status 返回
+struct StandIn {
+    url: String,
+    requests: Arc<Mutex<Vec<Captured>>>,
+    status: Arc<AtomicU16>,
+}
+
+async fn start_stand_in() -> StandIn {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let status = Arc::new(AtomicU16::new(200));
+    let (server_requests, server_status) = (requests.clone(), status.clone());
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (requests, status) = (server_requests.clone(), server_status.clone());
+            tokio::spawn(async move { handle_request(socket, requests, status).await });
+        }
+    });
+    StandIn { url, requests, status }
+}
+
+async fn handle_request(mut socket: TcpStream, requests: Arc<Mutex<Vec<Captured>>>, status: Arc<AtomicU16>) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (head_end, content_length) = loop {
+        let n = socket.read(&mut chunk).await.unwrap();
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&buf[..pos]).to_lowercase();
+        let content_length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        if buf.len() >= pos + 4 + content_length {
+            break (pos, content_length);
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let headers = head
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+        .collect();
+    let body = String::from_utf8_lossy(&buf[head_end + 4..head_end + 4 + content_length]).to_string();
+    requests.lock().unwrap().push(Captured { headers, body });
+    let response = format!(
+        "HTTP/1.1 {} Stand-In\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+        status.load(Ordering::SeqCst)
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+fn expected_signature(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[tokio::test]
+async fn webhook_signed_delivery_retry_and_redeliver() {
+    let _guard = helpers::db_lock().await;
+    let state = helpers::create_test_state().await;
+    let app = router::create_router(state.clone());
+    let stand_in = start_stand_in().await;
+    let secret = "0123456789abcdef-secret";
+
+    let username = helpers::unique_name("webhook");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(helpers::get_url("/api/admin/schools"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": helpers::unique_name("webhook_school"), "password": "school123"}))
+        .send(&app)
+        .await;
+    let school = helpers::print_response_body_get_json(response, "create_school_for_webhook").await;
+    let school_id = school["data"]["id"].as_i64().unwrap() as i32;
+
+    let response = TestClient::post(helpers::get_url("/api/admin/bind/school"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"school_id": school_id, "password": "school123"}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "bind_school_for_webhook").await;
+
+    // 学校成员不能配置 webhook, 只有管理员可以
+    let webhooks_url = helpers::get_url(&format!("/api/admin/schools/{}/webhooks", school_id));
+    let response = TestClient::post(&webhooks_url)
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"url": stand_in.url, "secret": secret}))
+        .send(&app)
+        .await;
+    let denied = helpers::print_response_body_get_json(response, "create_webhook_as_member").await;
+    assert!(!denied["success"].as_bool().unwrap());
+
+    let user = users::Entity::find()
+        .filter(users::Column::Username.eq(username.as_str()))
+        .one(&state.db)
+        .await
+        .unwrap()
+        .unwrap();
+    user_roles::ActiveModel {
+        user_id: Set(user.id),
+        role_id: Set(1),
+    }
+    .insert(&state.db)
+    .await
+    .unwrap();
+    let login = helpers::login_user(&app, &username, "testpass123", "login_webhook_admin").await;
+    let token = login["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::post(&webhooks_url)
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"url": stand_in.url, "secret": secret, "event_types": ["class.exploded"]}))
+        .send(&app)
+        .await;
+    let rejected = helpers::print_response_body_get_json(response, "create_webhook_invalid_event").await;
+    assert!(!rejected["success"].as_bool().unwrap());
+
+    let response = TestClient::post(&webhooks_url)
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"url": stand_in.url, "secret": secret, "event_types": ["class.status_changed"]}))
+        .send(&app)
+        .await;
+    let endpoint = helpers::print_response_body_get_json(response, "create_webhook").await;
+    let endpoint_id = endpoint["data"]["id"].as_i64().unwrap();
+    assert_eq!(endpoint["data"]["secret_hint"].as_str().unwrap(), "****cret");
+
+    let response = TestClient::post(helpers::get_url("/api/admin/classes"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"name": "webhook_class", "grade": 1, "class": 1, "school_id": school_id, "status": 1}))
+        .send(&app)
+        .await;
+    let class = helpers::print_response_body_get_json(response, "create_class_for_webhook").await;
+    let class_id = class["data"]["id"].as_i64().unwrap();
+
+    let response = TestClient::put(helpers::get_url(&format!("/api/admin/classes/{}", class_id)))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .add_header("content-type", "application/json", true)
+        .json(&json!({"status": 0}))
+        .send(&app)
+        .await;
+    helpers::print_response_body_get_json(response, "dismiss_class_for_webhook").await;
+
+    // 其它测试留下的事件也可能在队列里, 一直处理到清空
+    while outbox::dispatch_pending(&state).await.unwrap() > 0 {}
+    while webhook::deliver_pending(&state).await.unwrap() > 0 {}
+
+    let idempotency_key = {
+        let requests = stand_in.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.headers["x-webhook-event"], "class.status_changed");
+        assert_eq!(
+            request.headers["x-webhook-signature"],
+            expected_signature(secret, &request.headers["x-webhook-timestamp"], &request.body)
+        );
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["event_type"], "class.status_changed");
+        assert_eq!(body["payload"]["id"].as_i64().unwrap(), class_id);
+        assert_eq!(body["payload"]["status"], 0);
+        assert_eq!(body["idempotency_key"].as_str().unwrap(), request.headers["idempotency-key"]);
+        request.headers["idempotency-key"].clone()
+    };
+
+    let deliveries_url = helpers::get_url(&format!("/api/admin/webhooks/{}/deliveries", endpoint_id));
+    let response = TestClient::get(&deliveries_url)
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let deliveries = helpers::print_response_body_get_json(response, "webhook_deliveries").await;
+    assert_eq!(deliveries["data"]["total"].as_u64().unwrap(), 1);
+    let delivery = &deliveries["data"]["list"][0];
+    assert_eq!(delivery["status"], "succeeded");
+    assert_eq!(delivery["response_status"], 200);
+    let delivery_id = delivery["id"].as_i64().unwrap();
+
+    // 接收方出错时保留待重试的记录, 没到重试时间不会再次投递
+    stand_in.status.store(500, Ordering::SeqCst);
+    let response = TestClient::post(helpers::get_url(&format!(
+        "/api/admin/webhooks/deliveries/{}/redeliver",
+        delivery_id
+    )))
+    .add_header("Authorization", helpers::bearer(&token), true)
+    .send(&app)
+    .await;
+    let redelivery = helpers::print_response_body_get_json(response, "webhook_redeliver").await;
+    assert_eq!(redelivery["data"]["redelivery_of"].as_i64().unwrap(), delivery_id);
+    while webhook::deliver_pending(&state).await.unwrap() > 0 {}
+    {
+        let requests = stand_in.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers["idempotency-key"], idempotency_key);
+    }
+
+    let response = TestClient::get(format!("{}?status=pending", deliveries_url))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+    let pending = helpers::print_response_body_get_json(response, "webhook_pending_deliveries").await;
+    assert_eq!(pending["data"]["total"].as_u64().unwrap(), 1);
+    let retry = &pending["data"]["list"][0];
+    assert_eq!(retry["attempts"], 1);
+    assert_eq!(retry["response_status"], 500);
+    assert!(retry["last_error"].as_str().is_some());
+}
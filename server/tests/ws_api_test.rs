@@ -1,20 +1,193 @@
-use salvo::test::TestClient;
-
-mod helpers;
-
-#[tokio::test]
-async fn ws_handler_rejects_zero_id() {
-    let _guard = helpers::db_lock().await;
-    let app = helpers::create_test_app().await;
-    let username = helpers::unique_name("ws");
-    let register = helpers::register_user(&app, &username, "testpass123").await;
-    let token = register["data"]["token"].as_str().unwrap().to_string();
-
-    let response = TestClient::get(helpers::get_url("/api/admin/ws/school/0"))
-        .add_header("Authorization", helpers::bearer(&token), true)
-        .send(&app)
-        .await;
-
-    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
-}
-
+use futures_util::{SinkExt, StreamExt};
+use salvo::test::TestClient;
+use school_manager_server::apis::school_hold_api::SchoolHoldState;
+use school_manager_server::apis::ws_api::ServerMessage;
+use school_manager_server::core::db_listener::NotificationPayload;
+use school_manager_server::core::event_hub;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+
+mod helpers;
+
+#[tokio::test]
+async fn ws_handler_rejects_zero_id() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+    let username = helpers::unique_name("ws");
+    let register = helpers::register_user(&app, &username, "testpass123").await;
+    let token = register["data"]["token"].as_str().unwrap().to_string();
+
+    let response = TestClient::get(helpers::get_url("/api/admin/ws/school/0"))
+        .add_header("Authorization", helpers::bearer(&token), true)
+        .send(&app)
+        .await;
+
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn ws_handler_rejects_unknown_protocol() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+
+    let response = TestClient::get(helpers::get_url("/ws/school/1?protocol=99"))
+        .send(&app)
+        .await;
+
+    assert_eq!(response.status_code, Some(salvo::http::StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn ws_protocol_schema_is_exported() {
+    let _guard = helpers::db_lock().await;
+    let app = helpers::create_test_app().await;
+
+    let response = TestClient::get(helpers::get_url("/api/ws/protocol"))
+        .send(&app)
+        .await;
+    let body = helpers::print_response_body_get_json(response, "ws_protocol").await;
+    assert_eq!(body["version"].as_u64().unwrap(), 2);
+    assert!(body["subprotocols"].as_array().unwrap().iter().any(|p| p == "school-status.v2"));
+    assert!(body["server_message"].is_object());
+    assert!(body["client_message"].is_object());
+}
+
+type WsClient = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+fn status_update(school_id: i32, class_id: i32) -> ServerMessage {
+    ServerMessage::StatusUpdate(NotificationPayload {
+        school_id,
+        grade: 1,
+        class: 1,
+        class_id,
+        new_status: 2,
+        status_version: 1,
+    })
+}
+
+/// 连接建立后等到服务端完成订阅, 否则之后发布的事件可能收不到
+async fn connect(addr: std::net::SocketAddr, school_id: i32, query: &str) -> WsClient {
+    let url = format!("ws://{}/ws/school/{}{}", addr, school_id, query);
+    let (ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    for _ in 0..50 {
+        if event_hub::subscriber_count(school_id) > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    ws
+}
+
+async fn next_json(ws: &mut WsClient) -> Value {
+    let message = tokio::time::timeout(Duration::from_secs(5), ws.next())
+        .await
+        .expect("no websocket message")
+        .unwrap()
+        .unwrap();
+    serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+async fn send_json(ws: &mut WsClient, message: Value) {
+    ws.send(Message::text(message.to_string())).await.unwrap();
+}
+
+#[tokio::test]
+async fn ws_v2_handles_ping_subscribe_and_resume() {
+    let _guard = helpers::db_lock().await;
+    let addr = helpers::serve_test_app().await;
+    let school_id = 910_001;
+
+    let mut ws = connect(addr, school_id, "?protocol=2").await;
+    let initial = next_json(&mut ws).await;
+    assert_eq!(initial["type"], "initial_state");
+    assert_eq!(initial["version"], 2);
+
+    send_json(&mut ws, json!({"type": "ping"})).await;
+    assert_eq!(next_json(&mut ws).await["type"], "pong");
+
+    send_json(&mut ws, json!({"type": "subscribe", "payload": {"topics": ["hold"]}})).await;
+    let subscribed = next_json(&mut ws).await;
+    assert_eq!(subscribed["type"], "subscribed");
+    assert_eq!(subscribed["payload"]["topics"], json!(["hold"]));
+
+    // 没有订阅 status, 这条不会推送, 之后通过 resume 补发; 收到后面的 hold 说明它已经被跳过
+    let missed = event_hub::publish(school_id, status_update(school_id, 1));
+    event_hub::publish(school_id, ServerMessage::SchoolHold(SchoolHoldState {
+        school_id,
+        on_hold: false,
+        reason: None,
+        actor_id: None,
+        since: None,
+    }));
+    assert_eq!(next_json(&mut ws).await["type"], "school_hold");
+    send_json(&mut ws, json!({"type": "subscribe", "payload": {"topics": ["status"]}})).await;
+    assert_eq!(next_json(&mut ws).await["type"], "subscribed");
+
+    let live = event_hub::publish(school_id, status_update(school_id, 2));
+    let update = next_json(&mut ws).await;
+    assert_eq!(update["type"], "status_update");
+    assert_eq!(update["seq"].as_u64().unwrap(), live.seq());
+    assert_eq!(update["payload"]["class_id"], 2);
+
+    send_json(&mut ws, json!({"type": "resume", "payload": {"last_seq": missed.seq() - 1}})).await;
+    let replayed = next_json(&mut ws).await;
+    assert_eq!(replayed["seq"].as_u64().unwrap(), missed.seq());
+    assert_eq!(replayed["payload"]["class_id"], 1);
+    assert_eq!(next_json(&mut ws).await["seq"].as_u64().unwrap(), live.seq());
+
+    // 序号超出服务端记录(例如服务重启)时补发完整状态
+    send_json(&mut ws, json!({"type": "resume", "payload": {"last_seq": live.seq() + 100}})).await;
+    let snapshot = next_json(&mut ws).await;
+    assert_eq!(snapshot["type"], "initial_state");
+
+    send_json(&mut ws, json!({"type": "unknown"})).await;
+    let error = next_json(&mut ws).await;
+    assert_eq!(error["type"], "error");
+    assert_eq!(error["payload"]["code"], "INVALID_MESSAGE");
+}
+
+#[tokio::test]
+async fn ws_defaults_to_legacy_protocol() {
+    let _guard = helpers::db_lock().await;
+    let addr = helpers::serve_test_app().await;
+    let school_id = 910_002;
+
+    let mut ws = connect(addr, school_id, "").await;
+    // v1 没有 initial_state, 也不推送旧客户端不认识的消息
+    event_hub::publish(school_id, ServerMessage::AnnouncementRemoved { id: 1 });
+    event_hub::publish(school_id, status_update(school_id, 3));
+    let update = next_json(&mut ws).await;
+    assert!(update.get("type").is_none());
+    assert_eq!(update["class_id"], 3);
+    assert_eq!(update["new_status"], 2);
+}
+
+#[test]
+fn events_since_replays_only_cached_events() {
+    let school_id = 910_003;
+    assert_eq!(event_hub::events_since(school_id, 0).unwrap().len(), 0);
+    assert!(event_hub::events_since(school_id, 1).is_none());
+
+    let first = event_hub::publish(school_id, status_update(school_id, 1));
+    event_hub::publish(school_id, status_update(school_id, 2));
+    let last = event_hub::publish(school_id, status_update(school_id, 3));
+
+    let seqs: Vec<u64> = event_hub::events_since(school_id, first.seq())
+        .unwrap()
+        .iter()
+        .map(|e| e.seq())
+        .collect();
+    assert_eq!(seqs, vec![first.seq() + 1, last.seq()]);
+    assert!(event_hub::events_since(school_id, last.seq()).unwrap().is_empty());
+    // 服务重启后客户端带着更大的序号重连
+    assert!(event_hub::events_since(school_id, last.seq() + 1).is_none());
+
+    // 超出缓存的事件无法补发
+    let mut latest = last.seq();
+    for class_id in 0..300 {
+        latest = event_hub::publish(school_id, status_update(school_id, class_id)).seq();
+    }
+    assert!(event_hub::events_since(school_id, 0).is_none());
+    assert_eq!(event_hub::events_since(school_id, latest - 10).unwrap().len(), 10);
+}